@@ -0,0 +1,82 @@
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::ExitCode;
+
+use gmec::io::transform::TransformWriter;
+use gmec::patterns::index::Index;
+use gmec::patterns::PatternMatcher;
+use gmec::patterns::PatternSet;
+use gmec::testing::bench_support;
+use gmec::testing::bench_support::Measurement;
+use gmec::types::error_chain::ErrorChain;
+
+const ITERATIONS: usize = 15;
+
+// cargo bench --bench search; set GMEC_BENCH_BASELINE to a file written by an earlier run
+// (GMEC_BENCH_SAVE=path) to fail on regressions
+fn main() -> ExitCode {
+    let prose = bench_support::prose_corpus(1 << 20, 1);
+    let logs = bench_support::log_corpus(20_000, 2);
+    let needles = bench_support::needle_set(16, 3);
+    let prose_len = Some(prose.len() as u64);
+    let logs_len = Some(logs.len() as u64);
+    let index = Index::new(&prose);
+    let set: PatternSet<&str> = needles.iter().map(String::as_str).collect();
+    let byte_set: PatternSet<&[u8]> = needles.iter().map(|needle| needle.as_bytes()).collect();
+
+    let measurements = vec![
+        bench_support::measure("naive_str", ITERATIONS, prose_len, || prose.find_every(&"timeout").map(|found| found.len())),
+        bench_support::measure("naive_bytes", ITERATIONS, prose_len, || prose.as_bytes().find_every(&b"timeout").map(|found| found.len())),
+        bench_support::measure("compiled_index_query", ITERATIONS, prose_len, || index.find_every("timeout").len()),
+        bench_support::measure("multi_pattern_set", ITERATIONS, logs_len, || set.find_every_in(logs.as_str(), 0).len()),
+        bench_support::measure("replace_stream", ITERATIONS, logs_len, || {
+            let mut writer = TransformWriter::redact(Vec::new(), byte_set.clone(), b"***");
+            writer.write_all(logs.as_bytes()).expect("writing to a Vec cannot fail");
+            return writer.finish().map(|out| out.len()).unwrap_or(0);
+        }),
+        bench_support::measure("error_chain_build", ITERATIONS, None, || {
+            let mut error = ErrorChain::from(std::io::Error::other("disk full"), "writing block");
+            for level in 0..16 {
+                error = ErrorChain::from(error, format!("level {}", level));
+            }
+            return error.to_string().len();
+        }),
+    ];
+
+    report(&measurements);
+    if let Some(path) = env::var_os("GMEC_BENCH_SAVE") {
+        if let Err(error) = fs::write(&path, bench_support::encode_results(&measurements)) {
+            eprintln!("failed to save results: {}", error);
+            return ExitCode::FAILURE;
+        }
+    }
+    let Some(path) = env::var_os(bench_support::BASELINE_ENV_VAR) else {
+        return ExitCode::SUCCESS;
+    };
+    let regressions = fs::read_to_string(&path)
+        .map_err(|error| ErrorChain::from(error, "failed to read benchmark baseline"))
+        .and_then(|baseline| bench_support::compare_to_baseline(&baseline, &measurements, bench_support::DEFAULT_TOLERANCE));
+    match regressions {
+        Ok(regressions) if regressions.is_empty() => return ExitCode::SUCCESS,
+        Ok(regressions) => {
+            for regression in regressions {
+                eprintln!("regression: {} {:?} -> {:?} (+{:.1}%)", regression.name, regression.baseline, regression.current, regression.slowdown() * 100.0);
+            }
+            return ExitCode::FAILURE;
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    }
+}
+
+fn report(measurements: &[Measurement]) {
+    for measurement in measurements {
+        let throughput = measurement.throughput_mib_per_sec().map(|rate| format!("{:>10.1} MiB/s", rate)).unwrap_or_default();
+        println!("{:<24} median {:>12?}  min {:>12?} {}", measurement.name, measurement.median, measurement.min, throughput);
+    }
+}
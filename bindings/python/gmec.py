@@ -0,0 +1,161 @@
+"""ctypes bindings for gmec's matcher, PatternSet and line diff (src/ffi.rs, src/ffi/python.rs).
+
+Build the library with
+    cargo rustc --release --lib --features python --crate-type cdylib
+and point GMEC_LIBRARY at target/release/libgmec.so (or the .dylib/.dll), or load it explicitly
+with gmec.load(path). str inputs get str indices back; bytes inputs get byte offsets.
+"""
+
+import ctypes
+import ctypes.util
+import os
+
+EQUAL = 0
+DELETE = 1
+INSERT = 2
+
+_OK = 0
+
+
+class _Match(ctypes.Structure):
+    _fields_ = [("index", ctypes.c_size_t), ("length", ctypes.c_size_t)]
+
+
+class _Matches(ctypes.Structure):
+    _fields_ = [("matches", ctypes.POINTER(_Match)), ("len", ctypes.c_size_t)]
+
+
+class _SetMatch(ctypes.Structure):
+    _fields_ = [("pattern_id", ctypes.c_size_t), ("index", ctypes.c_size_t), ("length", ctypes.c_size_t)]
+
+
+class _SetMatches(ctypes.Structure):
+    _fields_ = [("matches", ctypes.POINTER(_SetMatch)), ("len", ctypes.c_size_t)]
+
+
+class _LineOp(ctypes.Structure):
+    _fields_ = [("op", ctypes.c_int), ("start", ctypes.c_size_t), ("end", ctypes.c_size_t)]
+
+
+class _LineOps(ctypes.Structure):
+    _fields_ = [("ops", ctypes.POINTER(_LineOp)), ("len", ctypes.c_size_t)]
+
+
+class GmecError(Exception):
+    pass
+
+
+_library = None
+
+
+def load(path=None):
+    global _library
+    library = ctypes.CDLL(path or os.environ.get("GMEC_LIBRARY") or ctypes.util.find_library("gmec") or "libgmec.so")
+    buffer = ctypes.c_char_p
+    error = ctypes.POINTER(ctypes.c_char_p)
+    library.gmec_find_every.argtypes = [buffer, ctypes.c_size_t, buffer, ctypes.c_size_t, ctypes.POINTER(_Matches), error]
+    library.gmec_set_find_every.argtypes = [buffer, ctypes.c_size_t, buffer, ctypes.c_size_t, ctypes.POINTER(_SetMatches), error]
+    library.gmec_diff_lines.argtypes = [buffer, ctypes.c_size_t, buffer, ctypes.c_size_t, ctypes.POINTER(_LineOps), error]
+    library.gmec_matches_free.argtypes = [_Matches]
+    library.gmec_set_matches_free.argtypes = [_SetMatches]
+    library.gmec_line_ops_free.argtypes = [_LineOps]
+    # c_void_p rather than c_char_p, so ctypes hands back the pointer itself to free
+    library.gmec_string_free.argtypes = [ctypes.c_void_p]
+    _library = library
+    return library
+
+
+def _lib():
+    return _library or load()
+
+
+def _encode(value):
+    if isinstance(value, str):
+        return value.encode("utf-8")
+    return bytes(value)
+
+
+def _call(function, out, *buffers):
+    args = []
+    for buffer in buffers:
+        args += [buffer, len(buffer)]
+    error = ctypes.c_void_p()
+    status = function(*args, ctypes.byref(out), ctypes.cast(ctypes.byref(error), ctypes.POINTER(ctypes.c_char_p)))
+    if status != _OK:
+        message = ctypes.string_at(error.value).decode("utf-8", "replace") if error.value else "gmec call failed"
+        _lib().gmec_string_free(error)
+        raise GmecError(message)
+
+
+def _str_offsets(data, offsets):
+    # byte offsets to str indices: a char starts at every byte that is not a UTF-8 continuation byte
+    mapped = {}
+    chars = 0
+    byte = 0
+    for offset in sorted(set(offsets)):
+        chars += sum(1 for value in data[byte:offset] if value & 0xC0 != 0x80)
+        byte = offset
+        mapped[offset] = chars
+    return mapped
+
+
+def find_every(haystack, pattern):
+    """(start, end) of every non-overlapping occurrence of pattern, leftmost first."""
+    data = _encode(haystack)
+    out = _Matches()
+    _call(_lib().gmec_find_every, out, data, _encode(pattern))
+    try:
+        spans = [(found.index, found.index + found.length) for found in out.matches[:out.len]]
+    finally:
+        _lib().gmec_matches_free(out)
+    if isinstance(haystack, str):
+        mapped = _str_offsets(data, [offset for span in spans for offset in span])
+        spans = [(mapped[start], mapped[end]) for start, end in spans]
+    return spans
+
+
+class PatternSet:
+    """Literal patterns searched together; matches report the index of the pattern that matched."""
+
+    def __init__(self, patterns=()):
+        self.patterns = []
+        for pattern in patterns:
+            self.push(pattern)
+
+    def push(self, pattern):
+        encoded = _encode(pattern)
+        if not encoded or b"\0" in encoded:
+            raise ValueError("patterns must be non-empty and contain no NUL bytes")
+        self.patterns.append(encoded)
+        return len(self.patterns) - 1
+
+    def find_every(self, haystack):
+        """(pattern id, start, end) of the leftmost match of any pattern, then the next after it."""
+        data = _encode(haystack)
+        out = _SetMatches()
+        _call(_lib().gmec_set_find_every, out, data, b"\0".join(self.patterns))
+        try:
+            found = [(match.pattern_id, match.index, match.index + match.length) for match in out.matches[:out.len]]
+        finally:
+            _lib().gmec_set_matches_free(out)
+        if isinstance(haystack, str):
+            mapped = _str_offsets(data, [offset for _, start, end in found for offset in (start, end)])
+            found = [(pattern_id, mapped[start], mapped[end]) for pattern_id, start, end in found]
+        return found
+
+
+def diff_lines(old, new):
+    """(op, line) per line, op being EQUAL, DELETE or INSERT; lines have no terminator."""
+    old_data = _encode(old)
+    new_data = _encode(new)
+    out = _LineOps()
+    _call(_lib().gmec_diff_lines, out, old_data, new_data)
+    try:
+        ops = [(op.op, op.start, op.end) for op in out.ops[:out.len]]
+    finally:
+        _lib().gmec_line_ops_free(out)
+    lines = []
+    for op, start, end in ops:
+        line = (new_data if op == INSERT else old_data)[start:end]
+        lines.append((op, line.decode("utf-8") if isinstance(old, str) else line))
+    return lines
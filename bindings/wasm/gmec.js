@@ -0,0 +1,98 @@
+// Browser glue for the wasm feature's exports (src/ffi/wasm.rs). Build the module with
+//   cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+// and load target/wasm32-unknown-unknown/release/gmec.wasm:
+//   const gmec = await Gmec.load(fetch("gmec.wasm"));
+//   gmec.find(text, "ERROR"); // Uint32Array of [start, end, ...] in JS string indices
+
+const OK = 0;
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+export const DIFF_EQUAL = 0;
+export const DIFF_DELETE = 1;
+export const DIFF_INSERT = 2;
+
+export class Gmec {
+    static async load(source) {
+        const { instance } = await WebAssembly.instantiateStreaming(source, {});
+        return new Gmec(instance.exports);
+    }
+
+    constructor(exports) {
+        this.exports = exports;
+    }
+
+    // (start, end) pairs of every non-overlapping occurrence of pattern, leftmost first
+    find(haystack, pattern) {
+        return this.#spans("gmec_wasm_find", [haystack, pattern]);
+    }
+
+    // (pattern index, start, end) triples for every match of any of the patterns
+    highlight(haystack, patterns) {
+        return this.#spans("gmec_wasm_highlight", [haystack, patterns.join("\0")]);
+    }
+
+    // (op, start, end) triples per line; DIFF_INSERT spans index into newText, the others into oldText
+    diff(oldText, newText) {
+        return this.#spans("gmec_wasm_diff", [oldText, newText]);
+    }
+
+    replace(haystack, pattern, replacement) {
+        return this.#call("gmec_wasm_replace", [haystack, pattern, replacement], (data, len) => {
+            const text = decoder.decode(this.#bytes(data, len));
+            this.exports.gmec_free(data, len);
+            return text;
+        });
+    }
+
+    #spans(name, strings) {
+        return this.#call(name, strings, (data, len) => {
+            const spans = new Uint32Array(this.exports.memory.buffer, data, len).slice();
+            this.exports.gmec_spans_free(data, len);
+            return spans;
+        });
+    }
+
+    // copies each string in, calls the export with (ptr, len) pairs and the out pointers, and
+    // hands the result's (data, len) to take, which must copy it out and free it
+    #call(name, strings, take) {
+        const inputs = strings.map((string) => this.#copyIn(encoder.encode(string)));
+        // a result struct of two u32s followed by the error pointer
+        const out = this.exports.gmec_alloc(12);
+        try {
+            const args = inputs.flatMap(({ data, len }) => [data, len]);
+            const status = this.exports[name](...args, out, out + 8);
+            const words = new Uint32Array(this.exports.memory.buffer, out, 3);
+            if (status !== OK) {
+                const message = this.#cString(words[2]);
+                this.exports.gmec_string_free(words[2]);
+                throw new Error(message);
+            }
+            return take(words[0], words[1]);
+        } finally {
+            this.exports.gmec_free(out, 12);
+            for (const { data, len } of inputs) {
+                this.exports.gmec_free(data, len);
+            }
+        }
+    }
+
+    #copyIn(bytes) {
+        const data = this.exports.gmec_alloc(bytes.length);
+        this.#bytes(data, bytes.length).set(bytes);
+        return { data, len: bytes.length };
+    }
+
+    #bytes(data, len) {
+        return new Uint8Array(this.exports.memory.buffer, data, len);
+    }
+
+    #cString(data) {
+        const memory = new Uint8Array(this.exports.memory.buffer);
+        let end = data;
+        while (memory[end] !== 0) {
+            end += 1;
+        }
+        return decoder.decode(memory.subarray(data, end));
+    }
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use gmec::patterns::expr::Expr;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    if let Ok(expr) = Expr::parse(data) {
+        // Display round-trips, so whatever parsed must parse again to the same tree
+        let reparsed = Expr::parse(&expr.to_string()).expect("rendered expression parses");
+        assert_eq!(reparsed, expr);
+        let _ = expr.matches_text(data);
+    }
+});
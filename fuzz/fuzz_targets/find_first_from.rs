@@ -0,0 +1,24 @@
+#![no_main]
+
+use gmec::patterns::PatternMatcher;
+use libfuzzer_sys::fuzz_target;
+
+// first two bytes pick the offset and the pattern length, the rest is split into pattern and haystack
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    let offset = data[0] as usize;
+    let rest = &data[2..];
+    let (pattern, haystack) = rest.split_at((data[1] as usize).min(rest.len()));
+    if let Some(found) = haystack.find_first_from(&pattern, offset) {
+        assert!(found.index >= offset);
+        assert_eq!(&haystack[found.range()], pattern);
+    }
+    if let (Ok(pattern), Ok(haystack)) = (std::str::from_utf8(pattern), std::str::from_utf8(haystack)) {
+        if let Some(found) = haystack.find_first_from(&pattern, offset) {
+            assert!(found.index >= offset);
+            assert_eq!(found.slice, pattern);
+        }
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use std::time::Duration;
+use std::time::Instant;
+
+use gmec::text::glob::glob_match;
+use libfuzzer_sys::fuzz_target;
+
+// glob_match is O(pattern * text); even at libFuzzer's default max_len that is a few million steps,
+// so anything slower than this is a backtracking blowup rather than a slow machine
+const MAX_MATCH_TIME: Duration = Duration::from_secs(1);
+
+// pattern and text are separated by the first newline
+fuzz_target!(|data: &str| {
+    let (pattern, text) = data.split_once('\n').unwrap_or((data, ""));
+    let started = Instant::now();
+    let _ = glob_match(pattern, text);
+    let elapsed = started.elapsed();
+    assert!(elapsed < MAX_MATCH_TIME, "glob_match took {:?} on a {} char pattern and {} char text", elapsed, pattern.chars().count(), text.chars().count());
+});
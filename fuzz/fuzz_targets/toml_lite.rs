@@ -0,0 +1,9 @@
+#![no_main]
+
+use gmec::formats::toml_lite;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    let _ = toml_lite::parse(data);
+    let _ = toml_lite::parse_front_matter(data);
+});
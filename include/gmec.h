@@ -0,0 +1,45 @@
+#ifndef GMEC_H
+#define GMEC_H
+
+/* C API for gmec, built with `cargo build --release --features ffi`.
+ * Regenerate with `cbindgen --config cbindgen.toml --output include/gmec.h`. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define GMEC_OK 0
+#define GMEC_ERROR -1
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct GmecMatch {
+    size_t index;
+    size_t length;
+} GmecMatch;
+
+typedef struct GmecMatches {
+    GmecMatch *matches;
+    size_t len;
+} GmecMatches;
+
+/* Finds every non-overlapping occurrence of pattern in haystack, leftmost first.
+ * Pointers may be NULL only when their length is 0. On GMEC_ERROR, *out_error (if out_error is
+ * not NULL) receives a rendered error to release with gmec_string_free. */
+int gmec_find_every(const uint8_t *haystack,
+                    size_t haystack_len,
+                    const uint8_t *pattern,
+                    size_t pattern_len,
+                    GmecMatches *out,
+                    char **out_error);
+
+void gmec_matches_free(GmecMatches matches);
+
+void gmec_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,7 @@
+pub mod compress;
+pub mod diff;
+pub mod entropy;
+pub mod hexdump;
+pub mod layout;
+pub mod rolling_hash;
+pub mod varint;
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use crate::types::error_chain::ErrorChain;
+
+const LZ_MAGIC: &[u8; 2] = b"LZ";
+const LZ_WINDOW: usize = 4096;
+const LZ_MIN_MATCH: usize = 3;
+const LZ_MAX_MATCH: usize = LZ_MIN_MATCH + 15;
+const LZ_MAX_CHAIN: usize = 32;
+
+pub fn rle_compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    let mut literal_start = 0;
+    while pos < input.len() {
+        let run = input[pos..].iter().take(129).take_while(|byte| **byte == input[pos]).count();
+        if run >= 2 {
+            flush_literals(&mut out, &input[literal_start..pos]);
+            out.push((run + 126) as u8);
+            out.push(input[pos]);
+            pos += run;
+            literal_start = pos;
+        } else {
+            pos += 1;
+        }
+    }
+    flush_literals(&mut out, &input[literal_start..]);
+    return out;
+}
+
+fn flush_literals(out: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(128) {
+        out.push((chunk.len() - 1) as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+pub fn rle_decompress(input: &[u8]) -> Result<Vec<u8>, ErrorChain> {
+    return rle_decompress_capped(input, usize::MAX);
+}
+
+pub fn rle_decompress_capped(input: &[u8], max_len: usize) -> Result<Vec<u8>, ErrorChain> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < input.len() {
+        let control = input[pos] as usize;
+        let produced = if control < 128 {
+            let literals = input.get(pos + 1..pos + 2 + control).ok_or_else(|| truncated("RLE", pos))?;
+            out.extend_from_slice(literals);
+            pos += 2 + control;
+            control + 1
+        } else {
+            let byte = *input.get(pos + 1).ok_or_else(|| truncated("RLE", pos))?;
+            out.resize(out.len() + control - 126, byte);
+            pos += 2;
+            control - 126
+        };
+        if out.len() > max_len {
+            return Err(ErrorChain::new(format!(
+                "RLE output exceeds the {} byte limit after a {} byte run at offset {}",
+                max_len,
+                produced,
+                pos
+            )));
+        }
+    }
+    return Ok(out);
+}
+
+pub fn lz_compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2 + 16);
+    out.extend_from_slice(LZ_MAGIC);
+    out.extend_from_slice(&(input.len() as u64).to_le_bytes());
+    let mut chains: HashMap<[u8; 3], Vec<usize>> = HashMap::new();
+    let mut flags_at = out.len();
+    let mut item = 8;
+    let mut pos = 0;
+    while pos < input.len() {
+        if item == 8 {
+            flags_at = out.len();
+            out.push(0);
+            item = 0;
+        }
+        let mut best_len = 0;
+        let mut best_distance = 0;
+        if pos + LZ_MIN_MATCH <= input.len() {
+            let key = [input[pos], input[pos + 1], input[pos + 2]];
+            if let Some(candidates) = chains.get(&key) {
+                for &candidate in candidates.iter().rev().take(LZ_MAX_CHAIN) {
+                    let distance = pos - candidate;
+                    if distance > LZ_WINDOW {
+                        break;
+                    }
+                    let len = input[pos..].iter()
+                        .zip(&input[candidate..])
+                        .take(LZ_MAX_MATCH)
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    if len > best_len {
+                        best_len = len;
+                        best_distance = distance;
+                    }
+                }
+            }
+        }
+        let advance = if best_len >= LZ_MIN_MATCH {
+            out[flags_at] |= 1 << item;
+            let encoded = ((best_distance - 1) << 4) | (best_len - LZ_MIN_MATCH);
+            out.extend_from_slice(&(encoded as u16).to_le_bytes());
+            best_len
+        } else {
+            out.push(input[pos]);
+            1
+        };
+        for index in pos..pos + advance {
+            if index + LZ_MIN_MATCH <= input.len() {
+                chains.entry([input[index], input[index + 1], input[index + 2]]).or_default().push(index);
+            }
+        }
+        pos += advance;
+        item += 1;
+    }
+    return out;
+}
+
+pub fn lz_decompress(input: &[u8]) -> Result<Vec<u8>, ErrorChain> {
+    return lz_decompress_capped(input, usize::MAX);
+}
+
+pub fn lz_decompress_capped(input: &[u8], max_len: usize) -> Result<Vec<u8>, ErrorChain> {
+    if input.len() < 10 || &input[..2] != LZ_MAGIC {
+        return Err(ErrorChain::new("input is not an LZ payload (bad header)"));
+    }
+    let declared = u64::from_le_bytes(input[2..10].try_into().unwrap());
+    if declared > max_len as u64 {
+        return Err(ErrorChain::new(format!("LZ payload declares {} bytes which exceeds the {} byte limit", declared, max_len)));
+    }
+    let declared = declared as usize;
+    let mut out = Vec::with_capacity(declared.min(1 << 20));
+    let mut pos = 10;
+    while out.len() < declared {
+        let flags = *input.get(pos).ok_or_else(|| truncated("LZ", pos))?;
+        pos += 1;
+        for item in 0..8 {
+            if out.len() >= declared {
+                break;
+            }
+            if flags & (1 << item) == 0 {
+                out.push(*input.get(pos).ok_or_else(|| truncated("LZ", pos))?);
+                pos += 1;
+                continue;
+            }
+            let pair = input.get(pos..pos + 2).ok_or_else(|| truncated("LZ", pos))?;
+            let encoded = u16::from_le_bytes([pair[0], pair[1]]) as usize;
+            let distance = (encoded >> 4) + 1;
+            let len = (encoded & 0xf) + LZ_MIN_MATCH;
+            if distance > out.len() {
+                return Err(ErrorChain::new(format!(
+                    "LZ back-reference at offset {} reaches {} bytes back but only {} bytes were produced",
+                    pos,
+                    distance,
+                    out.len()
+                )));
+            }
+            if out.len() + len > declared {
+                return Err(ErrorChain::new(format!("LZ back-reference at offset {} overruns the declared {} byte output", pos, declared)));
+            }
+            let start = out.len() - distance;
+            for index in 0..len {
+                out.push(out[start + index]);
+            }
+            pos += 2;
+        }
+    }
+    if pos != input.len() {
+        return Err(ErrorChain::new(format!("LZ payload has {} trailing bytes", input.len() - pos)));
+    }
+    return Ok(out);
+}
+
+fn truncated(format: &str, offset: usize) -> ErrorChain {
+    return ErrorChain::new(format!("{} payload is truncated at offset {}", format, offset));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rle_round_trip() {
+        let mut input = vec![7u8; 300];
+        input.extend_from_slice(b"abcdef");
+        input.extend(std::iter::repeat_n(0u8, 5));
+        let packed = rle_compress(&input);
+        assert!(packed.len() < 20);
+        assert_eq!(rle_decompress(&packed).unwrap(), input);
+        assert_eq!(rle_decompress(&rle_compress(b"")).unwrap(), b"");
+        assert!(rle_decompress(&[5, 1, 2]).is_err());
+        assert!(rle_decompress_capped(&packed, 100).is_err());
+    }
+
+    #[test]
+    fn test_lz_round_trip() {
+        let input = b"abracadabra abracadabra abracadabra, the quick brown fox jumps over the lazy dog".repeat(20);
+        let packed = lz_compress(&input);
+        assert!(packed.len() < input.len() / 4);
+        assert_eq!(lz_decompress(&packed).unwrap(), input);
+        assert_eq!(lz_decompress(&lz_compress(b"")).unwrap(), b"");
+        assert_eq!(lz_decompress(&lz_compress(b"ab")).unwrap(), b"ab");
+    }
+
+    #[test]
+    fn test_lz_rejects_bad_input() {
+        let packed = lz_compress(&b"hello hello hello hello".repeat(4));
+        assert!(lz_decompress(&packed[..packed.len() - 1]).is_err());
+        assert!(lz_decompress_capped(&packed, 10).is_err());
+        let mut bogus = LZ_MAGIC.to_vec();
+        bogus.extend_from_slice(&4u64.to_le_bytes());
+        bogus.extend_from_slice(&[1, 0x50, 0x00]);
+        assert!(lz_decompress(&bogus).is_err());
+        assert!(lz_decompress(b"nope").is_err());
+    }
+}
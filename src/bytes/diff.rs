@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use super::rolling_hash::RollingHash;
+use crate::types::error_chain::ErrorChain;
+
+pub const DEFAULT_BLOCK_SIZE: usize = 16;
+
+// blocks of repetitive input share one hash; extending every one of them forward would make
+// diffing zero padding quadratic
+const MAX_CANDIDATES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchOp {
+    Copy { offset: usize, len: usize },
+    Insert(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    pub source_len: usize,
+    pub target_len: usize,
+    pub ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    pub fn copied_bytes(&self) -> usize {
+        return self.ops.iter().map(|op| match op {
+            PatchOp::Copy { len, .. } => *len,
+            PatchOp::Insert(_) => 0,
+        }).sum();
+    }
+
+    pub fn inserted_bytes(&self) -> usize {
+        return self.ops.iter().map(|op| match op {
+            PatchOp::Copy { .. } => 0,
+            PatchOp::Insert(bytes) => bytes.len(),
+        }).sum();
+    }
+
+    fn push_copy(&mut self, offset: usize, len: usize) {
+        if let Some(PatchOp::Copy { offset: last_offset, len: last_len }) = self.ops.last_mut() {
+            if *last_offset + *last_len == offset {
+                *last_len += len;
+                return;
+            }
+        }
+        self.ops.push(PatchOp::Copy { offset, len });
+    }
+
+    fn push_insert(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        if let Some(PatchOp::Insert(last)) = self.ops.last_mut() {
+            last.extend_from_slice(bytes);
+            return;
+        }
+        self.ops.push(PatchOp::Insert(bytes.to_vec()));
+    }
+}
+
+pub fn diff(old: &[u8], new: &[u8]) -> Patch {
+    return diff_with_block_size(old, new, DEFAULT_BLOCK_SIZE);
+}
+
+pub fn diff_with_block_size(old: &[u8], new: &[u8], block_size: usize) -> Patch {
+    let block_size = block_size.max(1);
+    let mut patch = Patch { source_len: old.len(), target_len: new.len(), ops: Vec::new() };
+    let mut blocks: HashMap<u64, Vec<usize>> = HashMap::new();
+    for offset in (0..old.len().saturating_sub(block_size - 1)).step_by(block_size) {
+        blocks.entry(RollingHash::hash_of(&old[offset..offset + block_size])).or_default().push(offset);
+    }
+    if blocks.is_empty() || new.len() < block_size {
+        patch.push_insert(new);
+        return patch;
+    }
+
+    let mut literal_start = 0;
+    let mut pos = 0;
+    let mut hasher = RollingHash::new(&new[..block_size]);
+    while pos + block_size <= new.len() {
+        let mut best: Option<(usize, usize, usize)> = None;
+        if let Some(candidates) = blocks.get(&hasher.hash()) {
+            for &offset in candidates.iter().take(MAX_CANDIDATES) {
+                if old[offset..offset + block_size] != new[pos..pos + block_size] {
+                    continue;
+                }
+                let mut back = 0;
+                while pos - back > literal_start && offset > back && old[offset - back - 1] == new[pos - back - 1] {
+                    back += 1;
+                }
+                let mut len = block_size + back;
+                while offset - back + len < old.len() && pos - back + len < new.len() && old[offset - back + len] == new[pos - back + len] {
+                    len += 1;
+                }
+                if best.map(|(_, _, best_len)| len > best_len).unwrap_or(true) {
+                    best = Some((offset - back, pos - back, len));
+                }
+                // nothing can copy more than up to the end of either input
+                if offset - back + len == old.len() || pos - back + len == new.len() {
+                    break;
+                }
+            }
+        }
+        match best {
+            Some((old_start, new_start, len)) => {
+                patch.push_insert(&new[literal_start..new_start]);
+                patch.push_copy(old_start, len);
+                pos = new_start + len;
+                literal_start = pos;
+                if pos + block_size <= new.len() {
+                    hasher = RollingHash::new(&new[pos..pos + block_size]);
+                }
+            }
+            None => {
+                if pos + block_size < new.len() {
+                    hasher.roll(new[pos], new[pos + block_size]);
+                }
+                pos += 1;
+            }
+        }
+    }
+    patch.push_insert(&new[literal_start..]);
+    return patch;
+}
+
+pub fn apply_patch(old: &[u8], patch: &Patch) -> Result<Vec<u8>, ErrorChain> {
+    if old.len() != patch.source_len {
+        return Err(ErrorChain::new(format!(
+            "patch expects a {} byte source but got {} bytes",
+            patch.source_len,
+            old.len()
+        )));
+    }
+    let mut out = Vec::with_capacity(patch.target_len);
+    for (index, op) in patch.ops.iter().enumerate() {
+        match op {
+            PatchOp::Copy { offset, len } => {
+                let source = offset.checked_add(*len).and_then(|end| old.get(*offset..end));
+                match source {
+                    Some(bytes) => out.extend_from_slice(bytes),
+                    None => return Err(ErrorChain::new(format!(
+                        "patch op {} copies {}..{} outside of the {} byte source",
+                        index,
+                        offset,
+                        offset.saturating_add(*len),
+                        old.len()
+                    ))),
+                }
+            }
+            PatchOp::Insert(bytes) => out.extend_from_slice(bytes),
+        }
+        if out.len() > patch.target_len {
+            return Err(ErrorChain::new(format!("patch op {} overflows the {} byte target", index, patch.target_len)));
+        }
+    }
+    if out.len() != patch.target_len {
+        return Err(ErrorChain::new(format!("patch produced {} bytes but declares {}", out.len(), patch.target_len)));
+    }
+    return Ok(out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        return (0..len).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        }).collect();
+    }
+
+    #[test]
+    fn test_diff_round_trip() {
+        let old = sample(4096, 7);
+        let mut new = old.clone();
+        new.splice(1000..1010, b"inserted text here".iter().copied());
+        new.drain(3000..3100);
+        new.extend_from_slice(b"tail");
+        let patch = diff(&old, &new);
+        assert_eq!(apply_patch(&old, &patch).unwrap(), new);
+        assert!(patch.inserted_bytes() < 64);
+        assert!(patch.copied_bytes() > 3900);
+    }
+
+    #[test]
+    fn test_diff_unrelated_and_empty() {
+        let old = sample(100, 1);
+        let new = sample(100, 2);
+        assert_eq!(apply_patch(&old, &diff(&old, &new)).unwrap(), new);
+        assert_eq!(apply_patch(&old, &diff(&old, &[])).unwrap(), Vec::<u8>::new());
+        assert_eq!(apply_patch(&[], &diff(&[], &new)).unwrap(), new);
+    }
+
+    #[test]
+    fn test_diff_uniform_input() {
+        let old = vec![0u8; 4 << 20];
+        let mut new = old.clone();
+        new[1 << 20] = 1;
+        let patch = diff(&old, &new);
+        assert_eq!(apply_patch(&old, &patch).unwrap(), new);
+        assert!(patch.ops.len() <= 3, "{}", patch.ops.len());
+    }
+
+    #[test]
+    fn test_apply_patch_bounds() {
+        let patch = Patch { source_len: 4, target_len: 8, ops: vec![PatchOp::Copy { offset: 2, len: 8 }] };
+        assert!(apply_patch(b"abcd", &patch).is_err());
+        assert!(apply_patch(b"abc", &patch).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::types::span::HasSpan;
+use crate::types::span::Span;
+
+// bits per byte: 0.0 for a single repeated byte, up to 8.0 for uniformly distributed bytes
+pub fn shannon_entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+    return counts_entropy(&counts, bytes.len());
+}
+
+pub fn span_entropy<S>(bytes: &[u8], item: &S) -> f64
+where S: HasSpan {
+    let span = item.span();
+    return shannon_entropy(&bytes[span.start.min(bytes.len())..span.end.min(bytes.len())]);
+}
+
+fn counts_entropy(counts: &[usize; 256], total: usize) -> f64 {
+    let total = total as f64;
+    return counts.iter().filter(|count| **count > 0).map(|count| {
+        let probability = *count as f64 / total;
+        return -probability * probability.log2();
+    }).sum();
+}
+
+// every window of `window` bytes whose entropy reaches threshold, with overlapping or touching
+// windows merged into one span; inputs shorter than the window are judged as a whole
+pub fn high_entropy_regions(bytes: &[u8], window: usize, threshold: f64) -> Vec<Span> {
+    let window = window.max(1);
+    if bytes.is_empty() {
+        return Vec::new();
+    }
+    if bytes.len() <= window {
+        return if shannon_entropy(bytes) >= threshold { vec![Span::new(0, bytes.len())] } else { Vec::new() };
+    }
+    let mut counts = [0usize; 256];
+    for byte in &bytes[..window] {
+        counts[*byte as usize] += 1;
+    }
+    let mut regions: Vec<Span> = Vec::new();
+    let mut start = 0;
+    loop {
+        if counts_entropy(&counts, window) >= threshold {
+            let span = Span::new(start, start + window);
+            match regions.last_mut() {
+                Some(last) if last.end >= span.start => *last = last.join(&span),
+                _ => regions.push(span),
+            }
+        }
+        if start + window == bytes.len() {
+            return regions;
+        }
+        counts[bytes[start] as usize] -= 1;
+        counts[bytes[start + window] as usize] += 1;
+        start += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand_lite::Rng;
+
+    #[test]
+    fn test_shannon_entropy() {
+        assert_eq!(shannon_entropy(b""), 0.0);
+        assert_eq!(shannon_entropy(b"aaaa"), 0.0);
+        assert_eq!(shannon_entropy(b"abab"), 1.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(shannon_entropy(&all), 8.0);
+        assert_eq!(span_entropy(b"aaaaabcd", &Span::new(4, 8)), 2.0);
+    }
+
+    #[test]
+    fn test_high_entropy_regions() {
+        let mut rng = Rng::seed_from_u64(7);
+        let mut bytes = vec![b'a'; 300];
+        for byte in &mut bytes[100..200] {
+            *byte = rng.gen_index(256) as u8;
+        }
+        let regions = high_entropy_regions(&bytes, 32, 4.0);
+        assert_eq!(regions.len(), 1);
+        assert!(regions[0].start >= 80 && regions[0].start <= 110, "{:?}", regions);
+        assert!(regions[0].end >= 190 && regions[0].end <= 220, "{:?}", regions);
+        assert!(high_entropy_regions(&[b'z'; 50], 16, 0.5).is_empty());
+        assert_eq!(high_entropy_regions(b"abcd", 16, 2.0), vec![Span::new(0, 4)]);
+    }
+}
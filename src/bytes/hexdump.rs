@@ -0,0 +1,97 @@
+use std::fmt::Write;
+
+use crate::types::span::Span;
+
+pub const BYTES_PER_ROW: usize = 16;
+
+const RESET: &str = "\x1b[0m";
+const INVERSE: &str = "\x1b[7m";
+
+// one `hexdump -C` row per 16 bytes; base is the offset of bytes[0] in the whole input, and rows
+// stay aligned to absolute multiples of 16 so dumps of neighbouring windows line up
+pub fn hexdump(bytes: &[u8], base: usize) -> String {
+    return hexdump_highlighted(bytes, base, &[], false);
+}
+
+// highlights are absolute spans; with color they are shown in inverse video, without it as a row
+// of carets under the highlighted hex and text columns
+pub fn hexdump_highlighted(bytes: &[u8], base: usize, highlights: &[Span], color: bool) -> String {
+    let mut out = String::new();
+    let end = base + bytes.len();
+    let mut row = base - base % BYTES_PER_ROW;
+    while row < end {
+        write_row(&mut out, bytes, base, row, highlights, color);
+        row += BYTES_PER_ROW;
+    }
+    return out;
+}
+
+fn write_row(out: &mut String, bytes: &[u8], base: usize, row: usize, highlights: &[Span], color: bool) {
+    let byte_at = |offset: usize| offset.checked_sub(base).and_then(|index| bytes.get(index)).copied();
+    let highlighted = |offset: usize| highlights.iter().any(|span| span.start <= offset && offset < span.end);
+    let (mut hex, mut text, mut marks_hex, mut marks_text) = (String::new(), String::new(), String::new(), String::new());
+    let mut any_mark = false;
+    for column in 0..BYTES_PER_ROW {
+        let offset = row + column;
+        if column == BYTES_PER_ROW / 2 {
+            hex.push(' ');
+            marks_hex.push(' ');
+        }
+        let Some(byte) = byte_at(offset) else {
+            hex.push_str("   ");
+            marks_hex.push_str("   ");
+            if offset < base {
+                text.push(' ');
+                marks_text.push(' ');
+            }
+            continue;
+        };
+        let shown = if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' };
+        let mark = highlighted(offset);
+        any_mark |= mark;
+        // the space after a highlighted byte stays plain unless the next byte is highlighted too
+        let joined = mark && highlighted(offset + 1) && column + 1 != BYTES_PER_ROW / 2 && column + 1 < BYTES_PER_ROW;
+        match (mark, color) {
+            (true, true) => {
+                let _ = write!(hex, "{}{:02x}{}{}", INVERSE, byte, if joined { " " } else { "" }, RESET);
+                if !joined {
+                    hex.push(' ');
+                }
+                let _ = write!(text, "{}{}{}", INVERSE, shown, RESET);
+            }
+            _ => {
+                let _ = write!(hex, "{:02x} ", byte);
+                text.push(shown);
+            }
+        }
+        marks_hex.push_str(if mark { "^^ " } else { "   " });
+        marks_text.push(if mark { '^' } else { ' ' });
+    }
+    let _ = writeln!(out, "{:08x}  {} |{}|", row, hex, text);
+    if any_mark && !color {
+        let _ = writeln!(out, "{:8}  {}  {}", "", marks_hex, marks_text.trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_and_highlight() {
+        let bytes: Vec<u8> = (0x3e..0x52).collect();
+        assert_eq!(hexdump(&bytes, 0), "\
+00000000  3e 3f 40 41 42 43 44 45  46 47 48 49 4a 4b 4c 4d  |>?@ABCDEFGHIJKLM|
+00000010  4e 4f 50 51                                       |NOPQ|
+");
+        let dump = hexdump_highlighted(b"\x00key\x01", 0x1e, &[Span::new(0x1f, 0x22)], false);
+        assert_eq!(dump, "\
+00000010                                             00 6b  |              .k|
+                                                        ^^                  ^
+00000020  65 79 01                                          |ey.|
+          ^^ ^^                                              ^^
+");
+        let colored = hexdump_highlighted(b"ab", 0, &[Span::new(0, 2)], true);
+        assert!(colored.starts_with("00000000  \x1b[7m61 \x1b[0m\x1b[7m62\x1b[0m "), "{:?}", colored);
+    }
+}
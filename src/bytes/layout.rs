@@ -0,0 +1,192 @@
+use std::fmt;
+use std::fmt::Display;
+
+use crate::collections::interval_tree::IntervalTree;
+use crate::types::span::HasSpan;
+use crate::types::span::Span;
+
+// a named field at a fixed offset inside a structure, relative to the structure's start
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub span: Span,
+}
+
+// a fixed-size record layout: fields are appended in order, or placed at explicit offsets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    name: String,
+    fields: Vec<Field>,
+    size: usize,
+}
+
+impl Layout {
+    pub fn new<S>(name: S) -> Layout
+    where S: Into<String> {
+        return Layout { name: name.into(), fields: Vec::new(), size: 0 };
+    }
+
+    pub fn field<S>(self, name: S, len: usize) -> Layout
+    where S: Into<String> {
+        let offset = self.size;
+        return self.field_at(name, offset, len);
+    }
+
+    pub fn field_at<S>(mut self, name: S, offset: usize, len: usize) -> Layout
+    where S: Into<String> {
+        self.fields.push(Field { name: name.into(), span: Span::new(offset, offset + len) });
+        self.size = self.size.max(offset + len);
+        return self;
+    }
+
+    // reserved bytes that are part of the size but belong to no field
+    pub fn padding(mut self, len: usize) -> Layout {
+        self.size += len;
+        return self;
+    }
+
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        return &self.fields;
+    }
+
+    pub fn size(&self) -> usize {
+        return self.size;
+    }
+
+    pub fn at(&self, base: usize) -> Placement<'_> {
+        return Placement { base, layout: self };
+    }
+
+    // back-to-back records, as in a table of fixed-size entries
+    pub fn repeated(&self, base: usize, count: usize) -> Vec<Placement<'_>> {
+        return (0..count).map(|index| self.at(base + index * self.size)).collect();
+    }
+}
+
+// a layout placed at an absolute offset in the haystack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement<'a> {
+    pub base: usize,
+    pub layout: &'a Layout,
+}
+
+impl Placement<'_> {
+    pub fn span(&self) -> Span {
+        return Span::new(self.base, self.base + self.layout.size);
+    }
+}
+
+// one field a match touches; offset is where the match starts relative to the field, clamped to 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldHit {
+    pub structure: String,
+    pub base: usize,
+    pub field: String,
+    pub field_span: Span,
+    pub offset: usize,
+}
+
+impl Display for FieldHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{:#x}.{}", self.structure, self.base, self.field)?;
+        if self.offset > 0 {
+            write!(f, "+{}", self.offset)?;
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub span: Span,
+    pub hits: Vec<FieldHit>,
+}
+
+impl Annotation {
+    // the match covers exactly one field, start to end
+    pub fn is_aligned(&self) -> bool {
+        return match self.hits.as_slice() {
+            [hit] => hit.field_span == self.span,
+            _ => false,
+        };
+    }
+
+    pub fn straddles(&self) -> bool {
+        return self.hits.len() > 1;
+    }
+}
+
+impl Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}..{:#x}", self.span.start, self.span.end)?;
+        if self.hits.is_empty() {
+            return write!(f, " outside any field");
+        }
+        let labels: Vec<String> = self.hits.iter().map(|hit| hit.to_string()).collect();
+        return write!(f, " in {}", labels.join(", "));
+    }
+}
+
+struct PlacedField<'a> {
+    span: Span,
+    base: usize,
+    layout: &'a Layout,
+    field: &'a Field,
+}
+
+impl HasSpan for PlacedField<'_> {
+    fn span(&self) -> Span {
+        return self.span;
+    }
+}
+
+// labels every match with the fields it falls inside; a match spanning several fields lists each
+// in offset order, and a match in padding or outside every placement has no hits
+pub fn annotate<'a, I, M>(matches: I, placements: &[Placement<'a>]) -> Vec<Annotation>
+where I: IntoIterator<Item = M>, M: HasSpan {
+    let fields: Vec<PlacedField<'a>> = placements.iter().flat_map(|placement| {
+        return placement.layout.fields.iter().map(|field| PlacedField { span: field.span.shifted(placement.base), base: placement.base, layout: placement.layout, field });
+    }).collect();
+    let tree = IntervalTree::new(fields);
+    return matches.into_iter().map(|found| {
+        let span = found.span();
+        let query = if span.is_empty() { Span::new(span.start, span.start + 1) } else { span };
+        let hits = tree.overlapping(query).into_iter().map(|placed| FieldHit {
+            structure: placed.layout.name.clone(),
+            base: placed.base,
+            field: placed.field.name.clone(),
+            field_span: placed.span,
+            offset: span.start.saturating_sub(placed.span.start),
+        }).collect();
+        return Annotation { span, hits };
+    }).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_header_and_entries() {
+        let header = Layout::new("header").field("magic", 4).field("version", 2).padding(2).field("checksum", 4);
+        let entry = Layout::new("entry").field("id", 2).field("len", 2);
+        assert_eq!(header.size(), 12);
+        let mut placements = vec![header.at(0)];
+        placements.extend(entry.repeated(12, 2));
+        let matches = [Span::new(0, 4), Span::new(9, 11), Span::new(7, 8), Span::new(5, 14), Span::new(18, 20), Span::new(40, 41)];
+        let annotations = annotate(matches, &placements);
+        assert!(annotations[0].is_aligned());
+        assert_eq!(annotations[0].to_string(), "0x0..0x4 in header@0x0.magic");
+        assert_eq!(annotations[1].to_string(), "0x9..0xb in header@0x0.checksum+1");
+        assert!(annotations[2].hits.is_empty());
+        assert!(annotations[3].straddles());
+        let labels: Vec<String> = annotations[3].hits.iter().map(|hit| hit.to_string()).collect();
+        assert_eq!(labels, vec!["header@0x0.version+1", "header@0x0.checksum", "entry@0xc.id"]);
+        assert_eq!(annotations[4].to_string(), "0x12..0x14 in entry@0x10.len");
+        assert_eq!(annotations[5].to_string(), "0x28..0x29 outside any field");
+    }
+}
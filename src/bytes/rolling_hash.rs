@@ -0,0 +1,74 @@
+const BASE: u64 = 257;
+const MODULUS: u64 = (1 << 61) - 1;
+
+fn mul_mod(a: u64, b: u64) -> u64 {
+    return ((a as u128 * b as u128) % MODULUS as u128) as u64;
+}
+
+#[derive(Debug, Clone)]
+pub struct RollingHash {
+    window: usize,
+    hash: u64,
+    high_power: u64,
+}
+
+impl RollingHash {
+    pub fn new(window: &[u8]) -> RollingHash {
+        let mut high_power = 1;
+        for _ in 1..window.len() {
+            high_power = mul_mod(high_power, BASE);
+        }
+        return RollingHash { window: window.len(), hash: RollingHash::hash_of(window), high_power };
+    }
+
+    pub fn hash_of(bytes: &[u8]) -> u64 {
+        let mut hash = 0;
+        for byte in bytes {
+            hash = (mul_mod(hash, BASE) + *byte as u64 + 1) % MODULUS;
+        }
+        return hash;
+    }
+
+    pub fn window(&self) -> usize {
+        return self.window;
+    }
+
+    pub fn hash(&self) -> u64 {
+        return self.hash;
+    }
+
+    pub fn roll(&mut self, outgoing: u8, incoming: u8) -> u64 {
+        let removed = mul_mod(outgoing as u64 + 1, self.high_power);
+        self.hash = (self.hash + MODULUS - removed) % MODULUS;
+        self.hash = (mul_mod(self.hash, BASE) + incoming as u64 + 1) % MODULUS;
+        return self.hash;
+    }
+}
+
+pub fn window_hashes(bytes: &[u8], window: usize) -> Vec<u64> {
+    if window == 0 || bytes.len() < window {
+        return Vec::new();
+    }
+    let mut hasher = RollingHash::new(&bytes[..window]);
+    let mut hashes = Vec::with_capacity(bytes.len() - window + 1);
+    hashes.push(hasher.hash());
+    for index in window..bytes.len() {
+        hashes.push(hasher.roll(bytes[index - window], bytes[index]));
+    }
+    return hashes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_matches_direct_hash() {
+        let bytes = b"the quick brown fox jumps over the lazy dog";
+        let hashes = window_hashes(bytes, 4);
+        for (index, hash) in hashes.iter().enumerate() {
+            assert_eq!(*hash, RollingHash::hash_of(&bytes[index..index + 4]));
+        }
+        assert_eq!(hashes[0], hashes[31]);
+    }
+}
@@ -0,0 +1,151 @@
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub const MAX_VARINT_LEN: usize = 10;
+
+pub fn zigzag_encode(value: i64) -> u64 {
+    return ((value << 1) ^ (value >> 63)) as u64;
+}
+
+pub fn zigzag_decode(value: u64) -> i64 {
+    return ((value >> 1) as i64) ^ -((value & 1) as i64);
+}
+
+pub fn encoded_len(value: u64) -> usize {
+    let bits = 64 - value.leading_zeros() as usize;
+    return bits.div_ceil(7).max(1);
+}
+
+pub fn encode_u64(value: u64, out: &mut Vec<u8>) -> usize {
+    let mut value = value;
+    let mut written = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        written += 1;
+        if value == 0 {
+            out.push(byte);
+            return written;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub fn encode_i64(value: i64, out: &mut Vec<u8>) -> usize {
+    return encode_u64(zigzag_encode(value), out);
+}
+
+pub fn decode_u64(input: &[u8]) -> Result<(u64, usize), ErrorChain> {
+    let mut value: u64 = 0;
+    for (index, byte) in input.iter().enumerate() {
+        if index == MAX_VARINT_LEN - 1 && *byte > 1 {
+            return Err(ErrorChain::new(format!("varint overflows 64 bits at byte {}", index)));
+        }
+        value |= ((byte & 0x7f) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    return Err(ErrorChain::new(format!("varint is truncated after {} bytes", input.len())));
+}
+
+pub fn decode_i64(input: &[u8]) -> Result<(i64, usize), ErrorChain> {
+    let (value, len) = decode_u64(input)?;
+    return Ok((zigzag_decode(value), len));
+}
+
+pub trait ReadVarint: Read {
+    fn read_varint_u64(&mut self) -> Result<u64, ErrorChain> {
+        let mut buffer = [0u8; MAX_VARINT_LEN];
+        for index in 0..MAX_VARINT_LEN {
+            match self.read_exact(&mut buffer[index..index + 1]) {
+                Ok(()) => {}
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => {
+                    return Err(ErrorChain::from(error, format!("varint is truncated after {} bytes", index)));
+                }
+                Err(error) => return Err(ErrorChain::from(error, "failed to read varint")),
+            }
+            if buffer[index] & 0x80 == 0 || index == MAX_VARINT_LEN - 1 {
+                return decode_u64(&buffer[..index + 1]).map(|(value, _)| value);
+            }
+        }
+        unreachable!("the loop always returns on its last iteration");
+    }
+
+    fn read_varint_i64(&mut self) -> Result<i64, ErrorChain> {
+        return self.read_varint_u64().map(zigzag_decode);
+    }
+}
+
+impl<R> ReadVarint for R where R: Read + ?Sized {}
+
+pub trait WriteVarint: Write {
+    fn write_varint_u64(&mut self, value: u64) -> Result<usize, ErrorChain> {
+        let mut buffer = Vec::with_capacity(MAX_VARINT_LEN);
+        let len = encode_u64(value, &mut buffer);
+        self.write_all(&buffer).on_error("failed to write varint")?;
+        return Ok(len);
+    }
+
+    fn write_varint_i64(&mut self, value: i64) -> Result<usize, ErrorChain> {
+        return self.write_varint_u64(zigzag_encode(value));
+    }
+}
+
+impl<W> WriteVarint for W where W: Write + ?Sized {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let mut out = Vec::new();
+        assert_eq!(encode_u64(300, &mut out), 2);
+        assert_eq!(out, [0xac, 0x02]);
+        assert_eq!(decode_u64(&out).unwrap(), (300, 2));
+        for value in [0, 1, 127, 128, u32::MAX as u64, u64::MAX] {
+            let mut out = Vec::new();
+            let len = encode_u64(value, &mut out);
+            assert_eq!(len, encoded_len(value));
+            assert_eq!(decode_u64(&out).unwrap(), (value, len));
+        }
+    }
+
+    #[test]
+    fn test_zigzag() {
+        assert_eq!(zigzag_encode(0), 0);
+        assert_eq!(zigzag_encode(-1), 1);
+        assert_eq!(zigzag_encode(1), 2);
+        assert_eq!(zigzag_encode(i64::MIN), u64::MAX);
+        for value in [0, -1, 1, -64, 64, i64::MIN, i64::MAX] {
+            let mut out = Vec::new();
+            encode_i64(value, &mut out);
+            assert_eq!(decode_i64(&out).unwrap().0, value);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(decode_u64(&[0x80, 0x80]).is_err());
+        assert!(decode_u64(&[0xff; 10]).is_err());
+        assert!(decode_u64(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]).is_err());
+    }
+
+    #[test]
+    fn test_read_write_traits() {
+        let mut buffer = Vec::new();
+        buffer.write_varint_u64(150).unwrap();
+        buffer.write_varint_i64(-3).unwrap();
+        let mut reader = buffer.as_slice();
+        assert_eq!(reader.read_varint_u64().unwrap(), 150);
+        assert_eq!(reader.read_varint_i64().unwrap(), -3);
+        assert!(reader.read_varint_u64().is_err());
+        let mut truncated: &[u8] = &[0x96];
+        assert!(truncated.read_varint_u64().is_err());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod bitset;
+pub mod bloom;
+pub mod interval_tree;
+pub mod ring_buffer;
+pub mod sorted;
+pub mod trie;
@@ -0,0 +1,150 @@
+const WORD_BITS: usize = 64;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> BitSet {
+        return BitSet { words: Vec::new() };
+    }
+
+    pub fn with_capacity(bits: usize) -> BitSet {
+        return BitSet { words: vec![0; bits.div_ceil(WORD_BITS)] };
+    }
+
+    // number of bits that can be addressed without growing
+    pub fn capacity(&self) -> usize {
+        return self.words.len() * WORD_BITS;
+    }
+
+    pub fn insert(&mut self, bit: usize) -> bool {
+        let (word, mask) = (bit / WORD_BITS, 1u64 << (bit % WORD_BITS));
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let added = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        return added;
+    }
+
+    pub fn remove(&mut self, bit: usize) -> bool {
+        let (word, mask) = (bit / WORD_BITS, 1u64 << (bit % WORD_BITS));
+        match self.words.get_mut(word) {
+            Some(bits) if *bits & mask != 0 => {
+                *bits &= !mask;
+                return true;
+            }
+            _ => return false,
+        }
+    }
+
+    pub fn contains(&self, bit: usize) -> bool {
+        return self.words.get(bit / WORD_BITS).map(|word| word & (1u64 << (bit % WORD_BITS)) != 0).unwrap_or(false);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.words.iter().map(|word| word.count_ones() as usize).sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.words.iter().all(|word| *word == 0);
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    pub fn union_with(&mut self, other: &BitSet) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word |= other_word;
+        }
+    }
+
+    pub fn intersect_with(&mut self, other: &BitSet) {
+        self.words.truncate(other.words.len());
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word &= other_word;
+        }
+    }
+
+    pub fn difference_with(&mut self, other: &BitSet) {
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other_word;
+        }
+    }
+
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        let mut out = self.clone();
+        out.union_with(other);
+        return out;
+    }
+
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        let mut out = self.clone();
+        out.intersect_with(other);
+        return out;
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        return self.words.iter().enumerate().all(|(index, word)| word & !other.words.get(index).copied().unwrap_or(0) == 0);
+    }
+
+    // set bits in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        return self.words.iter().enumerate().flat_map(|(index, word)| {
+            let mut rest = *word;
+            return std::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros() as usize;
+                rest &= rest - 1;
+                return Some(index * WORD_BITS + bit);
+            });
+        });
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> BitSet {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        return set;
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for bit in iter {
+            self.insert(bit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_operations() {
+        let mut a: BitSet = [1, 5, 64, 200].into_iter().collect();
+        let b: BitSet = [5, 64, 65].into_iter().collect();
+        assert!(a.contains(200) && !a.contains(199) && !a.contains(10_000));
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.intersection(&b).iter().collect::<Vec<usize>>(), vec![5, 64]);
+        assert_eq!(a.union(&b).iter().collect::<Vec<usize>>(), vec![1, 5, 64, 65, 200]);
+        assert!(a.intersection(&b).is_subset(&b));
+        assert!(!a.is_subset(&b));
+        a.difference_with(&b);
+        assert_eq!(a.iter().collect::<Vec<usize>>(), vec![1, 200]);
+        assert!(a.remove(1) && !a.remove(1));
+        assert!(!a.insert(200));
+        a.intersect_with(&b);
+        assert!(a.is_empty());
+    }
+}
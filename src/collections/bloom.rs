@@ -0,0 +1,114 @@
+use std::collections::hash_map::DefaultHasher;
+use std::f64::consts::LN_2;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use crate::collections::bitset::BitSet;
+
+// sized from the expected number of items and the target false-positive rate; never reports a
+// false negative, and the false-positive rate only holds while len() stays near the expected count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bloom {
+    bits: BitSet,
+    bit_count: usize,
+    hash_count: usize,
+    len: usize,
+}
+
+impl Bloom {
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Bloom {
+        let items = expected_items.max(1) as f64;
+        let rate = false_positive_rate.clamp(1e-12, 0.5);
+        let bit_count = (-(items * rate.ln()) / (LN_2 * LN_2)).ceil().max(64.0) as usize;
+        let hash_count = ((bit_count as f64 / items) * LN_2).round().max(1.0) as usize;
+        return Bloom::with_size(bit_count, hash_count);
+    }
+
+    pub fn with_size(bit_count: usize, hash_count: usize) -> Bloom {
+        let bit_count = bit_count.max(1);
+        return Bloom { bits: BitSet::with_capacity(bit_count), bit_count, hash_count: hash_count.max(1), len: 0 };
+    }
+
+    pub fn bit_count(&self) -> usize {
+        return self.bit_count;
+    }
+
+    pub fn hash_count(&self) -> usize {
+        return self.hash_count;
+    }
+
+    // number of insert calls, duplicates included
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    // double hashing: bit i is h1 + i * h2, which behaves like k independent hashes
+    fn bit_indexes<T>(&self, item: &T) -> impl Iterator<Item = usize>
+    where T: Hash + ?Sized {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let first = hasher.finish();
+        first.hash(&mut hasher);
+        let second = hasher.finish() | 1;
+        let bit_count = self.bit_count as u64;
+        return (0..self.hash_count as u64).map(move |index| (first.wrapping_add(index.wrapping_mul(second)) % bit_count) as usize);
+    }
+
+    pub fn insert<T>(&mut self, item: &T)
+    where T: Hash + ?Sized {
+        let indexes: Vec<usize> = self.bit_indexes(item).collect();
+        for index in indexes {
+            self.bits.insert(index);
+        }
+        self.len += 1;
+    }
+
+    pub fn contains<T>(&self, item: &T) -> bool
+    where T: Hash + ?Sized {
+        return self.bit_indexes(item).all(|index| self.bits.contains(index));
+    }
+
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let filled = self.bits.len() as f64 / self.bit_count as f64;
+        return filled.powi(self.hash_count as i32);
+    }
+
+    pub fn clear(&mut self) {
+        self.bits = BitSet::with_capacity(self.bit_count);
+        self.len = 0;
+    }
+
+    pub fn union_with(&mut self, other: &Bloom) -> bool {
+        if self.bit_count != other.bit_count || self.hash_count != other.hash_count {
+            return false;
+        }
+        self.bits.union_with(&other.bits);
+        self.len += other.len;
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_false_negatives_and_bounded_false_positives() {
+        let mut bloom = Bloom::new(1000, 0.01);
+        for item in 0..1000u32 {
+            bloom.insert(&item);
+        }
+        assert!((0..1000u32).all(|item| bloom.contains(&item)));
+        let false_positives = (1000..11000u32).filter(|item| bloom.contains(item)).count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+        assert!(bloom.estimated_false_positive_rate() < 0.03);
+        bloom.insert("text keys");
+        assert!(bloom.contains("text keys"));
+        bloom.clear();
+        assert!(bloom.is_empty() && !bloom.contains(&1u32));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::types::span::HasSpan;
+use crate::types::span::Span;
+
+// an implicit balanced tree over items sorted by start: the node for range lo..hi is its midpoint,
+// and max_end[node] holds the largest end offset anywhere in that node's subtree
+#[derive(Debug, Clone)]
+pub struct IntervalTree<T>
+where T: HasSpan {
+    items: Vec<T>,
+    max_end: Vec<usize>,
+}
+
+impl<T> IntervalTree<T>
+where T: HasSpan {
+    pub fn new(mut items: Vec<T>) -> IntervalTree<T> {
+        items.sort_by_key(|item| item.span());
+        let mut tree = IntervalTree { max_end: vec![0; items.len()], items };
+        tree.build(0, tree.items.len());
+        return tree;
+    }
+
+    fn build(&mut self, lo: usize, hi: usize) -> usize {
+        if lo >= hi {
+            return 0;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = self.build(lo, mid);
+        let right = self.build(mid + 1, hi);
+        self.max_end[mid] = self.items[mid].span().end.max(left).max(right);
+        return self.max_end[mid];
+    }
+
+    pub fn len(&self) -> usize {
+        return self.items.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.items.is_empty();
+    }
+
+    // O(n): intended for occasional additions to a mostly static set
+    pub fn insert(&mut self, item: T) {
+        let span = item.span();
+        let index = self.items.partition_point(|existing| existing.span() <= span);
+        self.items.insert(index, item);
+        self.max_end.push(0);
+        self.build(0, self.items.len());
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        return self.items.iter();
+    }
+
+    pub fn overlapping(&self, span: Span) -> Vec<&T> {
+        let mut found = Vec::new();
+        self.collect(0, self.items.len(), span, &mut found);
+        return found;
+    }
+
+    pub fn stabbing(&self, offset: usize) -> Vec<&T> {
+        return self.overlapping(Span::new(offset, offset + 1));
+    }
+
+    pub fn any_overlapping(&self, span: Span) -> bool {
+        return self.first_overlapping(0, self.items.len(), span).is_some();
+    }
+
+    fn collect<'a>(&'a self, lo: usize, hi: usize, query: Span, found: &mut Vec<&'a T>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] <= query.start {
+            return;
+        }
+        self.collect(lo, mid, query, found);
+        let span = self.items[mid].span();
+        if span.start >= query.end {
+            return;
+        }
+        if span.overlaps(&query) {
+            found.push(&self.items[mid]);
+        }
+        self.collect(mid + 1, hi, query, found);
+    }
+
+    fn first_overlapping(&self, lo: usize, hi: usize, query: Span) -> Option<usize> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] <= query.start {
+            return None;
+        }
+        if let Some(index) = self.first_overlapping(lo, mid, query) {
+            return Some(index);
+        }
+        let span = self.items[mid].span();
+        if span.start >= query.end {
+            return None;
+        }
+        if span.overlaps(&query) {
+            return Some(mid);
+        }
+        return self.first_overlapping(mid + 1, hi, query);
+    }
+}
+
+impl<T> FromIterator<T> for IntervalTree<T>
+where T: HasSpan {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> IntervalTree<T> {
+        return IntervalTree::new(iter.into_iter().collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand_lite::Rng;
+
+    #[test]
+    fn test_stabbing_and_overlap() {
+        let tree: IntervalTree<Span> = [Span::new(10, 20), Span::new(0, 100), Span::new(15, 16), Span::new(30, 40)].into_iter().collect();
+        assert_eq!(tree.stabbing(15), vec![&Span::new(0, 100), &Span::new(10, 20), &Span::new(15, 16)]);
+        assert_eq!(tree.overlapping(Span::new(20, 31)), vec![&Span::new(0, 100), &Span::new(30, 40)]);
+        assert!(!tree.any_overlapping(Span::new(100, 200)));
+    }
+
+    #[test]
+    fn test_matches_linear_scan() {
+        let mut rng = Rng::seed_from_u64(17);
+        let spans: Vec<Span> = (0..500).map(|_| {
+            let start = rng.gen_index(1000);
+            Span::new(start, start + 1 + rng.gen_index(50))
+        }).collect();
+        let mut tree = IntervalTree::new(spans.clone());
+        tree.insert(Span::new(500, 900));
+        let mut all = spans.clone();
+        all.push(Span::new(500, 900));
+        for _ in 0..200 {
+            let start = rng.gen_index(1100);
+            let query = Span::new(start, start + rng.gen_index(30) + 1);
+            let mut expected: Vec<Span> = all.iter().filter(|span| span.overlaps(&query)).copied().collect();
+            expected.sort();
+            let mut actual: Vec<Span> = tree.overlapping(query).into_iter().copied().collect();
+            actual.sort();
+            assert_eq!(actual, expected);
+            assert_eq!(tree.any_overlapping(query), !expected.is_empty());
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::ops::Range;
+
+use crate::patterns::PatternMatch;
+use crate::patterns::PatternMatcher;
+use crate::types::error_chain::ErrorChain;
+
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    buf: Vec<T>,
+    capacity: usize,
+    head: usize,
+    len: usize,
+    dropped: u64,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn with_capacity(capacity: usize) -> RingBuffer<T> {
+        assert!(capacity > 0, "RingBuffer capacity must be non-zero");
+        return RingBuffer { buf: Vec::with_capacity(capacity), capacity, head: 0, len: 0, dropped: 0 };
+    }
+
+    pub fn capacity(&self) -> usize {
+        return self.capacity;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    pub fn is_full(&self) -> bool {
+        return self.len == self.capacity;
+    }
+
+    pub fn stream_offset(&self) -> u64 {
+        return self.dropped;
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.is_full() {
+            self.buf[self.head] = item;
+            self.head = (self.head + 1) % self.capacity;
+            self.dropped += 1;
+            return;
+        }
+        let tail = self.head + self.len;
+        if tail < self.capacity && tail == self.buf.len() {
+            self.buf.push(item);
+        } else {
+            self.buf[tail % self.capacity] = item;
+        }
+        self.len += 1;
+    }
+
+    pub fn try_push(&mut self, item: T) -> Result<(), ErrorChain> {
+        if self.is_full() {
+            return Err(ErrorChain::new(format!("RingBuffer capacity of {} exceeded", self.capacity)));
+        }
+        self.push(item);
+        return Ok(());
+    }
+
+    pub fn pop_front(&mut self) -> Option<&T> {
+        if self.len == 0 {
+            return None;
+        }
+        let index = self.head;
+        self.consume(1);
+        return Some(&self.buf[index]);
+    }
+
+    pub fn consume(&mut self, count: usize) {
+        let count = count.min(self.len);
+        self.head = (self.head + count) % self.capacity;
+        self.len -= count;
+        self.dropped += count as u64;
+        if self.len == 0 && self.buf.len() < self.capacity {
+            self.head = self.buf.len() % self.capacity;
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        return self.buf.get((self.head + index) % self.capacity);
+    }
+
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        return self.range_slices(0..self.len);
+    }
+
+    pub fn range_slices(&self, range: Range<usize>) -> (&[T], &[T]) {
+        assert!(range.start <= range.end && range.end <= self.len, "range out of bounds for RingBuffer");
+        let start = (self.head + range.start) % self.capacity;
+        let len = range.end - range.start;
+        let first_len = len.min(self.capacity - start).min(self.buf.len().saturating_sub(start));
+        return (&self.buf[start..start + first_len], &self.buf[..len - first_len]);
+    }
+
+    pub fn contiguous(&self, range: Range<usize>) -> Option<&[T]> {
+        let (first, second) = self.range_slices(range);
+        if second.is_empty() {
+            return Some(first);
+        }
+        if first.is_empty() {
+            return Some(second);
+        }
+        return None;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let (first, second) = self.as_slices();
+        return first.iter().chain(second.iter());
+    }
+}
+
+impl<T> RingBuffer<T>
+where T: Clone {
+    pub fn extend_from_slice(&mut self, items: &[T]) {
+        for item in items {
+            self.push(item.clone());
+        }
+    }
+
+    pub fn range_to_vec(&self, range: Range<usize>) -> Vec<T> {
+        let (first, second) = self.range_slices(range);
+        let mut out = first.to_vec();
+        out.extend_from_slice(second);
+        return out;
+    }
+}
+
+impl<'a, P, T> PatternMatcher<'a, P> for RingBuffer<T>
+where P: AsRef<[T]>,
+T: PartialEq + Clone {
+    fn find_first_from(&'a self, pattern: &P, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        let needle = pattern.as_ref();
+        if byte_offset > self.len || needle.len() > self.len - byte_offset {
+            return None;
+        }
+        let found = |index: usize| PatternMatch { index, length: needle.len(), slice: self };
+        let (first, second) = self.range_slices(byte_offset..self.len);
+        if let Some(hit) = first.find_first(&needle) {
+            return Some(found(byte_offset + hit.index));
+        }
+        if needle.len() > 1 && !second.is_empty() {
+            let left = first.len().saturating_sub(needle.len() - 1);
+            let right = second.len().min(needle.len() - 1);
+            let mut seam = first[left..].to_vec();
+            seam.extend_from_slice(&second[..right]);
+            if let Some(hit) = seam.find_first(&needle) {
+                return Some(found(byte_offset + left + hit.index));
+            }
+        }
+        if let Some(hit) = second.find_first(&needle) {
+            return Some(found(byte_offset + first.len() + hit.index));
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_wrap() {
+        let mut ring = RingBuffer::with_capacity(4);
+        ring.extend_from_slice(b"abcdef");
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring.stream_offset(), 2);
+        assert_eq!(ring.as_slices(), (&b"cd"[..], &b"ef"[..]));
+        assert_eq!(ring.range_to_vec(0..4), b"cdef");
+        assert!(ring.contiguous(1..3).is_none());
+        assert_eq!(ring.contiguous(0..2), Some(&b"cd"[..]));
+        assert!(ring.try_push(b'g').is_err());
+        ring.consume(3);
+        assert_eq!(ring.range_to_vec(0..1), b"f");
+        ring.try_push(b'g').unwrap();
+        assert_eq!(ring.iter().copied().collect::<Vec<u8>>(), b"fg");
+    }
+
+    #[test]
+    fn test_pattern_search_across_seam() {
+        let mut ring = RingBuffer::with_capacity(8);
+        ring.extend_from_slice(b"xxxxxHEL");
+        ring.consume(5);
+        ring.extend_from_slice(b"LO HE");
+        assert_eq!(ring.range_to_vec(0..ring.len()), b"HELLO HE");
+        let hit = ring.find_first(&b"LLO").unwrap();
+        assert_eq!(hit.range(), 2..5);
+        let hits = ring.find_every(&b"HE").unwrap();
+        assert_eq!(hits.iter().map(|hit| hit.index).collect::<Vec<usize>>(), vec![0, 6]);
+        assert!(ring.find_first_from(&b"HELLO", 1).is_none());
+    }
+}
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+use crate::types::span::HasSpan;
+use crate::types::span::Span;
+
+pub fn insert_sorted_by_key<T, K, F>(items: &mut Vec<T>, item: T, mut key: F) -> usize
+where F: FnMut(&T) -> K, K: Ord {
+    let item_key = key(&item);
+    // insert after any equal keys so repeated inserts keep arrival order
+    let index = items.partition_point(|existing| key(existing) <= item_key);
+    items.insert(index, item);
+    return index;
+}
+
+pub fn insert_sorted<T>(items: &mut Vec<T>, item: T) -> usize
+where T: Ord + Clone {
+    return insert_sorted_by_key(items, item, |existing| existing.clone());
+}
+
+fn merge_by<T, F>(left: Vec<T>, right: Vec<T>, mut take_left: F) -> Vec<T>
+where F: FnMut(&T, &T) -> bool {
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    loop {
+        let from_left = match (left.peek(), right.peek()) {
+            (Some(a), Some(b)) => take_left(a, b),
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => return merged,
+        };
+        let next = if from_left { left.next() } else { right.next() };
+        merged.extend(next);
+    }
+}
+
+pub fn merge_sorted_by_key<T, K, F>(left: Vec<T>, right: Vec<T>, mut key: F) -> Vec<T>
+where F: FnMut(&T) -> K, K: Ord {
+    return merge_by(left, right, |a, b| key(a) <= key(b));
+}
+
+pub fn merge_sorted<T>(left: Vec<T>, right: Vec<T>) -> Vec<T>
+where T: Ord {
+    return merge_by(left, right, |a, b| a <= b);
+}
+
+// the span helpers below expect items sorted by start offset and not overlapping one another,
+// which is what find_every and PatternSet::find_every_in produce
+
+pub fn covering_index<S>(items: &[S], offset: usize) -> Option<usize>
+where S: HasSpan {
+    let after = items.partition_point(|item| item.span().start <= offset);
+    if after == 0 {
+        return None;
+    }
+    if items[after - 1].span().contains(offset) {
+        return Some(after - 1);
+    }
+    return None;
+}
+
+pub fn covering<S>(items: &[S], offset: usize) -> Option<&S>
+where S: HasSpan {
+    return covering_index(items, offset).map(|index| &items[index]);
+}
+
+pub fn first_at_or_after<S>(items: &[S], offset: usize) -> usize
+where S: HasSpan {
+    return items.partition_point(|item| item.span().start < offset);
+}
+
+pub fn overlapping_range<S>(items: &[S], span: Span) -> Range<usize>
+where S: HasSpan {
+    let start = items.partition_point(|item| item.span().end <= span.start);
+    let end = items.partition_point(|item| item.span().start < span.end.max(span.start + 1));
+    return start..end.max(start);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::PatternMatcher;
+
+    #[test]
+    fn test_insert_and_merge() {
+        let mut items = vec![(1, "a"), (3, "b")];
+        assert_eq!(insert_sorted_by_key(&mut items, (3, "c"), |item| item.0), 2);
+        assert_eq!(insert_sorted_by_key(&mut items, (2, "d"), |item| item.0), 1);
+        assert_eq!(items, vec![(1, "a"), (2, "d"), (3, "b"), (3, "c")]);
+        assert_eq!(merge_sorted(vec![1, 4, 9], vec![2, 4, 10]), vec![1, 2, 4, 4, 9, 10]);
+        let merged = merge_sorted_by_key(vec![(1, 'l'), (5, 'l')], vec![(1, 'r'), (2, 'r')], |item| item.0);
+        assert_eq!(merged, vec![(1, 'l'), (1, 'r'), (2, 'r'), (5, 'l')]);
+    }
+
+    #[test]
+    fn test_span_queries_over_matches() {
+        let haystack = "one two one three one";
+        let matches = haystack.find_every(&"one").unwrap();
+        assert_eq!(covering(&matches, 9).map(|found| found.index), Some(8));
+        assert_eq!(covering_index(&matches, 3), None);
+        assert_eq!(covering_index(&matches, 0), Some(0));
+        assert_eq!(first_at_or_after(&matches, 9), 2);
+        let spans = [Span::new(0, 3), Span::new(8, 11), Span::new(18, 21)];
+        assert_eq!(overlapping_range(&spans, Span::new(2, 9)), 0..2);
+        assert_eq!(overlapping_range(&spans, Span::new(12, 15)), 2..2);
+        assert_eq!(overlapping_range(&spans, Span::at(19)), 2..3);
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+struct Node<V> {
+    children: BTreeMap<u8, Node<V>>,
+    wildcard: Option<Box<Node<V>>>,
+    value: Option<V>,
+}
+
+impl<V> Node<V> {
+    fn new() -> Node<V> {
+        return Node { children: BTreeMap::new(), wildcard: None, value: None };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Byte(u8),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieMatch<'t, 'i, V> {
+    pub len: usize,
+    pub value: &'t V,
+    pub captures: Vec<&'i str>,
+}
+
+// a '*' that fills a whole segment between separators matches any one non-empty segment
+#[derive(Debug, Clone)]
+pub struct Trie<V> {
+    root: Node<V>,
+    separator: u8,
+    len: usize,
+}
+
+impl<V> Trie<V> {
+    pub fn new() -> Trie<V> {
+        return Trie::with_separator(b'/');
+    }
+
+    pub fn with_separator(separator: u8) -> Trie<V> {
+        return Trie { root: Node::new(), separator, len: 0 };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    fn steps(&self, key: &str) -> Vec<Step> {
+        let bytes = key.as_bytes();
+        let mut steps = Vec::with_capacity(bytes.len());
+        for (index, byte) in bytes.iter().enumerate() {
+            let segment_start = index == 0 || bytes[index - 1] == self.separator;
+            let segment_end = index + 1 == bytes.len() || bytes[index + 1] == self.separator;
+            if *byte == b'*' && segment_start && segment_end {
+                steps.push(Step::Wildcard);
+            } else {
+                steps.push(Step::Byte(*byte));
+            }
+        }
+        return steps;
+    }
+
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let steps = self.steps(key);
+        let mut node = &mut self.root;
+        for step in steps {
+            node = match step {
+                Step::Byte(byte) => node.children.entry(byte).or_insert_with(Node::new),
+                Step::Wildcard => node.wildcard.get_or_insert_with(|| Box::new(Node::new())),
+            };
+        }
+        let previous = node.value.replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        return previous;
+    }
+
+    fn node(&self, key: &str) -> Option<&Node<V>> {
+        let mut node = &self.root;
+        for step in self.steps(key) {
+            node = match step {
+                Step::Byte(byte) => node.children.get(&byte)?,
+                Step::Wildcard => node.wildcard.as_deref()?,
+            };
+        }
+        return Some(node);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        return self.node(key)?.value.as_ref();
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        return self.get(key).is_some();
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let steps = self.steps(key);
+        let mut node = &mut self.root;
+        for step in steps {
+            node = match step {
+                Step::Byte(byte) => node.children.get_mut(&byte)?,
+                Step::Wildcard => node.wildcard.as_deref_mut()?,
+            };
+        }
+        let removed = node.value.take();
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        return removed;
+    }
+
+    fn segment_end(&self, input: &[u8], pos: usize) -> usize {
+        return input[pos..].iter().position(|byte| *byte == self.separator).map(|index| pos + index).unwrap_or(input.len());
+    }
+
+    // visits every stored key that matches a prefix of input, literal edges before wildcards
+    fn walk<'t, 'i, F>(&'t self, node: &'t Node<V>, input: &'i str, pos: usize, captures: &mut Vec<&'i str>, visit: &mut F)
+    where F: FnMut(usize, &'t V, &[&'i str]) {
+        if let Some(value) = &node.value {
+            visit(pos, value, captures);
+        }
+        let bytes = input.as_bytes();
+        if pos < bytes.len() {
+            if let Some(child) = node.children.get(&bytes[pos]) {
+                self.walk(child, input, pos + 1, captures, visit);
+            }
+        }
+        if let Some(wildcard) = &node.wildcard {
+            let at_segment_start = pos == 0 || bytes[pos - 1] == self.separator;
+            let end = if pos < bytes.len() { self.segment_end(bytes, pos) } else { pos };
+            if at_segment_start && end > pos {
+                captures.push(&input[pos..end]);
+                self.walk(wildcard, input, end, captures, visit);
+                captures.pop();
+            }
+        }
+    }
+
+    pub fn longest_prefix<'t, 'i>(&'t self, input: &'i str) -> Option<TrieMatch<'t, 'i, V>> {
+        let mut best: Option<TrieMatch<'t, 'i, V>> = None;
+        self.walk(&self.root, input, 0, &mut Vec::new(), &mut |len, value, captures| {
+            if best.as_ref().map(|current| len > current.len).unwrap_or(true) {
+                best = Some(TrieMatch { len, value, captures: captures.to_vec() });
+            }
+        });
+        return best;
+    }
+
+    pub fn prefixes_of<'t, 'i>(&'t self, input: &'i str) -> Vec<TrieMatch<'t, 'i, V>> {
+        let mut found = Vec::new();
+        self.walk(&self.root, input, 0, &mut Vec::new(), &mut |len, value, captures| {
+            found.push(TrieMatch { len, value, captures: captures.to_vec() });
+        });
+        found.sort_by_key(|found| found.len);
+        return found;
+    }
+
+    pub fn route<'t, 'i>(&'t self, input: &'i str) -> Option<TrieMatch<'t, 'i, V>> {
+        let mut routed: Option<TrieMatch<'t, 'i, V>> = None;
+        self.walk(&self.root, input, 0, &mut Vec::new(), &mut |len, value, captures| {
+            if len == input.len() && routed.is_none() {
+                routed = Some(TrieMatch { len, value, captures: captures.to_vec() });
+            }
+        });
+        return routed;
+    }
+
+    pub fn iter_prefix(&self, prefix: &str) -> Vec<(String, &V)> {
+        let mut entries = Vec::new();
+        if let Some(node) = self.node(prefix) {
+            let mut key = prefix.as_bytes().to_vec();
+            collect(node, &mut key, &mut entries);
+        }
+        return entries;
+    }
+
+    pub fn iter(&self) -> Vec<(String, &V)> {
+        return self.iter_prefix("");
+    }
+}
+
+fn collect<'t, V>(node: &'t Node<V>, key: &mut Vec<u8>, entries: &mut Vec<(String, &'t V)>) {
+    if let Some(value) = &node.value {
+        entries.push((String::from_utf8_lossy(key).into_owned(), value));
+    }
+    for (byte, child) in &node.children {
+        key.push(*byte);
+        collect(child, key, entries);
+        key.pop();
+    }
+    if let Some(wildcard) = &node.wildcard {
+        key.push(b'*');
+        collect(wildcard, key, entries);
+        key.pop();
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Trie<V> {
+        return Trie::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_and_prefix_queries() {
+        let mut trie = Trie::new();
+        trie.insert("car", 1);
+        trie.insert("cart", 2);
+        trie.insert("care", 3);
+        assert_eq!(trie.insert("car", 4), Some(1));
+        assert_eq!(trie.len(), 3);
+        assert_eq!(trie.get("cart"), Some(&2));
+        assert_eq!(trie.get("ca"), None);
+        let longest = trie.longest_prefix("cartwheel").unwrap();
+        assert_eq!((longest.len, *longest.value), (4, 2));
+        assert_eq!(trie.prefixes_of("cartwheel").iter().map(|found| found.len).collect::<Vec<usize>>(), vec![3, 4]);
+        let keys: Vec<String> = trie.iter_prefix("car").into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["car", "care", "cart"]);
+        assert_eq!(trie.remove("car"), Some(4));
+        assert_eq!(trie.len(), 2);
+        assert!(trie.longest_prefix("cab").is_none());
+    }
+
+    #[test]
+    fn test_wildcard_segments() {
+        let mut routes = Trie::new();
+        routes.insert("/users/*/posts", "user posts");
+        routes.insert("/users/me/posts", "my posts");
+        routes.insert("/files/*", "file");
+        routes.insert("/a*b", "literal star");
+        let routed = routes.route("/users/42/posts").unwrap();
+        assert_eq!((*routed.value, routed.captures), ("user posts", vec!["42"]));
+        assert_eq!(*routes.route("/users/me/posts").unwrap().value, "my posts");
+        assert!(routes.route("/users//posts").is_none());
+        assert!(routes.route("/files/a/b").is_none());
+        assert_eq!(routes.longest_prefix("/files/a/b").unwrap().len, 8);
+        assert_eq!(routes.get("/a*b"), Some(&"literal star"));
+        assert!(routes.route("/axb").is_none());
+        assert_eq!(routes.get("/files/*"), Some(&"file"));
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt;
+use std::fmt::Display;
+
+pub mod exit;
+
+use crate::text::line_index::LineIndex;
+use crate::types::error_chain::ErrorChain;
+use crate::types::parse_error::ParseError;
+use crate::types::span::Span;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Note,
+    Help,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    // the inverse of Display, for severities read back from config or saved reports
+    pub fn from_name(name: &str) -> Option<Severity> {
+        return match name {
+            "note" => Some(Severity::Note),
+            "help" => Some(Severity::Help),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        };
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Severity::Error => return RED,
+            Severity::Warning => return YELLOW,
+            Severity::Note | Severity::Help => return CYAN,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Note => "note",
+            Severity::Help => "help",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderOptions<'n> {
+    pub color: bool,
+    pub source_name: Option<&'n str>,
+}
+
+impl Diagnostic {
+    pub fn new<M>(severity: Severity, message: M) -> Diagnostic
+    where M: Into<String> {
+        return Diagnostic { severity, message: message.into(), labels: Vec::new(), notes: Vec::new(), help: Vec::new() };
+    }
+
+    pub fn error<M>(message: M) -> Diagnostic
+    where M: Into<String> {
+        return Diagnostic::new(Severity::Error, message);
+    }
+
+    pub fn warning<M>(message: M) -> Diagnostic
+    where M: Into<String> {
+        return Diagnostic::new(Severity::Warning, message);
+    }
+
+    pub fn label<S, M>(mut self, span: S, message: M) -> Diagnostic
+    where S: Into<Span>, M: Into<String> {
+        self.labels.push(Label { span: span.into(), message: message.into(), primary: true });
+        return self;
+    }
+
+    pub fn secondary_label<S, M>(mut self, span: S, message: M) -> Diagnostic
+    where S: Into<Span>, M: Into<String> {
+        self.labels.push(Label { span: span.into(), message: message.into(), primary: false });
+        return self;
+    }
+
+    pub fn note<M>(mut self, note: M) -> Diagnostic
+    where M: Into<String> {
+        self.notes.push(note.into());
+        return self;
+    }
+
+    pub fn with_help<M>(mut self, help: M) -> Diagnostic
+    where M: Into<String> {
+        self.help.push(help.into());
+        return self;
+    }
+
+    pub fn render(&self, src: &str, options: RenderOptions<'_>) -> String {
+        let paint = |color: &'static str| if options.color { color } else { "" };
+        let reset = paint(RESET);
+        let index = LineIndex::new(src);
+        let mut out = format!("{}{}{}{}: {}{}\n", paint(self.severity.color()), self.severity, reset, paint(BOLD), self.message, reset);
+
+        let mut lines: Vec<usize> = Vec::new();
+        for label in &self.labels {
+            let (_, _, first, last) = label_bounds(&index, label);
+            for line in [first, last] {
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+        }
+        lines.sort_unstable();
+        let gutter = lines.last().map(|line| (line + 1).to_string().len()).unwrap_or(1);
+        let blank_gutter = " ".repeat(gutter);
+
+        if let Some(primary) = self.labels.iter().find(|label| label.primary).or(self.labels.first()) {
+            let position = index.position(primary.span.start);
+            let name = options.source_name.unwrap_or("<input>");
+            out.push_str(&format!("{}{}-->{} {}:{}\n", blank_gutter, paint(BLUE), reset, name, position));
+        }
+        if !lines.is_empty() {
+            out.push_str(&format!("{} {}|{}\n", blank_gutter, paint(BLUE), reset));
+        }
+        let mut previous: Option<usize> = None;
+        for line in &lines {
+            if let Some(previous) = previous {
+                if *line > previous + 1 {
+                    out.push_str(&format!("{}{}...{}\n", blank_gutter, paint(BLUE), reset));
+                }
+            }
+            previous = Some(*line);
+            let span = index.line_span(*line).unwrap_or_default();
+            let text = &src[span.range()];
+            out.push_str(&format!("{}{:>width$} |{} {}\n", paint(BLUE), line + 1, reset, text.replace('\t', " "), width = gutter));
+            for label in &self.labels {
+                let (start, end, first, last) = label_bounds(&index, label);
+                if *line < first || *line > last {
+                    continue;
+                }
+                // a label on a line terminator ("\r\n" is outside the line span) sits right after the text
+                let from = start.max(span.start).min(span.end);
+                let to = end.min(span.end).max(from);
+                let column = src[span.start..from].chars().count();
+                let width = src[from..to].chars().count().max(1);
+                let (marker, color) = if label.primary { ('^', self.severity.color()) } else { ('-', BLUE) };
+                let message = if *line == last && !label.message.is_empty() { format!(" {}", label.message) } else { String::new() };
+                out.push_str(&format!(
+                    "{} {}|{} {}{}{}{}{}\n",
+                    blank_gutter,
+                    paint(BLUE),
+                    reset,
+                    " ".repeat(column),
+                    paint(color),
+                    marker.to_string().repeat(width),
+                    message,
+                    reset
+                ));
+            }
+        }
+        if !lines.is_empty() && (!self.notes.is_empty() || !self.help.is_empty()) {
+            out.push_str(&format!("{} {}|{}\n", blank_gutter, paint(BLUE), reset));
+        }
+        for note in &self.notes {
+            out.push_str(&format!("{} {}={} {}note{}: {}\n", blank_gutter, paint(BLUE), reset, paint(BOLD), reset, note));
+        }
+        for help in &self.help {
+            out.push_str(&format!("{} {}={} {}help{}: {}\n", blank_gutter, paint(BLUE), reset, paint(BOLD), reset, help));
+        }
+        return out;
+    }
+}
+
+fn label_bounds(index: &LineIndex<'_>, label: &Label) -> (usize, usize, usize, usize) {
+    let len = index.source().len();
+    let start = label.span.start.min(len);
+    let end = label.span.end.min(len).max(start);
+    let first = index.line_of(start);
+    let last = if end > start { index.line_of(end - 1) } else { first };
+    return (start, end, first, last);
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Diagnostic {
+        return Diagnostic::error(error.message.clone()).label(error.span, "");
+    }
+}
+
+// the outermost context is the message, inner links and the root cause become notes
+impl From<&ErrorChain> for Diagnostic {
+    fn from(error: &ErrorChain) -> Diagnostic {
+        let mut diagnostic = Diagnostic::error(error.context_string());
+        for (index, link) in error.links().enumerate() {
+            if index > 0 {
+                diagnostic = diagnostic.note(format!("caused by: {}", link.context_string()));
+            }
+            for help in link.help() {
+                diagnostic = diagnostic.with_help(help.clone());
+            }
+            if let Some(root) = link.cause().filter(|cause| !cause.is::<ErrorChain>()) {
+                diagnostic = diagnostic.note(format!("caused by: {}", root));
+            }
+        }
+        return diagnostic;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_single_label() {
+        let src = "name = \"gmec\"\nretries = three\n";
+        let diagnostic = Diagnostic::error("invalid integer")
+            .label(Span::new(24, 29), "expected a number")
+            .with_help("use a digit like 3");
+        let rendered = diagnostic.render(src, RenderOptions { color: false, source_name: Some("config.toml") });
+        let expected = "error: invalid integer\n \
+            --> config.toml:2:11\n  \
+            |\n\
+            2 | retries = three\n  \
+            |           ^^^^^ expected a number\n  \
+            |\n  \
+            = help: use a digit like 3\n";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn test_render_multiple_lines_and_secondary() {
+        let src = "a\nb\nc\nd\ne\nf\n";
+        let diagnostic = Diagnostic::warning("mismatch")
+            .label(Span::new(10, 11), "used here")
+            .secondary_label(Span::new(0, 1), "defined here");
+        let rendered = diagnostic.render(src, RenderOptions::default());
+        assert!(rendered.contains("1 | a\n  | - defined here\n"));
+        assert!(rendered.contains("...\n6 | f\n  | ^ used here\n"));
+        assert!(rendered.contains("--> <input>:6:1"));
+    }
+
+    #[test]
+    fn test_render_label_on_crlf() {
+        let src = "ab\r\ncd\r\n";
+        let rendered = Diagnostic::error("expected a value").label(Span::at(3), "here").render(src, RenderOptions::default());
+        assert!(rendered.contains("1 | ab\n  |   ^ here\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn test_render_color() {
+        let diagnostic = Diagnostic::from(&ParseError::new(0..1, "bad"));
+        let rendered = diagnostic.render("x", RenderOptions { color: true, source_name: None });
+        assert!(rendered.contains("\x1b[1;31merror"));
+    }
+}
@@ -0,0 +1,56 @@
+use std::io::ErrorKind;
+use std::io::IsTerminal;
+use std::io::Write;
+
+use super::Diagnostic;
+use super::RenderOptions;
+use crate::types::error_chain::ErrorChain;
+
+pub const DEFAULT_EXIT_CODE: i32 = 1;
+
+// a closed downstream pipe (`gmec ... | head`) is a normal way for a CLI to finish
+pub fn is_broken_pipe(error: &ErrorChain) -> bool {
+    return error.links().filter_map(ErrorChain::cause).filter_map(|cause| cause.downcast_ref::<std::io::Error>()).any(|io| io.kind() == ErrorKind::BrokenPipe);
+}
+
+// renders the error to out and returns the process exit code for the result
+pub fn report<W>(result: Result<(), ErrorChain>, out: &mut W, color: bool) -> i32
+where W: Write {
+    let error = match result {
+        Ok(()) => return 0,
+        Err(error) if is_broken_pipe(&error) => return 0,
+        Err(error) => error,
+    };
+    let rendered = Diagnostic::from(&error).render("", RenderOptions { color, source_name: None });
+    let _ = out.write_all(rendered.as_bytes());
+    let _ = out.flush();
+    return error.exit_code().unwrap_or(DEFAULT_EXIT_CODE);
+}
+
+// fn main() { gmec::diag::exit::run_main(real_main) }
+pub fn run_main(f: fn() -> Result<(), ErrorChain>) -> ! {
+    let stderr = std::io::stderr();
+    let color = stderr.is_terminal();
+    let code = report(f(), &mut stderr.lock(), color);
+    std::process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_exit_codes() {
+        let mut out = Vec::new();
+        assert_eq!(report(Ok(()), &mut out, false), 0);
+        let pipe = ErrorChain::from(ErrorChain::from(std::io::Error::from(ErrorKind::BrokenPipe), "writing match"), "printing results");
+        assert_eq!(report(Err(pipe), &mut out, false), 0);
+        assert!(out.is_empty());
+        let denied = ErrorChain::from(std::io::Error::other("permission denied"), "opening a.txt").with_help("check the file mode");
+        let error = ErrorChain::from(denied, "search failed").with_exit_code(2);
+        assert_eq!(report(Err(error), &mut out, false), 2);
+        let rendered = String::from_utf8(out).unwrap();
+        assert_eq!(rendered, "error: search failed\n  = note: caused by: opening a.txt\n  = note: caused by: permission denied\n  = help: check the file mode\n");
+        assert_eq!(report(Err(ErrorChain::new("bad input")), &mut Vec::new(), false), DEFAULT_EXIT_CODE);
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+// events emitted by gmec's own components; other event types can use EventBus<E> directly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    FileStarted { file_index: usize, path: String },
+    MatchFound { file_index: usize, path: String, index: usize, length: usize, pattern_id: usize },
+    FileFinished { file_index: usize, path: String, matches: u64 },
+    CheckpointTaken { file_index: usize, offset: u64 },
+    ErrorAttached { file_index: usize, path: String, message: String },
+}
+
+pub trait Listener<E>: Send + Sync {
+    fn on_event(&self, event: &E);
+}
+
+impl<E, F> Listener<E> for F
+where F: Fn(&E) + Send + Sync {
+    fn on_event(&self, event: &E) {
+        self(event);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerId(usize);
+
+type Listeners<E> = Vec<(ListenerId, Arc<dyn Listener<E>>)>;
+
+// listeners run synchronously on the emitting thread, in subscription order
+pub struct EventBus<E = Event> {
+    listeners: RwLock<Listeners<E>>,
+    next_id: AtomicUsize,
+}
+
+impl<E> EventBus<E> {
+    pub fn new() -> EventBus<E> {
+        return EventBus { listeners: RwLock::new(Vec::new()), next_id: AtomicUsize::new(0) };
+    }
+
+    pub fn subscribe<L>(&self, listener: L) -> ListenerId
+    where L: Listener<E> + 'static {
+        let id = ListenerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.listeners.write().expect("event bus lock poisoned").push((id, Arc::new(listener)));
+        return id;
+    }
+
+    pub fn unsubscribe(&self, id: ListenerId) -> bool {
+        let mut listeners = self.listeners.write().expect("event bus lock poisoned");
+        let before = listeners.len();
+        listeners.retain(|(existing, _)| *existing != id);
+        return listeners.len() != before;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.listeners.read().expect("event bus lock poisoned").len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    // the listener list is copied first so listeners may subscribe or unsubscribe while handling an event
+    pub fn emit(&self, event: &E) {
+        let listeners: Vec<Arc<dyn Listener<E>>> = self.listeners.read().expect("event bus lock poisoned").iter().map(|(_, listener)| listener.clone()).collect();
+        for listener in listeners {
+            listener.on_event(event);
+        }
+    }
+
+    // skips building the event when nobody is listening
+    pub fn emit_with<F>(&self, make_event: F)
+    where F: FnOnce() -> E {
+        if !self.is_empty() {
+            self.emit(&make_event());
+        }
+    }
+}
+
+impl<E> Default for EventBus<E> {
+    fn default() -> EventBus<E> {
+        return EventBus::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_subscribe_emit_unsubscribe() {
+        let bus: EventBus<u32> = EventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let first = bus.subscribe(move |event: &u32| sink.lock().unwrap().push(*event));
+        let sink = seen.clone();
+        bus.subscribe(move |event: &u32| sink.lock().unwrap().push(event * 10));
+        bus.emit(&1);
+        assert!(bus.unsubscribe(first));
+        assert!(!bus.unsubscribe(first));
+        bus.emit(&2);
+        bus.emit_with(|| 3);
+        assert_eq!(*seen.lock().unwrap(), vec![1, 10, 20, 30]);
+    }
+}
@@ -0,0 +1,133 @@
+#![allow(unsafe_code)]
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::c_char;
+use std::ffi::c_int;
+use std::ffi::CString;
+use std::ptr;
+
+use crate::patterns::PatternMatcher;
+use crate::types::error_chain::ErrorChain;
+
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// C API for the byte matcher; the matching declarations are in include/gmec.h.
+// Every pointer argument is either null (only allowed with a length of 0) or valid for its length,
+// and everything returned through an out pointer must be released with the matching gmec_*_free.
+
+pub const GMEC_OK: c_int = 0;
+pub const GMEC_ERROR: c_int = -1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GmecMatch {
+    pub index: usize,
+    pub length: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GmecMatches {
+    pub matches: *mut GmecMatch,
+    pub len: usize,
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize, name: &str) -> Result<&'a [u8], ErrorChain> {
+    if data.is_null() {
+        if len == 0 {
+            return Ok(&[]);
+        }
+        return Err(ErrorChain::new(format!("{} is null but its length is {}", name, len)));
+    }
+    return Ok(std::slice::from_raw_parts(data, len));
+}
+
+fn find_every(haystack: &[u8], pattern: &[u8]) -> Result<Vec<GmecMatch>, ErrorChain> {
+    if pattern.is_empty() {
+        return Err(ErrorChain::new("pattern is empty"));
+    }
+    let found = haystack.find_every(&pattern).unwrap_or_default();
+    return Ok(found.into_iter().map(|found| GmecMatch { index: found.index, length: found.length }).collect());
+}
+
+unsafe fn set_error(out_error: *mut *mut c_char, error: &ErrorChain) {
+    if out_error.is_null() {
+        return;
+    }
+    let rendered = error.to_string().replace('\0', "\\0");
+    *out_error = CString::new(rendered).map(CString::into_raw).unwrap_or(ptr::null_mut());
+}
+
+// finds every non-overlapping occurrence of pattern, leftmost first; on failure returns GMEC_ERROR
+// and, if out_error is not null, a rendered error chain to free with gmec_string_free
+#[no_mangle]
+pub unsafe extern "C" fn gmec_find_every(
+    haystack: *const u8,
+    haystack_len: usize,
+    pattern: *const u8,
+    pattern_len: usize,
+    out: *mut GmecMatches,
+    out_error: *mut *mut c_char,
+) -> c_int {
+    let result = (|| {
+        if out.is_null() {
+            return Err(ErrorChain::new("out is null"));
+        }
+        let haystack = bytes(haystack, haystack_len, "haystack")?;
+        let pattern = bytes(pattern, pattern_len, "pattern")?;
+        return find_every(haystack, pattern);
+    })();
+    match result {
+        Ok(found) => {
+            let found = found.into_boxed_slice();
+            let len = found.len();
+            *out = GmecMatches { matches: Box::into_raw(found) as *mut GmecMatch, len };
+            return GMEC_OK;
+        }
+        Err(error) => {
+            set_error(out_error, &ErrorChain::from(error, "gmec_find_every failed"));
+            return GMEC_ERROR;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_matches_free(matches: GmecMatches) {
+    if !matches.matches.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(matches.matches, matches.len)));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_find_and_errors() {
+        let haystack = b"abcabcab";
+        let mut out = GmecMatches { matches: ptr::null_mut(), len: 0 };
+        let mut error: *mut c_char = ptr::null_mut();
+        unsafe {
+            assert_eq!(gmec_find_every(haystack.as_ptr(), haystack.len(), b"ab".as_ptr(), 2, &mut out, &mut error), GMEC_OK);
+            let found = std::slice::from_raw_parts(out.matches, out.len).to_vec();
+            assert_eq!(found, vec![GmecMatch { index: 0, length: 2 }, GmecMatch { index: 3, length: 2 }, GmecMatch { index: 6, length: 2 }]);
+            gmec_matches_free(out);
+
+            assert_eq!(gmec_find_every(haystack.as_ptr(), haystack.len(), ptr::null(), 0, &mut out, &mut error), GMEC_ERROR);
+            assert_eq!(CStr::from_ptr(error).to_str().unwrap(), "gmec_find_every failed\n\\ \\ \\\npattern is empty");
+            gmec_string_free(error);
+            assert_eq!(gmec_find_every(ptr::null(), 4, b"a".as_ptr(), 1, &mut out, ptr::null_mut()), GMEC_ERROR);
+        }
+    }
+}
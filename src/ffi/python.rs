@@ -0,0 +1,187 @@
+#![allow(unsafe_code)]
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::c_char;
+use std::ffi::c_int;
+use std::ptr;
+
+use super::bytes;
+use super::set_error;
+use super::GMEC_ERROR;
+use super::GMEC_OK;
+use crate::patterns::PatternSet;
+use crate::text::diff::diff_lines;
+use crate::text::diff::LineOp;
+use crate::types::error_chain::ErrorChain;
+
+// the pattern set and line diff for bindings/python/gmec.py, which loads the library through
+// ctypes; together with gmec_find_every they give scripts the same matcher the services run.
+// Offsets are in bytes, the module maps them back onto str indices.
+
+pub const GMEC_LINE_EQUAL: c_int = 0;
+pub const GMEC_LINE_DELETE: c_int = 1;
+pub const GMEC_LINE_INSERT: c_int = 2;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GmecSetMatch {
+    pub pattern_id: usize,
+    pub index: usize,
+    pub length: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GmecSetMatches {
+    pub matches: *mut GmecSetMatch,
+    pub len: usize,
+}
+
+// a line without its terminator; equal and deleted lines index into old, inserted ones into new
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GmecLineOp {
+    pub op: c_int,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GmecLineOps {
+    pub ops: *mut GmecLineOp,
+    pub len: usize,
+}
+
+fn set_find_every(haystack: &[u8], patterns: &[u8]) -> Result<Vec<GmecSetMatch>, ErrorChain> {
+    let mut set = PatternSet::new();
+    for (pattern_id, pattern) in patterns.split(|byte| *byte == 0).enumerate() {
+        if pattern.is_empty() {
+            return Err(ErrorChain::new(format!("pattern {} is empty", pattern_id)));
+        }
+        set.push(pattern);
+    }
+    let found = set.find_every_in(haystack, 0);
+    return Ok(found.into_iter().map(|found| GmecSetMatch { pattern_id: found.pattern_id, index: found.index, length: found.length }).collect());
+}
+
+fn line_ops(old: &[u8], new: &[u8]) -> Result<Vec<GmecLineOp>, ErrorChain> {
+    let old = std::str::from_utf8(old).map_err(|error| ErrorChain::new(format!("old is not valid UTF-8: {}", error)))?;
+    let new = std::str::from_utf8(new).map_err(|error| ErrorChain::new(format!("new is not valid UTF-8: {}", error)))?;
+    let span = |op: c_int, text: &str, line: &str| {
+        let start = line.as_ptr() as usize - text.as_ptr() as usize;
+        return GmecLineOp { op, start, end: start + line.len() };
+    };
+    return Ok(diff_lines(old, new).into_iter().map(|op| match op {
+        LineOp::Equal(line) => span(GMEC_LINE_EQUAL, old, line),
+        LineOp::Delete(line) => span(GMEC_LINE_DELETE, old, line),
+        LineOp::Insert(line) => span(GMEC_LINE_INSERT, new, line),
+    }).collect());
+}
+
+fn into_raw<T>(values: Vec<T>) -> (*mut T, usize) {
+    let values = values.into_boxed_slice();
+    let len = values.len();
+    return (Box::into_raw(values) as *mut T, len);
+}
+
+// the leftmost match of any pattern, then the next from its end, with the lowest pattern id
+// winning ties; patterns are separated by NUL bytes and numbered from 0
+#[no_mangle]
+pub unsafe extern "C" fn gmec_set_find_every(
+    haystack: *const u8,
+    haystack_len: usize,
+    patterns: *const u8,
+    patterns_len: usize,
+    out: *mut GmecSetMatches,
+    out_error: *mut *mut c_char,
+) -> c_int {
+    let result = (|| {
+        if out.is_null() {
+            return Err(ErrorChain::new("out is null"));
+        }
+        let (matches, len) = into_raw(set_find_every(bytes(haystack, haystack_len, "haystack")?, bytes(patterns, patterns_len, "patterns")?)?);
+        *out = GmecSetMatches { matches, len };
+        return Ok(());
+    })();
+    match result {
+        Ok(()) => return GMEC_OK,
+        Err(error) => {
+            set_error(out_error, &ErrorChain::from(error, "gmec_set_find_every failed"));
+            return GMEC_ERROR;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_diff_lines(
+    old: *const u8,
+    old_len: usize,
+    new: *const u8,
+    new_len: usize,
+    out: *mut GmecLineOps,
+    out_error: *mut *mut c_char,
+) -> c_int {
+    let result = (|| {
+        if out.is_null() {
+            return Err(ErrorChain::new("out is null"));
+        }
+        let (ops, len) = into_raw(line_ops(bytes(old, old_len, "old")?, bytes(new, new_len, "new")?)?);
+        *out = GmecLineOps { ops, len };
+        return Ok(());
+    })();
+    match result {
+        Ok(()) => return GMEC_OK,
+        Err(error) => {
+            set_error(out_error, &ErrorChain::from(error, "gmec_diff_lines failed"));
+            return GMEC_ERROR;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_set_matches_free(matches: GmecSetMatches) {
+    if !matches.matches.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(matches.matches, matches.len)));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_line_ops_free(ops: GmecLineOps) {
+    if !ops.ops.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ops.ops, ops.len)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_set_and_diff() {
+        let mut out = GmecSetMatches { matches: ptr::null_mut(), len: 0 };
+        let mut error: *mut c_char = ptr::null_mut();
+        unsafe {
+            let haystack = b"warn: error";
+            assert_eq!(gmec_set_find_every(haystack.as_ptr(), haystack.len(), b"error\0warn".as_ptr(), 10, &mut out, &mut error), GMEC_OK);
+            let found = std::slice::from_raw_parts(out.matches, out.len).to_vec();
+            assert_eq!(found, vec![GmecSetMatch { pattern_id: 1, index: 0, length: 4 }, GmecSetMatch { pattern_id: 0, index: 6, length: 5 }]);
+            gmec_set_matches_free(out);
+            assert_eq!(gmec_set_find_every(haystack.as_ptr(), haystack.len(), b"\0a".as_ptr(), 2, &mut out, &mut error), GMEC_ERROR);
+            assert!(CStr::from_ptr(error).to_str().unwrap().contains("pattern 0 is empty"));
+            super::super::gmec_string_free(error);
+
+            let mut ops = GmecLineOps { ops: ptr::null_mut(), len: 0 };
+            assert_eq!(gmec_diff_lines(b"a\nb".as_ptr(), 3, b"a\nc".as_ptr(), 3, &mut ops, &mut error), GMEC_OK);
+            let found = std::slice::from_raw_parts(ops.ops, ops.len).to_vec();
+            let expected = vec![
+                GmecLineOp { op: GMEC_LINE_EQUAL, start: 0, end: 1 },
+                GmecLineOp { op: GMEC_LINE_DELETE, start: 2, end: 3 },
+                GmecLineOp { op: GMEC_LINE_INSERT, start: 2, end: 3 },
+            ];
+            assert_eq!(found, expected);
+            gmec_line_ops_free(ops);
+        }
+    }
+}
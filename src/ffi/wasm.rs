@@ -0,0 +1,302 @@
+#![allow(unsafe_code)]
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::c_char;
+use std::ffi::c_int;
+use std::ptr;
+
+use super::bytes;
+use super::set_error;
+use super::GMEC_ERROR;
+use super::GMEC_OK;
+use crate::patterns::PatternMatcher;
+use crate::patterns::PatternSet;
+use crate::text::diff::diff_lines;
+use crate::text::diff::LineOp;
+use crate::types::error_chain::ErrorChain;
+
+// exports for a wasm32 build, used through bindings/wasm/gmec.js. JS copies its strings into
+// buffers from gmec_alloc as UTF-8, and every span handed back is in UTF-16 code units so it can
+// slice the original JS string directly. Results arrive through out pointers, as in the C API,
+// and are released with gmec_free or gmec_spans_free; nothing is passed by value but scalars,
+// which keeps the exports callable without a wasm-bindgen style shim.
+
+pub const GMEC_DIFF_EQUAL: u32 = 0;
+pub const GMEC_DIFF_DELETE: u32 = 1;
+pub const GMEC_DIFF_INSERT: u32 = 2;
+
+// a flat array of u32s, viewed from JS as a Uint32Array over the module memory
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GmecSpans {
+    pub data: *mut u32,
+    pub len: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GmecBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+unsafe fn text<'a>(data: *const u8, len: usize, name: &str) -> Result<&'a str, ErrorChain> {
+    let bytes = bytes(data, len, name)?;
+    if u32::try_from(bytes.len()).is_err() {
+        return Err(ErrorChain::new(format!("{} is {} bytes, over the 4 GiB a span can address", name, bytes.len())));
+    }
+    return std::str::from_utf8(bytes).map_err(|error| ErrorChain::new(format!("{} is not valid UTF-8: {}", name, error)));
+}
+
+// byte offsets to UTF-16 offsets in one pass over the text; offsets are char boundaries of text
+fn utf16_offsets(text: &str, offsets: &mut [u32]) {
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_unstable_by_key(|index| offsets[*index]);
+    let mut chars = text.chars();
+    let (mut byte, mut units) = (0, 0);
+    for index in order {
+        while byte < offsets[index] {
+            let Some(character) = chars.next() else { break };
+            byte += character.len_utf8() as u32;
+            units += character.len_utf16() as u32;
+        }
+        offsets[index] = units;
+    }
+}
+
+fn utf16_spans(text: &str, spans: impl Iterator<Item = (usize, usize)>) -> Vec<u32> {
+    let mut offsets: Vec<u32> = spans.flat_map(|(start, end)| [start as u32, end as u32]).collect();
+    utf16_offsets(text, &mut offsets);
+    return offsets;
+}
+
+fn pattern_set(patterns: &str) -> Result<PatternSet<&str>, ErrorChain> {
+    let mut set = PatternSet::new();
+    for (pattern_id, pattern) in patterns.split('\0').enumerate() {
+        if pattern.is_empty() {
+            return Err(ErrorChain::new(format!("pattern {} is empty", pattern_id)));
+        }
+        set.push(pattern);
+    }
+    return Ok(set);
+}
+
+fn find(haystack: &str, pattern: &str) -> Result<Vec<u32>, ErrorChain> {
+    if pattern.is_empty() {
+        return Err(ErrorChain::new("pattern is empty"));
+    }
+    let found = haystack.find_every(&pattern).unwrap_or_default();
+    return Ok(utf16_spans(haystack, found.iter().map(|found| (found.start(), found.end()))));
+}
+
+fn replace(haystack: &str, pattern: &str, replacement: &str) -> Result<Vec<u8>, ErrorChain> {
+    if pattern.is_empty() {
+        return Err(ErrorChain::new("pattern is empty"));
+    }
+    let mut out = String::with_capacity(haystack.len());
+    let mut copied = 0;
+    for found in haystack.find_every(&pattern).unwrap_or_default() {
+        out.push_str(&haystack[copied..found.start()]);
+        out.push_str(replacement);
+        copied = found.end();
+    }
+    out.push_str(&haystack[copied..]);
+    return Ok(out.into_bytes());
+}
+
+// triples of (pattern id, start, end), leftmost first with the lowest id winning ties
+fn highlight(haystack: &str, patterns: &str) -> Result<Vec<u32>, ErrorChain> {
+    let found = pattern_set(patterns)?.find_every_in(haystack, 0);
+    let spans = utf16_spans(haystack, found.iter().map(|found| (found.start(), found.end())));
+    return Ok(found.iter().zip(spans.chunks(2)).flat_map(|(found, span)| [found.pattern_id as u32, span[0], span[1]]).collect());
+}
+
+// triples of (op, start, end) per line; equal and deleted lines are spans of old, inserted ones of new
+fn diff(old: &str, new: &str) -> Vec<u32> {
+    let ops = diff_lines(old, new);
+    let offset = |text: &str, line: &str| line.as_ptr() as usize - text.as_ptr() as usize;
+    let old_spans = utf16_spans(old, ops.iter().filter_map(|op| match op {
+        LineOp::Equal(line) | LineOp::Delete(line) => Some((offset(old, line), offset(old, line) + line.len())),
+        LineOp::Insert(_) => None,
+    }));
+    let new_spans = utf16_spans(new, ops.iter().filter_map(|op| match op {
+        LineOp::Insert(line) => Some((offset(new, line), offset(new, line) + line.len())),
+        _ => None,
+    }));
+    let (mut old_spans, mut new_spans) = (old_spans.chunks(2), new_spans.chunks(2));
+    let mut out = Vec::with_capacity(ops.len() * 3);
+    for op in &ops {
+        let (kind, span) = match op {
+            LineOp::Equal(_) => (GMEC_DIFF_EQUAL, old_spans.next()),
+            LineOp::Delete(_) => (GMEC_DIFF_DELETE, old_spans.next()),
+            LineOp::Insert(_) => (GMEC_DIFF_INSERT, new_spans.next()),
+        };
+        let span = span.unwrap_or(&[0, 0]);
+        out.extend([kind, span[0], span[1]]);
+    }
+    return out;
+}
+
+fn into_raw<T>(values: Vec<T>) -> (*mut T, usize) {
+    let values = values.into_boxed_slice();
+    let len = values.len();
+    return (Box::into_raw(values) as *mut T, len);
+}
+
+unsafe fn status(result: Result<(), ErrorChain>, out_error: *mut *mut c_char, name: &str) -> c_int {
+    match result {
+        Ok(()) => return GMEC_OK,
+        Err(error) => {
+            set_error(out_error, &ErrorChain::from(error, format!("{} failed", name)));
+            return GMEC_ERROR;
+        }
+    }
+}
+
+// zeroed memory for JS to copy its input into, or to receive results and errors through, so it
+// is aligned for any of them; release it with gmec_free and the same len
+#[no_mangle]
+pub unsafe extern "C" fn gmec_alloc(len: usize) -> *mut u8 {
+    return Box::into_raw(vec![0u64; len.div_ceil(8)].into_boxed_slice()) as *mut u8;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data as *mut u64, len.div_ceil(8))));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_spans_free(data: *mut u32, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+// (start, end) pairs of every non-overlapping occurrence of pattern, leftmost first
+#[no_mangle]
+pub unsafe extern "C" fn gmec_wasm_find(
+    haystack: *const u8,
+    haystack_len: usize,
+    pattern: *const u8,
+    pattern_len: usize,
+    out: *mut GmecSpans,
+    out_error: *mut *mut c_char,
+) -> c_int {
+    let result = (|| {
+        if out.is_null() {
+            return Err(ErrorChain::new("out is null"));
+        }
+        let (data, len) = into_raw(find(text(haystack, haystack_len, "haystack")?, text(pattern, pattern_len, "pattern")?)?);
+        *out = GmecSpans { data, len };
+        return Ok(());
+    })();
+    return status(result, out_error, "gmec_wasm_find");
+}
+
+// the haystack with every occurrence of pattern replaced, as UTF-8 in a buffer from gmec_alloc
+#[no_mangle]
+pub unsafe extern "C" fn gmec_wasm_replace(
+    haystack: *const u8,
+    haystack_len: usize,
+    pattern: *const u8,
+    pattern_len: usize,
+    replacement: *const u8,
+    replacement_len: usize,
+    out: *mut GmecBuffer,
+    out_error: *mut *mut c_char,
+) -> c_int {
+    let result = (|| {
+        if out.is_null() {
+            return Err(ErrorChain::new("out is null"));
+        }
+        let replaced = replace(text(haystack, haystack_len, "haystack")?, text(pattern, pattern_len, "pattern")?, text(replacement, replacement_len, "replacement")?)?;
+        let data = gmec_alloc(replaced.len());
+        ptr::copy_nonoverlapping(replaced.as_ptr(), data, replaced.len());
+        *out = GmecBuffer { data, len: replaced.len() };
+        return Ok(());
+    })();
+    return status(result, out_error, "gmec_wasm_replace");
+}
+
+// patterns are separated by NUL bytes and numbered from 0 in order
+#[no_mangle]
+pub unsafe extern "C" fn gmec_wasm_highlight(
+    haystack: *const u8,
+    haystack_len: usize,
+    patterns: *const u8,
+    patterns_len: usize,
+    out: *mut GmecSpans,
+    out_error: *mut *mut c_char,
+) -> c_int {
+    let result = (|| {
+        if out.is_null() {
+            return Err(ErrorChain::new("out is null"));
+        }
+        let (data, len) = into_raw(highlight(text(haystack, haystack_len, "haystack")?, text(patterns, patterns_len, "patterns")?)?);
+        *out = GmecSpans { data, len };
+        return Ok(());
+    })();
+    return status(result, out_error, "gmec_wasm_highlight");
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gmec_wasm_diff(
+    old: *const u8,
+    old_len: usize,
+    new: *const u8,
+    new_len: usize,
+    out: *mut GmecSpans,
+    out_error: *mut *mut c_char,
+) -> c_int {
+    let result = (|| {
+        if out.is_null() {
+            return Err(ErrorChain::new("out is null"));
+        }
+        let (data, len) = into_raw(diff(text(old, old_len, "old")?, text(new, new_len, "new")?));
+        *out = GmecSpans { data, len };
+        return Ok(());
+    })();
+    return status(result, out_error, "gmec_wasm_diff");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_utf16_spans() {
+        // "é" is two UTF-8 bytes but one UTF-16 unit, "😀" four bytes and two units
+        assert_eq!(find("é key 😀 key", "key").unwrap(), vec![2, 5, 9, 12]);
+        assert_eq!(highlight("é warn 😀 error", "error\0warn").unwrap(), vec![1, 2, 6, 0, 10, 15]);
+        assert_eq!(replace("é a é", "é", "e").unwrap(), b"e a e".to_vec());
+        assert_eq!(diff("a\né\nc", "a\nb\nc"), vec![GMEC_DIFF_EQUAL, 0, 1, GMEC_DIFF_DELETE, 2, 3, GMEC_DIFF_INSERT, 2, 3, GMEC_DIFF_EQUAL, 4, 5]);
+        assert!(highlight("abc", "a\0\0b").unwrap_err().to_string().contains("pattern 1 is empty"));
+    }
+
+    #[test]
+    fn test_exports() {
+        let haystack = "aé aé";
+        let mut out = GmecSpans { data: ptr::null_mut(), len: 0 };
+        let mut error: *mut c_char = ptr::null_mut();
+        unsafe {
+            let input = gmec_alloc(haystack.len());
+            ptr::copy_nonoverlapping(haystack.as_ptr(), input, haystack.len());
+            assert_eq!(gmec_wasm_find(input, haystack.len(), "é".as_ptr(), 2, &mut out, &mut error), GMEC_OK);
+            assert_eq!(std::slice::from_raw_parts(out.data, out.len), &[1, 2, 4, 5]);
+            gmec_spans_free(out.data, out.len);
+
+            let mut replaced = GmecBuffer { data: ptr::null_mut(), len: 0 };
+            assert_eq!(gmec_wasm_replace(input, haystack.len(), "é".as_ptr(), 2, "e".as_ptr(), 1, &mut replaced, &mut error), GMEC_OK);
+            assert_eq!(std::slice::from_raw_parts(replaced.data, replaced.len), b"ae ae");
+            gmec_free(replaced.data, replaced.len);
+
+            assert_eq!(gmec_wasm_find(input, 2, b"a".as_ptr(), 1, &mut out, &mut error), GMEC_ERROR);
+            assert!(CStr::from_ptr(error).to_str().unwrap().contains("haystack is not valid UTF-8"));
+            super::super::gmec_string_free(error);
+            gmec_free(input, haystack.len());
+        }
+    }
+}
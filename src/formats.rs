@@ -0,0 +1,4 @@
+pub mod logfmt;
+#[cfg(feature = "persist")]
+pub mod persist;
+pub mod toml_lite;
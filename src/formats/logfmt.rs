@@ -0,0 +1,237 @@
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::parse_error::ParseError;
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub key: String,
+    pub key_span: Span,
+    pub value: Option<String>,
+    pub value_span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Record {
+    pub fields: Vec<Field>,
+}
+
+impl Record {
+    pub fn new() -> Record {
+        return Record { fields: Vec::new() };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.fields.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.fields.is_empty();
+    }
+
+    pub fn field(&self, key: &str) -> Option<&Field> {
+        return self.fields.iter().find(|field| field.key == key);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        return self.field(key).and_then(|field| field.value.as_deref());
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        return self.field(key).is_some();
+    }
+
+    pub fn require(&self, key: &str) -> Result<&str, ErrorChain> {
+        return self.get(key).do_on_error(|| format!("missing required logfmt key '{}'", key));
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        return self.fields.iter().map(|field| field.key.as_str());
+    }
+
+    pub fn insert<K, V>(&mut self, key: K, value: V)
+    where K: Into<String>, V: Into<String> {
+        let key = key.into();
+        let value = Some(value.into());
+        if let Some(existing) = self.fields.iter_mut().find(|field| field.key == key) {
+            existing.value = value;
+            return;
+        }
+        self.fields.push(Field { key, key_span: Span::default(), value, value_span: Span::default() });
+    }
+
+    pub fn with<K, V>(mut self, key: K, value: V) -> Record
+    where K: Into<String>, V: Into<String> {
+        self.insert(key, value);
+        return self;
+    }
+}
+
+pub fn decode(line: &str) -> Result<Record, ErrorChain> {
+    return decode_at(line, 0).on_error("failed to decode logfmt");
+}
+
+pub fn decode_lines(src: &str) -> Result<Vec<Record>, ErrorChain> {
+    let mut records = Vec::new();
+    let mut line_start = 0;
+    for (number, line) in src.split('\n').enumerate() {
+        let text = line.strip_suffix('\r').unwrap_or(line);
+        if !text.trim().is_empty() {
+            records.push(decode_at(text, line_start).do_on_error(|| format!("failed to decode logfmt on line {}", number + 1))?);
+        }
+        line_start += line.len() + 1;
+    }
+    return Ok(records);
+}
+
+fn decode_at(line: &str, base: usize) -> Result<Record, ParseError> {
+    let bytes = line.as_bytes();
+    let span = |start: usize, end: usize| Span::new(base + start, base + end);
+    let mut record = Record::new();
+    let mut pos = 0;
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos >= bytes.len() {
+            return Ok(record);
+        }
+        let key_start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'=' && bytes[pos] != b'"' {
+            pos += 1;
+        }
+        if pos == key_start {
+            return Err(ParseError::new(span(pos, pos + 1), format!("expected a key, found '{}'", bytes[pos] as char)));
+        }
+        let key = line[key_start..pos].to_string();
+        let key_span = span(key_start, pos);
+        if pos >= bytes.len() || bytes[pos] != b'=' {
+            if pos < bytes.len() && bytes[pos] == b'"' {
+                return Err(ParseError::at(base + pos, "unexpected quote inside a key"));
+            }
+            record.fields.push(Field { key, key_span, value: None, value_span: span(pos, pos) });
+            continue;
+        }
+        pos += 1;
+        let value_start = pos;
+        let value = if pos < bytes.len() && bytes[pos] == b'"' {
+            let (value, end) = unquote(line, pos, base)?;
+            pos = end;
+            if pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                return Err(ParseError::at(base + pos, "expected whitespace after a quoted value"));
+            }
+            value
+        } else {
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                if bytes[pos] == b'"' {
+                    return Err(ParseError::at(base + pos, "unexpected quote inside an unquoted value"));
+                }
+                pos += 1;
+            }
+            line[value_start..pos].to_string()
+        };
+        record.fields.push(Field { key, key_span, value: Some(value), value_span: span(value_start, pos) });
+    }
+}
+
+fn unquote(line: &str, open: usize, base: usize) -> Result<(String, usize), ParseError> {
+    let mut value = String::new();
+    let mut chars = line[open + 1..].char_indices();
+    while let Some((index, character)) = chars.next() {
+        let at = open + 1 + index;
+        match character {
+            '"' => return Ok((value, at + 1)),
+            '\\' => match chars.next() {
+                Some((_, '"')) => value.push('"'),
+                Some((_, '\\')) => value.push('\\'),
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, other)) => {
+                    return Err(ParseError::new(Span::new(base + at, base + at + 1 + other.len_utf8()), format!("unknown escape '\\{}'", other)));
+                }
+                None => break,
+            },
+            _ => value.push(character),
+        }
+    }
+    return Err(ParseError::new(Span::new(base + open, base + line.len()), "unterminated quoted value"));
+}
+
+fn needs_quotes(value: &str) -> bool {
+    return value.is_empty() || value.chars().any(|character| character <= ' ' || character == '=' || character == '"' || character == '\\');
+}
+
+pub fn encode_value(value: &str) -> String {
+    if !needs_quotes(value) {
+        return value.to_string();
+    }
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(character),
+        }
+    }
+    out.push('"');
+    return out;
+}
+
+pub fn encode(record: &Record) -> Result<String, ErrorChain> {
+    let mut out = String::new();
+    for field in &record.fields {
+        if field.key.is_empty() || needs_quotes(&field.key) {
+            return Err(ErrorChain::new(format!("logfmt key {:?} cannot be encoded", field.key))
+                .with_help("keys must be non-empty and contain no whitespace, '=', '\"' or '\\'"));
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(&field.key);
+        if let Some(value) = &field.value {
+            out.push('=');
+            out.push_str(&encode_value(value));
+        }
+    }
+    return Ok(out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_with_spans() {
+        let record = decode(r#"level=info msg="request done \"ok\"" dur=12ms cached"#).unwrap();
+        assert_eq!(record.keys().collect::<Vec<&str>>(), vec!["level", "msg", "dur", "cached"]);
+        assert_eq!(record.get("msg"), Some("request done \"ok\""));
+        assert_eq!(record.field("level").unwrap().value_span, Span::new(6, 10));
+        assert_eq!(record.field("msg").unwrap().value_span, Span::new(15, 36));
+        assert!(record.has("cached"));
+        assert_eq!(record.get("cached"), None);
+        assert!(record.require("user").is_err());
+        assert_eq!(decode("").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_round_trip_and_errors() {
+        let record = Record::new().with("path", "/var/log/a b.log").with("n", "3").with("empty", "");
+        let line = encode(&record).unwrap();
+        assert_eq!(line, r#"path="/var/log/a b.log" n=3 empty="""#);
+        assert_eq!(decode(&line).unwrap().get("path"), Some("/var/log/a b.log"));
+
+        let error = decode(r#"a=1 msg="open"#).unwrap_err().to_string();
+        assert!(error.contains("unterminated quoted value at bytes 8..13"));
+        assert!(decode(r#"a="x"y"#).is_err());
+        assert!(encode(&Record::new().with("bad key", "v")).is_err());
+
+        let records = decode_lines("a=1\n\nb=2 c\n").unwrap();
+        assert_eq!(records[1].field("c").unwrap().key_span, Span::new(9, 10));
+        assert!(decode_lines("a=1\nb=\"\n").unwrap_err().to_string().contains("line 2"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::str::FromStr;
+
+use super::logfmt;
+use super::logfmt::Record;
+use crate::diag::Severity;
+use crate::patterns::PatternMatch;
+use crate::patterns::SetMatch;
+use crate::rules::PatternKind;
+use crate::rules::Rule;
+use crate::search::report::Report;
+use crate::search::report::ReportEntry;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::span::Span;
+
+// values saved as one logfmt record per line, so match results can be written to disk or sent
+// between processes and read back without hand written formatting at every call site
+pub trait ToRecord {
+    fn to_record(&self) -> Record;
+}
+
+pub trait FromRecord: Sized {
+    fn from_record(record: &Record) -> Result<Self, ErrorChain>;
+}
+
+fn number<T>(record: &Record, key: &str) -> Result<T, ErrorChain>
+where T: FromStr {
+    let value = record.require(key)?;
+    return value.parse().ok().do_on_error(|| format!("'{}' must be a number, found {:?}", key, value));
+}
+
+fn flag(record: &Record, key: &str) -> Result<bool, ErrorChain> {
+    return match record.require(key)? {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => Err(ErrorChain::new(format!("'{}' must be true or false, found {:?}", key, other))),
+    };
+}
+
+// only the position is kept; the slice is re-taken from the haystack when it is needed again
+impl<T> ToRecord for PatternMatch<T> {
+    fn to_record(&self) -> Record {
+        return Record::new().with("index", self.index.to_string()).with("length", self.length.to_string());
+    }
+}
+
+impl FromRecord for PatternMatch<()> {
+    fn from_record(record: &Record) -> Result<PatternMatch<()>, ErrorChain> {
+        return Ok(PatternMatch { index: number(record, "index")?, length: number(record, "length")?, slice: () });
+    }
+}
+
+impl ToRecord for SetMatch {
+    fn to_record(&self) -> Record {
+        return Record::new().with("pattern_id", self.pattern_id.to_string()).with("index", self.index.to_string()).with("length", self.length.to_string());
+    }
+}
+
+impl FromRecord for SetMatch {
+    fn from_record(record: &Record) -> Result<SetMatch, ErrorChain> {
+        return Ok(SetMatch { pattern_id: number(record, "pattern_id")?, index: number(record, "index")?, length: number(record, "length")? });
+    }
+}
+
+impl ToRecord for Span {
+    fn to_record(&self) -> Record {
+        return Record::new().with("start", self.start.to_string()).with("end", self.end.to_string());
+    }
+}
+
+impl FromRecord for Span {
+    fn from_record(record: &Record) -> Result<Span, ErrorChain> {
+        let span = Span::new(number(record, "start")?, number(record, "end")?);
+        if span.start > span.end {
+            return Err(ErrorChain::new(format!("span starts at {} after its end at {}", span.start, span.end)));
+        }
+        return Ok(span);
+    }
+}
+
+impl ToRecord for ReportEntry {
+    fn to_record(&self) -> Record {
+        let mut record = Record::new().with("path", self.path.as_str());
+        record.fields.extend(self.span.to_record().fields);
+        return record
+            .with("line", self.line.to_string())
+            .with("column", self.column.to_string())
+            .with("pattern_id", self.pattern_id.to_string())
+            .with("text", self.text.as_str());
+    }
+}
+
+impl FromRecord for ReportEntry {
+    fn from_record(record: &Record) -> Result<ReportEntry, ErrorChain> {
+        return Ok(ReportEntry {
+            path: record.require("path")?.to_string(),
+            span: Span::from_record(record)?,
+            line: number(record, "line")?,
+            column: number(record, "column")?,
+            pattern_id: number(record, "pattern_id")?,
+            text: record.require("text")?.to_string(),
+        });
+    }
+}
+
+// the validator is a function pointer and cannot be saved, so a rule read back has none; rules
+// that need one are rebuilt from their definition instead
+impl ToRecord for Rule {
+    fn to_record(&self) -> Record {
+        return Record::new()
+            .with("name", self.name.as_str())
+            .with("pattern", self.pattern.as_str())
+            .with("kind", self.kind.name())
+            .with("ignore_case", self.ignore_case.to_string())
+            .with("severity", self.severity.to_string())
+            .with("message", self.message.as_str());
+    }
+}
+
+impl FromRecord for Rule {
+    fn from_record(record: &Record) -> Result<Rule, ErrorChain> {
+        let kind = record.require("kind")?;
+        let kind = PatternKind::from_name(kind).do_on_error(|| format!("unknown pattern kind '{}'", kind))?;
+        let severity = record.require("severity")?;
+        let severity = Severity::from_name(severity).do_on_error(|| format!("unknown severity '{}'", severity))?;
+        return Ok(Rule::new(record.require("name")?, record.require("pattern")?)
+            .kind(kind)
+            .ignore_case(flag(record, "ignore_case")?)
+            .severity(severity)
+            .message(record.require("message")?));
+    }
+}
+
+pub fn encode<T>(value: &T) -> Result<String, ErrorChain>
+where T: ToRecord + ?Sized {
+    return logfmt::encode(&value.to_record());
+}
+
+pub fn decode<T>(line: &str) -> Result<T, ErrorChain>
+where T: FromRecord {
+    return T::from_record(&logfmt::decode(line)?);
+}
+
+pub fn encode_lines<'a, T, I>(values: I) -> Result<String, ErrorChain>
+where T: ToRecord + 'a, I: IntoIterator<Item = &'a T> {
+    let mut out = String::new();
+    for value in values {
+        out.push_str(&encode(value)?);
+        out.push('\n');
+    }
+    return Ok(out);
+}
+
+pub fn decode_lines<T>(src: &str) -> Result<Vec<T>, ErrorChain>
+where T: FromRecord {
+    let records = logfmt::decode_lines(src)?;
+    let mut values = Vec::with_capacity(records.len());
+    for (number, record) in records.iter().enumerate() {
+        values.push(T::from_record(record).do_on_error(|| format!("invalid record {}", number + 1))?);
+    }
+    return Ok(values);
+}
+
+pub fn encode_report(report: &Report) -> Result<String, ErrorChain> {
+    return encode_lines(report.entries());
+}
+
+pub fn decode_report(src: &str) -> Result<Report, ErrorChain> {
+    let mut report = Report::new();
+    report.extend(decode_lines::<ReportEntry>(src).on_error("failed to read report")?);
+    return Ok(report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::PatternMatcher;
+
+    #[test]
+    fn test_round_trip() {
+        let found = "a TODO here".find_first(&"TODO").unwrap();
+        assert_eq!(encode(&found).unwrap(), "index=2 length=4");
+        assert_eq!(decode::<PatternMatch<()>>("index=2 length=4").unwrap().range(), 2..6);
+        assert_eq!(decode::<Span>(&encode(&Span::new(3, 9)).unwrap()).unwrap(), Span::new(3, 9));
+
+        let mut report = Report::new();
+        report.push(ReportEntry { path: "src/a b.rs".to_string(), span: Span::new(4, 8), line: 2, column: 3, pattern_id: 1, text: "say \"hi\"\n".to_string() });
+        let saved = encode_report(&report).unwrap();
+        assert_eq!(decode_report(&saved).unwrap().entries(), report.entries());
+
+        let rule = Rule::new("todo", "TODO").kind(PatternKind::Word).ignore_case(true).severity(Severity::Note).message("left a todo");
+        let back = decode::<Rule>(&encode(&rule).unwrap()).unwrap();
+        assert_eq!((back.name, back.pattern, back.kind, back.ignore_case, back.severity, back.message), (rule.name, rule.pattern, rule.kind, true, Severity::Note, rule.message));
+    }
+
+    #[test]
+    fn test_invalid_records() {
+        assert!(decode::<Span>("start=5 end=2").unwrap_err().to_string().contains("span starts at 5 after its end at 2"));
+        assert!(decode::<SetMatch>("pattern_id=x index=0 length=1").unwrap_err().to_string().contains("'pattern_id' must be a number"));
+        assert!(decode::<Rule>("name=a pattern=b kind=regex ignore_case=false severity=note message=m").is_err());
+        let error = decode_lines::<SetMatch>("pattern_id=0 index=0 length=1\npattern_id=0 index=3\n").unwrap_err().to_string();
+        assert!(error.contains("invalid record 2"), "{}", error);
+    }
+}
@@ -0,0 +1,458 @@
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::parse_error::ParseError;
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => return Some(value),
+            _ => return None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => return Some(*value),
+            _ => return None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(value) => return Some(*value),
+            Value::Integer(value) => return Some(*value as f64),
+            _ => return None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(value) => return Some(*value),
+            _ => return None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => return Some(values),
+            _ => return None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => return "string",
+            Value::Integer(_) => return "integer",
+            Value::Float(_) => return "float",
+            Value::Boolean(_) => return "boolean",
+            Value::Array(_) => return "array",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub key_span: Span,
+    pub value: Value,
+    pub value_span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub name: String,
+    pub span: Span,
+    pub entries: Vec<Entry>,
+}
+
+impl Table {
+    pub fn entry(&self, key: &str) -> Option<&Entry> {
+        return self.entries.iter().find(|entry| entry.key == key);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        return self.entry(key).map(|entry| &entry.value);
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        return self.entries.iter().map(|entry| entry.key.as_str());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub tables: Vec<Table>,
+}
+
+impl Document {
+    pub fn root(&self) -> &Table {
+        return &self.tables[0];
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        return self.tables.iter().find(|table| table.name == name);
+    }
+
+    pub fn get(&self, table: &str, key: &str) -> Option<&Value> {
+        return self.table(table).and_then(|table| table.get(key));
+    }
+
+    pub fn require<'a>(&'a self, table: &str, key: &str) -> Result<&'a Entry, ErrorChain> {
+        return self.table(table)
+            .and_then(|found| found.entry(key))
+            .do_on_error(|| match table.is_empty() {
+                true => format!("missing required key '{}'", key),
+                false => format!("missing required key '{}' in table [{}]", key, table),
+            });
+    }
+}
+
+pub fn parse(src: &str) -> Result<Document, ErrorChain> {
+    return Parser::new(src).document().on_error("failed to parse TOML");
+}
+
+pub fn parse_front_matter(src: &str) -> Result<Option<(Document, &str)>, ErrorChain> {
+    let first_line_end = src.find('\n').map(|index| index + 1).unwrap_or(src.len());
+    if src[..first_line_end].trim_end() != "+++" {
+        return Ok(None);
+    }
+    let mut line_start = first_line_end;
+    while line_start < src.len() {
+        let line_end = src[line_start..].find('\n').map(|index| line_start + index + 1).unwrap_or(src.len());
+        if src[line_start..line_end].trim_end() == "+++" {
+            let mut parser = Parser::new(&src[..line_start]);
+            parser.pos = first_line_end;
+            let document = parser.document().on_error("failed to parse TOML front matter")?;
+            return Ok(Some((document, &src[line_end..])));
+        }
+        line_start = line_end;
+    }
+    return Err(ErrorChain::from(ParseError::new(0..3, "front matter is never closed"), "failed to parse TOML front matter"));
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Parser<'a> {
+        return Parser { src, bytes: src.as_bytes(), pos: 0 };
+    }
+
+    fn peek(&self) -> Option<u8> {
+        return self.bytes.get(self.pos).copied();
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(b' ') | Some(b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some(b'#') {
+            while !matches!(self.peek(), None | Some(b'\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some(b'\n') | Some(b'\r') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn line_end(&mut self) -> Result<(), ParseError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some(b'\r') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            None => return Ok(()),
+            Some(b'\n') => {
+                self.pos += 1;
+                return Ok(());
+            }
+            Some(_) => return Err(ParseError::at(self.pos, "expected end of line")),
+        }
+    }
+
+    fn document(&mut self) -> Result<Document, ParseError> {
+        let mut tables = vec![Table { name: String::new(), span: Span::at(0), entries: Vec::new() }];
+        loop {
+            self.skip_blank();
+            if self.pos >= self.bytes.len() {
+                return Ok(Document { tables });
+            }
+            if self.peek() == Some(b'[') {
+                let start = self.pos;
+                self.pos += 1;
+                self.skip_spaces();
+                if self.peek() == Some(b'[') {
+                    return Err(ParseError::new(start..self.pos + 1, "arrays of tables are not supported"));
+                }
+                let mut name = self.key()?.0;
+                self.skip_spaces();
+                while self.peek() == Some(b'.') {
+                    self.pos += 1;
+                    self.skip_spaces();
+                    name.push('.');
+                    name.push_str(&self.key()?.0);
+                    self.skip_spaces();
+                }
+                if self.peek() != Some(b']') {
+                    return Err(ParseError::at(self.pos, "expected ']' to close table header"));
+                }
+                self.pos += 1;
+                let span = Span::new(start, self.pos);
+                if tables.iter().any(|table| table.name == name) {
+                    return Err(ParseError::new(span, format!("duplicate table [{}]", name)));
+                }
+                tables.push(Table { name, span, entries: Vec::new() });
+            } else {
+                let (key, key_span) = self.key()?;
+                self.skip_spaces();
+                if self.peek() == Some(b'.') {
+                    return Err(ParseError::at(self.pos, "dotted keys are not supported"));
+                }
+                if self.peek() != Some(b'=') {
+                    return Err(ParseError::at(self.pos, "expected '=' after key"));
+                }
+                self.pos += 1;
+                self.skip_spaces();
+                let value_start = self.pos;
+                let value = self.value()?;
+                let value_span = Span::new(value_start, self.pos);
+                let table = tables.last_mut().unwrap();
+                if table.entry(&key).is_some() {
+                    return Err(ParseError::new(key_span, format!("duplicate key '{}'", key)));
+                }
+                table.entries.push(Entry { key, key_span, value, value_span });
+            }
+            self.line_end()?;
+        }
+    }
+
+    fn key(&mut self) -> Result<(String, Span), ParseError> {
+        let start = self.pos;
+        match self.peek() {
+            Some(b'"') => return Ok((self.basic_string()?, Span::new(start, self.pos))),
+            Some(b'\'') => return Ok((self.literal_string()?, Span::new(start, self.pos))),
+            _ => {}
+        }
+        while matches!(self.peek(), Some(byte) if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-') {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(ParseError::at(start, "expected a key"));
+        }
+        return Ok((self.src[start..self.pos].to_string(), Span::new(start, self.pos)));
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        match self.peek() {
+            Some(b'"') => return Ok(Value::String(self.basic_string()?)),
+            Some(b'\'') => return Ok(Value::String(self.literal_string()?)),
+            Some(b'[') => return self.array(),
+            Some(b'{') => return Err(ParseError::at(start, "inline tables are not supported")),
+            Some(b't') if self.src[start..].starts_with("true") => {
+                self.pos += 4;
+                return Ok(Value::Boolean(true));
+            }
+            Some(b'f') if self.src[start..].starts_with("false") => {
+                self.pos += 5;
+                return Ok(Value::Boolean(false));
+            }
+            Some(byte) if byte.is_ascii_digit() || byte == b'+' || byte == b'-' => return self.number(),
+            _ => return Err(ParseError::at(start, "expected a value")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut values = Vec::new();
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                None => return Err(ParseError::new(start..self.pos, "unclosed array")),
+                _ => {}
+            }
+            values.push(self.value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return Err(ParseError::at(self.pos, "expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(byte) if byte.is_ascii_alphanumeric() || matches!(byte, b'+' | b'-' | b'.' | b'_' | b':')) {
+            self.pos += 1;
+        }
+        let span = Span::new(start, self.pos);
+        let text = self.src[start..self.pos].replace('_', "");
+        if text.contains(':') || (text.len() > 4 && text.as_bytes()[4] == b'-' && text[..4].bytes().all(|b| b.is_ascii_digit())) {
+            return Err(ParseError::new(span, "dates and times are not supported"));
+        }
+        let (negative, unsigned) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text.as_str()),
+        };
+        for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+            if let Some(digits) = unsigned.strip_prefix(prefix) {
+                return i64::from_str_radix(digits, radix)
+                    .map(|value| Value::Integer(if negative { -value } else { value }))
+                    .map_err(|_| ParseError::new(span, "invalid integer"));
+            }
+        }
+        if !unsigned.contains(['.', 'e', 'E']) {
+            return text.parse::<i64>()
+                .map(Value::Integer)
+                .map_err(|_| ParseError::new(span, "invalid integer"));
+        }
+        return text.parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| ParseError::new(span, "invalid float"));
+    }
+
+    fn literal_string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        if self.src[start..].starts_with("'''") {
+            return Err(ParseError::new(start..start + 3, "multi-line strings are not supported"));
+        }
+        self.pos += 1;
+        while !matches!(self.peek(), None | Some(b'\'') | Some(b'\n')) {
+            self.pos += 1;
+        }
+        if self.peek() != Some(b'\'') {
+            return Err(ParseError::new(start..self.pos, "unterminated string"));
+        }
+        self.pos += 1;
+        return Ok(self.src[start + 1..self.pos - 1].to_string());
+    }
+
+    fn basic_string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        if self.src[start..].starts_with("\"\"\"") {
+            return Err(ParseError::new(start..start + 3, "multi-line strings are not supported"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let rest = &self.src[self.pos..];
+            let next = match rest.chars().next() {
+                None | Some('\n') => return Err(ParseError::new(start..self.pos, "unterminated string")),
+                Some(next) => next,
+            };
+            self.pos += next.len_utf8();
+            match next {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape_start = self.pos - 1;
+                    let escaped = match self.peek() {
+                        Some(b'n') => '\n',
+                        Some(b't') => '\t',
+                        Some(b'r') => '\r',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'u') | Some(b'U') => {
+                            let digits = if self.peek() == Some(b'u') { 4 } else { 8 };
+                            let hex = self.src.get(self.pos + 1..self.pos + 1 + digits).unwrap_or("");
+                            let code = u32::from_str_radix(hex, 16).ok().filter(|_| hex.len() == digits);
+                            match code.and_then(char::from_u32) {
+                                Some(escaped) => {
+                                    self.pos += digits;
+                                    escaped
+                                }
+                                None => return Err(ParseError::new(escape_start..self.pos + 1 + hex.len(), "invalid unicode escape")),
+                            }
+                        }
+                        _ => return Err(ParseError::new(escape_start..self.pos + 1, "invalid escape sequence")),
+                    };
+                    self.pos += 1;
+                    out.push(escaped);
+                }
+                other => out.push(other),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "title = \"gmec\" # trailing comment\n\
+        retries = 3\n\
+        ratio = 0.5\n\
+        [server]\n\
+        host = 'localhost'\n\
+        enabled = true\n\
+        ports = [\n  8080,\n  8081, # second\n]\n";
+
+    #[test]
+    fn test_parse_values() {
+        let document = parse(CONFIG).unwrap();
+        assert_eq!(document.get("", "title").unwrap().as_str(), Some("gmec"));
+        assert_eq!(document.get("", "retries").unwrap().as_integer(), Some(3));
+        assert_eq!(document.get("", "ratio").unwrap().as_float(), Some(0.5));
+        assert_eq!(document.get("server", "host").unwrap().as_str(), Some("localhost"));
+        assert_eq!(document.get("server", "enabled").unwrap().as_bool(), Some(true));
+        let ports = document.get("server", "ports").unwrap().as_array().unwrap();
+        assert_eq!(ports, &[Value::Integer(8080), Value::Integer(8081)]);
+        let entry = document.table("server").unwrap().entry("host").unwrap();
+        assert_eq!(&CONFIG[entry.value_span.range()], "'localhost'");
+    }
+
+    #[test]
+    fn test_parse_errors_carry_spans() {
+        let error = Parser::new("a = 1\na = 2\n").document().unwrap_err();
+        assert_eq!(error.span.range(), 6..7);
+        let error = Parser::new("when = 2023-01-01\n").document().unwrap_err();
+        assert_eq!(error.span.range(), 7..17);
+        let error = Parser::new("point = { x = 1 }\n").document().unwrap_err();
+        assert_eq!(error.span.start, 8);
+        assert!(parse("name = \"unterminated\n").is_err());
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let src = "+++\ntitle = \"post\"\n+++\n# Body\n";
+        let (document, body) = parse_front_matter(src).unwrap().unwrap();
+        assert_eq!(document.get("", "title").unwrap().as_str(), Some("post"));
+        assert_eq!(body, "# Body\n");
+        assert!(parse_front_matter("# No front matter\n").unwrap().is_none());
+        assert!(parse_front_matter("+++\ntitle = 1\n").is_err());
+    }
+}
@@ -0,0 +1,8 @@
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod atomic;
+pub mod ignore;
+pub mod sniff;
+pub mod tail;
+pub mod temp;
+pub mod walk;
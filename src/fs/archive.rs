@@ -0,0 +1,318 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+#[cfg(feature = "gzip")]
+use crate::io::gzip::Inflater;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+const TAR_BLOCK: usize = 512;
+const ZIP_LOCAL_HEADER: u32 = 0x04034b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x02014b50;
+const ZIP_END_OF_DIRECTORY: u32 = 0x06054b50;
+// the end record is 22 bytes plus a comment of at most 65535
+const ZIP_MAX_END_SEARCH: u64 = 22 + 65535;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArchiveKind {
+    Tar,
+    Zip,
+}
+
+pub fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+    if name.ends_with(".tar") {
+        return Some(ArchiveKind::Tar);
+    }
+    if name.ends_with(".zip") || name.ends_with(".jar") {
+        return Some(ArchiveKind::Zip);
+    }
+    return None;
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    return archive_kind(path).is_some();
+}
+
+// hands each regular file in the archive to f with its name and a reader over just its contents;
+// members are streamed, never extracted
+pub fn for_each_entry<F>(path: &Path, f: F) -> Result<(), ErrorChain>
+where F: FnMut(&str, &mut dyn Read) -> Result<(), ErrorChain> {
+    let file = File::open(path).do_on_error(|| format!("failed to open archive {}", path.display()))?;
+    let result = match archive_kind(path) {
+        Some(ArchiveKind::Tar) => for_each_tar_entry(BufReader::new(file), f),
+        Some(ArchiveKind::Zip) => for_each_zip_entry(file, f),
+        None => Err(ErrorChain::new("not a tar or zip archive").with_help("archives are recognized by a .tar, .zip or .jar extension")),
+    };
+    return result.do_on_error(|| format!("failed to read archive {}", path.display()));
+}
+
+fn octal(field: &[u8]) -> Result<u64, ErrorChain> {
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    return u64::from_str_radix(digits, 8).do_on_error(|| format!("bad octal field '{}'", digits));
+}
+
+fn nul_terminated(field: &[u8]) -> String {
+    let end = field.iter().position(|byte| *byte == 0).unwrap_or(field.len());
+    return String::from_utf8_lossy(&field[..end]).into_owned();
+}
+
+fn skip<R>(reader: &mut R, len: u64) -> Result<(), ErrorChain>
+where R: Read {
+    let skipped = std::io::copy(&mut reader.take(len), &mut std::io::sink()).on_error("failed to skip archive data")?;
+    if skipped < len {
+        return Err(ErrorChain::new("archive is truncated"));
+    }
+    return Ok(());
+}
+
+// false at a clean end of input; input ending inside the block is a truncated archive
+fn read_block<R>(reader: &mut R, block: &mut [u8; TAR_BLOCK]) -> Result<bool, ErrorChain>
+where R: Read {
+    let read = std::io::copy(&mut reader.take(TAR_BLOCK as u64), &mut &mut block[..]).on_error("failed to read tar header")?;
+    return match read {
+        0 => Ok(false),
+        read if read < TAR_BLOCK as u64 => Err(ErrorChain::new(format!("archive is truncated: tar header has {} of {} bytes", read, TAR_BLOCK))),
+        _ => Ok(true),
+    };
+}
+
+pub fn for_each_tar_entry<R, F>(mut reader: R, mut f: F) -> Result<(), ErrorChain>
+where R: Read, F: FnMut(&str, &mut dyn Read) -> Result<(), ErrorChain> {
+    let mut header = [0u8; TAR_BLOCK];
+    let mut long_name: Option<String> = None;
+    loop {
+        if !read_block(&mut reader, &mut header)? || header.iter().all(|byte| *byte == 0) {
+            return Ok(());
+        }
+        let size = octal(&header[124..136]).on_error("bad tar header")?;
+        let padding = (TAR_BLOCK as u64 - size % TAR_BLOCK as u64) % TAR_BLOCK as u64;
+        let mut name = nul_terminated(&header[..100]);
+        if &header[257..262] == b"ustar" {
+            let prefix = nul_terminated(&header[345..500]);
+            if !prefix.is_empty() {
+                name = format!("{}/{}", prefix, name);
+            }
+        }
+        match header[156] {
+            // GNU long name: the data is the name of the next entry
+            b'L' => {
+                let mut data = Vec::new();
+                (&mut reader).take(size).read_to_end(&mut data).on_error("failed to read tar long name")?;
+                if (data.len() as u64) < size {
+                    return Err(ErrorChain::new("archive is truncated"));
+                }
+                long_name = Some(nul_terminated(&data));
+                skip(&mut reader, padding)?;
+            }
+            b'0' | 0 => {
+                let name = long_name.take().unwrap_or(name);
+                let mut member = (&mut reader).take(size);
+                f(&name, &mut member).do_on_error(|| format!("failed to read {}", name))?;
+                let unread = member.limit();
+                skip(&mut reader, unread + padding)?;
+            }
+            _ => {
+                long_name = None;
+                skip(&mut reader, size + padding)?;
+            }
+        }
+    }
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> usize {
+    return u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize;
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    return u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+}
+
+struct ZipMember {
+    name: String,
+    method: usize,
+    compressed_size: u64,
+    header_offset: u64,
+}
+
+fn zip_directory<R>(reader: &mut R) -> Result<Vec<ZipMember>, ErrorChain>
+where R: Read + Seek {
+    let len = reader.seek(SeekFrom::End(0)).on_error("failed to seek")?;
+    let tail_len = len.min(ZIP_MAX_END_SEARCH);
+    reader.seek(SeekFrom::Start(len - tail_len)).on_error("failed to seek")?;
+    let mut tail = vec![0; tail_len as usize];
+    reader.read_exact(&mut tail).on_error("failed to read the end of the zip")?;
+    let end = (0..tail.len().saturating_sub(21)).rev().find(|at| u32_at(&tail, *at) == ZIP_END_OF_DIRECTORY)
+        .ok_or_else(|| ErrorChain::new("no zip end of central directory record"))?;
+    let count = u16_at(&tail, end + 10);
+    let directory_len = u32_at(&tail, end + 12) as usize;
+    let directory_offset = u32_at(&tail, end + 16) as u64;
+    // checked before allocating, so a corrupt record can't ask for 4 GiB
+    if directory_offset.saturating_add(directory_len as u64) > len {
+        return Err(ErrorChain::new(format!("zip directory at {}..{} is past the end of the file ({} bytes)", directory_offset, directory_offset + directory_len as u64, len)));
+    }
+    reader.seek(SeekFrom::Start(directory_offset)).on_error("failed to seek to the zip directory")?;
+    let mut directory = vec![0; directory_len];
+    reader.read_exact(&mut directory).on_error("zip directory is truncated")?;
+    let mut members = Vec::with_capacity(count);
+    let mut at = 0;
+    for _ in 0..count {
+        if at + 46 > directory.len() || u32_at(&directory, at) != ZIP_CENTRAL_HEADER {
+            return Err(ErrorChain::new(format!("bad zip directory entry at offset {}", directory_offset + at as u64)));
+        }
+        let name_len = u16_at(&directory, at + 28);
+        let skip_len = name_len + u16_at(&directory, at + 30) + u16_at(&directory, at + 32);
+        let name = directory.get(at + 46..at + 46 + name_len).ok_or_else(|| ErrorChain::new("zip directory is truncated"))?;
+        members.push(ZipMember {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: u16_at(&directory, at + 10),
+            compressed_size: u32_at(&directory, at + 20) as u64,
+            header_offset: u32_at(&directory, at + 42) as u64,
+        });
+        at += 46 + skip_len;
+    }
+    return Ok(members);
+}
+
+pub fn for_each_zip_entry<R, F>(mut reader: R, mut f: F) -> Result<(), ErrorChain>
+where R: Read + Seek, F: FnMut(&str, &mut dyn Read) -> Result<(), ErrorChain> {
+    for member in zip_directory(&mut reader)? {
+        if member.name.ends_with('/') {
+            continue;
+        }
+        reader.seek(SeekFrom::Start(member.header_offset)).on_error("failed to seek to a zip entry")?;
+        let mut local = [0u8; 30];
+        reader.read_exact(&mut local).on_error("zip entry header is truncated")?;
+        if u32_at(&local, 0) != ZIP_LOCAL_HEADER {
+            return Err(ErrorChain::new(format!("bad local header for {}", member.name)));
+        }
+        reader.seek(SeekFrom::Current((u16_at(&local, 26) + u16_at(&local, 28)) as i64)).on_error("failed to seek to zip entry data")?;
+        let mut data = (&mut reader).take(member.compressed_size);
+        match member.method {
+            0 => f(&member.name, &mut data),
+            #[cfg(feature = "gzip")]
+            8 => f(&member.name, &mut Inflater::new(data)),
+            method => Err(ErrorChain::new(format!("unsupported compression method {}", method)).with_help("stored entries are always readable; deflated ones need the gzip feature")),
+        }.do_on_error(|| format!("failed to read {}", member.name))?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+    use crate::fs::walk::Walker;
+    use crate::patterns::PatternSet;
+    use crate::search::stream::scan_walker;
+    use std::io::Cursor;
+
+    fn tar(members: &[(&str, &str)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, contents) in members {
+            let mut header = [0u8; TAR_BLOCK];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            out.extend_from_slice(&header);
+            out.extend_from_slice(contents.as_bytes());
+            out.resize(out.len().next_multiple_of(TAR_BLOCK), 0);
+        }
+        out.resize(out.len() + 2 * TAR_BLOCK, 0);
+        return out;
+    }
+
+    fn stored_zip(members: &[(&str, &str)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut directory = Vec::new();
+        for (name, contents) in members {
+            let offset = out.len() as u32;
+            out.extend_from_slice(&ZIP_LOCAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            out.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(contents.as_bytes());
+            directory.extend_from_slice(&ZIP_CENTRAL_HEADER.to_le_bytes());
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            directory.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = out.len() as u32;
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&ZIP_END_OF_DIRECTORY.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&directory_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        return out;
+    }
+
+    fn contents_of<F>(read: F) -> Vec<(String, String)>
+    where F: FnOnce(&mut dyn FnMut(&str, &mut dyn Read) -> Result<(), ErrorChain>) -> Result<(), ErrorChain> {
+        let mut seen = Vec::new();
+        read(&mut |name, reader| {
+            let mut contents = String::new();
+            reader.read_to_string(&mut contents).on_error("read failed")?;
+            seen.push((name.to_string(), contents));
+            return Ok(());
+        }).unwrap();
+        return seen;
+    }
+
+    #[test]
+    fn test_read_tar_and_zip_members() {
+        let members = [("app/a.log", "ERROR one\n"), ("b.log", &"x".repeat(700)[..])];
+        let expected: Vec<(String, String)> = members.iter().map(|(name, contents)| (name.to_string(), contents.to_string())).collect();
+        assert_eq!(contents_of(|f| for_each_tar_entry(&tar(&members)[..], f)), expected);
+        assert_eq!(contents_of(|f| for_each_zip_entry(Cursor::new(stored_zip(&members)), f)), expected);
+        assert!(for_each_zip_entry(Cursor::new(b"not a zip".to_vec()), |_, _| Ok(())).is_err());
+
+        let mut corrupt = stored_zip(&members);
+        let record = corrupt.len() - 22;
+        corrupt[record + 12..record + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = for_each_zip_entry(Cursor::new(corrupt), |_, _| Ok(())).unwrap_err().to_string();
+        assert!(error.contains("past the end of the file"), "{}", error);
+        let whole = tar(&members);
+        for cut in [100, TAR_BLOCK + 5, 2 * TAR_BLOCK + 10] {
+            let error = for_each_tar_entry(&whole[..cut], |_, reader| {
+                reader.read_to_end(&mut Vec::new()).on_error("read failed")?;
+                return Ok(());
+            }).unwrap_err().to_string();
+            assert!(error.contains("archive is truncated"), "{}: {}", cut, error);
+        }
+    }
+
+    #[test]
+    fn test_walker_descends_into_archives() {
+        let dir = TempDir::new().unwrap();
+        dir.create_file("logs.tar", tar(&[("app/a.log", "ok ERROR")])).unwrap();
+        dir.create_file("more.zip", stored_zip(&[("b.log", "ERROR ERROR")])).unwrap();
+        dir.create_file("plain.log", "ERROR").unwrap();
+        let set = PatternSet::new().with("ERROR");
+        let mut found = Vec::new();
+        scan_walker(&Walker::new(dir.path()).archives(true), &set, |path, found_match| {
+            found.push((path.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"), found_match.index));
+            return Ok(());
+        }).unwrap();
+        let expected = [("logs.tar/app/a.log", 3), ("more.zip/b.log", 0), ("more.zip/b.log", 6), ("plain.log", 0)];
+        assert_eq!(found, expected.map(|(path, index)| (path.to_string(), index)));
+    }
+}
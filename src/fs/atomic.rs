@@ -0,0 +1,85 @@
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+
+use super::temp::unique_name;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub fn write_atomic<P, B>(path: P, bytes: B) -> Result<(), ErrorChain>
+where P: AsRef<Path>, B: AsRef<[u8]> {
+    let path = path.as_ref();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name()
+        .do_on_error(|| format!("cannot atomically write {}: path has no file name", path.display()))?;
+    let temp_path = dir.join(unique_name(&format!(".{}.tmp", file_name.to_string_lossy())));
+
+    let result = write_and_sync(&temp_path, bytes.as_ref())
+        .and_then(|_| fs::rename(&temp_path, path).do_on_error(|| format!("failed to rename {} into place", temp_path.display())));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.do_on_error(|| format!("failed to atomically write {}", path.display()))?;
+
+    // the rename is only durable once the directory entry itself reaches the disk
+    #[cfg(unix)]
+    {
+        if let Ok(dir_handle) = File::open(dir) {
+            let _ = dir_handle.sync_all();
+        }
+    }
+    return Ok(());
+}
+
+fn write_and_sync(temp_path: &Path, bytes: &[u8]) -> Result<(), ErrorChain> {
+    let mut file = File::create(temp_path).do_on_error(|| format!("failed to create {}", temp_path.display()))?;
+    file.write_all(bytes).do_on_error(|| format!("failed to write {}", temp_path.display()))?;
+    file.sync_all().do_on_error(|| format!("failed to sync {}", temp_path.display()))?;
+    return Ok(());
+}
+
+pub fn read_to_string_capped<P>(path: P, max: u64) -> Result<String, ErrorChain>
+where P: AsRef<Path> {
+    let path = path.as_ref();
+    let file = File::open(path).do_on_error(|| format!("failed to open {}", path.display()))?;
+    let mut contents = String::new();
+    file.take(max + 1)
+        .read_to_string(&mut contents)
+        .do_on_error(|| format!("failed to read {}", path.display()))?;
+    if contents.len() as u64 > max {
+        return Err(ErrorChain::new(format!("{} is larger than the {} byte limit", path.display(), max)));
+    }
+    return Ok(contents);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = TempDir::new().unwrap();
+        let target = dir.join("config.toml");
+        write_atomic(&target, "a = 1\n").unwrap();
+        write_atomic(&target, "a = 2\n").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "a = 2\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        let error = write_atomic(dir.join("missing/dir/file"), "x").unwrap_err().to_string();
+        assert!(error.contains("missing/dir/file"));
+    }
+
+    #[test]
+    fn test_read_to_string_capped() {
+        let dir = TempDir::new().unwrap().with_file("small", "12345").unwrap();
+        assert_eq!(read_to_string_capped(dir.join("small"), 5).unwrap(), "12345");
+        let error = read_to_string_capped(dir.join("small"), 4).unwrap_err().to_string();
+        assert!(error.contains("4 byte limit"));
+        assert!(read_to_string_capped(dir.join("nope"), 4).unwrap_err().to_string().contains("nope"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use crate::fs::atomic::read_to_string_capped;
+use crate::text::glob::glob_match;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub const GITIGNORE: &str = ".gitignore";
+pub const GMECIGNORE: &str = ".gmecignore";
+
+const MAX_IGNORE_FILE_LEN: u64 = 1024 * 1024;
+
+// one gitignore line, already turned into a glob over paths relative to `base`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreRule {
+    pub glob: String,
+    pub negated: bool,
+    pub dir_only: bool,
+    // directory the rule is relative to, '/'-separated and relative to the walk root ("" for the root)
+    pub base: String,
+}
+
+impl IgnoreRule {
+    // None for blank lines and comments
+    pub fn parse(line: &str, base: &str) -> Option<IgnoreRule> {
+        let mut pattern = line.trim_end_matches(['\r', '\n']);
+        // trailing spaces are ignored unless escaped
+        if !pattern.ends_with("\\ ") {
+            pattern = pattern.trim_end_matches(' ');
+        }
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return None;
+        }
+        let negated = pattern.starts_with('!');
+        // "!x" negates, while "\\!x" and "\\#x" are the literal names
+        if negated || pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+            pattern = &pattern[1..];
+        }
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        // a slash anywhere but the end anchors the pattern to the ignore file's directory;
+        // otherwise it matches a name at any depth
+        let glob = match pattern.contains('/') {
+            true => pattern.trim_start_matches('/').to_string(),
+            false => format!("**/{}", pattern),
+        };
+        return Some(IgnoreRule { glob, negated, dir_only, base: base.trim_matches('/').to_string() });
+    }
+
+    // path is '/'-separated and relative to the walk root
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let relative = match self.base.is_empty() {
+            true => path,
+            false => match path.strip_prefix(self.base.as_str()).and_then(|rest| rest.strip_prefix('/')) {
+                Some(relative) => relative,
+                None => return false,
+            },
+        };
+        return glob_match(&self.glob, relative);
+    }
+}
+
+// rules in precedence order: a later rule overrides an earlier one, as lines do within a gitignore
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreSet {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreSet {
+    pub fn new() -> IgnoreSet {
+        return IgnoreSet { rules: Vec::new() };
+    }
+
+    pub fn parse(src: &str, base: &str) -> IgnoreSet {
+        let mut set = IgnoreSet::new();
+        set.extend(src, base);
+        return set;
+    }
+
+    pub fn extend(&mut self, src: &str, base: &str) {
+        self.rules.extend(src.lines().filter_map(|line| IgnoreRule::parse(line, base)));
+    }
+
+    pub fn push(&mut self, rule: IgnoreRule) {
+        self.rules.push(rule);
+    }
+
+    // a missing file adds nothing
+    pub fn add_file(&mut self, path: &Path, base: &str) -> Result<(), ErrorChain> {
+        if !path.is_file() {
+            return Ok(());
+        }
+        let src = read_to_string_capped(path, MAX_IGNORE_FILE_LEN).do_on_error(|| format!("failed to read ignore file {}", path.display()))?;
+        self.extend(&src, base);
+        return Ok(());
+    }
+
+    pub fn rules(&self) -> &[IgnoreRule] {
+        return &self.rules;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.rules.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.rules.is_empty();
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.rules.truncate(len);
+    }
+
+    // Some(true) if the last matching rule ignores the path, Some(false) if it re-includes it
+    pub fn decide(&self, path: &str, is_dir: bool) -> Option<bool> {
+        return self.rules.iter().rev().find(|rule| rule.matches(path, is_dir)).map(|rule| !rule.negated);
+    }
+
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        return self.decide(path, is_dir).unwrap_or(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_semantics() {
+        let set = IgnoreSet::parse("# build output\ntarget/\n*.log\n!keep.log\n/root-only.txt\ndocs/**/draft-*\n\\#literal\n", "");
+        assert_eq!(set.len(), 6);
+        assert!(set.is_ignored("target", true));
+        assert!(set.is_ignored("crates/a/target", true));
+        assert!(!set.is_ignored("target", false));
+        assert!(set.is_ignored("logs/app.log", false));
+        assert_eq!(set.decide("logs/keep.log", false), Some(false));
+        assert!(set.is_ignored("root-only.txt", false));
+        assert!(!set.is_ignored("sub/root-only.txt", false));
+        assert!(set.is_ignored("docs/a/b/draft-1.md", false));
+        assert!(set.is_ignored("#literal", false));
+        assert_eq!(set.decide("src/main.rs", false), None);
+
+        let nested = IgnoreSet::parse("*.tmp\n/local\n", "sub/dir");
+        assert!(nested.is_ignored("sub/dir/x/a.tmp", false));
+        assert!(nested.is_ignored("sub/dir/local", true));
+        assert!(!nested.is_ignored("other/a.tmp", false));
+        assert!(!nested.is_ignored("sub/directory/a.tmp", false));
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::text::encoding;
+use crate::text::encoding::Encoding;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub const SNIFF_LEN: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentKind {
+    Text,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sniffed {
+    pub kind: ContentKind,
+    pub confidence: f64,
+    pub encoding: Option<Encoding>,
+    pub examined: usize,
+}
+
+impl Sniffed {
+    pub fn is_text(&self) -> bool {
+        return self.kind == ContentKind::Text;
+    }
+
+    pub fn is_binary(&self) -> bool {
+        return self.kind == ContentKind::Binary;
+    }
+}
+
+fn is_suspicious_control(byte: u8) -> bool {
+    return matches!(byte, 0x01..=0x08 | 0x0e..=0x1a | 0x1c..=0x1f | 0x7f);
+}
+
+pub fn sniff_bytes(prefix: &[u8]) -> Sniffed {
+    let prefix = &prefix[..prefix.len().min(SNIFF_LEN)];
+    let examined = prefix.len();
+    if prefix.is_empty() {
+        return Sniffed { kind: ContentKind::Text, confidence: 1.0, encoding: Some(Encoding::Utf8), examined };
+    }
+    let detection = encoding::detect(prefix);
+    if matches!(detection.encoding, Encoding::Utf16Le | Encoding::Utf16Be) {
+        return Sniffed { kind: ContentKind::Text, confidence: 0.9, encoding: Some(detection.encoding), examined };
+    }
+    // a NUL byte is grep's binary signal too; text files essentially never contain one
+    if prefix.contains(&0) {
+        return Sniffed { kind: ContentKind::Binary, confidence: 0.99, encoding: None, examined };
+    }
+
+    let mut suspicious = prefix.iter().filter(|byte| is_suspicious_control(**byte)).count();
+    if detection.encoding == Encoding::Utf8 {
+        let decoded = encoding::decode_lossy_with(prefix, Encoding::Utf8, detection.bom_len);
+        let mut invalid: usize = decoded.replacements.iter().map(|replacement| replacement.source.len()).sum();
+        // the prefix may cut a multi-byte character in half, which says nothing about the file
+        if let Some(last) = decoded.replacements.last() {
+            if last.source.end == prefix.len() && last.source.len() < 4 {
+                invalid -= last.source.len();
+            }
+        }
+        suspicious += invalid;
+    }
+    let ratio = suspicious as f64 / examined as f64;
+    if ratio > 0.1 {
+        let confidence = (0.5 + ratio).min(0.99);
+        return Sniffed { kind: ContentKind::Binary, confidence, encoding: None, examined };
+    }
+    let confidence = (1.0 - ratio * 5.0).max(0.5);
+    return Sniffed { kind: ContentKind::Text, confidence, encoding: Some(detection.encoding), examined };
+}
+
+pub fn sniff_kind<R>(reader: R) -> Result<Sniffed, ErrorChain>
+where R: Read {
+    let mut prefix = Vec::with_capacity(SNIFF_LEN);
+    reader.take(SNIFF_LEN as u64)
+        .read_to_end(&mut prefix)
+        .on_error("failed to read content prefix for sniffing")?;
+    return Ok(sniff_bytes(&prefix));
+}
+
+pub fn sniff_path<P>(path: P) -> Result<Sniffed, ErrorChain>
+where P: AsRef<Path> {
+    let path = path.as_ref();
+    let file = File::open(path).do_on_error(|| format!("failed to open {}", path.display()))?;
+    return sniff_kind(file).do_on_error(|| format!("failed to sniff {}", path.display()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_and_binary() {
+        assert!(sniff_bytes(b"").is_text());
+        let text = sniff_bytes("fn main() {\n\tprintln!(\"héllo\");\n}\n".as_bytes());
+        assert!(text.is_text());
+        assert_eq!(text.confidence, 1.0);
+        assert!(sniff_bytes(b"\x7fELF\x02\x01\x01\x00\x00\x00").is_binary());
+        assert!(sniff_bytes(b"\xff\xfeh\x00i\x00").is_text());
+        assert!(sniff_bytes(&[0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x01, 0x02, 0x03]).is_binary());
+        assert_eq!(sniff_bytes(b"caf\xe9 cr\xe8me").encoding, Some(Encoding::Latin1));
+    }
+
+    #[test]
+    fn test_truncated_prefix_is_still_text() {
+        let mut text = "é".repeat(SNIFF_LEN / 2 - 1).into_bytes();
+        text.push(b'a');
+        text.extend_from_slice("é".as_bytes());
+        let sniffed = sniff_kind(&text[..]).unwrap();
+        assert_eq!(sniffed.examined, SNIFF_LEN);
+        assert!(sniffed.is_text());
+        assert_eq!(sniffed.confidence, 1.0);
+    }
+}
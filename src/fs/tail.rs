@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TailLine {
+    pub offset: u64,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct Tail {
+    path: PathBuf,
+    file: Option<File>,
+    offset: u64,
+    partial: Vec<u8>,
+    partial_offset: u64,
+    ready: VecDeque<TailLine>,
+    poll_interval: Duration,
+    idle_timeout: Option<Duration>,
+}
+
+pub fn tail<P>(path: P) -> Result<Tail, ErrorChain>
+where P: AsRef<Path> {
+    let mut tail = Tail::from_start(path);
+    let len = std::fs::metadata(&tail.path)
+        .do_on_error(|| format!("failed to tail {}", tail.path.display()))?
+        .len();
+    tail.offset = len;
+    tail.partial_offset = len;
+    return Ok(tail);
+}
+
+impl Tail {
+    pub fn from_start<P>(path: P) -> Tail
+    where P: AsRef<Path> {
+        return Tail {
+            path: path.as_ref().to_path_buf(),
+            file: None,
+            offset: 0,
+            partial: Vec::new(),
+            partial_offset: 0,
+            ready: VecDeque::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            idle_timeout: None,
+        };
+    }
+
+    pub fn poll_interval(mut self, interval: Duration) -> Tail {
+        self.poll_interval = interval;
+        return self;
+    }
+
+    pub fn idle_timeout(mut self, timeout: Duration) -> Tail {
+        self.idle_timeout = Some(timeout);
+        return self;
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    pub fn offset(&self) -> u64 {
+        return self.offset;
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<TailLine>, ErrorChain> {
+        self.read_available()?;
+        return Ok(self.ready.drain(..).collect());
+    }
+
+    fn read_available(&mut self) -> Result<(), ErrorChain> {
+        let len = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            // the file may be briefly missing while a logger rotates it
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                self.file = None;
+                return Ok(());
+            }
+            Err(error) => return Err(ErrorChain::from(error, format!("failed to tail {}", self.path.display()))),
+        };
+        if len < self.offset {
+            // truncated or replaced: start over from the beginning of the new contents
+            self.file = None;
+            self.offset = 0;
+            self.partial.clear();
+            self.partial_offset = 0;
+        }
+        if len == self.offset {
+            return Ok(());
+        }
+        if self.file.is_none() {
+            let mut file = File::open(&self.path).do_on_error(|| format!("failed to open {}", self.path.display()))?;
+            file.seek(SeekFrom::Start(self.offset)).do_on_error(|| format!("failed to seek {}", self.path.display()))?;
+            self.file = Some(file);
+        }
+        let file = self.file.as_mut().expect("file was just opened");
+        let mut chunk = Vec::new();
+        file.take(len - self.offset)
+            .read_to_end(&mut chunk)
+            .do_on_error(|| format!("failed to read {}", self.path.display()))?;
+        let chunk_start = self.offset;
+        self.offset += chunk.len() as u64;
+        for (index, byte) in chunk.into_iter().enumerate() {
+            if byte != b'\n' {
+                self.partial.push(byte);
+                continue;
+            }
+            if self.partial.last() == Some(&b'\r') {
+                self.partial.pop();
+            }
+            let text = String::from_utf8_lossy(&self.partial).into_owned();
+            self.ready.push_back(TailLine { offset: self.partial_offset, text });
+            self.partial.clear();
+            self.partial_offset = chunk_start + index as u64 + 1;
+        }
+        return Ok(());
+    }
+}
+
+impl Iterator for Tail {
+    type Item = Result<TailLine, ErrorChain>;
+
+    fn next(&mut self) -> Option<Result<TailLine, ErrorChain>> {
+        let mut last_activity = Instant::now();
+        loop {
+            if let Some(line) = self.ready.pop_front() {
+                return Some(Ok(line));
+            }
+            let before = self.offset;
+            if let Err(error) = self.read_available() {
+                return Some(Err(error));
+            }
+            if !self.ready.is_empty() {
+                continue;
+            }
+            if self.offset != before {
+                last_activity = Instant::now();
+            }
+            if let Some(timeout) = self.idle_timeout {
+                if last_activity.elapsed() >= timeout {
+                    return None;
+                }
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn test_tail_follows_appends() {
+        let dir = TempDir::new().unwrap().with_file("app.log", "old line\n").unwrap();
+        let path = dir.join("app.log");
+        let mut follower = tail(&path).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(b"first\r\nsec").unwrap();
+        assert_eq!(follower.poll().unwrap(), vec![TailLine { offset: 9, text: String::from("first") }]);
+        log.write_all(b"ond\n").unwrap();
+        assert_eq!(follower.poll().unwrap(), vec![TailLine { offset: 16, text: String::from("second") }]);
+
+        std::fs::write(&path, "rotated\n").unwrap();
+        let lines: Vec<String> = follower.poll_interval(Duration::from_millis(1))
+            .idle_timeout(Duration::from_millis(20))
+            .map(|line| line.unwrap().text)
+            .collect();
+        assert_eq!(lines, vec!["rotated"]);
+    }
+}
@@ -0,0 +1,195 @@
+use std::env;
+use std::fs;
+use std::mem;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn unique_name(prefix: &str) -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.subsec_nanos()).unwrap_or(0);
+    return format!("{}-{}-{}-{:08x}", prefix, std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed), nanos);
+}
+
+fn relative_path(root: &Path, relative: &str) -> Result<PathBuf, ErrorChain> {
+    let path = Path::new(relative);
+    if path.components().any(|component| !matches!(component, Component::Normal(_) | Component::CurDir)) {
+        return Err(ErrorChain::new(format!("path '{}' must stay inside the temporary directory", relative)));
+    }
+    return Ok(root.join(path));
+}
+
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl TempDir {
+    pub fn new() -> Result<TempDir, ErrorChain> {
+        return TempDir::with_prefix("gmec");
+    }
+
+    pub fn with_prefix(prefix: &str) -> Result<TempDir, ErrorChain> {
+        return TempDir::new_in(env::temp_dir(), prefix);
+    }
+
+    pub fn new_in<P>(parent: P, prefix: &str) -> Result<TempDir, ErrorChain>
+    where P: AsRef<Path> {
+        let path = parent.as_ref().join(unique_name(prefix));
+        fs::create_dir_all(&path).do_on_error(|| format!("failed to create temporary directory {}", path.display()))?;
+        return Ok(TempDir { path, keep: false });
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    pub fn join<P>(&self, relative: P) -> PathBuf
+    where P: AsRef<Path> {
+        return self.path.join(relative);
+    }
+
+    pub fn create_dir(&self, relative: &str) -> Result<PathBuf, ErrorChain> {
+        let path = relative_path(&self.path, relative)?;
+        fs::create_dir_all(&path).do_on_error(|| format!("failed to create directory {}", path.display()))?;
+        return Ok(path);
+    }
+
+    pub fn create_file<C>(&self, relative: &str, contents: C) -> Result<PathBuf, ErrorChain>
+    where C: AsRef<[u8]> {
+        let path = relative_path(&self.path, relative)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).do_on_error(|| format!("failed to create directory {}", parent.display()))?;
+        }
+        fs::write(&path, contents).do_on_error(|| format!("failed to write {}", path.display()))?;
+        return Ok(path);
+    }
+
+    pub fn with_dir(self, relative: &str) -> Result<TempDir, ErrorChain> {
+        self.create_dir(relative)?;
+        return Ok(self);
+    }
+
+    pub fn with_file<C>(self, relative: &str, contents: C) -> Result<TempDir, ErrorChain>
+    where C: AsRef<[u8]> {
+        self.create_file(relative, contents)?;
+        return Ok(self);
+    }
+
+    // entries ending in '/' are directories, everything else is a file with the given contents
+    pub fn populate<'s, I>(self, spec: I) -> Result<TempDir, ErrorChain>
+    where I: IntoIterator<Item = (&'s str, &'s str)> {
+        for (relative, contents) in spec {
+            if relative.ends_with('/') {
+                self.create_dir(relative)?;
+            } else {
+                self.create_file(relative, contents)?;
+            }
+        }
+        return Ok(self);
+    }
+
+    pub fn keep(mut self) -> PathBuf {
+        self.keep = true;
+        return mem::take(&mut self.path);
+    }
+
+    pub fn close(mut self) -> Result<(), ErrorChain> {
+        self.keep = true;
+        return fs::remove_dir_all(&self.path).do_on_error(|| format!("failed to remove temporary directory {}", self.path.display()));
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl TempFile {
+    pub fn new() -> Result<TempFile, ErrorChain> {
+        return TempFile::with_contents("");
+    }
+
+    pub fn with_contents<C>(contents: C) -> Result<TempFile, ErrorChain>
+    where C: AsRef<[u8]> {
+        return TempFile::new_in(env::temp_dir(), "gmec", contents);
+    }
+
+    pub fn new_in<P, C>(parent: P, prefix: &str, contents: C) -> Result<TempFile, ErrorChain>
+    where P: AsRef<Path>, C: AsRef<[u8]> {
+        let path = parent.as_ref().join(unique_name(prefix));
+        fs::write(&path, contents).do_on_error(|| format!("failed to create temporary file {}", path.display()))?;
+        return Ok(TempFile { path, keep: false });
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    pub fn read_to_string(&self) -> Result<String, ErrorChain> {
+        return fs::read_to_string(&self.path).do_on_error(|| format!("failed to read {}", self.path.display()));
+    }
+
+    pub fn keep(mut self) -> PathBuf {
+        self.keep = true;
+        return mem::take(&mut self.path);
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_dir_populate_and_cleanup() {
+        let dir = TempDir::new().unwrap()
+            .populate([("src/main.rs", "fn main() {}"), ("empty/", ""), ("README", "hi")])
+            .unwrap();
+        let root = dir.path().to_path_buf();
+        assert_eq!(fs::read_to_string(dir.join("src/main.rs")).unwrap(), "fn main() {}");
+        assert!(dir.join("empty").is_dir());
+        assert!(dir.create_file("../escape", "x").is_err());
+        assert!(dir.create_file("/abs", "x").is_err());
+        drop(dir);
+        assert!(!root.exists());
+
+        let kept = TempDir::with_prefix("gmec-keep").unwrap().with_file("a", "b").unwrap().keep();
+        assert!(kept.join("a").exists());
+        fs::remove_dir_all(kept).unwrap();
+    }
+
+    #[test]
+    fn test_temp_file() {
+        let file = TempFile::with_contents("data").unwrap();
+        let path = file.path().to_path_buf();
+        assert_eq!(file.read_to_string().unwrap(), "data");
+        drop(file);
+        assert!(!path.exists());
+    }
+}
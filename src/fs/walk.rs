@@ -0,0 +1,289 @@
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "archive")]
+use crate::fs::archive;
+use crate::fs::ignore::IgnoreRule;
+use crate::fs::ignore::IgnoreSet;
+use crate::fs::ignore::GITIGNORE;
+use crate::fs::ignore::GMECIGNORE;
+#[cfg(feature = "gzip")]
+use crate::io::gzip;
+use crate::search::budget::global_budget;
+use crate::search::budget::MemoryBudget;
+use crate::search::budget::ReadPlan;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::options::Options;
+
+// lists the files under a set of roots, depth first in name order so results are reproducible.
+// Ignore rules apply in increasing precedence: ignore_file()s, then the per-directory ignore files
+// from the root down (later names win within a directory), then include/exclude overrides. As
+// in git, nothing under an ignored directory can be re-included, since it is never entered
+#[derive(Debug, Clone)]
+pub struct Walker {
+    roots: Vec<PathBuf>,
+    archives: bool,
+    ignore_file_names: Vec<String>,
+    ignores: IgnoreSet,
+    overrides: IgnoreSet,
+    budget: Option<Arc<MemoryBudget>>,
+    allow_missing: bool,
+}
+
+impl Walker {
+    pub fn new<P>(root: P) -> Walker
+    where P: AsRef<Path> {
+        return Walker { roots: vec![root.as_ref().to_path_buf()], archives: false, ignore_file_names: Vec::new(), ignores: IgnoreSet::new(), overrides: IgnoreSet::new(), budget: None, allow_missing: false };
+    }
+
+    pub fn root<P>(mut self, root: P) -> Walker
+    where P: AsRef<Path> {
+        self.roots.push(root.as_ref().to_path_buf());
+        return self;
+    }
+
+    // present zip and tar members as files under the archive's path, e.g. logs.tar/app/a.log
+    #[cfg(feature = "archive")]
+    pub fn archives(mut self, archives: bool) -> Walker {
+        self.archives = archives;
+        return self;
+    }
+
+    // honor .gitignore and .gmecignore files in every directory, and skip .git directories
+    pub fn ignore_files(mut self, enabled: bool) -> Walker {
+        self.ignore_file_names.retain(|name| name != GITIGNORE && name != GMECIGNORE);
+        if enabled {
+            self.ignore_file_names.splice(0..0, [GITIGNORE.to_string(), GMECIGNORE.to_string()]);
+        }
+        return self;
+    }
+
+    // another per-directory ignore file, e.g. ".dockerignore"
+    pub fn ignore_file_name<S>(mut self, name: S) -> Walker
+    where S: Into<String> {
+        self.ignore_file_names.push(name.into());
+        return self;
+    }
+
+    // rules from one file, relative to every root, e.g. a global excludes file
+    pub fn ignore_file<P>(mut self, path: P) -> Result<Walker, ErrorChain>
+    where P: AsRef<Path> {
+        self.ignores.add_file(path.as_ref(), "")?;
+        return Ok(self);
+    }
+
+    // gitignore-syntax pattern that beats every ignore file
+    pub fn exclude(mut self, pattern: &str) -> Walker {
+        self.overrides.extend(pattern, "");
+        return self;
+    }
+
+    // re-includes paths an ignore file excluded; same syntax as exclude, without the '!'
+    pub fn include(mut self, pattern: &str) -> Walker {
+        if let Some(rule) = IgnoreRule::parse(pattern, "") {
+            self.overrides.push(IgnoreRule { negated: true, ..rule });
+        }
+        return self;
+    }
+
+    // files over the budget's max_file_len are skipped or refused by files() as its spill policy
+    // says, and searches over this walker charge their buffered matches to it
+    pub fn budget(mut self, budget: Arc<MemoryBudget>) -> Walker {
+        self.budget = Some(budget);
+        return self;
+    }
+
+    // roots that don't exist, and entries deleted while the walk runs, are left out instead of
+    // failing it; for callers that list the same paths again as they come and go
+    pub fn allow_missing(mut self, allow: bool) -> Walker {
+        self.allow_missing = allow;
+        return self;
+    }
+
+    // this walker's budget, else the global one
+    pub fn memory_budget(&self) -> Option<Arc<MemoryBudget>> {
+        return self.budget.clone().or_else(global_budget);
+    }
+
+    fn is_ignored(&self, relative: &str, is_dir: bool, rules: &IgnoreSet) -> bool {
+        if is_dir && !self.ignore_file_names.is_empty() && relative.rsplit('/').next() == Some(".git") {
+            return true;
+        }
+        return self.overrides.decide(relative, is_dir).or_else(|| rules.decide(relative, is_dir)).unwrap_or(false);
+    }
+
+    pub fn reads_archives(&self) -> bool {
+        return self.archives;
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        return &self.roots;
+    }
+
+    // files on disk; archives are listed as themselves
+    pub fn files(&self) -> Result<Vec<PathBuf>, ErrorChain> {
+        let mut files = Vec::new();
+        let budget = self.memory_budget();
+        if let Some(budget) = &budget {
+            budget.check_valid()?;
+        }
+        for root in &self.roots {
+            let mut rules = self.ignores.clone();
+            self.collect(root, "", &mut rules, &mut Vec::new(), budget.as_deref(), &mut files).do_on_error(|| format!("failed to walk {}", root.display()))?;
+        }
+        return Ok(files);
+    }
+
+    // hands every file, or archive member, to f with a reader over its contents; with the gzip
+    // feature .gz files are decompressed on the fly, so offsets are in the decompressed data
+    pub fn for_each<F>(&self, mut f: F) -> Result<(), ErrorChain>
+    where F: FnMut(&Path, &mut dyn Read) -> Result<(), ErrorChain> {
+        for path in self.files()? {
+            self.read_file(&path, &mut f)?;
+        }
+        return Ok(());
+    }
+
+    // one path from files(), read the way for_each reads it: as its members if it is an archive
+    // this walker descends into, decompressed if it is gzip, as is otherwise
+    pub fn read_file<F>(&self, path: &Path, mut f: F) -> Result<(), ErrorChain>
+    where F: FnMut(&Path, &mut dyn Read) -> Result<(), ErrorChain> {
+        #[cfg(feature = "archive")]
+        if self.archives && archive::is_archive(path) {
+            return archive::for_each_entry(path, |name, reader| f(&path.join(name), reader));
+        }
+        #[cfg(feature = "gzip")]
+        if gzip::is_gzip_path(path) {
+            let mut reader = gzip::open_decompressed(path)?;
+            return f(path, &mut reader).do_on_error(|| format!("failed to read {}", path.display()));
+        }
+        let file = File::open(path).do_on_error(|| format!("failed to open {}", path.display()))?;
+        return f(path, &mut BufReader::new(file)).do_on_error(|| format!("failed to read {}", path.display()));
+    }
+
+    // relative is '/'-separated from the root; rules holds the ignore files of every directory
+    // above path and is restored before returning. Symlinks are followed, but ancestors holds the
+    // real path of every directory being walked, and a link back to one of them is not entered,
+    // since that would loop forever
+    fn collect(&self, path: &Path, relative: &str, rules: &mut IgnoreSet, ancestors: &mut Vec<PathBuf>, budget: Option<&MemoryBudget>, out: &mut Vec<PathBuf>) -> Result<(), ErrorChain> {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if self.allow_missing && err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(ErrorChain::from(err, format!("failed to stat {}", path.display()))),
+        };
+        if !metadata.is_dir() {
+            let plan = match budget {
+                Some(budget) => budget.plan_read(path, metadata.len())?,
+                None => ReadPlan::Whole,
+            };
+            if plan != ReadPlan::Skip {
+                out.push(path.to_path_buf());
+            }
+            return Ok(());
+        }
+        let real = match fs::canonicalize(path) {
+            Ok(real) => real,
+            Err(err) if self.allow_missing && err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(ErrorChain::from(err, format!("failed to resolve {}", path.display()))),
+        };
+        if ancestors.contains(&real) {
+            return Ok(());
+        }
+        let inherited = rules.len();
+        for name in &self.ignore_file_names {
+            rules.add_file(&path.join(name), relative)?;
+        }
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(err) if self.allow_missing && err.kind() == std::io::ErrorKind::NotFound => {
+                rules.truncate(inherited);
+                return Ok(());
+            }
+            Err(err) => return Err(ErrorChain::from(err, format!("failed to list {}", path.display()))),
+        };
+        ancestors.push(real);
+        let mut children = Vec::new();
+        for entry in entries {
+            let entry = entry.do_on_error(|| format!("failed to list {}", path.display()))?;
+            let is_dir = entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false) || entry.path().is_dir();
+            children.push((entry.path(), is_dir));
+        }
+        children.sort();
+        for (child, is_dir) in children {
+            let name = child.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let child_relative = if relative.is_empty() { name } else { format!("{}/{}", relative, name) };
+            if self.is_ignored(&child_relative, is_dir, rules) {
+                continue;
+            }
+            self.collect(&child, &child_relative, rules, ancestors, budget, out)?;
+        }
+        ancestors.pop();
+        rules.truncate(inherited);
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+
+    #[test]
+    fn test_walk_in_name_order() {
+        let dir = TempDir::new().unwrap().populate([("b.txt", "b"), ("a/z.txt", "z"), ("a/c/d.txt", "d"), ("empty/", "")]).unwrap();
+        let files = Walker::new(dir.path()).files().unwrap();
+        let relative: Vec<String> = files.iter().map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/")).collect();
+        assert_eq!(relative, vec!["a/c/d.txt", "a/z.txt", "b.txt"]);
+        let mut contents = String::new();
+        Walker::new(dir.path()).for_each(|_, reader| {
+            reader.read_to_string(&mut contents).on_error("read failed")?;
+            return Ok(());
+        }).unwrap();
+        assert_eq!(contents, "dzb");
+        assert!(Walker::new(dir.join("missing")).files().is_err());
+        assert_eq!(Walker::new(dir.join("missing")).root(dir.join("b.txt")).allow_missing(true).files().unwrap(), vec![dir.join("b.txt")]);
+        let budget = MemoryBudget::new().max_file_len(0).spill(crate::search::budget::SpillPolicy::Skip);
+        assert!(Walker::new(dir.path()).budget(Arc::new(budget)).files().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ignore_files_and_overrides() {
+        let dir = TempDir::new().unwrap().populate([
+            (".gitignore", "target/\n*.log\n"),
+            (".git/config", "x"),
+            ("src/main.rs", "x"),
+            ("src/.gitignore", "generated.rs\n!debug.log\n"),
+            ("src/generated.rs", "x"),
+            ("src/debug.log", "x"),
+            ("app.log", "x"),
+            ("target/out.bin", "x"),
+            ("notes.md", "x"),
+            (".gmecignore", "notes.md\n"),
+        ]).unwrap();
+        let relative = |walker: Walker| -> Vec<String> {
+            return walker.files().unwrap().iter().map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/")).collect();
+        };
+        assert_eq!(relative(Walker::new(dir.path()).ignore_files(true)), vec![".gitignore", ".gmecignore", "src/.gitignore", "src/debug.log", "src/main.rs"]);
+        let walker = Walker::new(dir.path()).ignore_files(true).exclude(".*ignore").include("app.log").include("target/out.bin");
+        assert_eq!(relative(walker), vec!["app.log", "src/debug.log", "src/main.rs"]);
+        assert_eq!(relative(Walker::new(dir.path())).len(), 10);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loops_are_not_entered() {
+        let dir = TempDir::new().unwrap().populate([("a/b/c.txt", "c"), ("d.txt", "d")]).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.join("a/b/up")).unwrap();
+        std::os::unix::fs::symlink(dir.join("a/b"), dir.join("link")).unwrap();
+        let files = Walker::new(dir.path()).files().unwrap();
+        let relative: Vec<String> = files.iter().map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned()).collect();
+        // the link to a/b is a separate path to it, not a loop, so it is listed again
+        assert_eq!(relative, vec!["a/b/c.txt", "d.txt", "link/c.txt"]);
+    }
+}
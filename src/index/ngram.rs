@@ -0,0 +1,154 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use crate::collections::bitset::BitSet;
+use crate::patterns::PatternMatcher;
+use crate::types::span::Span;
+
+pub const DEFAULT_GRAM_LEN: usize = 3;
+
+// maps every n-byte window to the documents containing it; a pattern can only occur in documents
+// that contain all of its grams, so exact matching only has to run over that intersection
+#[derive(Debug, Clone)]
+pub struct NGramIndex {
+    gram_len: usize,
+    documents: Vec<Option<Vec<u8>>>,
+    postings: HashMap<Vec<u8>, BitSet>,
+    live: usize,
+}
+
+impl NGramIndex {
+    pub fn new() -> NGramIndex {
+        return NGramIndex::with_gram_len(DEFAULT_GRAM_LEN);
+    }
+
+    pub fn with_gram_len(gram_len: usize) -> NGramIndex {
+        return NGramIndex { gram_len: gram_len.max(1), documents: Vec::new(), postings: HashMap::new(), live: 0 };
+    }
+
+    pub fn gram_len(&self) -> usize {
+        return self.gram_len;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.live;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.live == 0;
+    }
+
+    fn grams<'d>(&self, bytes: &'d [u8]) -> BTreeSet<&'d [u8]> {
+        return bytes.windows(self.gram_len).collect();
+    }
+
+    pub fn add<D>(&mut self, document: D) -> usize
+    where D: AsRef<[u8]> {
+        let id = self.documents.len();
+        let bytes = document.as_ref().to_vec();
+        for gram in self.grams(&bytes) {
+            self.postings.entry(gram.to_vec()).or_default().insert(id);
+        }
+        self.documents.push(Some(bytes));
+        self.live += 1;
+        return id;
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Vec<u8>> {
+        let bytes = self.documents.get_mut(id)?.take()?;
+        for gram in self.grams(&bytes) {
+            if let Some(posting) = self.postings.get_mut(gram) {
+                posting.remove(id);
+                if posting.is_empty() {
+                    self.postings.remove(gram);
+                }
+            }
+        }
+        self.live -= 1;
+        return Some(bytes);
+    }
+
+    pub fn get(&self, id: usize) -> Option<&[u8]> {
+        return self.documents.get(id)?.as_deref();
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        return self.documents.iter().enumerate().filter(|(_, document)| document.is_some()).map(|(id, _)| id);
+    }
+
+    // ids of documents that might contain the pattern, ascending; patterns shorter than a gram can't be filtered
+    pub fn candidates<P>(&self, pattern: P) -> Vec<usize>
+    where P: AsRef<[u8]> {
+        let pattern = pattern.as_ref();
+        if pattern.len() < self.gram_len {
+            return self.ids().collect();
+        }
+        let mut postings = Vec::new();
+        for gram in self.grams(pattern) {
+            match self.postings.get(gram) {
+                Some(posting) => postings.push(posting),
+                None => return Vec::new(),
+            }
+        }
+        let mut candidates = postings[0].clone();
+        for posting in &postings[1..] {
+            candidates.intersect_with(posting);
+        }
+        return candidates.iter().collect();
+    }
+
+    // candidate filtering followed by exact matching; yields every document with at least one match
+    pub fn search<P>(&self, pattern: P) -> Vec<(usize, Vec<Span>)>
+    where P: AsRef<[u8]> {
+        let pattern = pattern.as_ref();
+        let mut found = Vec::new();
+        for id in self.candidates(pattern) {
+            let document = self.get(id).expect("candidates are live documents");
+            if let Some(matches) = document.find_every(&pattern) {
+                found.push((id, matches.iter().map(|hit| Span::new(hit.index, hit.index + hit.length)).collect()));
+            }
+        }
+        return found;
+    }
+}
+
+impl Default for NGramIndex {
+    fn default() -> NGramIndex {
+        return NGramIndex::new();
+    }
+}
+
+impl<D> FromIterator<D> for NGramIndex
+where D: AsRef<[u8]> {
+    fn from_iter<I: IntoIterator<Item = D>>(iter: I) -> NGramIndex {
+        let mut index = NGramIndex::new();
+        for document in iter {
+            index.add(document);
+        }
+        return index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_and_search() {
+        let mut index: NGramIndex = ["fn main() {}", "let mainly = 1;", "struct Domain;", "no match here"].into_iter().collect();
+        assert_eq!(index.candidates("main"), vec![0, 1, 2]);
+        assert_eq!(index.candidates("xyz"), Vec::<usize>::new());
+        assert_eq!(index.candidates("ma").len(), 4);
+        let found = index.search("main");
+        assert_eq!(found, vec![(0, vec![Span::new(3, 7)]), (1, vec![Span::new(4, 8)]), (2, vec![Span::new(9, 13)])]);
+        // every gram of "amain" occurs in "main; amai", but the pattern itself does not
+        let id = index.add("main; amai");
+        assert_eq!(index.candidates("amain"), vec![id]);
+        assert!(index.search("amain").is_empty());
+
+        assert_eq!(index.remove(1).as_deref(), Some(&b"let mainly = 1;"[..]));
+        assert_eq!(index.remove(1), None);
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.candidates("main"), vec![0, 2, id]);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod counting;
+#[cfg(feature = "gzip")]
+pub mod gzip;
+pub mod limited;
+pub mod multi;
+pub mod tee;
+pub mod transform;
@@ -0,0 +1,108 @@
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+#[derive(Debug)]
+pub struct CountingReader<R>
+where R: Read {
+    inner: R,
+    bytes: u64,
+    lines: u64,
+}
+
+impl<R> CountingReader<R>
+where R: Read {
+    pub fn new(inner: R) -> CountingReader<R> {
+        return CountingReader { inner, bytes: 0, lines: 0 };
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        return self.bytes;
+    }
+
+    pub fn lines_read(&self) -> u64 {
+        return self.lines;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        return &self.inner;
+    }
+
+    pub fn into_inner(self) -> R {
+        return self.inner;
+    }
+}
+
+impl<R> Read for CountingReader<R>
+where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes += read as u64;
+        self.lines += buf[..read].iter().filter(|byte| **byte == b'\n').count() as u64;
+        return Ok(read);
+    }
+}
+
+#[derive(Debug)]
+pub struct CountingWriter<W>
+where W: Write {
+    inner: W,
+    bytes: u64,
+    lines: u64,
+}
+
+impl<W> CountingWriter<W>
+where W: Write {
+    pub fn new(inner: W) -> CountingWriter<W> {
+        return CountingWriter { inner, bytes: 0, lines: 0 };
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        return self.bytes;
+    }
+
+    pub fn lines_written(&self) -> u64 {
+        return self.lines;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        return &self.inner;
+    }
+
+    pub fn into_inner(self) -> W {
+        return self.inner;
+    }
+}
+
+impl<W> Write for CountingWriter<W>
+where W: Write {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        self.lines += buf[..written].iter().filter(|byte| **byte == b'\n').count() as u64;
+        return Ok(written);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_bytes_and_lines() {
+        let mut reader = CountingReader::new(&b"one\ntwo\nthree"[..]);
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!((reader.bytes_read(), reader.lines_read()), (13, 2));
+
+        let mut writer = CountingWriter::new(Vec::new());
+        writeln!(writer, "a").unwrap();
+        writeln!(writer, "bc").unwrap();
+        assert_eq!((writer.bytes_written(), writer.lines_written()), (5, 2));
+        assert_eq!(writer.into_inner(), b"a\nbc\n");
+    }
+}
@@ -0,0 +1,438 @@
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+const WINDOW_LEN: usize = 32 * 1024;
+const MAX_BITS: usize = 15;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// the order code length code lengths are sent in
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn invalid(message: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, ErrorChain::new(message.to_string()));
+}
+
+struct BitReader<R> {
+    inner: R,
+    bits: u64,
+    count: u32,
+}
+
+impl<R> BitReader<R>
+where R: Read {
+    fn need(&mut self, count: u32) -> io::Result<()> {
+        while self.count < count {
+            let mut byte = [0u8; 1];
+            if self.inner.read(&mut byte)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, ErrorChain::new("compressed data is truncated")));
+            }
+            self.bits |= (byte[0] as u64) << self.count;
+            self.count += 8;
+        }
+        return Ok(());
+    }
+
+    fn bits(&mut self, count: u32) -> io::Result<u32> {
+        if count == 0 {
+            return Ok(0);
+        }
+        self.need(count)?;
+        let value = (self.bits & ((1u64 << count) - 1)) as u32;
+        self.bits >>= count;
+        self.count -= count;
+        return Ok(value);
+    }
+
+    fn align(&mut self) {
+        let partial = self.count % 8;
+        self.bits >>= partial;
+        self.count -= partial;
+    }
+
+    // after align(): whole bytes still in the bit buffer come first
+    fn byte(&mut self) -> io::Result<u8> {
+        return self.bits(8).map(|value| value as u8);
+    }
+
+    // true if there is at least one more byte of input
+    fn has_more(&mut self) -> io::Result<bool> {
+        if self.count >= 8 {
+            return Ok(true);
+        }
+        let mut byte = [0u8; 1];
+        if self.inner.read(&mut byte)? == 0 {
+            return Ok(false);
+        }
+        self.bits |= (byte[0] as u64) << self.count;
+        self.count += 8;
+        return Ok(true);
+    }
+}
+
+// canonical Huffman code, decoded a bit at a time
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for bits in 1..=MAX_BITS {
+            offsets[bits + 1] = offsets[bits] + counts[bits];
+        }
+        let mut symbols = vec![0u16; offsets[MAX_BITS + 1] as usize];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                symbols[offsets[*length as usize] as usize] = symbol as u16;
+                offsets[*length as usize] += 1;
+            }
+        }
+        return Huffman { counts, symbols };
+    }
+
+    fn decode<R>(&self, input: &mut BitReader<R>) -> io::Result<u16>
+    where R: Read {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for bits in 1..=MAX_BITS {
+            code |= input.bits(1)? as i32;
+            let count = self.counts[bits] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        return Err(invalid("invalid Huffman code"));
+    }
+}
+
+enum Block {
+    Header,
+    Stored(usize),
+    Coded(Box<(Huffman, Huffman)>),
+    Done,
+}
+
+// raw DEFLATE (RFC 1951) decoder, e.g. for zip entries
+pub struct Inflater<R> {
+    input: BitReader<R>,
+    block: Block,
+    last_block: bool,
+    window: Vec<u8>,
+    window_pos: usize,
+    // a back reference not yet fully copied out: (length left, distance)
+    copy: Option<(usize, usize)>,
+}
+
+impl<R> Inflater<R>
+where R: Read {
+    pub fn new(inner: R) -> Inflater<R> {
+        return Inflater { input: BitReader { inner, bits: 0, count: 0 }, block: Block::Header, last_block: false, window: vec![0; WINDOW_LEN], window_pos: 0, copy: None };
+    }
+
+    pub fn is_done(&self) -> bool {
+        return matches!(self.block, Block::Done);
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.window[self.window_pos % WINDOW_LEN] = byte;
+        self.window_pos += 1;
+    }
+
+    fn start_block(&mut self) -> io::Result<()> {
+        if self.last_block {
+            self.block = Block::Done;
+            return Ok(());
+        }
+        self.last_block = self.input.bits(1)? == 1;
+        self.block = match self.input.bits(2)? {
+            0 => {
+                self.input.align();
+                let len = self.input.bits(16)?;
+                let complement = self.input.bits(16)?;
+                if len != !complement & 0xffff {
+                    return Err(invalid("stored block length check failed"));
+                }
+                Block::Stored(len as usize)
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                Block::Coded(Box::new((Huffman::new(&lengths), Huffman::new(&[5; 30]))))
+            }
+            2 => Block::Coded(Box::new(self.dynamic_tables()?)),
+            _ => return Err(invalid("reserved block type")),
+        };
+        return Ok(());
+    }
+
+    fn dynamic_tables(&mut self) -> io::Result<(Huffman, Huffman)> {
+        let literal_count = self.input.bits(5)? as usize + 257;
+        let distance_count = self.input.bits(5)? as usize + 1;
+        let code_count = self.input.bits(4)? as usize + 4;
+        let mut code_lengths = [0u8; 19];
+        for position in CODE_LENGTH_ORDER.iter().take(code_count) {
+            code_lengths[*position] = self.input.bits(3)? as u8;
+        }
+        let code = Huffman::new(&code_lengths);
+        let mut lengths = vec![0u8; literal_count + distance_count];
+        let mut index = 0;
+        while index < lengths.len() {
+            let symbol = code.decode(&mut self.input)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 if index > 0 => (lengths[index - 1], 3 + self.input.bits(2)? as usize),
+                17 => (0, 3 + self.input.bits(3)? as usize),
+                18 => (0, 11 + self.input.bits(7)? as usize),
+                _ => return Err(invalid("invalid code length repeat")),
+            };
+            if index + repeat > lengths.len() {
+                return Err(invalid("code lengths overflow the table"));
+            }
+            lengths[index..index + repeat].fill(value);
+            index += repeat;
+        }
+        return Ok((Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..])));
+    }
+}
+
+impl<R> Read for Inflater<R>
+where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            if let Some((left, distance)) = self.copy {
+                let byte = self.window[(self.window_pos - distance) % WINDOW_LEN];
+                self.push(byte);
+                buf[written] = byte;
+                written += 1;
+                self.copy = if left > 1 { Some((left - 1, distance)) } else { None };
+                continue;
+            }
+            match &self.block {
+                Block::Done => break,
+                Block::Header => self.start_block()?,
+                Block::Stored(0) => self.block = Block::Header,
+                Block::Stored(left) => {
+                    let left = *left;
+                    let byte = self.input.byte()?;
+                    self.push(byte);
+                    buf[written] = byte;
+                    written += 1;
+                    self.block = Block::Stored(left - 1);
+                }
+                Block::Coded(tables) => {
+                    let symbol = tables.0.decode(&mut self.input)? as usize;
+                    if symbol < 256 {
+                        self.push(symbol as u8);
+                        buf[written] = symbol as u8;
+                        written += 1;
+                    } else if symbol == 256 {
+                        self.block = Block::Header;
+                    } else {
+                        let code = symbol - 257;
+                        if code >= LENGTH_BASE.len() {
+                            return Err(invalid("invalid length code"));
+                        }
+                        let length = LENGTH_BASE[code] as usize + self.input.bits(LENGTH_EXTRA[code] as u32)? as usize;
+                        let code = tables.1.decode(&mut self.input)? as usize;
+                        if code >= DISTANCE_BASE.len() {
+                            return Err(invalid("invalid distance code"));
+                        }
+                        let distance = DISTANCE_BASE[code] as usize + self.input.bits(DISTANCE_EXTRA[code] as u32)? as usize;
+                        if distance > self.window_pos.min(WINDOW_LEN) {
+                            return Err(invalid("back reference before the start of the data"));
+                        }
+                        self.copy = Some((length, distance));
+                    }
+                }
+            }
+        }
+        return Ok(written);
+    }
+}
+
+fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (index, entry) in table.iter_mut().enumerate() {
+        let mut crc = index as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { 0xedb88320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+        *entry = crc;
+    }
+    return table;
+}
+
+// gzip (RFC 1952) decoder; concatenated members, as left by `cat a.gz b.gz`, read as one stream,
+// and each member's CRC and length are checked at its end
+pub struct GzipReader<R> {
+    inflater: Inflater<R>,
+    in_member: bool,
+    crc_table: [u32; 256],
+    crc: u32,
+    len: u32,
+}
+
+impl<R> GzipReader<R>
+where R: Read {
+    pub fn new(inner: R) -> GzipReader<R> {
+        return GzipReader { inflater: Inflater::new(inner), in_member: false, crc_table: crc32_table(), crc: !0, len: 0 };
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        let input = &mut self.inflater.input;
+        let mut header = [0u8; 10];
+        for byte in header.iter_mut() {
+            *byte = input.byte()?;
+        }
+        if header[..2] != GZIP_MAGIC || header[2] != 8 {
+            return Err(invalid("not gzip data"));
+        }
+        let flags = header[3];
+        if flags & 0x04 != 0 {
+            let extra_len = input.bits(16)?;
+            for _ in 0..extra_len {
+                input.byte()?;
+            }
+        }
+        // file name, then comment, each NUL-terminated
+        for flag in [0x08, 0x10] {
+            if flags & flag != 0 {
+                while input.byte()? != 0 {}
+            }
+        }
+        if flags & 0x02 != 0 {
+            input.bits(16)?;
+        }
+        self.inflater.block = Block::Header;
+        self.inflater.last_block = false;
+        self.inflater.window_pos = 0;
+        self.in_member = true;
+        self.crc = !0;
+        self.len = 0;
+        return Ok(());
+    }
+
+    fn check_trailer(&mut self) -> io::Result<()> {
+        let input = &mut self.inflater.input;
+        input.align();
+        let crc = input.bits(16)? | (input.bits(16)? << 16);
+        let len = input.bits(16)? | (input.bits(16)? << 16);
+        if crc != !self.crc {
+            return Err(invalid("gzip CRC check failed"));
+        }
+        if len != self.len {
+            return Err(invalid("gzip length check failed"));
+        }
+        self.in_member = false;
+        return Ok(());
+    }
+}
+
+impl<R> Read for GzipReader<R>
+where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.in_member {
+                if !self.inflater.input.has_more()? {
+                    return Ok(0);
+                }
+                self.read_header()?;
+            }
+            let read = self.inflater.read(buf)?;
+            for byte in &buf[..read] {
+                self.crc = self.crc_table[((self.crc ^ *byte as u32) & 0xff) as usize] ^ (self.crc >> 8);
+            }
+            self.len = self.len.wrapping_add(read as u32);
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            self.check_trailer()?;
+        }
+    }
+}
+
+pub fn is_gzip_path(path: &Path) -> bool {
+    return path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+}
+
+// a reader over the file's contents, decompressed if it has a .gz extension
+pub fn open_decompressed(path: &Path) -> Result<Box<dyn Read>, ErrorChain> {
+    let file = BufReader::new(File::open(path).do_on_error(|| format!("failed to open {}", path.display()))?);
+    if is_gzip_path(path) {
+        return Ok(Box::new(GzipReader::new(file)));
+    }
+    return Ok(Box::new(file));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(src: &str) -> Vec<u8> {
+        return (0..src.len()).step_by(2).map(|at| u8::from_str_radix(&src[at..at + 2], 16).unwrap()).collect();
+    }
+
+    const FIXED: &str = "1f8b0800000000000203cb48cdc9c957c84022d3ab320bb8009a8b73da17000000";
+    const DYNAMIC: &str = "1f8b08000000000002038d94bb4a834110467b9f625f203097bd161616116c12f81b8b90c2228588c45c7c7f919d4112846ffa0373d8c37e4292575456c4e965f3bc4dbbb7aff77d3a1f4edf87cb3551ba1e8f1f8ff47979903ff0f569d9dc813cc1760bae9765bbdc913249ceb7e83fc77592c2f07a36b2e3f365a25ae0f93ac92cf07c3372e0f37da2a5c2f3639255f1db5ba546f83e7ba80605d84af58c0d2cd5e08081c51a1d1b582ca682152c17b3041c9ac3034b5832968a252c1aab063e02f94f202821ec6c8312e23face4808495e3ca58223bdbb184a76b2520e1edba60094fd70796f074a30624ac9d90e265229f26bc8ccace06c651ad9d48601e7d1f150fa4fa426a6022d5da49c623a9964e0a9e496dce068652ad9d543c95eae9da6fba1f551d4870d2060000";
+
+    fn gunzip(data: &[u8]) -> io::Result<String> {
+        let mut out = String::new();
+        GzipReader::new(data).read_to_string(&mut out)?;
+        return Ok(out);
+    }
+
+    #[test]
+    fn test_gunzip_fixed_dynamic_and_concatenated() {
+        assert_eq!(gunzip(&hex(FIXED)).unwrap(), "hello hello hello gzip\n");
+        let lines: String = (0..40).map(|i| format!("2024-05-01 {} [api] request {} took={}ms\n", ["INFO", "WARN", "ERROR"][i % 3], i, i * 7 % 500)).collect();
+        assert_eq!(gunzip(&hex(DYNAMIC)).unwrap(), lines);
+        let both = [hex(FIXED), hex(DYNAMIC)].concat();
+        assert_eq!(gunzip(&both).unwrap(), format!("hello hello hello gzip\n{}", lines));
+    }
+
+    #[test]
+    fn test_corrupt_input() {
+        let mut corrupt = hex(FIXED);
+        let crc_at = corrupt.len() - 8;
+        corrupt[crc_at] ^= 1;
+        assert!(gunzip(&corrupt).unwrap_err().to_string().contains("CRC"));
+        assert!(gunzip(&hex(FIXED)[..20]).is_err());
+        assert!(gunzip(b"plain text").is_err());
+    }
+
+    #[test]
+    fn test_walker_scans_rotated_logs() {
+        let dir = crate::fs::temp::TempDir::new().unwrap();
+        dir.create_file("app.log.1.gz", hex(DYNAMIC)).unwrap();
+        let set = crate::patterns::PatternSet::new().with("ERROR");
+        let mut found = Vec::new();
+        crate::search::stream::scan_walker(&crate::fs::walk::Walker::new(dir.path()), &set, |_, found_match| {
+            found.push(found_match.index);
+            return Ok(());
+        }).unwrap();
+        assert_eq!(found.len(), 13);
+        assert_eq!(found[0], 93);
+    }
+}
@@ -0,0 +1,70 @@
+use std::io;
+use std::io::Read;
+
+use crate::types::error_chain::ErrorChain;
+
+#[derive(Debug)]
+pub struct LimitedReader<R>
+where R: Read {
+    inner: R,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<R> LimitedReader<R>
+where R: Read {
+    pub fn new(inner: R, limit: u64) -> LimitedReader<R> {
+        return LimitedReader { inner, limit, remaining: limit };
+    }
+
+    pub fn limit(&self) -> u64 {
+        return self.limit;
+    }
+
+    pub fn remaining(&self) -> u64 {
+        return self.remaining;
+    }
+
+    pub fn into_inner(self) -> R {
+        return self.inner;
+    }
+}
+
+// unlike Take, running past the limit is an error rather than a silent end of input
+impl<R> Read for LimitedReader<R>
+where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            let mut probe = [0u8; 1];
+            if self.inner.read(&mut probe)? == 0 {
+                return Ok(0);
+            }
+            return Err(io::Error::other(ErrorChain::new(format!("input exceeds the limit of {} bytes", self.limit))));
+        }
+        let max = buf.len().min(self.remaining.min(usize::MAX as u64) as usize);
+        let read = self.inner.read(&mut buf[..max])?;
+        self.remaining -= read as u64;
+        return Ok(read);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_exact_and_exceeded() {
+        let mut exact = LimitedReader::new(&b"12345"[..], 5);
+        let mut out = Vec::new();
+        exact.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"12345");
+
+        let mut over = LimitedReader::new(&b"123456"[..], 5);
+        let error = over.read_to_end(&mut Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("limit of 5 bytes"));
+        assert!(error.get_ref().and_then(|inner| inner.downcast_ref::<ErrorChain>()).is_some());
+    }
+}
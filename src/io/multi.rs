@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::Read;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceOffset {
+    pub source: usize,
+    pub local_offset: u64,
+}
+
+#[derive(Debug)]
+pub struct MultiReader<R>
+where R: Read {
+    pending: VecDeque<R>,
+    current: Option<R>,
+    current_index: usize,
+    // starts[i] is the absolute offset where source i begins; only sources already reached are recorded
+    starts: Vec<u64>,
+    position: u64,
+}
+
+impl<R> MultiReader<R>
+where R: Read {
+    pub fn new<I>(sources: I) -> MultiReader<R>
+    where I: IntoIterator<Item = R> {
+        let mut pending: VecDeque<R> = sources.into_iter().collect();
+        let current = pending.pop_front();
+        let starts = if current.is_some() { vec![0] } else { Vec::new() };
+        return MultiReader { pending, current, current_index: 0, starts, position: 0 };
+    }
+
+    pub fn push(&mut self, source: R) {
+        if self.current.is_none() && self.pending.is_empty() {
+            self.current_index = self.starts.len();
+            self.starts.push(self.position);
+            self.current = Some(source);
+            return;
+        }
+        self.pending.push_back(source);
+    }
+
+    pub fn position(&self) -> u64 {
+        return self.position;
+    }
+
+    pub fn current_source(&self) -> Option<usize> {
+        return self.current.as_ref().map(|_| self.current_index);
+    }
+
+    pub fn sources_seen(&self) -> usize {
+        return self.starts.len();
+    }
+
+    pub fn locate(&self, offset: u64) -> Option<SourceOffset> {
+        if offset >= self.position {
+            return None;
+        }
+        let source = self.starts.partition_point(|start| *start <= offset) - 1;
+        return Some(SourceOffset { source, local_offset: offset - self.starts[source] });
+    }
+
+    pub fn source_range(&self, source: usize) -> Option<Range<u64>> {
+        let start = *self.starts.get(source)?;
+        let end = self.starts.get(source + 1).copied().unwrap_or(self.position);
+        return Some(start..end);
+    }
+
+    pub fn locate_range(&self, range: Range<u64>) -> Option<(SourceOffset, SourceOffset)> {
+        let start = self.locate(range.start)?;
+        let end = if range.end > range.start { self.locate(range.end - 1)? } else { start };
+        return Some((start, SourceOffset { source: end.source, local_offset: end.local_offset + (range.end > range.start) as u64 }));
+    }
+}
+
+impl<R> Read for MultiReader<R>
+where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while let Some(current) = self.current.as_mut() {
+            let read = current.read(buf)?;
+            if read > 0 {
+                self.position += read as u64;
+                return Ok(read);
+            }
+            self.current = self.pending.pop_front();
+            if self.current.is_some() {
+                self.current_index += 1;
+                self.starts.push(self.position);
+            }
+        }
+        return Ok(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concatenates_and_maps_offsets() {
+        let mut reader = MultiReader::new(vec![&b"app.log.1\n"[..], &b""[..], &b"app.log\n"[..]]);
+        let mut all = String::new();
+        reader.read_to_string(&mut all).unwrap();
+        assert_eq!(all, "app.log.1\napp.log\n");
+        assert_eq!(reader.locate(3), Some(SourceOffset { source: 0, local_offset: 3 }));
+        assert_eq!(reader.locate(10), Some(SourceOffset { source: 2, local_offset: 0 }));
+        assert_eq!(reader.locate(18), None);
+        assert_eq!(reader.source_range(1), Some(10..10));
+        let hit = all.find("log\n").unwrap() as u64;
+        let (start, end) = reader.locate_range(hit..hit + 4).unwrap();
+        assert_eq!((start.source, start.local_offset, end.local_offset), (2, 4, 8));
+    }
+}
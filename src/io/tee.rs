@@ -0,0 +1,51 @@
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+#[derive(Debug)]
+pub struct TeeReader<R, W>
+where R: Read, W: Write {
+    reader: R,
+    writer: W,
+}
+
+impl<R, W> TeeReader<R, W>
+where R: Read, W: Write {
+    pub fn new(reader: R, writer: W) -> TeeReader<R, W> {
+        return TeeReader { reader, writer };
+    }
+
+    pub fn writer(&self) -> &W {
+        return &self.writer;
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        return (self.reader, self.writer);
+    }
+}
+
+impl<R, W> Read for TeeReader<R, W>
+where R: Read, W: Write {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.writer.write_all(&buf[..read])?;
+        return Ok(read);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tee_copies_everything_read() {
+        let mut tee = TeeReader::new(&b"mirrored bytes"[..], Vec::new());
+        let mut first = [0u8; 8];
+        tee.read_exact(&mut first).unwrap();
+        assert_eq!(tee.writer(), b"mirrored");
+        let mut rest = Vec::new();
+        tee.read_to_end(&mut rest).unwrap();
+        let (_, copy) = tee.into_inner();
+        assert_eq!(copy, b"mirrored bytes");
+    }
+}
@@ -0,0 +1,129 @@
+use std::io;
+use std::io::Write;
+
+use crate::patterns::PatternSet;
+use crate::patterns::SetMatch;
+use crate::search::stream::for_each_decided;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+type Replacer = Box<dyn FnMut(&SetMatch, &[u8]) -> Vec<u8> + Send>;
+
+pub struct TransformWriter<W, P>
+where W: Write, P: AsRef<[u8]> {
+    inner: Option<W>,
+    set: PatternSet<P>,
+    replacer: Replacer,
+    pending: Vec<u8>,
+    max_len: usize,
+    replaced: u64,
+}
+
+impl<W, P> TransformWriter<W, P>
+where W: Write, P: AsRef<[u8]> {
+    pub fn with<F>(inner: W, set: PatternSet<P>, replacer: F) -> TransformWriter<W, P>
+    where F: FnMut(&SetMatch, &[u8]) -> Vec<u8> + Send + 'static {
+        let max_len = set.patterns().iter().map(|pattern| pattern.as_ref().len()).max().unwrap_or(0);
+        return TransformWriter { inner: Some(inner), set, replacer: Box::new(replacer), pending: Vec::new(), max_len, replaced: 0 };
+    }
+
+    pub fn replace<R>(inner: W, set: PatternSet<P>, replacements: Vec<R>) -> TransformWriter<W, P>
+    where R: AsRef<[u8]> {
+        let replacements: Vec<Vec<u8>> = replacements.iter().map(|replacement| replacement.as_ref().to_vec()).collect();
+        return TransformWriter::with(inner, set, move |found, _| {
+            return replacements.get(found.pattern_id).or(replacements.last()).cloned().unwrap_or_default();
+        });
+    }
+
+    pub fn redact(inner: W, set: PatternSet<P>, mask: &[u8]) -> TransformWriter<W, P> {
+        let mask = mask.to_vec();
+        return TransformWriter::with(inner, set, move |_, _| mask.clone());
+    }
+
+    pub fn replaced(&self) -> u64 {
+        return self.replaced;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        return self.inner.as_ref().expect("inner writer is only taken by finish");
+    }
+
+    // bytes that might still begin a match are held back until more input or finish() decides them
+    fn process(&mut self, at_end: bool) -> io::Result<()> {
+        let inner = self.inner.as_mut().expect("inner writer is only taken by finish");
+        let mut out = Vec::with_capacity(self.pending.len());
+        let mut pos = 0;
+        let (pending, replacer, replaced) = (&self.pending, &mut self.replacer, &mut self.replaced);
+        let decided = for_each_decided(&self.set, pending, self.max_len, at_end, |found| {
+            out.extend_from_slice(&pending[pos..found.index]);
+            out.extend_from_slice(&replacer(&found, &pending[found.range()]));
+            *replaced += 1;
+            pos = found.end();
+            return Ok::<(), io::Error>(());
+        })?;
+        out.extend_from_slice(&self.pending[pos..decided]);
+        inner.write_all(&out)?;
+        self.pending.drain(..decided);
+        return Ok(());
+    }
+
+    pub fn finish(mut self) -> Result<W, ErrorChain> {
+        self.process(true).on_error("failed to flush transformed output")?;
+        let mut inner = self.inner.take().expect("inner writer is only taken by finish");
+        inner.flush().on_error("failed to flush transformed output")?;
+        return Ok(inner);
+    }
+}
+
+impl<W, P> Write for TransformWriter<W, P>
+where W: Write, P: AsRef<[u8]> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.process(false)?;
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.as_mut().expect("inner writer is only taken by finish").flush();
+    }
+}
+
+impl<W, P> Drop for TransformWriter<W, P>
+where W: Write, P: AsRef<[u8]> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.process(true);
+            let _ = self.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacement_across_write_boundaries() {
+        let set = PatternSet::new().with("secret").with("token");
+        let mut writer = TransformWriter::replace(Vec::new(), set, vec!["[S]", "[T]"]);
+        for chunk in ["my sec", "ret is ", "t", "oken", "; secre"] {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        assert_eq!(writer.get_ref().as_slice(), b"my [S] is [T]; ");
+        let out = writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "my [S] is [T]; secre");
+    }
+
+    #[test]
+    fn test_redact_prefers_longest_and_flushes_on_drop() {
+        let mut out = Vec::new();
+        {
+            let set = PatternSet::new().with("pass").with("password");
+            let mut writer = TransformWriter::redact(&mut out, set, b"***");
+            write!(writer, "password=pa").unwrap();
+            write!(writer, "ss").unwrap();
+            assert_eq!(writer.replaced(), 1);
+        }
+        assert_eq!(out, b"***=***");
+    }
+}
@@ -0,0 +1,3 @@
+pub mod adapters;
+pub mod multi_peek;
+pub mod try_collect;
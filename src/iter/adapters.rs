@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::iter::Peekable;
+
+use super::multi_peek::MultiPeek;
+
+pub struct UniqueBy<I, F, K> {
+    iter: I,
+    key: F,
+    seen: HashSet<K>,
+}
+
+impl<I, F, K> Iterator for UniqueBy<I, F, K>
+where I: Iterator, F: FnMut(&I::Item) -> K, K: Hash + Eq {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let seen = &mut self.seen;
+        let key = &mut self.key;
+        return self.iter.by_ref().find(|item| seen.insert(key(item)));
+    }
+}
+
+pub struct GroupRunsBy<I, F>
+where I: Iterator {
+    iter: Peekable<I>,
+    key: F,
+}
+
+impl<I, F, K> Iterator for GroupRunsBy<I, F>
+where I: Iterator, F: FnMut(&I::Item) -> K, K: PartialEq {
+    type Item = (K, Vec<I::Item>);
+
+    fn next(&mut self) -> Option<(K, Vec<I::Item>)> {
+        let first = self.iter.next()?;
+        let key = (self.key)(&first);
+        let mut run = vec![first];
+        while let Some(item) = self.iter.next_if(|item| (self.key)(item) == key) {
+            run.push(item);
+        }
+        return Some((key, run));
+    }
+}
+
+pub struct DedupAdjacentBy<I, F>
+where I: Iterator {
+    iter: Peekable<I>,
+    same: F,
+}
+
+impl<I, F> Iterator for DedupAdjacentBy<I, F>
+where I: Iterator, F: FnMut(&I::Item, &I::Item) -> bool {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.iter.next()?;
+        while self.iter.next_if(|next| (self.same)(&item, next)).is_some() {}
+        return Some(item);
+    }
+}
+
+pub trait IterAdapters: Iterator + Sized {
+    fn unique_by<K, F>(self, key: F) -> UniqueBy<Self, F, K>
+    where F: FnMut(&Self::Item) -> K, K: Hash + Eq {
+        return UniqueBy { iter: self, key, seen: HashSet::new() };
+    }
+
+    fn group_runs_by<K, F>(self, key: F) -> GroupRunsBy<Self, F>
+    where F: FnMut(&Self::Item) -> K, K: PartialEq {
+        return GroupRunsBy { iter: self.peekable(), key };
+    }
+
+    // groups every item by key, not just adjacent ones; groups appear in order of their first item
+    fn chunk_by_key<K, F>(self, mut key: F) -> std::vec::IntoIter<(K, Vec<Self::Item>)>
+    where F: FnMut(&Self::Item) -> K, K: Hash + Eq + Clone {
+        let mut index: HashMap<K, usize> = HashMap::new();
+        let mut groups: Vec<(K, Vec<Self::Item>)> = Vec::new();
+        for item in self {
+            let item_key = key(&item);
+            let slot = *index.entry(item_key.clone()).or_insert_with(|| {
+                groups.push((item_key, Vec::new()));
+                groups.len() - 1
+            });
+            groups[slot].1.push(item);
+        }
+        return groups.into_iter();
+    }
+
+    fn dedup_adjacent_by<F>(self, same: F) -> DedupAdjacentBy<Self, F>
+    where F: FnMut(&Self::Item, &Self::Item) -> bool {
+        return DedupAdjacentBy { iter: self.peekable(), same };
+    }
+
+    fn multi_peek(self) -> MultiPeek<Self> {
+        return MultiPeek::new(self);
+    }
+}
+
+impl<I> IterAdapters for I
+where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_and_dedup() {
+        let words = ["b", "a", "B", "c", "a"];
+        let unique: Vec<&str> = words.iter().copied().unique_by(|word| word.to_lowercase()).collect();
+        assert_eq!(unique, vec!["b", "a", "c"]);
+        let deduped: Vec<i32> = [1, 1, 2, 2, 2, 1, 3, 3].into_iter().dedup_adjacent_by(|a, b| a == b).collect();
+        assert_eq!(deduped, vec![1, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_grouping() {
+        let matches = [("a.rs", 1), ("a.rs", 4), ("b.rs", 2), ("a.rs", 9)];
+        let runs: Vec<(&str, Vec<usize>)> = matches.iter()
+            .group_runs_by(|entry| entry.0)
+            .map(|(file, run)| (file, run.iter().map(|entry| entry.1).collect()))
+            .collect();
+        assert_eq!(runs, vec![("a.rs", vec![1, 4]), ("b.rs", vec![2]), ("a.rs", vec![9])]);
+        let grouped: Vec<(&str, usize)> = matches.iter()
+            .chunk_by_key(|entry| entry.0)
+            .map(|(file, group)| (file, group.len()))
+            .collect();
+        assert_eq!(grouped, vec![("a.rs", 3), ("b.rs", 1)]);
+    }
+}
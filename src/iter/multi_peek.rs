@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct MultiPeek<I>
+where I: Iterator {
+    iter: I,
+    buffer: VecDeque<I::Item>,
+    cursor: usize,
+}
+
+impl<I> MultiPeek<I>
+where I: Iterator {
+    pub fn new(iter: I) -> MultiPeek<I> {
+        return MultiPeek { iter, buffer: VecDeque::new(), cursor: 0 };
+    }
+
+    fn fill(&mut self, len: usize) -> bool {
+        while self.buffer.len() < len {
+            match self.iter.next() {
+                Some(item) => self.buffer.push_back(item),
+                None => return false,
+            }
+        }
+        return true;
+    }
+
+    pub fn peek_nth(&mut self, n: usize) -> Option<&I::Item> {
+        if !self.fill(n + 1) {
+            return None;
+        }
+        return self.buffer.get(n);
+    }
+
+    pub fn peek_nth_mut(&mut self, n: usize) -> Option<&mut I::Item> {
+        if !self.fill(n + 1) {
+            return None;
+        }
+        return self.buffer.get_mut(n);
+    }
+
+    // each call looks one item further ahead until reset_peek() or next()
+    pub fn peek(&mut self) -> Option<&I::Item> {
+        let index = self.cursor;
+        if !self.fill(index + 1) {
+            return None;
+        }
+        self.cursor += 1;
+        return self.buffer.get(index);
+    }
+
+    pub fn reset_peek(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn peek_cursor(&self) -> usize {
+        return self.cursor;
+    }
+
+    pub fn next_if<F>(&mut self, predicate: F) -> Option<I::Item>
+    where F: FnOnce(&I::Item) -> bool {
+        if self.peek_nth(0).is_some_and(predicate) {
+            return self.next();
+        }
+        return None;
+    }
+}
+
+impl<I> Iterator for MultiPeek<I>
+where I: Iterator {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.cursor = 0;
+        if let Some(item) = self.buffer.pop_front() {
+            return Some(item);
+        }
+        return self.iter.next();
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, high) = self.iter.size_hint();
+        return (low.saturating_add(self.buffer.len()), high.and_then(|high| high.checked_add(self.buffer.len())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iter::adapters::IterAdapters;
+
+    #[test]
+    fn test_peek_nth_and_cursor() {
+        let mut iter = "a b c d".split(' ').multi_peek();
+        assert_eq!(iter.peek_nth(2), Some(&"c"));
+        assert_eq!(iter.peek(), Some(&"a"));
+        assert_eq!(iter.peek(), Some(&"b"));
+        iter.reset_peek();
+        assert_eq!(iter.peek(), Some(&"a"));
+        assert_eq!(iter.next(), Some("a"));
+        assert_eq!(iter.peek(), Some(&"b"));
+        assert_eq!(iter.peek_nth(5), None);
+        assert_eq!(iter.next_if(|item| *item == "x"), None);
+        assert_eq!(iter.collect::<Vec<&str>>(), vec!["b", "c", "d"]);
+
+        let mut numbers = MultiPeek::new(0..3);
+        *numbers.peek_nth_mut(1).unwrap() = 10;
+        assert_eq!(numbers.size_hint(), (3, Some(3)));
+        assert_eq!(numbers.collect::<Vec<i32>>(), vec![0, 10, 2]);
+    }
+}
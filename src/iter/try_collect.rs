@@ -0,0 +1,66 @@
+use std::fmt::Display;
+
+use crate::types::error_accumulator::aggregate;
+use crate::types::error_chain::ErrorChain;
+
+pub trait TryAdapters: Iterator + Sized {
+    fn partition_results<T>(self) -> (Vec<T>, Vec<ErrorChain>)
+    where Self: Iterator<Item = Result<T, ErrorChain>> {
+        let mut values = Vec::new();
+        let mut errors = Vec::new();
+        for result in self {
+            match result {
+                Ok(value) => values.push(value),
+                Err(error) => errors.push(error),
+            }
+        }
+        return (values, errors);
+    }
+
+    // unlike collect::<Result<_, _>>() this runs every item and reports every failure
+    fn try_map_collect<T, F, C>(self, mut map: F, context: C) -> Result<Vec<T>, ErrorChain>
+    where F: FnMut(Self::Item) -> Result<T, ErrorChain>, C: Display + Send + Sync + 'static {
+        let (values, errors) = self.map(&mut map).partition_results();
+        return match aggregate(errors, context) {
+            Some(error) => Err(error),
+            None => Ok(values),
+        };
+    }
+
+    fn try_filter_map_collect<T, F, C>(self, mut filter_map: F, context: C) -> Result<Vec<T>, ErrorChain>
+    where F: FnMut(Self::Item) -> Result<Option<T>, ErrorChain>, C: Display + Send + Sync + 'static {
+        let (values, errors) = self.map(&mut filter_map).partition_results();
+        return match aggregate(errors, context) {
+            Some(error) => Err(error),
+            None => Ok(values.into_iter().flatten().collect()),
+        };
+    }
+}
+
+impl<I> TryAdapters for I
+where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<i32, ErrorChain> {
+        return text.parse::<i32>().map_err(|error| ErrorChain::from(error, format!("bad number '{}'", text)));
+    }
+
+    #[test]
+    fn test_collects_all_failures() {
+        assert_eq!(["1", "2"].into_iter().try_map_collect(parse, "failed to parse").unwrap(), vec![1, 2]);
+        let error = ["1", "x", "3", "y"].into_iter().try_map_collect(parse, "failed to parse").unwrap_err().to_string();
+        assert!(error.starts_with("failed to parse (2 errors)"));
+        assert!(error.contains("[1] bad number 'x'") && error.contains("[2] bad number 'y'"));
+
+        let evens = ["1", "2", "4"].into_iter()
+            .try_filter_map_collect(|text| parse(text).map(|n| (n % 2 == 0).then_some(n)), "failed")
+            .unwrap();
+        assert_eq!(evens, vec![2, 4]);
+
+        let (values, errors) = ["5", "z"].into_iter().map(parse).partition_results();
+        assert_eq!((values, errors.len()), (vec![5], 1));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::text::line_index::LineIndex;
+use crate::text::line_index::Position;
+use crate::types::error_chain::ErrorChain;
+use crate::types::parse_error::ParseError;
+use crate::types::span::Span;
+
+pub const ERROR_KIND: &str = "error";
+
+pub trait TokenPattern {
+    fn match_len(&self, rest: &str) -> Option<usize>;
+}
+
+impl<P> TokenPattern for P
+where P: AsRef<str> {
+    fn match_len(&self, rest: &str) -> Option<usize> {
+        let literal = self.as_ref();
+        if !literal.is_empty() && rest.starts_with(literal) {
+            return Some(literal.len());
+        }
+        return None;
+    }
+}
+
+pub struct FnPattern<F>(pub F);
+
+impl<F> TokenPattern for FnPattern<F>
+where F: Fn(&str) -> Option<usize> {
+    fn match_len(&self, rest: &str) -> Option<usize> {
+        return (self.0)(rest).filter(|len| *len > 0 && *len <= rest.len());
+    }
+}
+
+pub struct CharsWhile<F>(pub F);
+
+impl<F> TokenPattern for CharsWhile<F>
+where F: Fn(char) -> bool {
+    fn match_len(&self, rest: &str) -> Option<usize> {
+        let len = rest.char_indices()
+            .find(|(_, character)| !(self.0)(*character))
+            .map(|(index, _)| index)
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return None;
+        }
+        return Some(len);
+    }
+}
+
+pub struct Delimited<O, C>(pub O, pub C);
+
+impl<O, C> TokenPattern for Delimited<O, C>
+where O: AsRef<str>, C: AsRef<str> {
+    fn match_len(&self, rest: &str) -> Option<usize> {
+        let open = self.0.as_ref();
+        let close = self.1.as_ref();
+        if !rest.starts_with(open) {
+            return None;
+        }
+        return rest[open.len()..].find(close).map(|index| open.len() + index + close.len());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    Skip,
+    ErrorToken,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: &'static str,
+    pub text: &'a str,
+    pub span: Span,
+    pub position: Position,
+}
+
+impl<'a> Token<'a> {
+    pub fn is(&self, kind: &str) -> bool {
+        return self.kind == kind;
+    }
+
+    pub fn is_error(&self) -> bool {
+        return self.kind == ERROR_KIND;
+    }
+}
+
+struct Rule {
+    name: &'static str,
+    pattern: Box<dyn TokenPattern>,
+    skip: bool,
+}
+
+pub struct Lexer {
+    rules: Vec<Rule>,
+    recovery: Recovery,
+}
+
+impl Lexer {
+    pub fn new() -> Lexer {
+        return Lexer { rules: Vec::new(), recovery: Recovery::Abort };
+    }
+
+    pub fn rule<P>(mut self, name: &'static str, pattern: P) -> Lexer
+    where P: TokenPattern + 'static {
+        self.rules.push(Rule { name, pattern: Box::new(pattern), skip: false });
+        return self;
+    }
+
+    pub fn skip<P>(mut self, name: &'static str, pattern: P) -> Lexer
+    where P: TokenPattern + 'static {
+        self.rules.push(Rule { name, pattern: Box::new(pattern), skip: true });
+        return self;
+    }
+
+    pub fn recovery(mut self, recovery: Recovery) -> Lexer {
+        self.recovery = recovery;
+        return self;
+    }
+
+    pub fn tokens<'l, 'a>(&'l self, src: &'a str) -> Tokens<'l, 'a> {
+        return Tokens { lexer: self, index: LineIndex::new(src), pos: 0, failed: false };
+    }
+
+    pub fn tokenize<'a>(&self, src: &'a str) -> Result<Vec<Token<'a>>, ErrorChain> {
+        return self.tokens(src).collect();
+    }
+}
+
+impl Default for Lexer {
+    fn default() -> Lexer {
+        return Lexer::new();
+    }
+}
+
+pub struct Tokens<'l, 'a> {
+    lexer: &'l Lexer,
+    index: LineIndex<'a>,
+    pos: usize,
+    failed: bool,
+}
+
+impl<'l, 'a> Tokens<'l, 'a> {
+    fn token(&self, kind: &'static str, start: usize, end: usize) -> Token<'a> {
+        let src = self.index.source();
+        return Token { kind, text: &src[start..end], span: Span::new(start, end), position: self.index.position(start) };
+    }
+
+    fn match_rule(&self) -> Option<(&'l Rule, usize)> {
+        let rest = &self.index.source()[self.pos..];
+        for rule in &self.lexer.rules {
+            if let Some(len) = rule.pattern.match_len(rest).filter(|len| *len > 0) {
+                return Some((rule, len));
+            }
+        }
+        return None;
+    }
+}
+
+impl<'l, 'a> Iterator for Tokens<'l, 'a> {
+    type Item = Result<Token<'a>, ErrorChain>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let src = self.index.source();
+        while !self.failed && self.pos < src.len() {
+            let start = self.pos;
+            match self.match_rule() {
+                Some((rule, len)) => {
+                    self.pos = start + len;
+                    if !src.is_char_boundary(self.pos) {
+                        self.failed = true;
+                        let error = ParseError::new(start..self.pos, format!("rule '{}' split a character", rule.name));
+                        return Some(Err(ErrorChain::from(error, "lexer rule produced an invalid token")));
+                    }
+                    if !rule.skip {
+                        return Some(Ok(self.token(rule.name, start, self.pos)));
+                    }
+                }
+                None => {
+                    let end = start + src[start..].chars().next().map(char::len_utf8).unwrap_or(1);
+                    match self.lexer.recovery {
+                        Recovery::Skip => self.pos = end,
+                        Recovery::ErrorToken => {
+                            self.pos = end;
+                            while self.pos < src.len() && self.match_rule().is_none() {
+                                self.pos += src[self.pos..].chars().next().map(char::len_utf8).unwrap_or(1);
+                            }
+                            return Some(Ok(self.token(ERROR_KIND, start, self.pos)));
+                        }
+                        Recovery::Abort => {
+                            self.failed = true;
+                            let position = self.index.position(start);
+                            let unexpected = &src[start..end];
+                            let error = ParseError::new(start..end, format!("unexpected character {:?}", unexpected));
+                            return Some(Err(ErrorChain::from(error, format!("failed to tokenize input at {}", position))));
+                        }
+                    }
+                }
+            }
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexer() -> Lexer {
+        return Lexer::new()
+            .skip("space", CharsWhile(char::is_whitespace))
+            .skip("comment", Delimited("#", "\n"))
+            .rule("let", "let")
+            .rule("ident", CharsWhile(|c: char| c.is_alphanumeric() || c == '_'))
+            .rule("eq", "=")
+            .rule("string", Delimited("\"", "\""));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = lexer().tokenize("let x = \"hi\" # note\nlet yz").unwrap();
+        let kinds: Vec<&str> = tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(kinds, ["let", "ident", "eq", "string", "let", "ident"]);
+        assert_eq!(tokens[3].text, "\"hi\"");
+        assert_eq!(tokens[5].span, Span::new(24, 26));
+        assert_eq!(tokens[5].position, Position { line: 2, column: 5 });
+    }
+
+    #[test]
+    fn test_recovery_policies() {
+        assert!(lexer().tokenize("let x = $$ y").is_err());
+        let skipped = lexer().recovery(Recovery::Skip).tokenize("let x = $$ y").unwrap();
+        assert_eq!(skipped.len(), 4);
+        let errors = lexer().recovery(Recovery::ErrorToken).tokenize("x $$ y").unwrap();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[1].is_error());
+        assert_eq!(errors[1].text, "$$");
+    }
+
+    #[test]
+    fn test_fn_pattern() {
+        let digits = FnPattern(|rest: &str| Some(rest.bytes().take_while(u8::is_ascii_digit).count()));
+        let tokens = Lexer::new().rule("number", digits).skip("space", " ").tokenize("12 345").unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1].text, "345");
+    }
+}
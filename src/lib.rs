@@ -1,2 +1,30 @@
+#![allow(clippy::needless_return)]
+// ffi is the only module allowed to use unsafe, so a build without it contains no unsafe code
+#![cfg_attr(not(feature = "ffi"), forbid(unsafe_code))]
+#![cfg_attr(feature = "ffi", deny(unsafe_code))]
+
+pub mod bytes;
+pub mod collections;
+pub mod diag;
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod formats;
+pub mod fs;
+pub mod index;
+pub mod io;
+pub mod iter;
+pub mod lex;
+pub mod metrics;
+pub mod parse;
 pub mod patterns;
-pub mod types;
\ No newline at end of file
+pub mod rand_lite;
+pub mod refactor;
+pub mod rules;
+pub mod search;
+pub mod stats;
+pub mod term;
+pub mod testing;
+pub mod text;
+pub mod time;
+pub mod types;
@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use crate::types::error_chain::ErrorChain;
+
+pub const BYTES_SCANNED: &str = "gmec.bytes_scanned";
+pub const MATCHES_FOUND: &str = "gmec.matches_found";
+pub const RETRIES_PERFORMED: &str = "gmec.retries_performed";
+pub const ERRORS_CREATED: &str = "gmec.errors_created";
+pub const FILE_SCAN_SECONDS: &str = "gmec.file_scan_seconds";
+
+pub trait Recorder: Send + Sync {
+    fn increment_counter(&self, name: &'static str, value: u64);
+
+    fn record_histogram(&self, name: &'static str, value: f64);
+}
+
+impl<R> Recorder for Arc<R>
+where R: Recorder + ?Sized {
+    fn increment_counter(&self, name: &'static str, value: u64) {
+        (**self).increment_counter(name, value);
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64) {
+        (**self).record_histogram(name, value);
+    }
+}
+
+static GLOBAL: OnceLock<Box<dyn Recorder>> = OnceLock::new();
+
+thread_local! {
+    static LOCAL: RefCell<Option<Arc<dyn Recorder>>> = const { RefCell::new(None) };
+}
+
+// can only be set once per process, like a global logger
+pub fn set_global_recorder<R>(recorder: R) -> Result<(), ErrorChain>
+where R: Recorder + 'static {
+    return GLOBAL.set(Box::new(recorder)).map_err(|_| ErrorChain::new("a global metrics recorder is already installed"));
+}
+
+// routes this thread's metrics to recorder instead of the global one while f runs
+pub fn with_local_recorder<T, F>(recorder: Arc<dyn Recorder>, f: F) -> T
+where F: FnOnce() -> T {
+    let previous = LOCAL.with(|local| local.borrow_mut().replace(recorder));
+    let result = f();
+    LOCAL.with(|local| *local.borrow_mut() = previous);
+    return result;
+}
+
+fn with_recorder<F>(f: F)
+where F: FnOnce(&dyn Recorder) {
+    let local = LOCAL.try_with(|local| local.borrow().clone()).ok().flatten();
+    match local {
+        Some(recorder) => f(recorder.as_ref()),
+        None => {
+            if let Some(recorder) = GLOBAL.get() {
+                f(recorder.as_ref());
+            }
+        }
+    }
+}
+
+pub fn counter(name: &'static str, value: u64) {
+    with_recorder(|recorder| recorder.increment_counter(name, value));
+}
+
+pub fn histogram(name: &'static str, value: f64) {
+    with_recorder(|recorder| recorder.record_histogram(name, value));
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryRecorder {
+    counters: Mutex<BTreeMap<&'static str, u64>>,
+    histograms: Mutex<BTreeMap<&'static str, Vec<f64>>>,
+}
+
+impl InMemoryRecorder {
+    pub fn new() -> InMemoryRecorder {
+        return InMemoryRecorder::default();
+    }
+
+    pub fn counter(&self, name: &str) -> u64 {
+        return self.counters.lock().expect("metrics lock poisoned").get(name).copied().unwrap_or(0);
+    }
+
+    pub fn histogram(&self, name: &str) -> Vec<f64> {
+        return self.histograms.lock().expect("metrics lock poisoned").get(name).cloned().unwrap_or_default();
+    }
+}
+
+impl Recorder for InMemoryRecorder {
+    fn increment_counter(&self, name: &'static str, value: u64) {
+        *self.counters.lock().expect("metrics lock poisoned").entry(name).or_default() += value;
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64) {
+        self.histograms.lock().expect("metrics lock poisoned").entry(name).or_default().push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::rules::RuleSet;
+
+    #[test]
+    fn test_local_recorder_sees_instrumented_calls() {
+        let recorder = Arc::new(InMemoryRecorder::new());
+        with_local_recorder(recorder.clone(), || {
+            let rules = RuleSet::new(vec![Rule::new("todo", "TODO")]).unwrap();
+            rules.run("TODO one, TODO two");
+            let _ = ErrorChain::new("boom");
+            histogram("custom", 1.5);
+        });
+        let _ = ErrorChain::new("outside the scope");
+        assert_eq!(recorder.counter(BYTES_SCANNED), 18);
+        assert_eq!(recorder.counter(MATCHES_FOUND), 2);
+        assert_eq!(recorder.counter(ERRORS_CREATED), 1);
+        assert_eq!(recorder.histogram("custom"), vec![1.5]);
+    }
+}
@@ -0,0 +1,359 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+use crate::lex::Token;
+use crate::text::line_index::Position;
+use crate::types::error_chain::ErrorChain;
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Input<'t> {
+    tokens: &'t [Token<'t>],
+    pos: usize,
+}
+
+impl<'t> Input<'t> {
+    pub fn new(tokens: &'t [Token<'t>]) -> Input<'t> {
+        return Input { tokens, pos: 0 };
+    }
+
+    pub fn peek(&self) -> Option<&'t Token<'t>> {
+        return self.tokens.get(self.pos);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.pos >= self.tokens.len();
+    }
+
+    pub fn position(&self) -> usize {
+        return self.pos;
+    }
+
+    pub fn advance(&self) -> Input<'t> {
+        return Input { tokens: self.tokens, pos: (self.pos + 1).min(self.tokens.len()) };
+    }
+
+    pub fn span(&self) -> Span {
+        match self.peek() {
+            Some(token) => return token.span,
+            None => return Span::at(self.tokens.last().map(|token| token.span.end).unwrap_or(0)),
+        }
+    }
+
+    fn span_since(&self, start: Input<'t>) -> Span {
+        if self.pos == start.pos {
+            return Span::at(start.span().start);
+        }
+        return Span::new(start.tokens[start.pos].span.start, self.tokens[self.pos - 1].span.end);
+    }
+
+    pub fn fail<E>(&self, expected: E) -> Failure
+    where E: Into<String> {
+        let found = match self.peek() {
+            Some(token) => format!("{} '{}'", token.kind, token.text),
+            None => String::from("end of input"),
+        };
+        return Failure {
+            span: self.span(),
+            position: self.peek().map(|token| token.position),
+            expected: vec![expected.into()],
+            found,
+            cut: false,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub span: Span,
+    pub position: Option<Position>,
+    pub expected: Vec<String>,
+    pub found: String,
+    pub cut: bool,
+}
+
+impl Failure {
+    fn merge(self, other: Failure) -> Failure {
+        if other.cut || other.span.start > self.span.start {
+            return other;
+        }
+        if self.cut || self.span.start > other.span.start {
+            return self;
+        }
+        let mut merged = self;
+        for expected in other.expected {
+            if !merged.expected.contains(&expected) {
+                merged.expected.push(expected);
+            }
+        }
+        return merged;
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected ")?;
+        for (index, expected) in self.expected.iter().enumerate() {
+            if index > 0 {
+                let separator = if index + 1 == self.expected.len() { " or " } else { ", " };
+                write!(f, "{}", separator)?;
+            }
+            write!(f, "{}", expected)?;
+        }
+        return write!(f, ", found {}", self.found);
+    }
+}
+
+impl Error for Failure {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+pub type ParseResult<'t, T> = Result<(T, Input<'t>), Failure>;
+
+pub trait Parser<'t, T> {
+    fn parse(&self, input: Input<'t>) -> ParseResult<'t, T>;
+}
+
+impl<'t, T, F> Parser<'t, T> for F
+where F: Fn(Input<'t>) -> ParseResult<'t, T> {
+    fn parse(&self, input: Input<'t>) -> ParseResult<'t, T> {
+        return self(input);
+    }
+}
+
+pub fn token<'t>(kind: &'static str) -> impl Parser<'t, Token<'t>> {
+    return move |input: Input<'t>| match input.peek() {
+        Some(token) if token.kind == kind => Ok((*token, input.advance())),
+        _ => Err(input.fail(kind)),
+    };
+}
+
+pub fn text<'t>(kind: &'static str, expected: &'static str) -> impl Parser<'t, Token<'t>> {
+    return move |input: Input<'t>| match input.peek() {
+        Some(token) if token.kind == kind && token.text == expected => Ok((*token, input.advance())),
+        _ => Err(input.fail(format!("'{}'", expected))),
+    };
+}
+
+pub fn end<'t>() -> impl Parser<'t, ()> {
+    return move |input: Input<'t>| match input.is_empty() {
+        true => Ok(((), input)),
+        false => Err(input.fail("end of input")),
+    };
+}
+
+pub fn seq<'t, A, B, PA, PB>(first: PA, second: PB) -> impl Parser<'t, (A, B)>
+where PA: Parser<'t, A>, PB: Parser<'t, B> {
+    return move |input: Input<'t>| {
+        let (a, rest) = first.parse(input)?;
+        let (b, rest) = second.parse(rest)?;
+        return Ok(((a, b), rest));
+    };
+}
+
+pub fn alt<'t, T, PA, PB>(first: PA, second: PB) -> impl Parser<'t, T>
+where PA: Parser<'t, T>, PB: Parser<'t, T> {
+    return move |input: Input<'t>| match first.parse(input) {
+        Ok(result) => Ok(result),
+        Err(failure) if failure.cut => Err(failure),
+        Err(failure) => second.parse(input).map_err(|other| failure.merge(other)),
+    };
+}
+
+pub fn choice<'t, T>(parsers: Vec<Box<dyn Parser<'t, T> + 't>>) -> impl Parser<'t, T> {
+    return move |input: Input<'t>| {
+        let mut failure: Option<Failure> = None;
+        for parser in &parsers {
+            match parser.parse(input) {
+                Ok(result) => return Ok(result),
+                Err(next) if next.cut => return Err(next),
+                Err(next) => failure = Some(match failure.take() {
+                    Some(previous) => previous.merge(next),
+                    None => next,
+                }),
+            }
+        }
+        return Err(failure.unwrap_or_else(|| input.fail("a choice")));
+    };
+}
+
+pub fn many<'t, T, P>(parser: P) -> impl Parser<'t, Vec<T>>
+where P: Parser<'t, T> {
+    return move |input: Input<'t>| {
+        let mut values = Vec::new();
+        let mut rest = input;
+        loop {
+            match parser.parse(rest) {
+                Ok((value, next)) if next.position() > rest.position() => {
+                    values.push(value);
+                    rest = next;
+                }
+                Ok(_) => return Ok((values, rest)),
+                Err(failure) if failure.cut => return Err(failure),
+                Err(_) => return Ok((values, rest)),
+            }
+        }
+    };
+}
+
+pub fn many1<'t, T, P>(parser: P) -> impl Parser<'t, Vec<T>>
+where P: Parser<'t, T> {
+    let repeated = many(parser);
+    return move |input: Input<'t>| {
+        let (values, rest) = repeated.parse(input)?;
+        if values.is_empty() {
+            return Err(input.fail("at least one item"));
+        }
+        return Ok((values, rest));
+    };
+}
+
+pub fn separated<'t, T, S, P, PS>(parser: P, separator: PS) -> impl Parser<'t, Vec<T>>
+where P: Parser<'t, T>, PS: Parser<'t, S> {
+    return move |input: Input<'t>| {
+        let mut values = Vec::new();
+        let (first, mut rest) = match parser.parse(input) {
+            Ok(result) => result,
+            Err(failure) if failure.cut => return Err(failure),
+            Err(_) => return Ok((values, input)),
+        };
+        values.push(first);
+        loop {
+            let after_separator = match separator.parse(rest) {
+                Ok((_, next)) => next,
+                Err(failure) if failure.cut => return Err(failure),
+                Err(_) => return Ok((values, rest)),
+            };
+            let (value, next) = parser.parse(after_separator)?;
+            values.push(value);
+            rest = next;
+        }
+    };
+}
+
+pub fn opt<'t, T, P>(parser: P) -> impl Parser<'t, Option<T>>
+where P: Parser<'t, T> {
+    return move |input: Input<'t>| match parser.parse(input) {
+        Ok((value, rest)) => Ok((Some(value), rest)),
+        Err(failure) if failure.cut => Err(failure),
+        Err(_) => Ok((None, input)),
+    };
+}
+
+pub fn delimited<'t, O, T, C, PO, P, PC>(open: PO, parser: P, close: PC) -> impl Parser<'t, T>
+where PO: Parser<'t, O>, P: Parser<'t, T>, PC: Parser<'t, C> {
+    return move |input: Input<'t>| {
+        let (_, rest) = open.parse(input)?;
+        let (value, rest) = parser.parse(rest)?;
+        let (_, rest) = close.parse(rest)?;
+        return Ok((value, rest));
+    };
+}
+
+pub fn map<'t, T, U, P, F>(parser: P, func: F) -> impl Parser<'t, U>
+where P: Parser<'t, T>, F: Fn(T) -> U {
+    return move |input: Input<'t>| parser.parse(input).map(|(value, rest)| (func(value), rest));
+}
+
+pub fn spanned<'t, T, P>(parser: P) -> impl Parser<'t, Spanned<T>>
+where P: Parser<'t, T> {
+    return move |input: Input<'t>| {
+        let (value, rest) = parser.parse(input)?;
+        return Ok((Spanned { value, span: rest.span_since(input) }, rest));
+    };
+}
+
+pub fn cut<'t, T, P>(parser: P) -> impl Parser<'t, T>
+where P: Parser<'t, T> {
+    return move |input: Input<'t>| parser.parse(input).map_err(|mut failure| {
+        failure.cut = true;
+        failure
+    });
+}
+
+pub fn label<'t, T, P>(parser: P, expected: &'static str) -> impl Parser<'t, T>
+where P: Parser<'t, T> {
+    return move |input: Input<'t>| parser.parse(input).map_err(|mut failure| {
+        if failure.span.start == input.span().start {
+            failure.expected = vec![String::from(expected)];
+        }
+        failure
+    });
+}
+
+pub fn parse_all<'t, T, P>(parser: P, tokens: &'t [Token<'t>]) -> Result<T, ErrorChain>
+where P: Parser<'t, T> {
+    let input = Input::new(tokens);
+    let result = parser.parse(input).and_then(|(value, rest)| end().parse(rest).map(|_| value));
+    match result {
+        Ok(value) => return Ok(value),
+        Err(failure) => {
+            let context = match failure.position {
+                Some(position) => format!("syntax error at {}", position),
+                None => String::from("syntax error at end of input"),
+            };
+            return Err(ErrorChain::from(failure, context));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lex::CharsWhile;
+    use crate::lex::FnPattern;
+    use crate::lex::Lexer;
+
+    fn tokenize(src: &str) -> Vec<Token<'_>> {
+        return Lexer::new()
+            .skip("space", CharsWhile(char::is_whitespace))
+            .rule("number", CharsWhile(|c: char| c.is_ascii_digit()))
+            .rule("ident", CharsWhile(char::is_alphabetic))
+            .rule("punct", FnPattern(|rest: &str| rest.chars().next().filter(|c| "[],=".contains(*c)).map(|_| 1)))
+            .tokenize(src)
+            .unwrap();
+    }
+
+    fn number<'t>() -> impl Parser<'t, u32> {
+        return map(token("number"), |token: Token| token.text.parse().unwrap());
+    }
+
+    fn list<'t>() -> impl Parser<'t, Vec<u32>> {
+        return delimited(text("punct", "["), separated(number(), text("punct", ",")), cut(text("punct", "]")));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let tokens = tokenize("xs = [1, 2, 3]");
+        let parser = seq(seq(token("ident"), text("punct", "=")), spanned(list()));
+        let ((name, _), values) = parse_all(parser, &tokens).unwrap();
+        assert_eq!(name.text, "xs");
+        assert_eq!(values.value, vec![1, 2, 3]);
+        assert_eq!(values.span, Span::new(5, 14));
+    }
+
+    #[test]
+    fn test_alt_merges_expected() {
+        let tokens = tokenize("=");
+        let parser = alt(map(token("number"), |_| 0), map(token("ident"), |_| 1));
+        let failure = parser.parse(Input::new(&tokens)).unwrap_err();
+        assert_eq!(failure.to_string(), "expected number or ident, found punct '='");
+    }
+
+    #[test]
+    fn test_cut_reports_expected_vs_found() {
+        let tokens = tokenize("[1, 2 x");
+        let error = parse_all(list(), &tokens).unwrap_err();
+        let rendered = error.to_string();
+        assert!(rendered.starts_with("syntax error at 1:7"));
+        assert!(rendered.contains("expected ']', found ident 'x'"));
+        let tokens = tokenize("1 2 3");
+        let values = parse_all(many1(number()), &tokens).unwrap();
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+}
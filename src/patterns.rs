@@ -1,3 +1,19 @@
+pub mod anchored;
+pub mod common;
+pub mod comparator;
+pub mod complexity;
+pub mod expr;
+pub mod index;
+pub mod logic;
+pub mod prepared;
+pub mod tokens;
+pub mod wildcard;
+
+use std::ops::ControlFlow;
+
+use crate::types::match_id::MatchId;
+use crate::types::match_id::MatchIds;
+
 pub struct PatternMatch<T> {
     pub index: usize,
     pub length: usize,
@@ -21,23 +37,43 @@ impl<T> PatternMatch<T> {
 pub trait PatternMatcher<'a, P> {
     fn find_first_from(&'a self, pattern: &P, byte_offset: usize) -> Option<PatternMatch<&'a Self>>;
 
+    // like find_first_from, but only for matches starting before `before`; matchers that can stop
+    // scanning early override it, the default just drops a later match
+    fn find_first_before(&'a self, pattern: &P, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a Self>> {
+        return self.find_first_from(pattern, byte_offset).filter(|found_match| found_match.index < before);
+    }
+
     #[inline(always)]
     fn find_first(&'a self, pattern: &P) -> Option<PatternMatch<&'a Self>>{
         return self.find_first_from(pattern, 0);
     }
 
-    fn find_every_from(&'a self, pattern: &P, byte_offset: usize) -> Option<Vec<PatternMatch<&'a Self>>> {
+    // hands each non-overlapping match to sink, leftmost first, until sink breaks or matches run
+    // out; empty matches advance the search by one byte so they can't stall it
+    #[inline(always)]
+    fn for_each_match<F>(&'a self, pattern: &P, byte_offset: usize, sink: F) -> ControlFlow<()>
+    where F: FnMut(PatternMatch<&'a Self>) -> ControlFlow<()> {
+        return self.for_each_match_before(pattern, byte_offset, usize::MAX, sink);
+    }
+
+    // for_each_match over the matches starting before `before`, each found with find_first_before
+    // so a matcher that stops scanning early does so here too
+    fn for_each_match_before<F>(&'a self, pattern: &P, byte_offset: usize, before: usize, mut sink: F) -> ControlFlow<()>
+    where F: FnMut(PatternMatch<&'a Self>) -> ControlFlow<()> {
         let mut total_offset: usize = byte_offset;
-        let mut matches = Vec::new();
-        loop {
-            match self.find_first_from(pattern, total_offset) {
-                Some(found_match) => {
-                    total_offset = found_match.end();
-                    matches.push(found_match)
-                },
-                None => break
-            }
+        while let Some(found_match) = self.find_first_before(pattern, total_offset, before) {
+            total_offset = if found_match.length == 0 { found_match.end() + 1 } else { found_match.end() };
+            sink(found_match)?;
         }
+        return ControlFlow::Continue(());
+    }
+
+    fn find_every_from(&'a self, pattern: &P, byte_offset: usize) -> Option<Vec<PatternMatch<&'a Self>>> {
+        let mut matches = Vec::new();
+        let _ = self.for_each_match(pattern, byte_offset, |found_match| {
+            matches.push(found_match);
+            return ControlFlow::Continue(());
+        });
         if matches.is_empty() {
             return None;
         }
@@ -49,20 +85,19 @@ pub trait PatternMatcher<'a, P> {
         return self.find_every_from(pattern, 0);
     }
 
+    // each pattern only searches up to the best match so far, and a match right at the offset ends
+    // the scan; on a tie the earlier pattern wins
     fn find_any_from<IIP: IntoIterator<Item = P>>(&'a self, patterns: IIP, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
-        let mut matches = Vec::new();
-        for pattern in patterns.into_iter() {
-            if let Some(found_match) = self.find_first_from(&pattern, byte_offset) {
-                matches.push(found_match);
-            }
-        }
         let mut earliest_match: Option<PatternMatch<&'a Self>> = None;
-        let mut earliest_index: usize = usize::MAX;
-        for current_match in matches {
-            if earliest_match.is_none() || current_match.index < earliest_index {
-                earliest_index = current_match.index;
-                earliest_match = Some(current_match);
+        for pattern in patterns.into_iter() {
+            let before = earliest_match.as_ref().map(|earliest| earliest.index).unwrap_or(usize::MAX);
+            if before <= byte_offset {
+                break;
             }
+            let _ = self.for_each_match_before(&pattern, byte_offset, before, |found_match| {
+                earliest_match = Some(found_match);
+                return ControlFlow::Break(());
+            });
         }
         return earliest_match;
     }
@@ -72,16 +107,20 @@ pub trait PatternMatcher<'a, P> {
         self.find_any_from(patterns, 0)
     }
 
+    // every pattern's non-overlapping matches, in result order (see SetMatch); the stable sort
+    // keeps equal matches in the order their patterns were given
     fn find_all_from<IIP: IntoIterator<Item = P>>(&'a self, patterns: IIP, byte_offset: usize) -> Option<Vec<PatternMatch<&'a Self>>> {
         let mut matches = Vec::new();
         for pattern in patterns.into_iter() {
-            if let Some(mut found_matches) = self.find_every_from(&pattern, byte_offset) {
-                matches.append(&mut found_matches);
-            }
+            let _ = self.for_each_match(&pattern, byte_offset, |found_match| {
+                matches.push(found_match);
+                return ControlFlow::Continue(());
+            });
         }
         if matches.is_empty() {
             return None;
         }
+        matches.sort_by_key(|found_match| (found_match.index, found_match.length));
         return Some(matches);
     }
 
@@ -94,14 +133,25 @@ pub trait PatternMatcher<'a, P> {
 impl<'a, P> PatternMatcher<'a, P> for str
 where P: AsRef<str> {
     fn find_first_from(&'a self, pattern: &P, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return self.find_first_before(pattern, byte_offset, usize::MAX);
+    }
+
+    fn find_first_before(&'a self, pattern: &P, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a str>> {
         let pattern_str = pattern.as_ref();
-        if let Some(index) = self[byte_offset..].find(pattern_str) {
-            let byte_len = pattern_str.len();
-            let slice = &self[byte_offset + index..byte_offset + index + byte_len];
-            Some(PatternMatch { index: byte_offset + index, length: byte_len, slice })
-        } else {
-            None
+        // offsets past the end find nothing; one inside a character starts at the next boundary
+        let byte_offset = (byte_offset..=self.len()).find(|offset| self.is_char_boundary(*offset))?;
+        if before <= byte_offset {
+            return None;
+        }
+        // the last allowed start plus the pattern, rounded up to a character boundary; the rounding
+        // can let in a match starting at or after `before`, which is dropped
+        let search_end = (before - 1).saturating_add(pattern_str.len()).min(self.len());
+        let search_end = (search_end..=self.len()).find(|end| self.is_char_boundary(*end))?;
+        let index = byte_offset + self[byte_offset..search_end].find(pattern_str)?;
+        if index >= before {
+            return None;
         }
+        return Some(PatternMatch { index, length: pattern_str.len(), slice: &self[index..index + pattern_str.len()] });
     }
 }
 
@@ -110,10 +160,14 @@ impl<'a, P, T> PatternMatcher<'a, P> for [T]
 where P: AsRef<[T]>,
 T: PartialEq {
     fn find_first_from(&'a self, pattern: &P, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        return self.find_first_before(pattern, byte_offset, usize::MAX);
+    }
+
+    fn find_first_before(&'a self, pattern: &P, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a Self>> {
         let pattern_slice = pattern.as_ref();
         let pattern_len = pattern_slice.len();
-        let offset_slice = &self[byte_offset..];
-        for compare_start in 0..offset_slice.len() {
+        let offset_slice = self.get(byte_offset..)?;
+        for compare_start in 0..offset_slice.len().min(before.saturating_sub(byte_offset)) {
             let compare_end = compare_start + pattern_len;
             if compare_end > offset_slice.len() {
                 return None;
@@ -128,6 +182,261 @@ T: PartialEq {
     }
 }
 
+// result order, which every multi-pattern result follows (find_all, PatternSet::find_all_in,
+// RuleSet::run, Report::sort): offset, then length (shorter first), then pattern id. It never
+// depends on the order patterns were added in beyond the id itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SetMatch {
+    pub pattern_id: usize,
+    pub index: usize,
+    pub length: usize,
+}
+
+impl Ord for SetMatch {
+    fn cmp(&self, other: &SetMatch) -> std::cmp::Ordering {
+        return (self.index, self.length, self.pattern_id).cmp(&(other.index, other.length, other.pattern_id));
+    }
+}
+
+impl PartialOrd for SetMatch {
+    fn partial_cmp(&self, other: &SetMatch) -> Option<std::cmp::Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl SetMatch {
+    pub fn start(&self) -> usize {
+        return self.index;
+    }
+
+    pub fn end(&self) -> usize {
+        return self.index+self.length;
+    }
+
+    pub fn range(&self) -> std::ops::Range<usize> {
+        return self.index..self.index+self.length;
+    }
+
+    // haystack is the one the match was found in; the pattern is named by its id
+    pub fn match_id(&self, ids: &mut MatchIds, source: &str, haystack: &[u8]) -> MatchId {
+        return ids.next(source, &self.pattern_id.to_string(), &haystack[self.range()]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternSet<P> {
+    patterns: Vec<P>,
+}
+
+impl<P> PatternSet<P> {
+    pub const fn new() -> PatternSet<P> {
+        return PatternSet { patterns: Vec::new() };
+    }
+
+    pub fn push(&mut self, pattern: P) -> usize {
+        self.patterns.push(pattern);
+        return self.patterns.len() - 1;
+    }
+
+    pub fn with(mut self, pattern: P) -> PatternSet<P> {
+        self.patterns.push(pattern);
+        return self;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.patterns.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.patterns.is_empty();
+    }
+
+    pub fn get(&self, pattern_id: usize) -> Option<&P> {
+        return self.patterns.get(pattern_id);
+    }
+
+    pub fn patterns(&self) -> &[P] {
+        return &self.patterns;
+    }
+
+    pub fn find_first_in<'a, H>(&self, haystack: &'a H, byte_offset: usize) -> Option<SetMatch>
+    where H: PatternMatcher<'a, P> + ?Sized {
+        return find_first_of(&self.patterns, haystack, byte_offset);
+    }
+
+    pub fn find_every_in<'a, H>(&self, haystack: &'a H, byte_offset: usize) -> Vec<SetMatch>
+    where H: PatternMatcher<'a, P> + ?Sized {
+        return find_every_of(&self.patterns, haystack, byte_offset);
+    }
+
+    pub fn cursor<'a, H>(&self, haystack: &'a H) -> SetCursor<'_, 'a, P, H>
+    where H: PatternMatcher<'a, P> + ?Sized {
+        return SetCursor::new(&self.patterns, haystack);
+    }
+
+    // each pattern's matches, overlapping those of other patterns, in result order
+    pub fn find_all_in<'a, H>(&self, haystack: &'a H, byte_offset: usize) -> Vec<SetMatch>
+    where H: PatternMatcher<'a, P> + ?Sized {
+        return find_all_of(&self.patterns, haystack, byte_offset);
+    }
+}
+
+// leftmost match wins, then the longest, then the lowest pattern id; empty matches are ignored
+fn find_first_of<'a, P, H>(patterns: &[P], haystack: &'a H, byte_offset: usize) -> Option<SetMatch>
+where H: PatternMatcher<'a, P> + ?Sized {
+    let mut best: Option<SetMatch> = None;
+    for (pattern_id, pattern) in patterns.iter().enumerate() {
+        let found = match haystack.find_first_from(pattern, byte_offset) {
+            Some(found) if found.length > 0 => SetMatch { pattern_id, index: found.index, length: found.length },
+            _ => continue,
+        };
+        if beats(found, best) {
+            best = Some(found);
+        }
+    }
+    return best;
+}
+
+fn beats(found: SetMatch, best: Option<SetMatch>) -> bool {
+    return match best {
+        None => true,
+        Some(current) => (found.index, std::cmp::Reverse(found.length)) < (current.index, std::cmp::Reverse(current.length)),
+    };
+}
+
+fn find_every_of<'a, P, H>(patterns: &[P], haystack: &'a H, byte_offset: usize) -> Vec<SetMatch>
+where H: PatternMatcher<'a, P> + ?Sized {
+    let mut cursor = SetCursor::new(patterns, haystack);
+    let mut matches = Vec::new();
+    let mut offset = byte_offset;
+    while let Some(found) = cursor.find_first_from(offset) {
+        offset = found.end();
+        matches.push(found);
+    }
+    return matches;
+}
+
+// find_first_in for offsets that only move forward: each pattern's next match is kept until the
+// offset passes its start, so a pattern whose next match is far away is not searched up to it
+// again after every nearer match of another pattern
+pub struct SetCursor<'s, 'a, P, H>
+where H: ?Sized {
+    patterns: &'s [P],
+    haystack: &'a H,
+    // (index, length) of each pattern's first match from the last offset it was searched at,
+    // None once it has no more
+    next: Vec<Option<(usize, usize)>>,
+    started: bool,
+}
+
+impl<'s, 'a, P, H> SetCursor<'s, 'a, P, H>
+where H: PatternMatcher<'a, P> + ?Sized {
+    pub fn new(patterns: &'s [P], haystack: &'a H) -> SetCursor<'s, 'a, P, H> {
+        return SetCursor { patterns, haystack, next: vec![None; patterns.len()], started: false };
+    }
+
+    // the same match find_first_in gives; byte_offset must not be less than on the previous call
+    pub fn find_first_from(&mut self, byte_offset: usize) -> Option<SetMatch> {
+        let mut best: Option<SetMatch> = None;
+        for (pattern_id, pattern) in self.patterns.iter().enumerate() {
+            let stale = match self.next[pattern_id] {
+                Some((index, _)) => index < byte_offset,
+                None => !self.started,
+            };
+            if stale {
+                self.next[pattern_id] = self.haystack.find_first_from(pattern, byte_offset).map(|found| (found.index, found.length));
+            }
+            let found = match self.next[pattern_id] {
+                Some((index, length)) if length > 0 => SetMatch { pattern_id, index, length },
+                _ => continue,
+            };
+            if beats(found, best) {
+                best = Some(found);
+            }
+        }
+        self.started = true;
+        return best;
+    }
+}
+
+fn find_all_of<'a, P, H>(patterns: &[P], haystack: &'a H, byte_offset: usize) -> Vec<SetMatch>
+where H: PatternMatcher<'a, P> + ?Sized {
+    let mut matches = Vec::new();
+    for (pattern_id, pattern) in patterns.iter().enumerate() {
+        let _ = haystack.for_each_match(pattern, byte_offset, |found| {
+            if found.length > 0 {
+                matches.push(SetMatch { pattern_id, index: found.index, length: found.length });
+            }
+            return ControlFlow::Continue(());
+        });
+    }
+    matches.sort_unstable();
+    return matches;
+}
+
+// a PatternSet over a static table, so rule sets can live in a `static` with no startup work:
+// static KEYWORDS: StaticSet<&str> = StaticSet::new(&["TODO", "FIXME"]);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticSet<P>
+where P: 'static {
+    patterns: &'static [P],
+}
+
+impl<P> StaticSet<P>
+where P: 'static {
+    pub const fn new(patterns: &'static [P]) -> StaticSet<P> {
+        return StaticSet { patterns };
+    }
+
+    pub const fn len(&self) -> usize {
+        return self.patterns.len();
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        return self.patterns.is_empty();
+    }
+
+    pub const fn patterns(&self) -> &'static [P] {
+        return self.patterns;
+    }
+
+    pub fn get(&self, pattern_id: usize) -> Option<&'static P> {
+        return self.patterns.get(pattern_id);
+    }
+
+    pub fn find_first_in<'a, H>(&self, haystack: &'a H, byte_offset: usize) -> Option<SetMatch>
+    where H: PatternMatcher<'a, P> + ?Sized {
+        return find_first_of(self.patterns, haystack, byte_offset);
+    }
+
+    pub fn find_every_in<'a, H>(&self, haystack: &'a H, byte_offset: usize) -> Vec<SetMatch>
+    where H: PatternMatcher<'a, P> + ?Sized {
+        return find_every_of(self.patterns, haystack, byte_offset);
+    }
+
+    pub fn find_all_in<'a, H>(&self, haystack: &'a H, byte_offset: usize) -> Vec<SetMatch>
+    where H: PatternMatcher<'a, P> + ?Sized {
+        return find_all_of(self.patterns, haystack, byte_offset);
+    }
+
+    pub fn to_set(&self) -> PatternSet<P>
+    where P: Clone {
+        return PatternSet { patterns: self.patterns.to_vec() };
+    }
+}
+
+impl<P> Default for PatternSet<P> {
+    fn default() -> PatternSet<P> {
+        return PatternSet::new();
+    }
+}
+
+impl<P> FromIterator<P> for PatternSet<P> {
+    fn from_iter<I: IntoIterator<Item = P>>(iter: I) -> PatternSet<P> {
+        return PatternSet { patterns: iter.into_iter().collect() };
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,11 +499,109 @@ mod tests {
         assert_eq!(pms[0].end(), 3);
         assert_eq!(pms[1].start(), 3);
         assert_eq!(pms[1].end(), 4);
-        assert_eq!(pms[2].start(), 9);
-        assert_eq!(pms[2].end(), 10);
-        assert_eq!(pms[3].start(), 4);
-        assert_eq!(pms[3].end(), 5);
-        assert_eq!(pms[4].start(), 7);
-        assert_eq!(pms[4].end(), 8);
+        assert_eq!(pms[2].start(), 4);
+        assert_eq!(pms[2].end(), 5);
+        assert_eq!(pms[3].start(), 7);
+        assert_eq!(pms[3].end(), 8);
+        assert_eq!(pms[4].start(), 9);
+        assert_eq!(pms[4].end(), 10);
+    }
+
+    #[test]
+    fn test_result_order_ignores_pattern_order() {
+        let haystack = "abcab abc bca";
+        let patterns = ["abc", "ab", "bc", "b", "ca", "abc"];
+        let set: PatternSet<&str> = patterns.iter().copied().collect();
+        let expected = set.find_all_in(haystack, 0);
+        assert!(expected.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(&expected[..4], &[
+            SetMatch { pattern_id: 1, index: 0, length: 2 },
+            SetMatch { pattern_id: 0, index: 0, length: 3 },
+            SetMatch { pattern_id: 5, index: 0, length: 3 },
+            SetMatch { pattern_id: 3, index: 1, length: 1 },
+        ]);
+        let text = |found: &SetMatch| (found.index, found.length, patterns[found.pattern_id]);
+        let expected_text: Vec<_> = expected.iter().map(text).collect();
+        let mut rng = crate::rand_lite::Rng::seed_from_u64(478);
+        for _ in 0..20 {
+            let mut shuffled = patterns;
+            rng.shuffle(&mut shuffled);
+            let found: Vec<_> = shuffled.iter().copied().collect::<PatternSet<&str>>().find_all_in(haystack, 0).iter().map(|found| (found.index, found.length, shuffled[found.pattern_id])).collect();
+            assert_eq!(found, expected_text);
+            let all: Vec<(usize, usize)> = haystack.find_all(shuffled).unwrap().iter().map(|found| (found.index, found.length)).collect();
+            assert_eq!(all, expected.iter().map(|found| (found.index, found.length)).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_find_first_from_any_offset() {
+        let s = "héllo héllo";
+        assert_eq!(s.find_first_from(&"llo", 2).map(|found| found.index), Some(3));
+        assert_eq!(s.find_first_from(&"h", 2).map(|found| found.index), Some(7));
+        assert!(s.find_first_from(&"llo", 100).is_none());
+        assert!(s.as_bytes().find_first_from(&b"llo", 100).is_none());
+        assert_eq!(s.find_first_from(&"", s.len()).map(|found| found.index), Some(s.len()));
+    }
+
+    #[test]
+    fn test_pattern_set_leftmost_longest() {
+        let set: PatternSet<&str> = ["he", "hello", "", "world", "lo w"].into_iter().collect();
+        let first = set.find_first_in("say hello world", 0).unwrap();
+        assert_eq!((first.pattern_id, first.range()), (1, 4..9));
+        let every = set.find_every_in("say hello world", 0);
+        assert_eq!(every.iter().map(|found| found.pattern_id).collect::<Vec<usize>>(), vec![1, 3]);
+        let bytes: PatternSet<&[u8]> = PatternSet::new().with(&b"ab"[..]).with(&b"b"[..]);
+        assert_eq!(bytes.find_every_in(&b"abab"[..], 1).len(), 2);
+    }
+
+    #[test]
+    fn test_cursor_agrees_with_find_first_in() {
+        let set: PatternSet<&str> = ["far", "a", "ab", "b"].into_iter().collect();
+        let haystack = "ab a b ab far a";
+        let mut cursor = set.cursor(haystack);
+        for offset in [0, 0, 1, 3, 4, 9, 10, 14, 15] {
+            assert_eq!(cursor.find_first_from(offset), set.find_first_in(haystack, offset), "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn test_for_each_match_stops_early() {
+        let mut seen = Vec::new();
+        let flow = "a-b-c-d".for_each_match(&"-", 0, |found| {
+            seen.push(found.index);
+            return if seen.len() == 2 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) };
+        });
+        assert_eq!((flow, seen), (ControlFlow::Break(()), vec![1, 3]));
+        let mut bounded = Vec::new();
+        let _ = "a-b-c-d".for_each_match_before(&"-", 0, 5, |found| {
+            bounded.push(found.index);
+            return ControlFlow::Continue(());
+        });
+        assert_eq!(bounded, vec![1, 3]);
+        assert_eq!("aé".find_every(&"").map(|found| found.len()), Some(3));
+        assert_eq!("x".find_any(["q", "x"]).map(|found| found.index), Some(0));
+    }
+
+    #[test]
+    fn test_find_any_bounded_by_earliest() {
+        let text = "xx café yy tail";
+        assert_eq!(text.find_first_before(&"tail", 0, 12).map(|found| found.index), None);
+        assert_eq!(text.find_first_before(&"tail", 0, 13).map(|found| found.index), Some(12));
+        assert_eq!(text.find_first_before(&"é", 0, 7).map(|found| found.index), Some(6));
+        assert_eq!(text.find_any(["tail", "yy", "café", "caf"]).map(|found| (found.index, found.length)), Some((3, 5)));
+        assert_eq!(b"abcabc".find_first_before(&b"c", 3, 5).map(|found| found.index), None);
+        assert_eq!("xxxxxaé".find_first_before(&"aé", 0, 5).map(|found| found.index), None);
+        assert_eq!("xxxxxaé".find_any(["a", "aé"]).map(|found| (found.index, found.length)), Some((5, 1)));
+        assert_eq!(b"abcabc".find_any([&b"bc"[..], b"a", b"ab"]).map(|found| (found.index, found.length)), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_static_set() {
+        static MARKERS: StaticSet<&str> = StaticSet::new(&["TODO", "FIXME", "TODO:"]);
+        const EMPTY: StaticSet<&[u8]> = StaticSet::new(&[]);
+        let found = MARKERS.find_every_in("// TODO: fix FIXME", 0);
+        assert_eq!(found.iter().map(|found| (found.pattern_id, found.index)).collect::<Vec<_>>(), vec![(2, 3), (1, 13)]);
+        assert_eq!(MARKERS.to_set().find_every_in("// TODO: fix FIXME", 0), found);
+        assert!(EMPTY.is_empty() && EMPTY.find_first_in(&b"x"[..], 0).is_none());
     }
 }
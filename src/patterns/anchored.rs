@@ -0,0 +1,117 @@
+use super::PatternMatch;
+use super::PatternMatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineAnchor {
+    Start,
+    End,
+    Whole,
+}
+
+impl LineAnchor {
+    fn needs_start(self) -> bool {
+        return matches!(self, LineAnchor::Start | LineAnchor::Whole);
+    }
+
+    fn needs_end(self) -> bool {
+        return matches!(self, LineAnchor::End | LineAnchor::Whole);
+    }
+}
+
+// a pattern that only matches at a line boundary of the haystack, so callers don't have to split
+// it into lines first; a line ends before "\n", before "\r\n" or at the end of the haystack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineAnchored<P> {
+    pub pattern: P,
+    pub anchor: LineAnchor,
+}
+
+pub fn at_line_start<P>(pattern: P) -> LineAnchored<P> {
+    return LineAnchored { pattern, anchor: LineAnchor::Start };
+}
+
+pub fn at_line_end<P>(pattern: P) -> LineAnchored<P> {
+    return LineAnchored { pattern, anchor: LineAnchor::End };
+}
+
+pub fn whole_line<P>(pattern: P) -> LineAnchored<P> {
+    return LineAnchored { pattern, anchor: LineAnchor::Whole };
+}
+
+fn is_line_start(bytes: &[u8], index: usize) -> bool {
+    return index == 0 || bytes.get(index - 1) == Some(&b'\n');
+}
+
+fn is_line_end(bytes: &[u8], end: usize) -> bool {
+    return matches!(&bytes[end.min(bytes.len())..], [] | [b'\n', ..] | [b'\r', b'\n', ..]);
+}
+
+// the inner pattern's leftmost match is only a candidate: one failing the anchor is retried a byte
+// later, or from the next line start when the start is anchored, since nothing between can match
+fn find_anchored<'a, H, P>(haystack: &'a H, pattern: &LineAnchored<P>, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a H>>
+where H: PatternMatcher<'a, P> + AsRef<[u8]> + ?Sized {
+    let bytes = haystack.as_ref();
+    let mut from = byte_offset;
+    if pattern.anchor.needs_start() && !is_line_start(bytes, from) {
+        from = next_line_start(bytes, from)?;
+    }
+    loop {
+        let found = haystack.find_first_before(&pattern.pattern, from, before)?;
+        let start_ok = !pattern.anchor.needs_start() || is_line_start(bytes, found.index);
+        let end_ok = !pattern.anchor.needs_end() || is_line_end(bytes, found.end());
+        if start_ok && end_ok {
+            return Some(found);
+        }
+        from = match pattern.anchor.needs_start() {
+            true => next_line_start(bytes, found.index)?,
+            false => found.index + 1,
+        };
+    }
+}
+
+fn next_line_start(bytes: &[u8], from: usize) -> Option<usize> {
+    return bytes.get(from..)?.iter().position(|byte| *byte == b'\n').map(|newline| from + newline + 1);
+}
+
+impl<'a, P> PatternMatcher<'a, LineAnchored<P>> for str
+where str: PatternMatcher<'a, P> {
+    fn find_first_from(&'a self, pattern: &LineAnchored<P>, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        return find_anchored(self, pattern, byte_offset, usize::MAX);
+    }
+
+    fn find_first_before(&'a self, pattern: &LineAnchored<P>, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a Self>> {
+        return find_anchored(self, pattern, byte_offset, before);
+    }
+}
+
+impl<'a, P> PatternMatcher<'a, LineAnchored<P>> for [u8]
+where [u8]: PatternMatcher<'a, P> {
+    fn find_first_from(&'a self, pattern: &LineAnchored<P>, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        return find_anchored(self, pattern, byte_offset, usize::MAX);
+    }
+
+    fn find_first_before(&'a self, pattern: &LineAnchored<P>, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a Self>> {
+        return find_anchored(self, pattern, byte_offset, before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchors() {
+        let text = "a key\nkey = 1\r\nmonkey\nkey";
+        let ranges = |found: Option<Vec<PatternMatch<&str>>>| found.unwrap_or_default().into_iter().map(|found| found.range()).collect::<Vec<_>>();
+        assert_eq!(ranges(text.find_every(&at_line_start("key"))), vec![6..9, 22..25]);
+        assert_eq!(ranges(text.find_every(&at_line_end("key"))), vec![2..5, 18..21, 22..25]);
+        assert_eq!(ranges(text.find_every(&whole_line("key"))), vec![22..25]);
+        assert_eq!(ranges(text.find_every(&whole_line("key = 1"))), vec![6..13]);
+        assert_eq!(text.find_first_from(&at_line_start("key"), 7).map(|found| found.index), Some(22));
+        assert!(text.find_first_before(&at_line_start("key"), 0, 6).is_none());
+
+        let bytes = b"\x00\nMAGIC\x01MAGIC".as_slice();
+        assert_eq!(bytes.find_first(&at_line_start(b"MAGIC")).map(|found| found.index), Some(2));
+        assert_eq!(bytes.find_first(&at_line_end(b"MAGIC")).map(|found| found.index), Some(8));
+    }
+}
@@ -0,0 +1,363 @@
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+
+use super::PatternMatch;
+use super::PatternMatcher;
+
+// pattern types for common tokens, usable anywhere a str pattern is: `text.find_every(&Email)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Email;
+
+// http, https and ftp URLs; trailing sentence punctuation is left out of the match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ipv4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ipv6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Uuid;
+
+// YYYY-MM-DD with an optional THH:MM[:SS[.fff]] time and Z or ±HH:MM offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IsoDate;
+
+// a run of at least min_len hex digits, optionally prefixed with 0x
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexBlob {
+    pub min_len: usize,
+}
+
+impl Default for HexBlob {
+    fn default() -> HexBlob {
+        return HexBlob { min_len: 16 };
+    }
+}
+
+trait Extract {
+    // length of the token starting exactly at start, if there is one
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize>;
+}
+
+fn is_word(byte: Option<&u8>) -> bool {
+    return byte.map(|byte| byte.is_ascii_alphanumeric() || *byte == b'_').unwrap_or(false);
+}
+
+fn preceded_by_word(bytes: &[u8], start: usize) -> bool {
+    return start > 0 && is_word(bytes.get(start - 1));
+}
+
+fn run_len(bytes: &[u8], start: usize, accept: fn(u8) -> bool) -> usize {
+    return bytes[start..].iter().take_while(|byte| accept(**byte)).count();
+}
+
+fn find_from<'a, E>(haystack: &'a str, extract: &E, byte_offset: usize) -> Option<PatternMatch<&'a str>>
+where E: Extract {
+    let bytes = haystack.as_bytes();
+    for start in byte_offset..bytes.len() {
+        if !haystack.is_char_boundary(start) {
+            continue;
+        }
+        if let Some(length) = extract.match_len(bytes, start) {
+            return Some(PatternMatch { index: start, length, slice: &haystack[start..start + length] });
+        }
+    }
+    return None;
+}
+
+impl Extract for Email {
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        let is_local = |byte: u8| byte.is_ascii_alphanumeric() || b"._%+-".contains(&byte);
+        if start > 0 && is_local(bytes[start - 1]) {
+            return None;
+        }
+        let local = run_len(bytes, start, is_local);
+        if local == 0 || bytes.get(start + local) != Some(&b'@') || bytes[start] == b'.' || bytes[start + local - 1] == b'.' {
+            return None;
+        }
+        let domain_start = start + local + 1;
+        let mut pos = domain_start;
+        let mut labels = 0;
+        let mut last_label = 0..0;
+        loop {
+            let label = run_len(bytes, pos, |byte| byte.is_ascii_alphanumeric() || byte == b'-');
+            if label == 0 || bytes[pos] == b'-' || bytes[pos + label - 1] == b'-' {
+                break;
+            }
+            labels += 1;
+            last_label = pos..pos + label;
+            pos += label;
+            if bytes.get(pos) == Some(&b'.') && bytes.get(pos + 1).map(|byte| byte.is_ascii_alphanumeric()).unwrap_or(false) {
+                pos += 1;
+            } else {
+                break;
+            }
+        }
+        let top_level = &bytes[last_label.clone()];
+        if labels < 2 || top_level.len() < 2 || !top_level.iter().all(u8::is_ascii_alphabetic) {
+            return None;
+        }
+        return Some(last_label.end - start);
+    }
+}
+
+impl Extract for Url {
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        if preceded_by_word(bytes, start) {
+            return None;
+        }
+        let rest = &bytes[start..];
+        let scheme = ["https://", "http://", "ftp://"].iter().find(|scheme| {
+            return rest.len() >= scheme.len() && rest[..scheme.len()].eq_ignore_ascii_case(scheme.as_bytes());
+        })?;
+        let body = run_len(bytes, start + scheme.len(), |byte| byte > b' ' && byte != 0x7f && !b"<>\"'`".contains(&byte));
+        let mut end = start + scheme.len() + body;
+        loop {
+            let last = bytes[end - 1];
+            let unbalanced_paren = last == b')' && {
+                let url = &bytes[start..end];
+                url.iter().filter(|byte| **byte == b')').count() > url.iter().filter(|byte| **byte == b'(').count()
+            };
+            if b".,;:!?]}".contains(&last) || unbalanced_paren {
+                end -= 1;
+            } else {
+                break;
+            }
+        }
+        if !bytes[start + scheme.len()..end].first().map(u8::is_ascii_alphanumeric).unwrap_or(false) {
+            return None;
+        }
+        return Some(end - start);
+    }
+}
+
+impl Extract for Ipv4 {
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        if start > 0 && (bytes[start - 1].is_ascii_digit() || bytes[start - 1] == b'.') {
+            return None;
+        }
+        let length = run_len(bytes, start, |byte| byte.is_ascii_digit() || byte == b'.');
+        let mut candidate = &bytes[start..start + length];
+        // a sentence may end right after the address
+        if candidate.ends_with(b".") {
+            candidate = &candidate[..candidate.len() - 1];
+        }
+        let text = std::str::from_utf8(candidate).ok()?;
+        text.parse::<Ipv4Addr>().ok()?;
+        if is_word(bytes.get(start + candidate.len())) {
+            return None;
+        }
+        return Some(candidate.len());
+    }
+}
+
+impl Extract for Ipv6 {
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        if preceded_by_word(bytes, start) || (start > 0 && bytes[start - 1] == b':') {
+            return None;
+        }
+        let length = run_len(bytes, start, |byte| byte.is_ascii_hexdigit() || byte == b':' || byte == b'.');
+        // trailing punctuation such as "addr fe80::1." or "[::1]:" is not part of the address
+        for end in (start + 2..=start + length).rev() {
+            let candidate = &bytes[start..end];
+            if is_word(bytes.get(end)) || !candidate.iter().any(u8::is_ascii_digit) {
+                continue;
+            }
+            let parsed = std::str::from_utf8(candidate).ok().and_then(|text| text.parse::<Ipv6Addr>().ok());
+            if parsed.is_some() {
+                return Some(candidate.len());
+            }
+        }
+        return None;
+    }
+}
+
+impl Extract for Uuid {
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        if preceded_by_word(bytes, start) || bytes.len() < start + 36 {
+            return None;
+        }
+        for (index, byte) in bytes[start..start + 36].iter().enumerate() {
+            let valid = match index {
+                8 | 13 | 18 | 23 => *byte == b'-',
+                _ => byte.is_ascii_hexdigit(),
+            };
+            if !valid {
+                return None;
+            }
+        }
+        if is_word(bytes.get(start + 36)) || bytes.get(start + 36) == Some(&b'-') {
+            return None;
+        }
+        return Some(36);
+    }
+}
+
+fn digits(bytes: &[u8], start: usize, count: usize) -> Option<u32> {
+    let slice = bytes.get(start..start + count)?;
+    if !slice.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    return Some(slice.iter().fold(0, |value, byte| value * 10 + (byte - b'0') as u32));
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    return match month {
+        2 if (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+}
+
+impl IsoDate {
+    // length of THH:MM[:SS[.fff]][Z|±HH:MM] at pos, or 0 when there is no valid time
+    fn time_len(bytes: &[u8], pos: usize) -> usize {
+        if !matches!(bytes.get(pos), Some(b'T') | Some(b't')) || bytes.get(pos + 3) != Some(&b':') {
+            return 0;
+        }
+        let (Some(hour), Some(minute)) = (digits(bytes, pos + 1, 2), digits(bytes, pos + 4, 2)) else {
+            return 0;
+        };
+        if hour > 23 || minute > 59 {
+            return 0;
+        }
+        let mut end = pos + 6;
+        if bytes.get(end) == Some(&b':') {
+            match digits(bytes, end + 1, 2) {
+                Some(second) if second <= 60 => end += 3,
+                _ => return 0,
+            }
+            if bytes.get(end) == Some(&b'.') {
+                let fraction = run_len(bytes, end + 1, |byte| byte.is_ascii_digit());
+                if fraction > 0 {
+                    end += 1 + fraction;
+                }
+            }
+        }
+        match bytes.get(end) {
+            Some(b'Z') | Some(b'z') => end += 1,
+            Some(b'+') | Some(b'-') => {
+                let offset_hour = digits(bytes, end + 1, 2);
+                let offset_minute = digits(bytes, end + 4, 2);
+                if bytes.get(end + 3) == Some(&b':') && offset_hour.map(|hour| hour <= 23).unwrap_or(false) && offset_minute.map(|minute| minute <= 59).unwrap_or(false) {
+                    end += 6;
+                }
+            }
+            _ => {}
+        }
+        return end - pos;
+    }
+}
+
+impl Extract for IsoDate {
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        if preceded_by_word(bytes, start) || bytes.get(start + 4) != Some(&b'-') || bytes.get(start + 7) != Some(&b'-') {
+            return None;
+        }
+        let (year, month, day) = (digits(bytes, start, 4)?, digits(bytes, start + 5, 2)?, digits(bytes, start + 8, 2)?);
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        let length = 10 + IsoDate::time_len(bytes, start + 10);
+        if is_word(bytes.get(start + length)) {
+            return None;
+        }
+        return Some(length);
+    }
+}
+
+impl Extract for HexBlob {
+    fn match_len(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        if preceded_by_word(bytes, start) {
+            return None;
+        }
+        let prefix = if bytes[start..].starts_with(b"0x") || bytes[start..].starts_with(b"0X") { 2 } else { 0 };
+        let length = run_len(bytes, start + prefix, |byte| byte.is_ascii_hexdigit());
+        if length < self.min_len.max(1) || is_word(bytes.get(start + prefix + length)) {
+            return None;
+        }
+        return Some(prefix + length);
+    }
+}
+
+impl<'a> PatternMatcher<'a, Email> for str {
+    fn find_first_from(&'a self, pattern: &Email, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return find_from(self, pattern, byte_offset);
+    }
+}
+
+impl<'a> PatternMatcher<'a, Url> for str {
+    fn find_first_from(&'a self, pattern: &Url, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return find_from(self, pattern, byte_offset);
+    }
+}
+
+impl<'a> PatternMatcher<'a, Ipv4> for str {
+    fn find_first_from(&'a self, pattern: &Ipv4, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return find_from(self, pattern, byte_offset);
+    }
+}
+
+impl<'a> PatternMatcher<'a, Ipv6> for str {
+    fn find_first_from(&'a self, pattern: &Ipv6, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return find_from(self, pattern, byte_offset);
+    }
+}
+
+impl<'a> PatternMatcher<'a, Uuid> for str {
+    fn find_first_from(&'a self, pattern: &Uuid, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return find_from(self, pattern, byte_offset);
+    }
+}
+
+impl<'a> PatternMatcher<'a, IsoDate> for str {
+    fn find_first_from(&'a self, pattern: &IsoDate, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return find_from(self, pattern, byte_offset);
+    }
+}
+
+impl<'a> PatternMatcher<'a, HexBlob> for str {
+    fn find_first_from(&'a self, pattern: &HexBlob, byte_offset: usize) -> Option<PatternMatch<&'a str>> {
+        return find_from(self, pattern, byte_offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found<'a, P>(haystack: &'a str, pattern: &P) -> Vec<&'a str>
+    where str: PatternMatcher<'a, P> {
+        return haystack.find_every(pattern).unwrap_or_default().iter().map(|hit| hit.slice).collect();
+    }
+
+    #[test]
+    fn test_email_and_url() {
+        let text = "Mail jane.doe+ops@mail.example.com or @handle, not a@b or x@y.c1. See https://example.com/a_(b)?q=1. (http://x.org/p) or ftp://";
+        assert_eq!(found(text, &Email), vec!["jane.doe+ops@mail.example.com"]);
+        assert_eq!(found(text, &Url), vec!["https://example.com/a_(b)?q=1", "http://x.org/p"]);
+        let hit = text.find_first(&Email).unwrap();
+        assert_eq!(&text[hit.range()], hit.slice);
+    }
+
+    #[test]
+    fn test_addresses() {
+        let text = "hosts 10.0.0.1, 256.1.1.1 and 1.2.3.4.5; v6 fe80::1%eth0, [2001:db8::ff00:42:8329]:80, ::ffff:192.0.2.1. std::fmt a::b";
+        assert_eq!(found(text, &Ipv4), vec!["10.0.0.1", "192.0.2.1"]);
+        assert_eq!(found(text, &Ipv6), vec!["fe80::1", "2001:db8::ff00:42:8329", "::ffff:192.0.2.1"]);
+    }
+
+    #[test]
+    fn test_ids_dates_and_blobs() {
+        let text = "id=123e4567-e89b-12d3-a456-426614174000 at 2024-02-29T13:05:09.5+02:00, not 2023-02-29 or 2024-13-01; sha 0xdeadbeefcafebabe1234 short abcdef";
+        assert_eq!(found(text, &Uuid), vec!["123e4567-e89b-12d3-a456-426614174000"]);
+        assert_eq!(found(text, &IsoDate), vec!["2024-02-29T13:05:09.5+02:00"]);
+        assert_eq!(found("on 2024-01-05.", &IsoDate), vec!["2024-01-05"]);
+        assert_eq!(found(text, &HexBlob::default()), vec!["0xdeadbeefcafebabe1234"]);
+        assert_eq!(found(text, &HexBlob { min_len: 6 }).last(), Some(&"abcdef"));
+    }
+}
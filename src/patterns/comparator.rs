@@ -0,0 +1,68 @@
+use super::PatternMatch;
+use super::PatternMatcher;
+
+// a slice pattern matched with a custom equality instead of PartialEq, e.g. case-folded bytes,
+// floats within an epsilon, or structs compared on one key field
+#[derive(Debug, Clone, Copy)]
+pub struct Compared<P, F> {
+    pub pattern: P,
+    pub eq: F,
+}
+
+impl<P, F> Compared<P, F> {
+    pub fn new(pattern: P, eq: F) -> Compared<P, F> {
+        return Compared { pattern, eq };
+    }
+}
+
+// the haystack element comes first, the pattern element second
+pub fn compared_by<P, F>(pattern: P, eq: F) -> Compared<P, F> {
+    return Compared::new(pattern, eq);
+}
+
+impl<'a, P, F, T> PatternMatcher<'a, Compared<P, F>> for [T]
+where P: AsRef<[T]>, F: Fn(&T, &T) -> bool {
+    fn find_first_from(&'a self, pattern: &Compared<P, F>, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        let needle = pattern.pattern.as_ref();
+        let haystack = self.get(byte_offset..)?;
+        if needle.len() > haystack.len() {
+            return None;
+        }
+        for start in 0..=haystack.len() - needle.len() {
+            let window = &haystack[start..start + needle.len()];
+            if window.iter().zip(needle).all(|(item, expected)| (pattern.eq)(item, expected)) {
+                let index = byte_offset + start;
+                return Some(PatternMatch { index, length: needle.len(), slice: &self[index..index + needle.len()] });
+            }
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_equality() {
+        let haystack = b"Content-Type: TEXT/plain";
+        let folded = compared_by(b"text/PLAIN", |a: &u8, b: &u8| a.eq_ignore_ascii_case(b));
+        assert_eq!(haystack.find_first(&folded).map(|found| found.index), Some(14));
+
+        let samples = [0.0, 0.49, 1.01, 1.98, 0.5, 1.0];
+        let near = compared_by([0.5, 1.0, 2.0], |a: &f64, b: &f64| (a - b).abs() < 0.05);
+        assert_eq!(samples.find_first(&near).map(|found| found.index), Some(1));
+        assert_eq!(samples.find_every(&compared_by([0.5, 1.0], |a: &f64, b: &f64| (a - b).abs() < 0.05)).map(|found| found.len()), Some(2));
+
+        struct Event {
+            kind: &'static str,
+            at: u32,
+        }
+        let event = |kind: &'static str, at: u32| Event { kind, at };
+        let events = [event("open", 1), event("read", 2), event("close", 3)];
+        let by_kind = compared_by([event("read", 0), event("close", 0)], |a: &Event, b: &Event| a.kind == b.kind);
+        let found = events.find_first(&by_kind).unwrap();
+        assert_eq!((found.index, found.slice[0].at), (1, 2));
+        assert!(events.find_first_from(&by_kind, 2).is_none());
+    }
+}
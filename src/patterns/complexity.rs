@@ -0,0 +1,177 @@
+use std::fmt;
+use std::fmt::Display;
+use std::mem::size_of;
+
+use super::anchored::LineAnchored;
+use super::PatternSet;
+use crate::types::error_chain::ErrorChain;
+use crate::types::options::Checks;
+use crate::types::options::Options;
+
+// how the work of a full search grows with the haystack length n
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Growth {
+    Linear,
+    Quadratic,
+}
+
+impl Display for Growth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            Growth::Linear => "O(n)",
+            Growth::Quadratic => "O(n^2)",
+        });
+    }
+}
+
+// worst-case figures for searching with a pattern: about steps_per_byte * n^growth comparisons,
+// up to max_lookahead bytes read past a match start, and memory bytes held apart from the haystack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Analysis {
+    pub growth: Growth,
+    pub steps_per_byte: u64,
+    pub max_lookahead: usize,
+    pub memory: usize,
+}
+
+impl Analysis {
+    pub fn new(growth: Growth, steps_per_byte: u64, max_lookahead: usize, memory: usize) -> Analysis {
+        return Analysis { growth, steps_per_byte, max_lookahead, memory };
+    }
+
+    // what nothing costs, the start of a combine fold
+    pub fn empty() -> Analysis {
+        return Analysis::new(Growth::Linear, 0, 0, 0);
+    }
+
+    // both patterns searched over the same haystack
+    pub fn combine(self, other: Analysis) -> Analysis {
+        return Analysis {
+            growth: self.growth.max(other.growth),
+            steps_per_byte: self.steps_per_byte.saturating_add(other.steps_per_byte),
+            max_lookahead: self.max_lookahead.max(other.max_lookahead),
+            memory: self.memory.saturating_add(other.memory),
+        };
+    }
+
+    pub fn check(&self, limits: &ComplexityLimits) -> Result<(), ErrorChain> {
+        let mut over = Vec::new();
+        if self.growth > limits.max_growth {
+            over.push(format!("growth {} over {}", self.growth, limits.max_growth));
+        }
+        if self.steps_per_byte > limits.max_steps_per_byte {
+            over.push(format!("{} steps per byte over {}", self.steps_per_byte, limits.max_steps_per_byte));
+        }
+        if self.max_lookahead > limits.max_lookahead {
+            over.push(format!("lookahead of {} bytes over {}", self.max_lookahead, limits.max_lookahead));
+        }
+        if self.memory > limits.max_memory {
+            over.push(format!("{} bytes of memory over {}", self.memory, limits.max_memory));
+        }
+        if over.is_empty() {
+            return Ok(());
+        }
+        return Err(ErrorChain::new(format!("pattern is too complex to run: {}", over.join(", "))).with_help("simplify the pattern or raise the limits"));
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}, {} steps per byte, lookahead {} bytes, memory {} bytes", self.growth, self.steps_per_byte, self.max_lookahead, self.memory);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComplexityLimits {
+    pub max_growth: Growth,
+    pub max_steps_per_byte: u64,
+    pub max_lookahead: usize,
+    pub max_memory: usize,
+}
+
+impl Default for ComplexityLimits {
+    fn default() -> ComplexityLimits {
+        return ComplexityLimits { max_growth: Growth::Linear, max_steps_per_byte: 1 << 16, max_lookahead: 1 << 16, max_memory: 1 << 20 };
+    }
+}
+
+// limits of zero would refuse every pattern, which is a misconfiguration rather than a policy
+impl Options for ComplexityLimits {
+    const NAME: &'static str = "complexity limits";
+
+    fn validate(&self, checks: &mut Checks) {
+        checks.require(self.max_steps_per_byte > 0, "max_steps_per_byte", "must be at least 1");
+        checks.require(self.max_lookahead > 0, "max_lookahead", "must be at least 1 byte");
+        checks.require(self.max_memory > 0, "max_memory", "must be at least 1 byte");
+    }
+}
+
+pub trait Analyze {
+    fn analyze(&self) -> Analysis;
+}
+
+// a literal is compared in place at every offset
+impl<P> Analyze for P
+where P: AsRef<[u8]> {
+    fn analyze(&self) -> Analysis {
+        let len = self.as_ref().len();
+        return Analysis::new(Growth::Linear, len as u64, len, len);
+    }
+}
+
+// a candidate failing the anchor is retried after its start, so the inner searches never go over
+// the same text twice; checking the line end reads up to "\r\n" past the match
+impl<P> Analyze for LineAnchored<P>
+where P: Analyze {
+    fn analyze(&self) -> Analysis {
+        let inner = self.pattern.analyze();
+        return Analysis { max_lookahead: inner.max_lookahead.saturating_add(2), ..inner };
+    }
+}
+
+// find_every_in keeps each member's next match in a SetCursor and searches a member again only
+// once the offset passes it, so members cost what they would alone plus the cached matches
+impl<P> Analyze for PatternSet<P>
+where P: Analyze {
+    fn analyze(&self) -> Analysis {
+        let members = self.patterns().iter().map(Analyze::analyze).fold(Analysis::empty(), Analysis::combine);
+        return Analysis { memory: members.memory.saturating_add(self.len() * size_of::<Option<(usize, usize)>>()), ..members };
+    }
+}
+
+// analyzes before running, so user supplied patterns are refused before they cost anything
+pub fn check<P>(pattern: &P, limits: &ComplexityLimits) -> Result<Analysis, ErrorChain>
+where P: Analyze + ?Sized {
+    limits.check_valid()?;
+    let analysis = pattern.analyze();
+    analysis.check(limits)?;
+    return Ok(analysis);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::anchored::at_line_end;
+    use crate::patterns::wildcard::Wildcard;
+    use crate::patterns::wildcard::WildcardLimits;
+
+    #[test]
+    fn test_analyze_and_refuse() {
+        assert_eq!("TODO".analyze(), Analysis::new(Growth::Linear, 4, 4, 4));
+        assert_eq!(at_line_end("TODO").analyze().max_lookahead, 6);
+        let set = PatternSet::new().with("TODO").with("FIXME");
+        let cached = 2 * size_of::<Option<(usize, usize)>>();
+        assert_eq!(set.analyze(), Analysis::new(Growth::Linear, 9, 5, 9 + cached));
+        let limits = ComplexityLimits::default();
+        assert!(check(&set, &limits).is_ok());
+        let error = Analysis::new(Growth::Quadratic, 1, 0, 0).check(&limits).unwrap_err().to_string();
+        assert!(error.contains("pattern is too complex to run: growth O(n^2) over O(n)"), "{}", error);
+        assert!(Analysis::new(Growth::Quadratic, 1, 0, 0).check(&ComplexityLimits { max_growth: Growth::Quadratic, ..limits }).is_ok());
+
+        let wildcard = Wildcard::with_limits("key=*;", WildcardLimits { max_match_len: 100, ..WildcardLimits::default() }).unwrap();
+        let analysis = check(&wildcard, &limits).unwrap();
+        assert_eq!((analysis.steps_per_byte, analysis.max_lookahead), (600, 100));
+        let error = check(&wildcard, &ComplexityLimits { max_steps_per_byte: 500, max_lookahead: 10, ..limits }).unwrap_err().to_string();
+        assert!(error.contains("600 steps per byte over 500, lookahead of 100 bytes over 10"), "{}", error);
+    }
+}
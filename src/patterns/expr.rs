@@ -0,0 +1,313 @@
+use std::fmt;
+use std::fmt::Display;
+use std::ops::BitAnd;
+use std::ops::BitOr;
+use std::ops::Not;
+
+use crate::lex::CharsWhile;
+use crate::lex::FnPattern;
+use crate::lex::Lexer;
+use crate::lex::Token;
+use crate::parse::combinator::alt;
+use crate::parse::combinator::cut;
+use crate::parse::combinator::delimited;
+use crate::parse::combinator::label;
+use crate::parse::combinator::parse_all;
+use crate::parse::combinator::text;
+use crate::parse::combinator::token;
+use crate::parse::combinator::Input;
+use crate::parse::combinator::ParseResult;
+use crate::parse::combinator::Parser;
+use crate::text::glob::glob_match;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::parse_error::ParseError;
+
+pub const FUNCTIONS: [&str; 1] = ["glob"];
+
+// what an expression is evaluated against: literals search the text, globs test the path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subject<'a> {
+    pub path: Option<&'a str>,
+    pub text: &'a str,
+}
+
+impl<'a> Subject<'a> {
+    pub fn text(text: &'a str) -> Subject<'a> {
+        return Subject { path: None, text };
+    }
+
+    pub fn file(path: &'a str, text: &'a str) -> Subject<'a> {
+        return Subject { path: Some(path), text };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Literal(String),
+    // patterns without a '/' are matched against the file name only
+    Glob(String),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn literal<S>(literal: S) -> Expr
+    where S: Into<String> {
+        return Expr::Literal(literal.into());
+    }
+
+    pub fn glob<S>(glob: S) -> Expr
+    where S: Into<String> {
+        return Expr::Glob(glob.into());
+    }
+
+    pub fn parse(src: &str) -> Result<Expr, ErrorChain> {
+        let tokens = lexer().tokenize(src).on_error("failed to parse pattern expression")?;
+        let expr = parse_all(or_expr, &tokens).on_error("failed to parse pattern expression")?;
+        expr.validate().on_error("failed to parse pattern expression")?;
+        return Ok(expr);
+    }
+
+    fn validate(&self) -> Result<(), ErrorChain> {
+        match self {
+            Expr::Literal(literal) if literal.is_empty() => return Err(ErrorChain::new("empty string literals match everything")),
+            Expr::Not(inner) => return inner.validate(),
+            Expr::And(items) | Expr::Or(items) => return items.iter().try_for_each(Expr::validate),
+            _ => return Ok(()),
+        }
+    }
+
+    pub fn matches(&self, subject: &Subject) -> bool {
+        match self {
+            Expr::Literal(literal) => return subject.text.contains(literal.as_str()),
+            Expr::Glob(glob) => {
+                let Some(path) = subject.path else {
+                    return false;
+                };
+                let path = path.replace('\\', "/");
+                let target = if glob.contains('/') { path.as_str() } else { path.rsplit('/').next().unwrap_or(&path) };
+                return glob_match(glob, target);
+            }
+            Expr::Not(inner) => return !inner.matches(subject),
+            Expr::And(items) => return items.iter().all(|item| item.matches(subject)),
+            Expr::Or(items) => return items.iter().any(|item| item.matches(subject)),
+        }
+    }
+
+    pub fn matches_text(&self, text: &str) -> bool {
+        return self.matches(&Subject::text(text));
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(_) => return 0,
+            Expr::And(_) => return 1,
+            _ => return 2,
+        }
+    }
+}
+
+impl Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        return Expr::Not(Box::new(self));
+    }
+}
+
+impl BitAnd for Expr {
+    type Output = Expr;
+
+    fn bitand(self, other: Expr) -> Expr {
+        match self {
+            Expr::And(mut items) => {
+                items.push(other);
+                return Expr::And(items);
+            }
+            first => return Expr::And(vec![first, other]),
+        }
+    }
+}
+
+impl BitOr for Expr {
+    type Output = Expr;
+
+    fn bitor(self, other: Expr) -> Expr {
+        match self {
+            Expr::Or(mut items) => {
+                items.push(other);
+                return Expr::Or(items);
+            }
+            first => return Expr::Or(vec![first, other]),
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    return format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+// prints in the expression syntax, so parse(expr.to_string()) gives back an equal expression
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, items: &[Expr], operator: &str, precedence: u8| -> fmt::Result {
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    write!(f, " {} ", operator)?;
+                }
+                if item.precedence() <= precedence {
+                    write!(f, "({})", item)?;
+                } else {
+                    write!(f, "{}", item)?;
+                }
+            }
+            return Ok(());
+        };
+        match self {
+            Expr::Literal(literal) => return write!(f, "{}", quote(literal)),
+            Expr::Glob(glob) => return write!(f, "glob({})", quote(glob)),
+            Expr::Not(inner) if inner.precedence() < 2 => return write!(f, "!({})", inner),
+            Expr::Not(inner) => return write!(f, "!{}", inner),
+            Expr::And(items) => return join(f, items, "&", 1),
+            Expr::Or(items) => return join(f, items, "|", 0),
+        }
+    }
+}
+
+fn string_len(rest: &str) -> Option<usize> {
+    if !rest.starts_with('"') {
+        return None;
+    }
+    let mut escaped = false;
+    for (index, character) in rest.char_indices().skip(1) {
+        match character {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(index + 1),
+            _ => escaped = false,
+        }
+    }
+    // unterminated: take the rest so the parser reports it with a span
+    return Some(rest.len());
+}
+
+fn lexer() -> Lexer {
+    return Lexer::new()
+        .skip("space", CharsWhile(char::is_whitespace))
+        .rule("string", FnPattern(string_len))
+        .rule("ident", CharsWhile(|character: char| character.is_ascii_alphanumeric() || character == '_'))
+        .rule("punct", FnPattern(|rest: &str| rest.chars().next().filter(|character| "&|!()".contains(*character)).map(|_| 1)));
+}
+
+fn unquote(token: &Token) -> Result<String, ParseError> {
+    let inner = token.text.strip_prefix('"').unwrap_or(token.text);
+    let Some(inner) = inner.strip_suffix('"').filter(|_| token.text.len() >= 2) else {
+        return Err(ParseError::new(token.span, "unterminated string"));
+    };
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' => value.push(chars.next().unwrap_or('\\')),
+            other => value.push(other),
+        }
+    }
+    return Ok(value);
+}
+
+fn string_literal(input: Input<'_>) -> ParseResult<'_, String> {
+    let (token, rest) = token("string").parse(input)?;
+    return match unquote(&token) {
+        Ok(value) => Ok((value, rest)),
+        Err(error) => {
+            let mut failure = input.fail("a closing '\"'");
+            failure.span = error.span;
+            failure.cut = true;
+            Err(failure)
+        }
+    };
+}
+
+fn call(input: Input<'_>) -> ParseResult<'_, Expr> {
+    let (name, rest) = token("ident").parse(input)?;
+    if !FUNCTIONS.contains(&name.text) {
+        let mut failure = input.fail(format!("one of {}", FUNCTIONS.join(", ")));
+        failure.cut = true;
+        return Err(failure);
+    }
+    let (argument, rest) = cut(delimited(text("punct", "("), string_literal, text("punct", ")"))).parse(rest)?;
+    return Ok((Expr::Glob(argument), rest));
+}
+
+fn atom(input: Input<'_>) -> ParseResult<'_, Expr> {
+    let literal = |input| string_literal(input).map(|(value, rest)| (Expr::Literal(value), rest));
+    if input.peek().map(|next| next.is("ident")).unwrap_or(false) {
+        return call(input);
+    }
+    let group = delimited(text("punct", "("), or_expr, cut(text("punct", ")")));
+    return label(alt(literal, group), "a string, glob(...) or '('").parse(input);
+}
+
+fn unary(input: Input<'_>) -> ParseResult<'_, Expr> {
+    if let Ok((_, rest)) = text("punct", "!").parse(input) {
+        let (inner, rest) = cut(unary).parse(rest)?;
+        return Ok((!inner, rest));
+    }
+    return atom(input);
+}
+
+fn binary<'t>(input: Input<'t>, operator: &'static str, operand: fn(Input<'t>) -> ParseResult<'t, Expr>, build: fn(Vec<Expr>) -> Expr) -> ParseResult<'t, Expr> {
+    let (first, mut rest) = operand(input)?;
+    let mut items = vec![first];
+    while let Ok((_, next)) = text("punct", operator).parse(rest) {
+        let (item, next) = cut(operand).parse(next)?;
+        items.push(item);
+        rest = next;
+    }
+    if items.len() == 1 {
+        return Ok((items.pop().expect("one item"), rest));
+    }
+    return Ok((build(items), rest));
+}
+
+fn and_expr(input: Input<'_>) -> ParseResult<'_, Expr> {
+    return binary(input, "&", unary, Expr::And);
+}
+
+fn or_expr(input: Input<'_>) -> ParseResult<'_, Expr> {
+    return binary(input, "|", and_expr, Expr::Or);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let expr = Expr::parse(r#""foo" & !"bar" | glob("*.rs")"#).unwrap();
+        assert_eq!(expr, (Expr::literal("foo") & !Expr::literal("bar")) | Expr::glob("*.rs"));
+        assert!(expr.matches_text("foo only"));
+        assert!(!expr.matches_text("foo and bar"));
+        assert!(expr.matches(&Subject::file("src/lib.rs", "bar")));
+        assert!(!expr.matches(&Subject::file("README.md", "")));
+        let grouped = Expr::parse(r#"!("q" | "z") & "say \"hi\"""#).unwrap();
+        assert!(grouped.matches_text("say \"hi\""));
+        assert!(!grouped.matches_text("q say \"hi\""));
+        for expr in [expr, grouped] {
+            assert_eq!(Expr::parse(&expr.to_string()).unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = Expr::parse(r#""foo" & "#).unwrap_err().to_string();
+        assert!(error.contains("expected a string, glob(...) or '(', found end of input"), "{}", error);
+        let error = Expr::parse(r#"regex("x")"#).unwrap_err().to_string();
+        assert!(error.contains("expected one of glob, found ident 'regex'"), "{}", error);
+        let error = Expr::parse(r#"("a" | "b""#).unwrap_err().to_string();
+        assert!(error.contains("expected ')'"), "{}", error);
+        assert!(Expr::parse(r#""open"#).is_err());
+        assert!(Expr::parse(r#""""#).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use super::PatternMatch;
+use super::PatternMatcher;
+use crate::types::span::Span;
+
+// suffix array + LCP over a fixed haystack: built once, then each query is a binary search over
+// the sorted suffixes, O(m log n) to find the block of suffixes that start with the pattern
+#[derive(Debug, Clone)]
+pub struct Index {
+    text: Vec<u8>,
+    suffixes: Vec<usize>,
+    lcp: Vec<usize>,
+}
+
+impl Index {
+    pub fn new<H>(haystack: H) -> Index
+    where H: AsRef<[u8]> {
+        let text = haystack.as_ref().to_vec();
+        let suffixes = suffix_array(&text);
+        let lcp = lcp_array(&text, &suffixes);
+        return Index { text, suffixes, lcp };
+    }
+
+    pub fn haystack(&self) -> &[u8] {
+        return &self.text;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.text.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.text.is_empty();
+    }
+
+    fn compare_prefix(&self, suffix: usize, pattern: &[u8]) -> Ordering {
+        let end = (suffix + pattern.len()).min(self.text.len());
+        return self.text[suffix..end].cmp(pattern);
+    }
+
+    fn suffix_block(&self, pattern: &[u8]) -> Range<usize> {
+        if pattern.is_empty() {
+            return 0..0;
+        }
+        let start = self.suffixes.partition_point(|suffix| self.compare_prefix(*suffix, pattern) == Ordering::Less);
+        let end = start + self.suffixes[start..].partition_point(|suffix| self.compare_prefix(*suffix, pattern) == Ordering::Equal);
+        return start..end;
+    }
+
+    pub fn count<P>(&self, pattern: P) -> usize
+    where P: AsRef<[u8]> {
+        return self.suffix_block(pattern.as_ref()).len();
+    }
+
+    // every occurrence, overlapping ones included, in ascending order
+    pub fn positions<P>(&self, pattern: P) -> Vec<usize>
+    where P: AsRef<[u8]> {
+        let mut positions = self.suffixes[self.suffix_block(pattern.as_ref())].to_vec();
+        positions.sort_unstable();
+        return positions;
+    }
+
+    // same results as PatternMatcher::find_every_from: leftmost first, non-overlapping
+    pub fn find_every_from<P>(&self, pattern: P, byte_offset: usize) -> Vec<Span>
+    where P: AsRef<[u8]> {
+        let len = pattern.as_ref().len();
+        let mut found: Vec<Span> = Vec::new();
+        for position in self.positions(pattern) {
+            if position >= byte_offset && found.last().map(|last| position >= last.end).unwrap_or(true) {
+                found.push(Span::new(position, position + len));
+            }
+        }
+        return found;
+    }
+
+    pub fn find_every<P>(&self, pattern: P) -> Vec<Span>
+    where P: AsRef<[u8]> {
+        return self.find_every_from(pattern, 0);
+    }
+
+    // the earliest occurrence of the longest substring that appears at least twice
+    pub fn longest_repeated_substring(&self) -> Option<Span> {
+        let length = self.lcp.iter().copied().max().filter(|length| *length > 0)?;
+        let shared = self.lcp.iter().enumerate().filter(|(_, other)| **other == length).flat_map(|(index, _)| [self.suffixes[index], self.suffixes[index + 1]]);
+        let start = shared.min()?;
+        return Some(Span::new(start, start + length));
+    }
+}
+
+impl<'a, P> PatternMatcher<'a, P> for Index
+where P: AsRef<[u8]> {
+    fn find_first_from(&'a self, pattern: &P, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        let length = pattern.as_ref().len();
+        let block = self.suffix_block(pattern.as_ref());
+        let index = self.suffixes[block].iter().copied().filter(|position| *position >= byte_offset).min()?;
+        return Some(PatternMatch { index, length, slice: self });
+    }
+}
+
+// prefix doubling: rank suffixes by their first 2^k bytes until every rank is distinct
+fn suffix_array(text: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let mut suffixes: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = text.iter().map(|byte| *byte as usize).collect();
+    let mut next_rank = vec![0; n];
+    let mut width = 1;
+    if n < 2 {
+        return suffixes;
+    }
+    loop {
+        let key = |suffix: usize| (rank[suffix], if suffix + width < n { rank[suffix + width] + 1 } else { 0 });
+        suffixes.sort_unstable_by_key(|suffix| key(*suffix));
+        next_rank[suffixes[0]] = 0;
+        for index in 1..n {
+            let bump = (key(suffixes[index - 1]) != key(suffixes[index])) as usize;
+            next_rank[suffixes[index]] = next_rank[suffixes[index - 1]] + bump;
+        }
+        std::mem::swap(&mut rank, &mut next_rank);
+        if rank[suffixes[n - 1]] == n - 1 {
+            return suffixes;
+        }
+        width *= 2;
+    }
+}
+
+// Kasai: lcp[i] is the length of the common prefix of suffixes[i] and suffixes[i + 1]
+fn lcp_array(text: &[u8], suffixes: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut rank = vec![0; n];
+    for (index, suffix) in suffixes.iter().enumerate() {
+        rank[*suffix] = index;
+    }
+    let mut lcp = vec![0; n.saturating_sub(1)];
+    let mut shared = 0;
+    for suffix in 0..n {
+        if rank[suffix] + 1 == n {
+            shared = 0;
+            continue;
+        }
+        let next = suffixes[rank[suffix] + 1];
+        while suffix + shared < n && next + shared < n && text[suffix + shared] == text[next + shared] {
+            shared += 1;
+        }
+        lcp[rank[suffix]] = shared;
+        shared = shared.saturating_sub(1);
+    }
+    return lcp;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::haystack::oracle_find_every;
+    use crate::testing::haystack::HaystackSpec;
+
+    #[test]
+    fn test_queries() {
+        let index = Index::new("banana bandana");
+        assert_eq!(index.positions("ana"), vec![1, 3, 11]);
+        assert_eq!(index.count("an"), 4);
+        assert_eq!(index.find_every("ana"), vec![Span::new(1, 4), Span::new(11, 14)]);
+        assert_eq!(index.count("xyz"), 0);
+        assert_eq!(index.find_first_from(&"ban", 1).map(|found| found.index), Some(7));
+        let repeated = index.longest_repeated_substring().unwrap();
+        assert_eq!(&index.haystack()[repeated.range()], b"ban");
+        assert_eq!(Index::new("xabcyabc").longest_repeated_substring(), Some(Span::new(1, 4)));
+        assert!(Index::new("abc").longest_repeated_substring().is_none());
+        assert!(Index::new("").positions("a").is_empty());
+    }
+
+    #[test]
+    fn test_agrees_with_oracle() {
+        let spec = HaystackSpec::new(2000).alphabet(b"abc").needle("abca").needle("cc").plants(20);
+        for seed in 0..5 {
+            let generated = spec.generate(seed);
+            let index = Index::new(&generated.bytes);
+            for needle in &spec.needles {
+                let expected: Vec<Span> = oracle_find_every(&generated.bytes, needle, 0).into_iter().map(Span::from).collect();
+                assert_eq!(index.find_every(needle), expected);
+            }
+        }
+    }
+}
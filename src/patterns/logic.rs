@@ -0,0 +1,239 @@
+use crate::text::line_endings::split_lines;
+use crate::types::span::Span;
+
+// why a condition did or did not hold: the spans that support the verdict and a readable reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evidence {
+    pub matched: bool,
+    pub spans: Vec<Span>,
+    pub reason: String,
+}
+
+impl Evidence {
+    fn shifted(mut self, offset: usize) -> Evidence {
+        self.spans = self.spans.iter().map(|span| span.shifted(offset)).collect();
+        return self;
+    }
+}
+
+pub trait Condition {
+    fn evaluate(&self, haystack: &str) -> Evidence;
+
+    fn describe(&self) -> String;
+
+    fn is_match(&self, haystack: &str) -> bool {
+        return self.evaluate(haystack).matched;
+    }
+
+    // one result per line that satisfies the condition, with spans relative to the whole haystack
+    fn evaluate_lines(&self, haystack: &str) -> Vec<(usize, Evidence)> {
+        let mut found = Vec::new();
+        for (index, line) in split_lines(haystack).enumerate() {
+            let evidence = self.evaluate(line.text);
+            if evidence.matched {
+                found.push((index + 1, evidence.shifted(line.span.start)));
+            }
+        }
+        return found;
+    }
+}
+
+fn literal_evidence(literal: &str, haystack: &str) -> Evidence {
+    let spans: Vec<Span> = if literal.is_empty() {
+        Vec::new()
+    } else {
+        haystack.match_indices(literal).map(|(index, _)| Span::new(index, index + literal.len())).collect()
+    };
+    let reason = match spans.len() {
+        0 => format!("{:?} not found", literal),
+        1 => format!("{:?} found once", literal),
+        count => format!("{:?} found {} times", literal, count),
+    };
+    return Evidence { matched: !spans.is_empty(), spans, reason };
+}
+
+impl Condition for str {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        return literal_evidence(self, haystack);
+    }
+
+    fn describe(&self) -> String {
+        return format!("{:?}", self);
+    }
+}
+
+impl Condition for String {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        return literal_evidence(self, haystack);
+    }
+
+    fn describe(&self) -> String {
+        return format!("{:?}", self);
+    }
+}
+
+impl<C> Condition for &C
+where C: Condition + ?Sized {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        return (**self).evaluate(haystack);
+    }
+
+    fn describe(&self) -> String {
+        return (**self).describe();
+    }
+}
+
+impl Condition for Box<dyn Condition> {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        return (**self).evaluate(haystack);
+    }
+
+    fn describe(&self) -> String {
+        return (**self).describe();
+    }
+}
+
+fn describe_list<C>(name: &str, conditions: &[C]) -> String
+where C: Condition {
+    let parts: Vec<String> = conditions.iter().map(Condition::describe).collect();
+    return format!("{}({})", name, parts.join(", "));
+}
+
+// every condition holds; an empty list always holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct All<C>(pub Vec<C>);
+
+impl<C> Condition for All<C>
+where C: Condition {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        let mut spans = Vec::new();
+        for condition in &self.0 {
+            let evidence = condition.evaluate(haystack);
+            if !evidence.matched {
+                return Evidence { matched: false, spans: evidence.spans, reason: format!("{} failed: {}", self.describe(), evidence.reason) };
+            }
+            spans.extend(evidence.spans);
+        }
+        spans.sort();
+        return Evidence { matched: true, spans, reason: format!("all {} conditions held", self.0.len()) };
+    }
+
+    fn describe(&self) -> String {
+        return describe_list("all", &self.0);
+    }
+}
+
+// at least one condition holds; the first one that does is the evidence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Any<C>(pub Vec<C>);
+
+impl<C> Condition for Any<C>
+where C: Condition {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        for condition in &self.0 {
+            let evidence = condition.evaluate(haystack);
+            if evidence.matched {
+                return Evidence { matched: true, spans: evidence.spans, reason: format!("{} held: {}", condition.describe(), evidence.reason) };
+            }
+        }
+        return Evidence { matched: false, spans: Vec::new(), reason: format!("none of {} held", self.describe()) };
+    }
+
+    fn describe(&self) -> String {
+        return describe_list("any", &self.0);
+    }
+}
+
+// holds when the inner condition does not; its spans show what ruled a non-match out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Not<C>(pub C);
+
+impl<C> Condition for Not<C>
+where C: Condition {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        let inner = self.0.evaluate(haystack);
+        return Evidence { matched: !inner.matched, spans: inner.spans, reason: format!("not: {}", inner.reason) };
+    }
+
+    fn describe(&self) -> String {
+        return format!("not({})", self.0.describe());
+    }
+}
+
+// some span of A and some span of B are at most max_distance bytes apart (0 when they touch or overlap)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Near<A, B>(pub A, pub B, pub usize);
+
+fn gap(a: Span, b: Span) -> usize {
+    return a.start.saturating_sub(b.end).max(b.start.saturating_sub(a.end));
+}
+
+impl<A, B> Condition for Near<A, B>
+where A: Condition, B: Condition {
+    fn evaluate(&self, haystack: &str) -> Evidence {
+        let (first, second) = (self.0.evaluate(haystack), self.1.evaluate(haystack));
+        if !first.matched || !second.matched {
+            let missing = if first.matched { second.reason } else { first.reason };
+            return Evidence { matched: false, spans: Vec::new(), reason: format!("{} failed: {}", self.describe(), missing) };
+        }
+        // both span lists are sorted, so a merge-style walk finds the closest pair
+        let (mut i, mut j) = (0, 0);
+        let mut best: Option<(usize, Span, Span)> = None;
+        while i < first.spans.len() && j < second.spans.len() {
+            let (a, b) = (first.spans[i], second.spans[j]);
+            let distance = gap(a, b);
+            if best.map(|(current, _, _)| distance < current).unwrap_or(true) {
+                best = Some((distance, a, b));
+            }
+            if a.start <= b.start {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        let (distance, a, b) = best.expect("both conditions matched with at least one span");
+        let mut spans = vec![a, b];
+        spans.sort();
+        if distance <= self.2 {
+            return Evidence { matched: true, spans, reason: format!("{} and {} are {} bytes apart (max {})", self.0.describe(), self.1.describe(), distance, self.2) };
+        }
+        return Evidence { matched: false, spans, reason: format!("closest {} and {} are {} bytes apart (max {})", self.0.describe(), self.1.describe(), distance, self.2) };
+    }
+
+    fn describe(&self) -> String {
+        return format!("near({}, {}, {})", self.0.describe(), self.1.describe(), self.2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "error: disk full on /dev/sda\nwarning: retrying write\nerror: timeout talking to db (retrying)\n";
+
+    #[test]
+    fn test_combinators_with_evidence() {
+        let rule = All(vec![Box::new("error") as Box<dyn Condition>, Box::new(Not("timeout")), Box::new(Near("disk", "full", 1))]);
+        let evidence = rule.evaluate("error: disk full");
+        assert!(evidence.matched, "{}", evidence.reason);
+        assert_eq!(evidence.spans, vec![Span::new(0, 5), Span::new(7, 11), Span::new(12, 16)]);
+        let failed = rule.evaluate("error: disk is full, timeout");
+        assert!(!failed.matched);
+        assert!(failed.reason.contains("not: \"timeout\" found once"), "{}", failed.reason);
+        assert_eq!(failed.spans, vec![Span::new(21, 28)]);
+        let far = Near("disk", "full", 1).evaluate("disk is full");
+        assert!(!far.matched && far.reason.contains("4 bytes apart"), "{}", far.reason);
+        assert!(Any(vec!["x", "full"]).is_match("disk full"));
+        assert_eq!(Any(vec!["x", "y"]).evaluate("z").reason, "none of any(\"x\", \"y\") held");
+    }
+
+    #[test]
+    fn test_per_line_evaluation() {
+        let lines = All(vec!["error", "retrying"]).evaluate_lines(LOG);
+        assert_eq!(lines.len(), 1);
+        let (line, evidence) = &lines[0];
+        assert_eq!(*line, 3);
+        assert_eq!(&LOG[evidence.spans[1].range()], "retrying");
+        assert!(All(vec!["error", "retrying"]).is_match(LOG));
+    }
+}
@@ -0,0 +1,194 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::PatternMatch;
+use super::PatternMatcher;
+use crate::text::line_index::line_starts;
+use crate::text::line_index::LineIndex;
+use crate::types::error_chain::ErrorChain;
+
+const GRAM_LEN: usize = 3;
+
+// a literal pattern matched without regard to ASCII case
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IgnoreAsciiCase<P>(pub P);
+
+// a haystack that is searched many times: the lowercased copy, the line starts and the trigram
+// positions are built the first time a search needs them and reused until the text is edited.
+// The trigram table costs at least a usize per byte of text, so haystacks too large to afford it
+// can turn it off and have literal searches scan the text instead
+#[derive(Debug, Clone, Default)]
+pub struct PreparedHaystack {
+    text: String,
+    without_grams: bool,
+    lowered: OnceCell<String>,
+    starts: OnceCell<Vec<usize>>,
+    grams: OnceCell<HashMap<[u8; GRAM_LEN], Vec<usize>>>,
+}
+
+impl PreparedHaystack {
+    pub fn new<S>(text: S) -> PreparedHaystack
+    where S: Into<String> {
+        return PreparedHaystack { text: text.into(), ..PreparedHaystack::default() };
+    }
+
+    pub fn trigrams(mut self, enabled: bool) -> PreparedHaystack {
+        self.without_grams = !enabled;
+        self.grams = OnceCell::new();
+        return self;
+    }
+
+    pub fn as_str(&self) -> &str {
+        return &self.text;
+    }
+
+    pub fn into_string(self) -> String {
+        return self.text;
+    }
+
+    pub fn lowercased(&self) -> &str {
+        return self.lowered.get_or_init(|| self.text.to_ascii_lowercase());
+    }
+
+    pub fn line_index(&self) -> LineIndex<'_> {
+        let starts = self.starts.get_or_init(|| line_starts(&self.text));
+        return LineIndex::from_starts(&self.text, starts);
+    }
+
+    fn grams(&self) -> &HashMap<[u8; GRAM_LEN], Vec<usize>> {
+        return self.grams.get_or_init(|| {
+            let mut grams: HashMap<[u8; GRAM_LEN], Vec<usize>> = HashMap::new();
+            for (index, window) in self.text.as_bytes().windows(GRAM_LEN).enumerate() {
+                grams.entry([window[0], window[1], window[2]]).or_default().push(index);
+            }
+            return grams;
+        });
+    }
+
+    pub fn is_cached(&self) -> bool {
+        return self.lowered.get().is_some() || self.starts.get().is_some() || self.grams.get().is_some();
+    }
+
+    fn invalidate(&mut self) {
+        self.lowered = OnceCell::new();
+        self.starts = OnceCell::new();
+        self.grams = OnceCell::new();
+    }
+
+    pub fn set_text<S>(&mut self, text: S)
+    where S: Into<String> {
+        self.text = text.into();
+        self.invalidate();
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.invalidate();
+    }
+
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<(), ErrorChain> {
+        if range.start > range.end || range.end > self.text.len() {
+            return Err(ErrorChain::new(format!("edit range {}..{} is outside the haystack (length {})", range.start, range.end, self.text.len())));
+        }
+        if !self.text.is_char_boundary(range.start) || !self.text.is_char_boundary(range.end) {
+            return Err(ErrorChain::new(format!("edit range {}..{} splits a character", range.start, range.end)));
+        }
+        self.text.replace_range(range, replacement);
+        self.invalidate();
+        return Ok(());
+    }
+}
+
+// patterns of at least GRAM_LEN bytes only verify the positions where their first trigram occurs
+impl<'a, P> PatternMatcher<'a, P> for PreparedHaystack
+where P: AsRef<str> {
+    fn find_first_from(&'a self, pattern: &P, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        let needle = pattern.as_ref();
+        if needle.len() < GRAM_LEN || self.without_grams {
+            let found = self.text.find_first_from(&needle, byte_offset)?;
+            return Some(PatternMatch { index: found.index, length: found.length, slice: self });
+        }
+        let bytes = needle.as_bytes();
+        let positions = self.grams().get(&[bytes[0], bytes[1], bytes[2]])?;
+        let first = positions.partition_point(|position| *position < byte_offset);
+        let index = positions[first..].iter().copied().find(|position| self.text.as_bytes()[*position..].starts_with(bytes))?;
+        return Some(PatternMatch { index, length: needle.len(), slice: self });
+    }
+}
+
+impl<'a, P> PatternMatcher<'a, IgnoreAsciiCase<P>> for PreparedHaystack
+where P: AsRef<str> {
+    fn find_first_from(&'a self, pattern: &IgnoreAsciiCase<P>, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        // ASCII lowercasing keeps every byte offset, so a match in the copy is a match in the text
+        let needle = pattern.0.as_ref().to_ascii_lowercase();
+        let found = self.lowercased().find_first_from(&needle, byte_offset)?;
+        return Some(PatternMatch { index: found.index, length: found.length, slice: self });
+    }
+}
+
+// compared in place: only ASCII letters fold, so every other byte must be equal and a match starts
+// and ends on the same character boundaries as the needle
+impl<'a, P> PatternMatcher<'a, IgnoreAsciiCase<P>> for str
+where P: AsRef<str> {
+    fn find_first_from(&'a self, pattern: &IgnoreAsciiCase<P>, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        return self.find_first_before(pattern, byte_offset, usize::MAX);
+    }
+
+    fn find_first_before(&'a self, pattern: &IgnoreAsciiCase<P>, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a Self>> {
+        let needle = pattern.0.as_ref().as_bytes();
+        let bytes = self.as_bytes();
+        let byte_offset = (byte_offset..=self.len()).find(|offset| self.is_char_boundary(*offset))?;
+        if before <= byte_offset {
+            return None;
+        }
+        let last = bytes.len().checked_sub(needle.len())?.min(before.saturating_sub(1));
+        let index = (byte_offset..=last).find(|index| bytes[*index..*index + needle.len()].eq_ignore_ascii_case(needle))?;
+        return Some(PatternMatch { index, length: needle.len(), slice: &self[index..index + needle.len()] });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepared_matches_plain_search() {
+        let text = "GET /index.html\npost /api/items\nGET /api/items/7\n";
+        let prepared = PreparedHaystack::new(text);
+        assert!(!prepared.is_cached());
+        let ranges = |found: Option<Vec<PatternMatch<&PreparedHaystack>>>| found.unwrap_or_default().into_iter().map(|found| found.range()).collect::<Vec<_>>();
+        let plain = |found: Option<Vec<PatternMatch<&str>>>| found.unwrap_or_default().into_iter().map(|found| found.range()).collect::<Vec<_>>();
+        for needle in ["/api/", "GET", "7", "\n", "missing", "ms/"] {
+            assert_eq!(ranges(prepared.find_every(&needle)), plain(text.find_every(&needle)), "{}", needle);
+        }
+        assert_eq!(ranges(prepared.find_every(&IgnoreAsciiCase("get"))), vec![0..3, 32..35]);
+        assert_eq!(ranges(prepared.find_every(&IgnoreAsciiCase("POST"))), plain(text.find_every(&IgnoreAsciiCase("POST"))));
+        assert_eq!(prepared.line_index().line_of(prepared.find_first(&"/api/items/7").unwrap().index), 2);
+        assert!(prepared.is_cached());
+
+        let scanned = PreparedHaystack::new(text).trigrams(false);
+        assert_eq!(ranges(scanned.find_every(&"/api/")), plain(text.find_every(&"/api/")));
+        assert!(!scanned.is_cached());
+        assert_eq!(plain("Été éTe ete".find_every(&IgnoreAsciiCase("éte"))), vec![6..10]);
+        assert_eq!("xxGetx".find_first_before(&IgnoreAsciiCase("GET"), 1, 2).map(|found| found.index), None);
+        assert_eq!("xxGetx".find_first_before(&IgnoreAsciiCase("GET"), 1, 3).map(|found| found.index), Some(2));
+    }
+
+    #[test]
+    fn test_edit_invalidates() {
+        let mut prepared = PreparedHaystack::new("alpha beta\ngamma");
+        assert_eq!(prepared.find_first(&"beta").map(|found| found.index), Some(6));
+        assert_eq!(prepared.line_index().line_count(), 2);
+        prepared.edit(0..6, "Beta ").unwrap();
+        assert!(!prepared.is_cached());
+        assert_eq!(prepared.find_first(&"beta").map(|found| found.index), Some(5));
+        assert_eq!(prepared.find_first(&IgnoreAsciiCase("BETA")).map(|found| found.index), Some(0));
+        prepared.push_str("\ndelta");
+        assert_eq!(prepared.line_index().line_count(), 3);
+        assert!(prepared.edit(3..100, "").is_err());
+        prepared.set_text("é");
+        assert_eq!(prepared.find_first(&IgnoreAsciiCase("É")).map(|found| found.index), None);
+        assert!(prepared.edit(1..2, "e").is_err());
+    }
+}
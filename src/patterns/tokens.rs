@@ -0,0 +1,189 @@
+use crate::lex::Token;
+use crate::types::span::Span;
+
+// anything with a kind and source text: lexer tokens, or (kind, text) pairs from another tokenizer
+pub trait TokenLike {
+    fn kind(&self) -> &str;
+
+    fn text(&self) -> &str;
+}
+
+impl TokenLike for Token<'_> {
+    fn kind(&self) -> &str {
+        return self.kind;
+    }
+
+    fn text(&self) -> &str {
+        return self.text;
+    }
+}
+
+impl TokenLike for (&str, &str) {
+    fn kind(&self) -> &str {
+        return self.0;
+    }
+
+    fn text(&self) -> &str {
+        return self.1;
+    }
+}
+
+impl<T> TokenLike for &T
+where T: TokenLike + ?Sized {
+    fn kind(&self) -> &str {
+        return (**self).kind();
+    }
+
+    fn text(&self) -> &str {
+        return (**self).text();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Kind(String),
+    Text(String),
+    KindText(String, String),
+    OneOf(Vec<String>),
+    Any,
+}
+
+impl Step {
+    fn accepts<T>(&self, token: &T) -> bool
+    where T: TokenLike + ?Sized {
+        return match self {
+            Step::Kind(kind) => token.kind() == kind,
+            Step::Text(text) => token.text() == text,
+            Step::KindText(kind, text) => token.kind() == kind && token.text() == text,
+            Step::OneOf(kinds) => kinds.iter().any(|kind| token.kind() == kind),
+            Step::Any => true,
+        };
+    }
+}
+
+// a run of consecutive tokens, one step per token; matches are token-index spans, not byte spans
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TokenPatternMatcher {
+    steps: Vec<Step>,
+}
+
+impl TokenPatternMatcher {
+    pub fn new() -> TokenPatternMatcher {
+        return TokenPatternMatcher { steps: Vec::new() };
+    }
+
+    pub fn kinds<I, S>(kinds: I) -> TokenPatternMatcher
+    where I: IntoIterator<Item = S>, S: Into<String> {
+        return TokenPatternMatcher { steps: kinds.into_iter().map(|kind| Step::Kind(kind.into())).collect() };
+    }
+
+    pub fn kind<S>(mut self, kind: S) -> TokenPatternMatcher
+    where S: Into<String> {
+        self.steps.push(Step::Kind(kind.into()));
+        return self;
+    }
+
+    pub fn text<S>(mut self, text: S) -> TokenPatternMatcher
+    where S: Into<String> {
+        self.steps.push(Step::Text(text.into()));
+        return self;
+    }
+
+    pub fn kind_text<K, S>(mut self, kind: K, text: S) -> TokenPatternMatcher
+    where K: Into<String>, S: Into<String> {
+        self.steps.push(Step::KindText(kind.into(), text.into()));
+        return self;
+    }
+
+    pub fn one_of<I, S>(mut self, kinds: I) -> TokenPatternMatcher
+    where I: IntoIterator<Item = S>, S: Into<String> {
+        self.steps.push(Step::OneOf(kinds.into_iter().map(Into::into).collect()));
+        return self;
+    }
+
+    pub fn any(mut self) -> TokenPatternMatcher {
+        self.steps.push(Step::Any);
+        return self;
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        return &self.steps;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.steps.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.steps.is_empty();
+    }
+
+    fn matches_at<T>(&self, tokens: &[T], start: usize) -> bool
+    where T: TokenLike {
+        return self.steps.iter().zip(&tokens[start..]).all(|(step, token)| step.accepts(token));
+    }
+
+    // an empty pattern matches nothing, like empty patterns in a PatternSet
+    pub fn find_first_from<T>(&self, tokens: &[T], token_offset: usize) -> Option<Span>
+    where T: TokenLike {
+        if self.steps.is_empty() || token_offset > tokens.len() || tokens.len() - token_offset < self.steps.len() {
+            return None;
+        }
+        let start = (token_offset..=tokens.len() - self.steps.len()).find(|start| self.matches_at(tokens, *start))?;
+        return Some(Span::new(start, start + self.steps.len()));
+    }
+
+    // leftmost first, non-overlapping
+    pub fn find_every<T>(&self, tokens: &[T]) -> Vec<Span>
+    where T: TokenLike {
+        let mut found = Vec::new();
+        let mut offset = 0;
+        while let Some(span) = self.find_first_from(tokens, offset) {
+            offset = span.end;
+            found.push(span);
+        }
+        return found;
+    }
+
+    // for token streams that aren't already in a slice
+    pub fn find_every_in<I>(&self, tokens: I) -> Vec<Span>
+    where I: IntoIterator, I::Item: TokenLike {
+        let tokens: Vec<I::Item> = tokens.into_iter().collect();
+        return self.find_every(&tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lex::CharsWhile;
+    use crate::lex::Lexer;
+
+    #[test]
+    fn test_token_sequences() {
+        let lexer = Lexer::new()
+            .skip("space", CharsWhile(char::is_whitespace))
+            .rule("ident", CharsWhile(|c: char| c.is_alphanumeric() || c == '_'))
+            .rule("open", "{")
+            .rule("close", "}")
+            .rule("punct", CharsWhile(|c: char| "();=.".contains(c)));
+        let src = "fn f() { unsafe { g(); } let unsafe_x = 1; unsafe fn h(); }";
+        let tokens = lexer.tokenize(src).unwrap();
+        let unsafe_block = TokenPatternMatcher::new().kind_text("ident", "unsafe").kind("open");
+        let found = unsafe_block.find_every(&tokens);
+        assert_eq!(found, vec![Span::new(4, 6)]);
+        assert_eq!(tokens[found[0].start].span.start, 9);
+        let unsafe_anything = TokenPatternMatcher::new().text("unsafe").any();
+        assert_eq!(unsafe_anything.find_every(&tokens).len(), 2);
+        assert_eq!(unsafe_anything.find_first_from(&tokens, 6), Some(Span::new(14, 16)));
+        assert!(unsafe_anything.find_first_from(&tokens, 100).is_none());
+    }
+
+    #[test]
+    fn test_any_token_iterator() {
+        let pairs = [("kw", "if"), ("punct", "("), ("ident", "x"), ("punct", ")"), ("kw", "if")];
+        let matcher = TokenPatternMatcher::kinds(["kw", "punct"]).one_of(["ident", "number"]);
+        assert_eq!(matcher.find_every_in(pairs.iter()), vec![Span::new(0, 3)]);
+        assert!(TokenPatternMatcher::new().find_every(&pairs).is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+use std::mem::size_of;
+
+use super::complexity::Analysis;
+use super::complexity::Analyze;
+use super::complexity::Growth;
+use super::PatternMatch;
+use super::PatternMatcher;
+use crate::text::glob::parse_class;
+use crate::text::glob::CharClass;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::options::Checks;
+use crate::types::options::Options;
+
+// bounds that make a wildcard safe to compile from untrusted input: matching keeps one set of
+// program states and never backtracks, so a search costs at most program len * max_match_len per
+// start position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WildcardLimits {
+    // in bytes; longer text never matches
+    pub max_match_len: usize,
+    // the largest n of a{m,n}
+    pub max_repeat: usize,
+    // instructions after repetitions are expanded
+    pub max_program_len: usize,
+}
+
+impl Default for WildcardLimits {
+    fn default() -> WildcardLimits {
+        return WildcardLimits { max_match_len: 4096, max_repeat: 255, max_program_len: 1024 };
+    }
+}
+
+impl Options for WildcardLimits {
+    const NAME: &'static str = "wildcard limits";
+
+    fn validate(&self, checks: &mut Checks) {
+        checks.require(self.max_match_len > 0, "max_match_len", "must be at least 1 byte");
+        checks.require(self.max_repeat > 0, "max_repeat", "must be at least 1");
+        checks.require(self.max_program_len > 0, "max_program_len", "must be at least 1");
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Atom {
+    Char(char),
+    // '?', any character but a newline
+    Any,
+    Class(CharClass),
+}
+
+impl Atom {
+    fn accepts(&self, character: char) -> bool {
+        match self {
+            Atom::Char(expected) => return *expected == character,
+            Atom::Any => return character != '\n',
+            Atom::Class(class) => return character != '\n' && class.contains(character),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    One(Atom),
+    // the optional tail of a{m,n}
+    Maybe(Atom),
+    // '*', any run of characters within a line
+    Run,
+}
+
+// text search with '*' (any run within a line), '?' (one character), [abc], [a-z], [!abc], '\'
+// escapes and a{m,n} / a{n} repetition of the character, '?' or class before it. Matches are
+// leftmost-longest and never cross a newline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wildcard {
+    source: String,
+    program: Vec<Inst>,
+    limits: WildcardLimits,
+}
+
+impl Wildcard {
+    pub fn new(pattern: &str) -> Result<Wildcard, ErrorChain> {
+        return Wildcard::with_limits(pattern, WildcardLimits::default());
+    }
+
+    pub fn with_limits(pattern: &str, limits: WildcardLimits) -> Result<Wildcard, ErrorChain> {
+        limits.check_valid()?;
+        let program = compile(pattern, &limits).do_on_error(|| format!("invalid wildcard '{}'", pattern))?;
+        let wildcard = Wildcard { source: pattern.to_string(), program, limits };
+        if wildcard.accepting(&wildcard.closure(vec![true])) {
+            return Err(ErrorChain::new(format!("invalid wildcard '{}'", pattern)).with_help("it matches the empty string, so it would match everywhere"));
+        }
+        return Ok(wildcard);
+    }
+
+    pub fn as_str(&self) -> &str {
+        return &self.source;
+    }
+
+    pub fn limits(&self) -> WildcardLimits {
+        return self.limits;
+    }
+
+    // the whole text has to match
+    pub fn matches(&self, text: &str) -> bool {
+        return text.len() <= self.limits.max_match_len && self.longest_at(text, 0) == Some(text.len());
+    }
+
+    // epsilon edges only go forward, so one pass in order settles every state
+    fn closure(&self, mut states: Vec<bool>) -> Vec<bool> {
+        states.resize(self.program.len() + 1, false);
+        for index in 0..self.program.len() {
+            if states[index] && matches!(self.program[index], Inst::Maybe(_) | Inst::Run) {
+                states[index + 1] = true;
+            }
+        }
+        return states;
+    }
+
+    fn accepting(&self, states: &[bool]) -> bool {
+        return states[self.program.len()];
+    }
+
+    // the end of the longest match starting at start, within max_match_len
+    fn longest_at(&self, text: &str, start: usize) -> Option<usize> {
+        let mut states = self.closure(vec![true]);
+        let mut longest = None;
+        for (offset, character) in text[start..].char_indices() {
+            let end = start + offset + character.len_utf8();
+            if end - start > self.limits.max_match_len {
+                break;
+            }
+            let mut next = vec![false; states.len()];
+            let mut alive = false;
+            for (index, inst) in self.program.iter().enumerate() {
+                if !states[index] {
+                    continue;
+                }
+                match inst {
+                    Inst::One(atom) | Inst::Maybe(atom) if atom.accepts(character) => next[index + 1] = true,
+                    Inst::Run if character != '\n' => next[index] = true,
+                    _ => continue,
+                }
+                alive = true;
+            }
+            if !alive {
+                break;
+            }
+            states = self.closure(next);
+            if self.accepting(&states) {
+                longest = Some(end);
+            }
+        }
+        return longest;
+    }
+
+    // cheap test of the first character, when the program starts with a required atom
+    fn may_start_with(&self, character: char) -> bool {
+        return match self.program.first() {
+            Some(Inst::One(atom)) => atom.accepts(character),
+            _ => true,
+        };
+    }
+}
+
+fn compile(pattern: &str, limits: &WildcardLimits) -> Result<Vec<Inst>, ErrorChain> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut program = Vec::new();
+    // the atom a following {m,n} repeats; only set right after a character, '?' or class
+    let mut last_atom: Option<Atom> = None;
+    let mut index = 0;
+    while index < chars.len() {
+        let atom = match chars[index] {
+            '*' => {
+                if program.last() != Some(&Inst::Run) {
+                    program.push(Inst::Run);
+                }
+                last_atom = None;
+                index += 1;
+                continue;
+            }
+            '{' => {
+                let Some(atom) = last_atom.take() else {
+                    return Err(ErrorChain::new(format!("repetition at {} has nothing to repeat", index)).with_help("put a character, '?' or class before it, or write \\{ for a literal brace"));
+                };
+                let (min, max, consumed) = repetition(&chars[index..], index, limits)?;
+                program.pop();
+                if program.len() + max > limits.max_program_len {
+                    return Err(ErrorChain::new(format!("pattern is longer than {} instructions once repetitions are expanded", limits.max_program_len)));
+                }
+                program.extend((0..min).map(|_| Inst::One(atom.clone())));
+                program.extend((min..max).map(|_| Inst::Maybe(atom.clone())));
+                index += consumed;
+                continue;
+            }
+            '?' => {
+                index += 1;
+                Atom::Any
+            }
+            '[' => match parse_class(&chars[index + 1..]) {
+                Some((class, consumed)) => {
+                    index += 1 + consumed;
+                    Atom::Class(class)
+                }
+                // an unclosed '[' is a literal
+                None => {
+                    index += 1;
+                    Atom::Char('[')
+                }
+            },
+            '\\' if index + 1 < chars.len() => {
+                index += 2;
+                Atom::Char(chars[index - 1])
+            }
+            literal => {
+                index += 1;
+                Atom::Char(literal)
+            }
+        };
+        if program.len() + 1 > limits.max_program_len {
+            return Err(ErrorChain::new(format!("pattern is longer than {} instructions", limits.max_program_len)));
+        }
+        program.push(Inst::One(atom.clone()));
+        last_atom = Some(atom);
+    }
+    return Ok(program);
+}
+
+// (min, max, chars consumed including the braces) of "{m,n}" or "{n}"
+fn repetition(chars: &[char], at: usize, limits: &WildcardLimits) -> Result<(usize, usize, usize), ErrorChain> {
+    let Some(close) = chars.iter().position(|character| *character == '}') else {
+        return Err(ErrorChain::new(format!("unclosed repetition at {}", at)));
+    };
+    let body: String = chars[1..close].iter().collect();
+    let number = |digits: &str| digits.trim().parse::<usize>().do_on_error(|| format!("bad repetition '{{{}}}' at {}", body, at));
+    let (min, max) = match body.split_once(',') {
+        Some((_, max)) if max.trim().is_empty() => {
+            return Err(ErrorChain::new(format!("open-ended repetition '{{{}}}' at {}", body, at)).with_help(format!("give an upper bound of at most {}, or use *", limits.max_repeat)));
+        }
+        Some((min, max)) => (number(min)?, number(max)?),
+        None => (number(&body)?, number(&body)?),
+    };
+    if min > max || max == 0 {
+        return Err(ErrorChain::new(format!("bad repetition '{{{}}}' at {}", body, at)).with_help("write {m,n} with 0 <= m <= n and n > 0"));
+    }
+    if max > limits.max_repeat {
+        return Err(ErrorChain::new(format!("repetition '{{{}}}' at {} is over the limit of {}", body, at, limits.max_repeat)));
+    }
+    return Ok((min, max, close + 1));
+}
+
+// every start position steps the whole program over up to max_match_len bytes; the two state sets
+// are what a search allocates besides the program
+impl Analyze for Wildcard {
+    fn analyze(&self) -> Analysis {
+        let classes: usize = self.program.iter().map(|inst| match inst {
+            Inst::One(Atom::Class(class)) | Inst::Maybe(Atom::Class(class)) => class.ranges.len() * size_of::<(char, char)>(),
+            _ => 0,
+        }).sum();
+        let memory = self.program.len() * size_of::<Inst>() + classes + 2 * (self.program.len() + 1);
+        let steps = (self.program.len() as u64).saturating_mul(self.limits.max_match_len as u64);
+        return Analysis::new(Growth::Linear, steps, self.limits.max_match_len, memory);
+    }
+}
+
+impl<'a> PatternMatcher<'a, Wildcard> for str {
+    fn find_first_from(&'a self, pattern: &Wildcard, byte_offset: usize) -> Option<PatternMatch<&'a Self>> {
+        return self.find_first_before(pattern, byte_offset, usize::MAX);
+    }
+
+    fn find_first_before(&'a self, pattern: &Wildcard, byte_offset: usize, before: usize) -> Option<PatternMatch<&'a Self>> {
+        // an offset inside a character starts the search at the next one
+        let mut from = byte_offset.min(self.len());
+        while !self.is_char_boundary(from) {
+            from += 1;
+        }
+        let (start, end) = self[from..]
+            .char_indices()
+            .map(|(offset, character)| (from + offset, character))
+            .take_while(|(start, _)| *start < before)
+            .filter(|(_, character)| pattern.may_start_with(*character))
+            .find_map(|(start, _)| pattern.longest_at(self, start).map(|end| (start, end)))?;
+        return Some(PatternMatch { index: start, length: end - start, slice: &self[start..end] });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_and_repetition() {
+        let text = "id=ab12 id=abc123 id=x\nnote: a*b";
+        let found = |pattern: &str| text.find_every(&Wildcard::new(pattern).unwrap()).unwrap_or_default().into_iter().map(|found| found.slice).collect::<Vec<_>>();
+        assert_eq!(found("id=[a-z]{1,3}[0-9]{2,3}"), vec!["id=ab12", "id=abc123"]);
+        assert_eq!(found("id=?{1}"), vec!["id=a", "id=a", "id=x"]);
+        assert_eq!(found("note*b"), vec!["note: a*b"]);
+        assert_eq!(found("a\\*b"), vec!["a*b"]);
+        assert!(found("id=*note").is_empty());
+        assert_eq!("éab".find_first_from(&Wildcard::new("ab").unwrap(), 1).map(|found| found.index), Some(2));
+
+        let wildcard = Wildcard::new("[!/]{2}x{0,2}").unwrap();
+        assert!(wildcard.matches("abxx") && wildcard.matches("ab") && !wildcard.matches("abxxx") && !wildcard.matches("a/"));
+        let capped = Wildcard::with_limits("a*z", WildcardLimits { max_match_len: 5, ..WildcardLimits::default() }).unwrap();
+        assert_eq!("a123z a12345z".find_every(&capped).map(|found| found.len()), Some(1));
+    }
+
+    #[test]
+    fn test_rejects_unbounded() {
+        let error = |pattern: &str| Wildcard::new(pattern).unwrap_err().to_string();
+        assert!(error("a{2,}").contains("open-ended repetition '{2,}' at 1"), "{}", error("a{2,}"));
+        assert!(error("a{1,256}").contains("over the limit of 255"));
+        assert!(error("*{3}").contains("nothing to repeat"));
+        assert!(error("a{3,1}").contains("bad repetition"));
+        assert!(error("a{x}").contains("bad repetition '{x}'"));
+        assert!(error("x{0,3}").contains("matches the empty string"));
+        let limits = WildcardLimits::builder().set(|limits| limits.max_program_len = 8).build().unwrap();
+        assert!(Wildcard::with_limits("?{5}?{4}", limits).is_err());
+        assert!(Wildcard::with_limits("?{5}?{3}", limits).is_ok());
+        let error = Wildcard::with_limits("a", WildcardLimits { max_match_len: 0, ..limits }).unwrap_err().to_string();
+        assert!(error.contains("invalid wildcard limits") && error.contains("max_match_len: must be at least 1 byte"), "{}", error);
+    }
+}
@@ -0,0 +1,201 @@
+use std::ops::Range;
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    return z ^ (z >> 31);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    pub fn seed_from_u64(seed: u64) -> Rng {
+        let mut mix = seed;
+        let state = [splitmix64(&mut mix), splitmix64(&mut mix), splitmix64(&mut mix), splitmix64(&mut mix)];
+        return Rng { state };
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let shifted = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= shifted;
+        self.state[3] = self.state[3].rotate_left(45);
+        return result;
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        return (self.next_u64() >> 32) as u32;
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
+    }
+
+    pub fn gen_bool(&mut self, probability: f64) -> bool {
+        return self.next_f64() < probability;
+    }
+
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be non-zero");
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let product = self.next_u64() as u128 * bound as u128;
+            if (product as u64) >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+
+    pub fn gen_range(&mut self, range: Range<u64>) -> u64 {
+        assert!(range.start < range.end, "cannot sample an empty range");
+        return range.start + self.below(range.end - range.start);
+    }
+
+    pub fn gen_index(&mut self, len: usize) -> usize {
+        return self.below(len as u64) as usize;
+    }
+
+    pub fn fill_bytes(&mut self, out: &mut [u8]) {
+        for chunk in out.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            let other = self.gen_index(index + 1);
+            items.swap(index, other);
+        }
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        return items.get(self.gen_index(items.len()));
+    }
+
+    pub fn choose_weighted<'a, T, F>(&mut self, items: &'a [T], weight: F) -> Option<&'a T>
+    where F: Fn(&T) -> f64 {
+        let total: f64 = items.iter().map(|item| weight(item).max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = self.next_f64() * total;
+        for item in items {
+            let item_weight = weight(item).max(0.0);
+            if target < item_weight {
+                return Some(item);
+            }
+            target -= item_weight;
+        }
+        return items.iter().rev().find(|item| weight(item) > 0.0);
+    }
+
+    pub fn reservoir_sample<I>(&mut self, items: I, count: usize) -> Vec<I::Item>
+    where I: IntoIterator {
+        let mut reservoir = Vec::with_capacity(count);
+        for (seen, item) in items.into_iter().enumerate() {
+            if seen < count {
+                reservoir.push(item);
+            } else {
+                let slot = self.gen_index(seen + 1);
+                if slot < count {
+                    reservoir[slot] = item;
+                }
+            }
+        }
+        return reservoir;
+    }
+
+    pub fn weighted_reservoir_sample<T, I>(&mut self, items: I, count: usize) -> Vec<T>
+    where I: IntoIterator<Item = (T, f64)> {
+        let mut reservoir: Vec<(f64, T)> = Vec::with_capacity(count + 1);
+        for (item, weight) in items {
+            if weight <= 0.0 || count == 0 {
+                continue;
+            }
+            let key = self.next_f64().max(f64::MIN_POSITIVE).ln() / weight;
+            if reservoir.len() < count {
+                reservoir.push((key, item));
+                continue;
+            }
+            let smallest = reservoir.iter()
+                .enumerate()
+                .min_by(|a, b| a.1.0.total_cmp(&b.1.0))
+                .map(|(index, _)| index)
+                .expect("reservoir is full and non-empty");
+            if key > reservoir[smallest].0 {
+                reservoir[smallest] = (key, item);
+            }
+        }
+        reservoir.sort_by(|a, b| b.0.total_cmp(&a.0));
+        return reservoir.into_iter().map(|(_, item)| item).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic_from_seed() {
+        let mut a = Rng::seed_from_u64(42);
+        let mut b = Rng::seed_from_u64(42);
+        let mut c = Rng::seed_from_u64(43);
+        let a_values: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let b_values: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        assert_eq!(a_values, b_values);
+        assert_ne!(a_values[0], c.next_u64());
+    }
+
+    #[test]
+    fn test_ranges_and_floats() {
+        let mut rng = Rng::seed_from_u64(7);
+        for _ in 0..1000 {
+            let value = rng.gen_range(10..20);
+            assert!((10..20).contains(&value));
+            let float = rng.next_f64();
+            assert!((0.0..1.0).contains(&float));
+        }
+        let mut bytes = [0u8; 13];
+        rng.fill_bytes(&mut bytes);
+        assert!(bytes.iter().any(|byte| *byte != 0));
+    }
+
+    #[test]
+    fn test_shuffle_and_choose() {
+        let mut rng = Rng::seed_from_u64(1);
+        let mut items: Vec<u32> = (0..50).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<u32>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..50).collect::<Vec<u32>>());
+        assert!(rng.choose::<u32>(&[]).is_none());
+        assert_eq!(rng.choose_weighted(&[1, 2, 3], |item| if *item == 2 { 1.0 } else { 0.0 }), Some(&2));
+    }
+
+    #[test]
+    fn test_reservoir_sampling() {
+        let mut rng = Rng::seed_from_u64(9);
+        let sample = rng.reservoir_sample(0..1000, 10);
+        assert_eq!(sample.len(), 10);
+        assert!(sample.iter().all(|value| *value < 1000));
+        assert_eq!(rng.reservoir_sample(0..3, 10), vec![0, 1, 2]);
+        let heavy = (0..200).filter(|_| {
+            let picked = rng.weighted_reservoir_sample(vec![("light", 1.0), ("heavy", 100.0), ("zero", 0.0)], 1);
+            picked == vec!["heavy"]
+        }).count();
+        assert!(heavy > 180);
+    }
+}
@@ -0,0 +1,416 @@
+pub mod journal;
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::fs::atomic::write_atomic;
+use crate::fs::sniff::sniff_bytes;
+use crate::fs::walk::Walker;
+use crate::refactor::journal::Journal;
+use crate::refactor::journal::JournalEdit;
+use crate::refactor::journal::JournalFile;
+use crate::rules::Finding;
+use crate::rules::RuleSet;
+use crate::search::budget::MemoryBudget;
+use crate::text::diff::unified_diff;
+use crate::types::error_accumulator::aggregate;
+use crate::types::error_accumulator::ErrorAccumulator;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::options::Checks;
+use crate::types::options::Options;
+use crate::types::span::Span;
+
+const DEFAULT_MAX_FILE_LEN: u64 = 64 * 1024 * 1024;
+// e.g. 32k lines on both sides
+const DEFAULT_MAX_DIFF_CELLS: u64 = 1 << 30;
+
+#[derive(Debug, Clone)]
+pub struct ReplaceOptions {
+    walker: Walker,
+    dry_run: bool,
+    patch: Option<PathBuf>,
+    journal: Option<PathBuf>,
+    backup_suffix: Option<String>,
+    context: usize,
+    diffs: bool,
+    max_file_len: u64,
+    max_diff_cells: u64,
+}
+
+impl ReplaceOptions {
+    pub fn new(walker: Walker) -> ReplaceOptions {
+        return ReplaceOptions { walker, dry_run: false, patch: None, journal: None, backup_suffix: None, context: 3, diffs: false, max_file_len: DEFAULT_MAX_FILE_LEN, max_diff_cells: DEFAULT_MAX_DIFF_CELLS };
+    }
+
+    // compute every change and its diff without touching the files
+    pub fn dry_run(mut self, dry_run: bool) -> ReplaceOptions {
+        self.dry_run = dry_run;
+        return self;
+    }
+
+    // write every change as one unified diff patch to path, for review or `git apply`, instead of
+    // modifying the files
+    pub fn patch_to<P>(mut self, path: P) -> ReplaceOptions
+    where P: AsRef<Path> {
+        self.patch = Some(path.as_ref().to_path_buf());
+        return self;
+    }
+
+    // record every modification in a journal at path, saved before each file is written, so
+    // journal::revert can undo the run even if it stops half way
+    pub fn journal_to<P>(mut self, path: P) -> ReplaceOptions
+    where P: AsRef<Path> {
+        self.journal = Some(path.as_ref().to_path_buf());
+        return self;
+    }
+
+    fn writes_files(&self) -> bool {
+        return !self.dry_run && self.patch.is_none();
+    }
+
+    // a dry run or patch is made of the diffs; files written in place only get one when asked
+    fn wants_diffs(&self) -> bool {
+        return self.diffs || !self.writes_files();
+    }
+
+    // paths in diffs are relative to the walker root they were found under
+    fn display_name(&self, path: &Path) -> String {
+        let relative = self.walker.roots().iter().find_map(|root| path.strip_prefix(root).ok()).filter(|relative| !relative.as_os_str().is_empty()).unwrap_or(path);
+        return relative.to_string_lossy().replace('\\', "/");
+    }
+
+    // keep the original of every changed file next to it, e.g. "main.rs.orig" for ".orig"
+    pub fn backup<S>(mut self, suffix: S) -> ReplaceOptions
+    where S: Into<String> {
+        self.backup_suffix = Some(suffix.into());
+        return self;
+    }
+
+    // lines of context around each diff hunk
+    pub fn context(mut self, context: usize) -> ReplaceOptions {
+        self.context = context;
+        return self;
+    }
+
+    // keep each change's diff when the files are written too
+    pub fn diffs(mut self, diffs: bool) -> ReplaceOptions {
+        self.diffs = diffs;
+        return self;
+    }
+
+    // files whose old lines times new lines is larger are reported as budget failures instead of
+    // being diffed
+    pub fn max_diff_cells(mut self, max_diff_cells: u64) -> ReplaceOptions {
+        self.max_diff_cells = max_diff_cells;
+        return self;
+    }
+
+    // larger files are reported as budget failures instead of being read
+    pub fn max_file_len(mut self, max_file_len: u64) -> ReplaceOptions {
+        self.max_file_len = max_file_len;
+        return self;
+    }
+}
+
+impl Options for ReplaceOptions {
+    const NAME: &'static str = "replace options";
+
+    fn validate(&self, checks: &mut Checks) {
+        if let Some(suffix) = &self.backup_suffix {
+            // an empty suffix would write the backup over the file it is backing up
+            checks.require(!suffix.is_empty(), "backup", "the suffix must not be empty");
+            checks.require(!suffix.contains(['/', '\\']), "backup", format!("the suffix '{}' must not contain a path separator", suffix));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    // spans in the original file that were replaced, in order
+    pub replaced: Vec<Span>,
+    // empty when the file was written and diffs were not asked for
+    pub diff: String,
+    pub backup: Option<PathBuf>,
+}
+
+// answer to one proposed replacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Accept,
+    Reject,
+    // this one and every later match in the same file
+    AcceptFile,
+    // keep what was accepted so far, in this file too, and stop
+    Quit,
+}
+
+// a match offered to the decision callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate<'a> {
+    pub path: &'a Path,
+    // where the match is in the file as read
+    pub original: Span,
+    // where it is in the file with the replacements accepted so far applied
+    pub current: Span,
+    pub text: &'a str,
+    pub replacement: &'a str,
+}
+
+// files changed (or, on a dry run, that would change) and the files that failed; a failure in
+// one file never stops the others
+#[derive(Debug, Default)]
+pub struct ReplaceOutcome {
+    pub changes: Vec<FileChange>,
+    pub errors: ErrorAccumulator,
+    // the callback answered Quit
+    pub quit: bool,
+    // what was written, for journal::revert; empty unless files were modified
+    pub journal: Journal,
+}
+
+impl ReplaceOutcome {
+    pub fn replacements(&self) -> usize {
+        return self.changes.iter().map(|change| change.replaced.len()).sum();
+    }
+
+    // the whole change set as one patch
+    pub fn diff(&self) -> String {
+        return self.changes.iter().map(|change| change.diff.as_str()).collect();
+    }
+
+    pub fn write_patch<W>(&self, writer: &mut W) -> Result<(), ErrorChain>
+    where W: Write {
+        for change in &self.changes {
+            writer.write_all(change.diff.as_bytes()).do_on_error(|| format!("failed to write patch for {}", change.path.display()))?;
+        }
+        return Ok(());
+    }
+
+    pub fn into_result(self) -> Result<Vec<FileChange>, ErrorChain> {
+        return match aggregate(self.errors.into_errors(), "replace failed") {
+            Some(error) => Err(error),
+            None => Ok(self.changes),
+        };
+    }
+}
+
+// replaces every finding of rules under the walker with replacement; overlapping findings keep
+// the earliest. Binary files are skipped
+pub fn replace_in_files(rules: &RuleSet, replacement: &str, options: &ReplaceOptions) -> Result<ReplaceOutcome, ErrorChain> {
+    return replace_in_files_with(rules, replacement, options, |_| Decision::Accept);
+}
+
+// like replace_in_files, but asks decide about every match, in file and position order, which is
+// what an interactive front-end needs
+pub fn replace_in_files_with<F>(rules: &RuleSet, replacement: &str, options: &ReplaceOptions, mut decide: F) -> Result<ReplaceOutcome, ErrorChain>
+where F: FnMut(&Candidate) -> Decision {
+    options.check_valid()?;
+    let mut outcome = ReplaceOutcome::default();
+    for path in options.walker.files()? {
+        let change = replace_in_file(rules, replacement, options, &path, &mut decide, &mut outcome.quit, &mut outcome.journal).do_on_error(|| format!("failed to replace in {}", path.display()));
+        if let Some(Some(change)) = outcome.errors.capture(change) {
+            outcome.changes.push(change);
+        }
+        if outcome.quit {
+            break;
+        }
+    }
+    if let Some(patch) = &options.patch {
+        write_atomic(patch, outcome.diff()).on_error("failed to write patch")?;
+    }
+    return Ok(outcome);
+}
+
+fn replace_in_file<F>(rules: &RuleSet, replacement: &str, options: &ReplaceOptions, path: &Path, decide: &mut F, quit: &mut bool, journal: &mut Journal) -> Result<Option<FileChange>, ErrorChain>
+where F: FnMut(&Candidate) -> Decision {
+    let len = fs::metadata(path).do_on_error(|| format!("failed to stat {}", path.display()))?.len();
+    // the whole file is needed, so a streaming spill policy cannot help
+    MemoryBudget::new().max_file_len(options.max_file_len).check_file_len(path, len)?;
+    if let Some(budget) = options.walker.memory_budget() {
+        budget.check_file_len(path, len)?;
+    }
+    let bytes = fs::read(path).do_on_error(|| format!("failed to read {}", path.display()))?;
+    if sniff_bytes(&bytes).is_binary() {
+        return Ok(None);
+    }
+    let old = String::from_utf8(bytes).on_error("file is not valid UTF-8")?;
+    let (new, replaced) = apply_replacements(path, &old, &rules.run(&old), replacement, decide, quit);
+    if replaced.is_empty() {
+        return Ok(None);
+    }
+    let diff = if options.wants_diffs() { file_diff(options, path, &old, &new)? } else { String::new() };
+    let mut change = FileChange { path: path.to_path_buf(), replaced, diff, backup: None };
+    if !options.writes_files() {
+        return Ok(Some(change));
+    }
+    if let Some(suffix) = &options.backup_suffix {
+        let mut backup = OsString::from(path.as_os_str());
+        backup.push(suffix);
+        let backup = PathBuf::from(backup);
+        write_atomic(&backup, &old).on_error("failed to write backup")?;
+        change.backup = Some(backup);
+    }
+    let edits = change.replaced.iter().map(|span| JournalEdit { span: *span, original: old[span.range()].to_string(), replacement: replacement.to_string() }).collect();
+    journal.push(JournalFile::new(path, &old, &new, edits));
+    if let Some(journal_path) = &options.journal {
+        if let Err(error) = journal.save(journal_path) {
+            journal.truncate(journal.len() - 1);
+            return Err(error);
+        }
+    }
+    write_atomic(path, &new)?;
+    return Ok(Some(change));
+}
+
+fn file_diff(options: &ReplaceOptions, path: &Path, old: &str, new: &str) -> Result<String, ErrorChain> {
+    let (old_lines, new_lines) = (old.lines().count(), new.lines().count());
+    MemoryBudget::new().max_diff_cells(options.max_diff_cells).check_diff(path, old_lines, new_lines)?;
+    if let Some(budget) = options.walker.memory_budget() {
+        budget.check_diff(path, old_lines, new_lines)?;
+    }
+    let name = options.display_name(path);
+    return Ok(unified_diff(old, new, &format!("a/{}", name), &format!("b/{}", name), options.context));
+}
+
+// findings must be sorted by position, as RuleSet::run returns them; a rejected match does not
+// hide the ones overlapping it
+fn apply_replacements<F>(path: &Path, old: &str, findings: &[Finding], replacement: &str, decide: &mut F, quit: &mut bool) -> (String, Vec<Span>)
+where F: FnMut(&Candidate) -> Decision {
+    let mut new = String::with_capacity(old.len());
+    let mut replaced = Vec::new();
+    let mut copied = 0;
+    let mut accept_rest = false;
+    for finding in findings {
+        if finding.span.start < copied {
+            continue;
+        }
+        if !accept_rest {
+            // everything before the match is copied unchanged, so its current start is where new ends then
+            let start = new.len() + finding.span.start - copied;
+            let candidate = Candidate {
+                path,
+                original: finding.span,
+                current: Span::new(start, start + finding.span.len()),
+                text: &old[finding.span.range()],
+                replacement,
+            };
+            match decide(&candidate) {
+                Decision::Accept => {}
+                Decision::Reject => continue,
+                Decision::AcceptFile => accept_rest = true,
+                Decision::Quit => {
+                    *quit = true;
+                    break;
+                }
+            }
+        }
+        new.push_str(&old[copied..finding.span.start]);
+        new.push_str(replacement);
+        copied = finding.span.end;
+        replaced.push(finding.span);
+    }
+    new.push_str(&old[copied..]);
+    return (new, replaced);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+    use crate::rules::Rule;
+
+    #[test]
+    fn test_replace_dry_run_and_backup() {
+        let dir = TempDir::new().unwrap().populate([("a.txt", "old_name();\nkeep\nold_name\n"), ("b/c.txt", "nothing"), ("bin.dat", "old_name\0\0\0\x01\x02")]).unwrap();
+        let rules = RuleSet::new(vec![Rule::new("rename", "old_name")]).unwrap();
+
+        let outcome = replace_in_files(&rules, "new_name", &ReplaceOptions::new(Walker::new(dir.path())).dry_run(true)).unwrap();
+        assert_eq!(outcome.replacements(), 2);
+        assert!(outcome.diff().starts_with("--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n-old_name();\n+new_name();\n"), "{}", outcome.diff());
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "old_name();\nkeep\nold_name\n");
+
+        let changes = replace_in_files(&rules, "new_name", &ReplaceOptions::new(Walker::new(dir.path())).backup(".orig")).unwrap().into_result().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "new_name();\nkeep\nnew_name\n");
+        assert_eq!(fs::read_to_string(dir.join("a.txt.orig")).unwrap(), "old_name();\nkeep\nold_name\n");
+        assert_eq!(changes[0].backup, Some(dir.join("a.txt.orig")));
+        assert!(changes[0].diff.is_empty());
+        let back = RuleSet::new(vec![Rule::new("rename", "new_name")]).unwrap();
+        let changes = replace_in_files(&back, "old_name", &ReplaceOptions::new(Walker::new(dir.path())).diffs(true)).unwrap().into_result().unwrap();
+        assert!(changes[0].diff.starts_with("--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n-new_name();\n"), "{}", changes[0].diff);
+
+        let options = ReplaceOptions::new(Walker::new(dir.path())).dry_run(true).max_diff_cells(8);
+        let error = replace_in_files(&rules, "x", &options).unwrap().into_result().unwrap_err().to_string();
+        assert!(error.contains("a.txt is too large to diff") && error.contains("diff cells 9 over the limit of 8"), "{}", error);
+
+        let options = ReplaceOptions::new(Walker::new(dir.path())).max_file_len(4);
+        let error = replace_in_files(&rules, "x", &options).unwrap().into_result().unwrap_err().to_string();
+        assert!(error.contains("replace failed (4 errors)") && error.contains("a.txt.orig"), "{}", error);
+        let error = replace_in_files(&rules, "x", &ReplaceOptions::new(Walker::new(dir.path())).backup("")).unwrap_err().to_string();
+        assert!(error.contains("invalid replace options") && error.contains("backup: the suffix must not be empty"), "{}", error);
+    }
+
+    #[test]
+    fn test_patch_instead_of_writing() {
+        let dir = TempDir::new().unwrap().populate([("src/a.rs", "x\nfoo\n"), ("src/b.rs", "foo")]).unwrap();
+        let rules = RuleSet::new(vec![Rule::new("foo", "foo")]).unwrap();
+        let patch = dir.join("change.patch");
+        let outcome = replace_in_files(&rules, "bar", &ReplaceOptions::new(Walker::new(dir.join("src"))).patch_to(&patch)).unwrap();
+        let expected = "--- a/a.rs\n+++ b/a.rs\n@@ -1,2 +1,2 @@\n x\n-foo\n+bar\n\
+            --- a/b.rs\n+++ b/b.rs\n@@ -1,1 +1,1 @@\n-foo\n\\ No newline at end of file\n+bar\n\\ No newline at end of file\n";
+        assert_eq!(fs::read_to_string(&patch).unwrap(), expected);
+        let mut written = Vec::new();
+        outcome.write_patch(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), expected);
+        assert_eq!(fs::read_to_string(dir.join("src/b.rs")).unwrap(), "foo");
+    }
+
+    #[test]
+    fn test_journal_and_revert() {
+        let dir = TempDir::new().unwrap().populate([("a.txt", "one two one\n"), ("b.txt", "one\tone \"q\"\x01")]).unwrap();
+        let rules = RuleSet::new(vec![Rule::new("one", "one")]).unwrap();
+        let journal_path = dir.join("undo.log");
+        let outcome = replace_in_files(&rules, "three", &ReplaceOptions::new(Walker::new(dir.path())).journal_to(&journal_path)).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "three\tthree \"q\"\x01");
+        let journal = Journal::load(&journal_path).unwrap();
+        assert_eq!(journal, outcome.journal);
+        assert_eq!(journal.files()[0].edits[1], JournalEdit { span: Span::new(8, 11), original: "one".to_string(), replacement: "three".to_string() });
+
+        fs::write(dir.join("b.txt"), "edited later").unwrap();
+        let error = journal::revert(&journal).unwrap_err().to_string();
+        assert!(error.contains("b.txt changed since it was modified"), "{}", error);
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "one two one\n");
+        fs::write(dir.join("b.txt"), "three\tthree \"q\"\x01").unwrap();
+        assert_eq!(journal::revert(&journal).unwrap(), 1);
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "one\tone \"q\"\x01");
+        assert_eq!(journal::revert(&journal).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_interactive_decisions() {
+        let dir = TempDir::new().unwrap().populate([("a.txt", "aa aa aa aa"), ("b.txt", "aa aa"), ("c.txt", "aa")]).unwrap();
+        let rules = RuleSet::new(vec![Rule::new("a", "aa")]).unwrap();
+        let mut answers = vec![Decision::Reject, Decision::Accept, Decision::AcceptFile, Decision::Accept, Decision::Quit].into_iter();
+        let mut seen = Vec::new();
+        let outcome = replace_in_files_with(&rules, "bbbb", &ReplaceOptions::new(Walker::new(dir.path())), |candidate| {
+            seen.push((candidate.path.file_name().unwrap().to_string_lossy().into_owned(), candidate.original, candidate.current));
+            return answers.next().unwrap();
+        }).unwrap();
+        assert!(outcome.quit);
+        assert_eq!(seen, vec![
+            ("a.txt".to_string(), Span::new(0, 2), Span::new(0, 2)),
+            ("a.txt".to_string(), Span::new(3, 5), Span::new(3, 5)),
+            ("a.txt".to_string(), Span::new(6, 8), Span::new(8, 10)),
+            ("b.txt".to_string(), Span::new(0, 2), Span::new(0, 2)),
+            ("b.txt".to_string(), Span::new(3, 5), Span::new(5, 7)),
+        ]);
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "aa bbbb bbbb bbbb");
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "bbbb aa");
+        assert_eq!(fs::read_to_string(dir.join("c.txt")).unwrap(), "aa");
+    }
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::formats::logfmt;
+use crate::formats::logfmt::Record;
+use crate::fs::atomic::read_to_string_capped;
+use crate::fs::atomic::write_atomic;
+use crate::types::error_accumulator::ErrorAccumulator;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::match_id::fnv1a;
+use crate::types::span::Span;
+
+const MAX_JOURNAL_LEN: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEdit {
+    // where the replaced text was in the original file
+    pub span: Span,
+    pub original: String,
+    pub replacement: String,
+}
+
+// the fingerprints tell revert whether a file is still as the replace left it, or already restored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalFile {
+    pub path: PathBuf,
+    pub before: u64,
+    pub after: u64,
+    // in position order, not overlapping
+    pub edits: Vec<JournalEdit>,
+}
+
+impl JournalFile {
+    pub fn new(path: &Path, original: &str, modified: &str, edits: Vec<JournalEdit>) -> JournalFile {
+        return JournalFile { path: path.to_path_buf(), before: fingerprint(original), after: fingerprint(modified), edits };
+    }
+
+    // undoes the edits on the modified text
+    pub fn restore(&self, modified: &str) -> Result<String, ErrorChain> {
+        let mut original = String::with_capacity(modified.len());
+        let mut copied = 0;
+        // how far the edits so far moved later text: sum of replacement.len() - original.len()
+        let mut shift: isize = 0;
+        for edit in &self.edits {
+            let start = (edit.span.start as isize + shift) as usize;
+            let end = start + edit.replacement.len();
+            if start < copied || modified.get(start..end) != Some(edit.replacement.as_str()) {
+                return Err(ErrorChain::new(format!("{} does not contain the replacement at {}", self.path.display(), start)));
+            }
+            original.push_str(&modified[copied..start]);
+            original.push_str(&edit.original);
+            copied = end;
+            shift += edit.replacement.len() as isize - edit.original.len() as isize;
+        }
+        original.push_str(&modified[copied..]);
+        return Ok(original);
+    }
+}
+
+fn fingerprint(text: &str) -> u64 {
+    return fnv1a(text.bytes());
+}
+
+// every modification one replace run made, in the order the files were written
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Journal {
+    files: Vec<JournalFile>,
+}
+
+impl Journal {
+    pub fn new() -> Journal {
+        return Journal { files: Vec::new() };
+    }
+
+    pub fn push(&mut self, file: JournalFile) {
+        self.files.push(file);
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.files.truncate(len);
+    }
+
+    pub fn files(&self) -> &[JournalFile] {
+        return &self.files;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.files.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.files.is_empty();
+    }
+
+    // logfmt: a path record for each file followed by one record per edit
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        let mut line = |record: Record| {
+            out.push_str(&logfmt::encode(&record).expect("journal keys are valid logfmt keys"));
+            out.push('\n');
+        };
+        for file in &self.files {
+            line(Record::new()
+                .with("path", file.path.to_string_lossy().as_ref())
+                .with("before", format!("{:016x}", file.before))
+                .with("after", format!("{:016x}", file.after)));
+            for edit in &file.edits {
+                line(Record::new()
+                    .with("start", edit.span.start.to_string())
+                    .with("end", edit.span.end.to_string())
+                    .with("original", edit.original.as_str())
+                    .with("replacement", edit.replacement.as_str()));
+            }
+        }
+        return out;
+    }
+
+    pub fn decode(src: &str) -> Result<Journal, ErrorChain> {
+        let mut journal = Journal::new();
+        for (line, record) in logfmt::decode_lines(src).on_error("failed to decode journal")?.into_iter().enumerate() {
+            let invalid = || format!("invalid journal entry on line {}", line + 1);
+            let number = |key: &str| -> Result<usize, ErrorChain> {
+                let value = record.require(key).do_on_error(invalid)?;
+                return value.parse().do_on_error(|| format!("{}: bad {} '{}'", invalid(), key, value));
+            };
+            let hash = |key: &str| -> Result<u64, ErrorChain> {
+                let value = record.require(key).do_on_error(invalid)?;
+                return u64::from_str_radix(value, 16).do_on_error(|| format!("{}: bad {} '{}'", invalid(), key, value));
+            };
+            if let Some(path) = record.get("path") {
+                journal.files.push(JournalFile { path: PathBuf::from(path), before: hash("before")?, after: hash("after")?, edits: Vec::new() });
+                continue;
+            }
+            let Some(file) = journal.files.last_mut() else {
+                return Err(ErrorChain::new(format!("{}: edit before any path", invalid())));
+            };
+            let span = Span::new(number("start")?, number("end")?);
+            let original = record.require("original").do_on_error(invalid)?.to_string();
+            let replacement = record.require("replacement").do_on_error(invalid)?.to_string();
+            file.edits.push(JournalEdit { span, original, replacement });
+        }
+        return Ok(journal);
+    }
+
+    pub fn load<P>(path: P) -> Result<Journal, ErrorChain>
+    where P: AsRef<Path> {
+        let path = path.as_ref();
+        let src = read_to_string_capped(path, MAX_JOURNAL_LEN).on_error("failed to load journal")?;
+        return Journal::decode(&src).do_on_error(|| format!("failed to load journal {}", path.display()));
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), ErrorChain>
+    where P: AsRef<Path> {
+        let path = path.as_ref();
+        return write_atomic(path, self.encode()).do_on_error(|| format!("failed to save journal {}", path.display()));
+    }
+}
+
+// restores every file of the journal, newest first. Files already restored are left alone, and
+// files changed since the replace are refused rather than clobbered; returns how many were restored
+pub fn revert(journal: &Journal) -> Result<usize, ErrorChain> {
+    let mut errors = ErrorAccumulator::new();
+    let mut restored = 0;
+    for file in journal.files.iter().rev() {
+        let result = revert_file(file).do_on_error(|| format!("failed to revert {}", file.path.display()));
+        if let Some(true) = errors.capture(result) {
+            restored += 1;
+        }
+    }
+    errors.into_result("revert failed")?;
+    return Ok(restored);
+}
+
+fn revert_file(file: &JournalFile) -> Result<bool, ErrorChain> {
+    let bytes = fs::read(&file.path).do_on_error(|| format!("failed to read {}", file.path.display()))?;
+    let current = fnv1a(bytes.iter().copied());
+    if current == file.before && current != file.after {
+        return Ok(false);
+    }
+    if current != file.after {
+        return Err(ErrorChain::new(format!("{} changed since it was modified", file.path.display())).with_help("restore it by hand or from a backup"));
+    }
+    let modified = String::from_utf8(bytes).on_error("file is not valid UTF-8")?;
+    let original = file.restore(&modified)?;
+    write_atomic(&file.path, original)?;
+    return Ok(true);
+}
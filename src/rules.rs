@@ -0,0 +1,351 @@
+pub mod baseline;
+pub mod file_types;
+pub mod secrets;
+
+use crate::diag::Diagnostic;
+use crate::diag::Severity;
+use crate::formats::toml_lite;
+use crate::formats::toml_lite::Table;
+use crate::formats::toml_lite::Value;
+use crate::metrics;
+use crate::patterns::complexity::Analysis;
+use crate::patterns::complexity::Analyze;
+use crate::patterns::PatternSet;
+use crate::text::folding::fold_case;
+use crate::text::folding::Folded;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::match_id::MatchId;
+use crate::types::match_id::MatchIds;
+use crate::types::parse_error::ParseError;
+use crate::types::span::Span;
+
+pub const RULE_TABLE_PREFIX: &str = "rule.";
+
+const RULE_KEYS: [&str; 5] = ["pattern", "kind", "ignore_case", "severity", "message"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PatternKind {
+    #[default]
+    Literal,
+    // a literal that must not touch a word character on either side
+    Word,
+}
+
+impl PatternKind {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            PatternKind::Literal => "literal",
+            PatternKind::Word => "word",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<PatternKind> {
+        return match name {
+            "literal" => Some(PatternKind::Literal),
+            "word" => Some(PatternKind::Word),
+            _ => None,
+        };
+    }
+}
+
+// checks a match in the original haystack and returns the span to report, which may be
+// extended past the literal (e.g. a key prefix plus its body) or moved onto a value
+pub type Validator = fn(&[u8], Span) -> Option<Span>;
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub pattern: String,
+    pub kind: PatternKind,
+    pub ignore_case: bool,
+    pub severity: Severity,
+    pub message: String,
+    pub validator: Option<Validator>,
+}
+
+impl Rule {
+    pub fn new<N, P>(name: N, pattern: P) -> Rule
+    where N: Into<String>, P: Into<String> {
+        let name = name.into();
+        let message = format!("matched rule '{}'", name);
+        return Rule { name, pattern: pattern.into(), kind: PatternKind::Literal, ignore_case: false, severity: Severity::Warning, message, validator: None };
+    }
+
+    pub fn kind(mut self, kind: PatternKind) -> Rule {
+        self.kind = kind;
+        return self;
+    }
+
+    pub fn ignore_case(mut self, ignore_case: bool) -> Rule {
+        self.ignore_case = ignore_case;
+        return self;
+    }
+
+    pub fn severity(mut self, severity: Severity) -> Rule {
+        self.severity = severity;
+        return self;
+    }
+
+    pub fn message<M>(mut self, message: M) -> Rule
+    where M: Into<String> {
+        self.message = message.into();
+        return self;
+    }
+
+    pub fn validator(mut self, validator: Validator) -> Rule {
+        self.validator = Some(validator);
+        return self;
+    }
+
+    fn accept(&self, haystack: &[u8], span: Span) -> Option<Span> {
+        let is_word = |byte: Option<&u8>| byte.map(|byte| byte.is_ascii_alphanumeric() || *byte == b'_').unwrap_or(false);
+        if self.kind == PatternKind::Word && (is_word(span.start.checked_sub(1).and_then(|before| haystack.get(before))) || is_word(haystack.get(span.end))) {
+            return None;
+        }
+        return match self.validator {
+            Some(validator) => validator(haystack, span),
+            None => Some(span),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule_id: usize,
+    pub span: Span,
+}
+
+impl Finding {
+    // source names the scanned haystack, usually its path; the rule is named by its name, so the
+    // id matches the finding's baseline key (see rules::baseline) and survives reordered rules
+    pub fn match_id(&self, ids: &mut MatchIds, rules: &RuleSet, source: &str, haystack: &[u8]) -> MatchId {
+        return ids.next(source, &rules.rules[self.rule_id].name, &haystack[self.span.range()]);
+    }
+}
+
+// case-sensitive rules are matched against the haystack as is, ignore_case rules against its case
+// folded copy (see text::folding), whose matches map back to whole characters of the original; a
+// haystack that is not UTF-8 is only ASCII lowercased, which keeps every byte offset unchanged
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    exact: PatternSet<Vec<u8>>,
+    exact_ids: Vec<usize>,
+    folded: PatternSet<Vec<u8>>,
+    folded_ids: Vec<usize>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Result<RuleSet, ErrorChain> {
+        let mut set = RuleSet { rules: Vec::new(), exact: PatternSet::new(), exact_ids: Vec::new(), folded: PatternSet::new(), folded_ids: Vec::new() };
+        for rule in rules {
+            set.push(rule)?;
+        }
+        return Ok(set);
+    }
+
+    pub fn push(&mut self, rule: Rule) -> Result<usize, ErrorChain> {
+        if rule.pattern.is_empty() {
+            return Err(ErrorChain::new(format!("rule '{}' has an empty pattern", rule.name)));
+        }
+        if self.rules.iter().any(|existing| existing.name == rule.name) {
+            return Err(ErrorChain::new(format!("duplicate rule '{}'", rule.name)));
+        }
+        let id = self.rules.len();
+        if rule.ignore_case {
+            self.folded.push(fold_case(&rule.pattern).as_str().as_bytes().to_vec());
+            self.folded_ids.push(id);
+        } else {
+            self.exact.push(rule.pattern.clone().into_bytes());
+            self.exact_ids.push(id);
+        }
+        self.rules.push(rule);
+        return Ok(id);
+    }
+
+    pub fn from_toml(src: &str) -> Result<RuleSet, ErrorChain> {
+        let document = toml_lite::parse(src).on_error("failed to load rules")?;
+        let mut set = RuleSet::new(Vec::new())?;
+        for table in &document.tables {
+            let Some(name) = table.name.strip_prefix(RULE_TABLE_PREFIX) else {
+                continue;
+            };
+            let rule = rule_from_table(name, table).do_on_error(|| format!("failed to load rule '{}'", name))?;
+            set.push(rule).on_error("failed to load rules")?;
+        }
+        return Ok(set);
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        return &self.rules;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.rules.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.rules.is_empty();
+    }
+
+    // searched may be a case-folded copy of haystack, with `folded` mapping its offsets back when
+    // they differ; rules always judge the original bytes
+    fn scan(&self, set: &PatternSet<Vec<u8>>, ids: &[usize], searched: &[u8], folded: Option<&Folded>, haystack: &[u8], findings: &mut Vec<Finding>) {
+        let mut cursor = set.cursor(searched);
+        let mut pos = 0;
+        while let Some(found) = cursor.find_first_from(pos) {
+            let rule_id = ids[found.pattern_id];
+            let span = Span::new(found.start(), found.end());
+            let span = folded.map(|folded| folded.original_span(span)).unwrap_or(span);
+            match self.rules[rule_id].accept(haystack, span) {
+                Some(span) => {
+                    findings.push(Finding { rule_id, span });
+                    pos = found.end().max(folded.map(|folded| folded.folded_offset(span.end)).unwrap_or(span.end));
+                }
+                None => pos = found.start() + 1,
+            }
+        }
+    }
+
+    // findings ordered by position, then rule id
+    pub fn run<H>(&self, haystack: H) -> Vec<Finding>
+    where H: AsRef<[u8]> {
+        let haystack = haystack.as_ref();
+        let mut findings = Vec::new();
+        self.scan(&self.exact, &self.exact_ids, haystack, None, haystack, &mut findings);
+        if !self.folded.is_empty() {
+            match std::str::from_utf8(haystack) {
+                Ok(text) => {
+                    let folded = fold_case(text);
+                    self.scan(&self.folded, &self.folded_ids, folded.as_str().as_bytes(), Some(&folded), haystack, &mut findings);
+                }
+                Err(_) => self.scan(&self.folded, &self.folded_ids, &haystack.to_ascii_lowercase(), None, haystack, &mut findings),
+            }
+        }
+        findings.sort_by(|a, b| a.span.cmp(&b.span).then(a.rule_id.cmp(&b.rule_id)));
+        metrics::counter(metrics::BYTES_SCANNED, haystack.len() as u64);
+        metrics::counter(metrics::MATCHES_FOUND, findings.len() as u64);
+        return findings;
+    }
+
+    pub fn diagnostics(&self, haystack: &str) -> Vec<Diagnostic> {
+        return self.run(haystack).into_iter().map(|finding| {
+            let rule = &self.rules[finding.rule_id];
+            return Diagnostic::new(rule.severity, rule.message.clone()).label(finding.span, format!("rule '{}'", rule.name));
+        }).collect();
+    }
+}
+
+// both pattern sets; a word rule looks one byte past its match. Validators are trusted code and
+// not counted, and the folded copy made for ignore_case rules is part of the haystack
+impl Analyze for RuleSet {
+    fn analyze(&self) -> Analysis {
+        let analysis = self.exact.analyze().combine(self.folded.analyze());
+        let word = self.rules.iter().any(|rule| rule.kind == PatternKind::Word) as usize;
+        return Analysis { max_lookahead: analysis.max_lookahead + word, ..analysis };
+    }
+}
+
+fn string_value<'a>(table: &'a Table, key: &str) -> Result<Option<(&'a str, Span)>, ParseError> {
+    let Some(entry) = table.entry(key) else {
+        return Ok(None);
+    };
+    match &entry.value {
+        Value::String(value) => return Ok(Some((value, entry.value_span))),
+        other => return Err(ParseError::new(entry.value_span, format!("'{}' must be a string, found {}", key, other.type_name()))),
+    }
+}
+
+fn rule_from_table(name: &str, table: &Table) -> Result<Rule, ErrorChain> {
+    if let Some(unknown) = table.entries.iter().find(|entry| !RULE_KEYS.contains(&entry.key.as_str())) {
+        return Err(ErrorChain::from(ParseError::new(unknown.key_span, format!("unknown rule key '{}'", unknown.key)), "invalid rule definition")
+            .with_suggestions_from(&unknown.key, &RULE_KEYS));
+    }
+    let invalid = |error: ParseError| ErrorChain::from(error, "invalid rule definition");
+    let pattern = match string_value(table, "pattern").map_err(invalid)? {
+        Some((pattern, _)) => pattern,
+        None => return Err(ErrorChain::from(ParseError::new(table.span, "missing required key 'pattern'"), "invalid rule definition")),
+    };
+    let mut rule = Rule::new(name, pattern);
+    if let Some((kind, span)) = string_value(table, "kind").map_err(invalid)? {
+        rule.kind = match PatternKind::from_name(kind) {
+            Some(kind) => kind,
+            None => return Err(invalid(ParseError::new(span, format!("unknown pattern kind '{}'", kind))).with_help("expected 'literal' or 'word'")),
+        };
+    }
+    if let Some(entry) = table.entry("ignore_case") {
+        rule.ignore_case = entry.value.as_bool()
+            .ok_or_else(|| invalid(ParseError::new(entry.value_span, format!("'ignore_case' must be a boolean, found {}", entry.value.type_name()))))?;
+    }
+    if let Some((severity, span)) = string_value(table, "severity").map_err(invalid)? {
+        rule.severity = match Severity::from_name(severity) {
+            Some(severity) => severity,
+            None => return Err(invalid(ParseError::new(span, format!("unknown severity '{}'", severity))).with_help("expected 'error', 'warning', 'note' or 'help'")),
+        };
+    }
+    if let Some((message, _)) = string_value(table, "message").map_err(invalid)? {
+        rule.message = message.to_string();
+    }
+    return Ok(rule);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::complexity::Growth;
+
+    const CONFIG: &str = r#"
+[rule.todo]
+pattern = "TODO"
+kind = "word"
+severity = "note"
+message = "unfinished work"
+
+[rule.password]
+pattern = "password"
+ignore_case = true
+severity = "error"
+"#;
+
+    #[test]
+    fn test_load_and_run() {
+        let rules = RuleSet::from_toml(CONFIG).unwrap();
+        assert_eq!(rules.len(), 2);
+        let haystack = "TODO: drop PASSWORD=x, TODOS stay";
+        let findings = rules.run(haystack);
+        assert_eq!(findings, vec![Finding { rule_id: 0, span: Span::new(0, 4) }, Finding { rule_id: 1, span: Span::new(11, 19) }]);
+        let diagnostics = rules.diagnostics(haystack);
+        assert_eq!(diagnostics[0].severity, Severity::Note);
+        assert_eq!(diagnostics[0].message, "unfinished work");
+        assert_eq!(diagnostics[1].message, "matched rule 'password'");
+
+        let rules = RuleSet::new(vec![Rule::new("city", "İstanbul").ignore_case(true).kind(PatternKind::Word)]).unwrap();
+        let haystack = "to İSTANBUL and İSTANBULs";
+        assert_eq!(rules.run(haystack), vec![Finding { rule_id: 0, span: Span::new(3, 12) }]);
+        let rules = RuleSet::new(vec![Rule::new("password", "Password").ignore_case(true)]).unwrap();
+        assert_eq!(rules.run(b"\xff PASSWORD".as_slice()), vec![Finding { rule_id: 0, span: Span::new(2, 10) }]);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let rules = RuleSet::from_toml(CONFIG).unwrap();
+        let analysis = rules.analyze();
+        // both sets' searches added up; the longest pattern plus the byte a word rule looks past it
+        assert_eq!((analysis.growth, analysis.steps_per_byte, analysis.max_lookahead), (Growth::Linear, 12, 9));
+        assert!(analysis.memory >= 12);
+        let plain = RuleSet::new(vec![Rule::new("password", "password")]).unwrap().analyze();
+        assert_eq!(plain.max_lookahead, 8);
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let error = RuleSet::from_toml("[rule.x]\npatern = \"a\"\n").unwrap_err().to_string();
+        assert!(error.contains("did you mean 'pattern'?"), "{}", error);
+        assert!(error.contains("unknown rule key 'patern' at bytes 9..15"), "{}", error);
+        let error = RuleSet::from_toml("[rule.x]\npattern = \"a\"\nseverity = \"fatal\"\n").unwrap_err().to_string();
+        assert!(error.contains("failed to load rule 'x'") && error.contains("unknown severity 'fatal'"), "{}", error);
+        assert!(RuleSet::from_toml("[rule.x]\nkind = \"word\"\n").is_err());
+        assert!(RuleSet::new(vec![Rule::new("a", "x"), Rule::new("a", "y")]).is_err());
+    }
+}
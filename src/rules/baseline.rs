@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::Finding;
+use super::RuleSet;
+use crate::formats::logfmt;
+use crate::formats::logfmt::Record;
+use crate::fs::atomic::read_to_string_capped;
+use crate::fs::atomic::write_atomic;
+use crate::text::line_index::LineIndex;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::match_id::fingerprint_match_id;
+use crate::types::match_id::text_fingerprint;
+use crate::types::match_id::MatchId;
+
+pub const IGNORE_MARKER: &str = "gmec:ignore";
+
+const MAX_BASELINE_LEN: u64 = 64 * 1024 * 1024;
+
+// a finding is known by its file, rule and a hash of the matched text, not its offset, so edits
+// elsewhere in the file don't resurface it; the text itself is never stored since it may be a secret
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BaselineKey {
+    pub path: String,
+    pub rule: String,
+    pub fingerprint: u64,
+}
+
+impl BaselineKey {
+    pub fn new(path: &str, rule: &str, text: &[u8]) -> BaselineKey {
+        return BaselineKey { path: path.to_string(), rule: rule.to_string(), fingerprint: text_fingerprint(text) };
+    }
+
+    // the id of the key's nth finding, equal to Finding::match_id for it
+    pub fn match_id(&self, occurrence: usize) -> MatchId {
+        return fingerprint_match_id(&self.path, &self.rule, self.fingerprint, occurrence);
+    }
+}
+
+// existing findings accepted as is; a key seen twice in the baseline suppresses two occurrences
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Baseline {
+    counts: BTreeMap<BaselineKey, usize>,
+}
+
+impl Baseline {
+    pub fn new() -> Baseline {
+        return Baseline { counts: BTreeMap::new() };
+    }
+
+    pub fn add(&mut self, key: BaselineKey) {
+        *self.counts.entry(key).or_insert(0) += 1;
+    }
+
+    pub fn add_findings(&mut self, rules: &RuleSet, path: &str, haystack: &[u8], findings: &[Finding]) {
+        for finding in findings {
+            self.add(BaselineKey::new(path, &rules.rules()[finding.rule_id].name, &haystack[finding.span.range()]));
+        }
+    }
+
+    pub fn count(&self, key: &BaselineKey) -> usize {
+        return self.counts.get(key).copied().unwrap_or(0);
+    }
+
+    // occurrences, not distinct keys
+    pub fn len(&self) -> usize {
+        return self.counts.values().sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.counts.is_empty();
+    }
+
+    // findings the baseline doesn't account for, in their original order
+    pub fn filter(&self, rules: &RuleSet, path: &str, haystack: &[u8], findings: Vec<Finding>) -> Vec<Finding> {
+        let mut remaining = self.counts.clone();
+        return findings.into_iter().filter(|finding| {
+            let key = BaselineKey::new(path, &rules.rules()[finding.rule_id].name, &haystack[finding.span.range()]);
+            match remaining.get_mut(&key) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    return false;
+                }
+                _ => return true,
+            }
+        }).collect();
+    }
+
+    // one logfmt record per key, sorted, so baseline files diff cleanly
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        for (key, count) in &self.counts {
+            let record = Record::new()
+                .with("path", key.path.as_str())
+                .with("rule", key.rule.as_str())
+                .with("fingerprint", format!("{:016x}", key.fingerprint))
+                .with("count", count.to_string());
+            out.push_str(&logfmt::encode(&record).expect("baseline keys are valid logfmt keys"));
+            out.push('\n');
+        }
+        return out;
+    }
+
+    pub fn decode(src: &str) -> Result<Baseline, ErrorChain> {
+        let mut baseline = Baseline::new();
+        for (line, record) in logfmt::decode_lines(src).on_error("failed to decode baseline")?.into_iter().enumerate() {
+            let invalid = || format!("invalid baseline entry on line {}", line + 1);
+            let fingerprint = record.require("fingerprint").do_on_error(invalid)?;
+            let fingerprint = u64::from_str_radix(fingerprint, 16).do_on_error(|| format!("{}: bad fingerprint '{}'", invalid(), fingerprint))?;
+            let count = match record.get("count") {
+                Some(count) => count.parse().do_on_error(|| format!("{}: bad count '{}'", invalid(), count))?,
+                None => 1,
+            };
+            let key = BaselineKey { path: record.require("path").do_on_error(invalid)?.to_string(), rule: record.require("rule").do_on_error(invalid)?.to_string(), fingerprint };
+            *baseline.counts.entry(key).or_insert(0) += count;
+        }
+        return Ok(baseline);
+    }
+
+    pub fn load<P>(path: P) -> Result<Baseline, ErrorChain>
+    where P: AsRef<Path> {
+        let path = path.as_ref();
+        let src = read_to_string_capped(path, MAX_BASELINE_LEN).on_error("failed to load baseline")?;
+        return Baseline::decode(&src).do_on_error(|| format!("failed to load baseline {}", path.display()));
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), ErrorChain>
+    where P: AsRef<Path> {
+        let path = path.as_ref();
+        return write_atomic(path, self.encode()).do_on_error(|| format!("failed to save baseline {}", path.display()));
+    }
+}
+
+// `gmec:ignore` on a finding's line, or alone on the line above it, suppresses it; names after the
+// marker (comma or space separated) limit it to those rules
+pub fn inline_suppressed(index: &LineIndex, line: usize, rule: &str) -> bool {
+    let ignores = |text: &str| {
+        let Some(at) = text.find(IGNORE_MARKER) else {
+            return false;
+        };
+        let names: Vec<&str> = text[at + IGNORE_MARKER.len()..]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .take_while(|name| !name.starts_with("*/") && !name.starts_with("-->"))
+            .filter(|name| !name.is_empty())
+            .collect();
+        return names.is_empty() || names.contains(&rule);
+    };
+    if index.line_text(line).is_some_and(ignores) {
+        return true;
+    }
+    let Some(above) = line.checked_sub(1).and_then(|above| index.line_text(above)) else {
+        return false;
+    };
+    let above = above.trim_start();
+    return above.find(IGNORE_MARKER).is_some_and(|at| above[..at].chars().all(|c| !c.is_alphanumeric())) && ignores(above);
+}
+
+// what a CI scan reports: findings minus inline suppressions and, if given, the baseline
+pub fn run_suppressed(rules: &RuleSet, path: &str, haystack: &str, baseline: Option<&Baseline>) -> Vec<Finding> {
+    let index = LineIndex::new(haystack);
+    let findings: Vec<Finding> = rules.run(haystack).into_iter()
+        .filter(|finding| !inline_suppressed(&index, index.line_of(finding.span.start), &rules.rules()[finding.rule_id].name))
+        .collect();
+    return match baseline {
+        Some(baseline) => baseline.filter(rules, path, haystack.as_bytes(), findings),
+        None => findings,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::types::match_id::MatchIds;
+
+    fn rules() -> RuleSet {
+        return RuleSet::new(vec![Rule::new("todo", "TODO"), Rule::new("key", "sk_live_")]).unwrap();
+    }
+
+    #[test]
+    fn test_baseline_round_trip_and_filter() {
+        let rules = rules();
+        let old = "TODO one\nsk_live_abc\n";
+        let mut baseline = Baseline::new();
+        baseline.add_findings(&rules, "a.txt", old.as_bytes(), &rules.run(old));
+        assert_eq!(baseline.len(), 2);
+        let decoded = Baseline::decode(&baseline.encode()).unwrap();
+        assert_eq!(decoded, baseline);
+        let new = "header\nTODO one\nTODO two\nsk_live_abc TODO\n";
+        let left = run_suppressed(&rules, "a.txt", new, Some(&decoded));
+        assert_eq!(left.iter().map(|finding| &new[finding.span.range()]).collect::<Vec<_>>(), vec!["TODO", "TODO"]);
+        assert_eq!(run_suppressed(&rules, "b.txt", new, Some(&decoded)).len(), 4);
+        assert!(Baseline::decode("path=a rule=todo fingerprint=zz\n").unwrap_err().to_string().contains("line 1: bad fingerprint 'zz'"));
+
+        let findings = rules.run(new);
+        let mut ids = MatchIds::new();
+        let second_todo = findings.iter().map(|finding| finding.match_id(&mut ids, &rules, "a.txt", new.as_bytes())).nth(1).unwrap();
+        assert_eq!(second_todo, BaselineKey::new("a.txt", "todo", b"TODO").match_id(1));
+    }
+
+    #[test]
+    fn test_inline_ignore_comments() {
+        let rules = rules();
+        let src = "TODO keep\nTODO x // gmec:ignore todo\n// gmec:ignore key, todo\nsk_live_1 TODO\nsk_live_2 # gmec:ignore todo\n/* gmec:ignore */ TODO\nlet x = 1; // gmec:ignore\nTODO\n";
+        let left = run_suppressed(&rules, "a.rs", src, None);
+        let lines: Vec<usize> = left.iter().map(|finding| LineIndex::new(src).line_of(finding.span.start)).collect();
+        assert_eq!(lines, vec![0, 4, 7]);
+    }
+}
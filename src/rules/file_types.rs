@@ -0,0 +1,301 @@
+use std::path::Path;
+
+use super::string_value;
+use super::Finding;
+use super::RuleSet;
+use crate::formats::toml_lite;
+use crate::formats::toml_lite::Table;
+use crate::text::comments::blank_comments;
+use crate::text::comments::CommentStyle;
+use crate::text::encoding::decode_lossy_to_string;
+use crate::text::encoding::decode_lossy_with;
+use crate::text::encoding::detect;
+use crate::text::encoding::Encoding;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::parse_error::ParseError;
+
+pub const TYPE_TABLE_PREFIX: &str = "type.";
+
+const TYPE_KEYS: [&str; 6] = ["extensions", "file_names", "interpreters", "comments", "encoding", "rules"];
+
+// how one language is recognized and searched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileType {
+    pub name: String,
+    // without the dot, compared ignoring ASCII case
+    pub extensions: Vec<String>,
+    // exact file names, e.g. "Makefile"
+    pub file_names: Vec<String>,
+    // shebang interpreters; "python" also matches "python3" and "python3.12"
+    pub interpreters: Vec<String>,
+    // comments are blanked out before matching, which keeps every offset unchanged
+    pub comments: Option<CommentStyle>,
+    // None detects the encoding from the content
+    pub encoding: Option<Encoding>,
+    // names of the rules that apply; None applies every rule
+    pub rules: Option<Vec<String>>,
+}
+
+impl FileType {
+    pub fn new<S>(name: S) -> FileType
+    where S: Into<String> {
+        return FileType { name: name.into(), extensions: Vec::new(), file_names: Vec::new(), interpreters: Vec::new(), comments: None, encoding: None, rules: None };
+    }
+
+    pub fn extension<S>(mut self, extension: S) -> FileType
+    where S: Into<String> {
+        self.extensions.push(extension.into());
+        return self;
+    }
+
+    pub fn file_name<S>(mut self, file_name: S) -> FileType
+    where S: Into<String> {
+        self.file_names.push(file_name.into());
+        return self;
+    }
+
+    pub fn interpreter<S>(mut self, interpreter: S) -> FileType
+    where S: Into<String> {
+        self.interpreters.push(interpreter.into());
+        return self;
+    }
+
+    pub fn comments(mut self, style: CommentStyle) -> FileType {
+        self.comments = Some(style);
+        return self;
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> FileType {
+        self.encoding = Some(encoding);
+        return self;
+    }
+
+    pub fn rules<I, S>(mut self, names: I) -> FileType
+    where I: IntoIterator<Item = S>, S: Into<String> {
+        self.rules = Some(names.into_iter().map(Into::into).collect());
+        return self;
+    }
+
+    fn has_extension(&self, path: &Path) -> bool {
+        let Some(extension) = path.extension().and_then(|extension| extension.to_str()) else {
+            return false;
+        };
+        return self.extensions.iter().any(|candidate| candidate.eq_ignore_ascii_case(extension));
+    }
+
+    fn has_interpreter(&self, interpreter: &str) -> bool {
+        return self.interpreters.iter().any(|candidate| match interpreter.strip_prefix(candidate.as_str()) {
+            Some(version) => version.chars().all(|c| c.is_ascii_digit() || c == '.'),
+            None => false,
+        });
+    }
+
+    pub fn applies(&self, rule_name: &str) -> bool {
+        return match &self.rules {
+            Some(names) => names.iter().any(|name| name == rule_name),
+            None => true,
+        };
+    }
+}
+
+// "#!/usr/bin/env -S python3 -u" and "#!/usr/bin/python3 -u" both name python3
+pub fn shebang_interpreter(head: &[u8]) -> Option<&str> {
+    let line = head.strip_prefix(b"#!")?;
+    let line = &line[..line.iter().position(|byte| *byte == b'\n').unwrap_or(line.len())];
+    let line = std::str::from_utf8(line).ok()?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    return Some(program);
+}
+
+// the types the searcher knows about; a type pushed later replaces one with the same name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileTypes {
+    types: Vec<FileType>,
+}
+
+impl FileTypes {
+    pub fn new() -> FileTypes {
+        return FileTypes { types: Vec::new() };
+    }
+
+    pub fn defaults() -> FileTypes {
+        let mut types = FileTypes::new();
+        types.push(FileType::new("c").extension("c").extension("h").comments(CommentStyle::C));
+        types.push(FileType::new("cpp").extension("cc").extension("cpp").extension("cxx").extension("hpp").comments(CommentStyle::C));
+        types.push(FileType::new("rust").extension("rs").comments(CommentStyle::RUST));
+        types.push(FileType::new("js").extension("js").extension("ts").extension("mjs").comments(CommentStyle::C));
+        types.push(FileType::new("python").extension("py").interpreter("python").comments(CommentStyle::HASH));
+        types.push(FileType::new("shell").extension("sh").extension("bash").interpreter("sh").interpreter("bash").interpreter("zsh").comments(CommentStyle::HASH));
+        types.push(FileType::new("yaml").extension("yaml").extension("yml").comments(CommentStyle::HASH));
+        types.push(FileType::new("toml").extension("toml").comments(CommentStyle::HASH));
+        types.push(FileType::new("sql").extension("sql").comments(CommentStyle::SQL));
+        types.push(FileType::new("make").file_name("Makefile").file_name("GNUmakefile").extension("mk").comments(CommentStyle::HASH));
+        return types;
+    }
+
+    // the defaults, then every [type.<name>] table of a rules config
+    pub fn from_toml(src: &str) -> Result<FileTypes, ErrorChain> {
+        let mut types = FileTypes::defaults();
+        types.load_toml(src)?;
+        return Ok(types);
+    }
+
+    pub fn load_toml(&mut self, src: &str) -> Result<(), ErrorChain> {
+        let document = toml_lite::parse(src).on_error("failed to load file types")?;
+        for table in &document.tables {
+            let Some(name) = table.name.strip_prefix(TYPE_TABLE_PREFIX) else {
+                continue;
+            };
+            let file_type = type_from_table(name, table).do_on_error(|| format!("failed to load file type '{}'", name))?;
+            self.push(file_type);
+        }
+        return Ok(());
+    }
+
+    pub fn push(&mut self, file_type: FileType) {
+        match self.types.iter_mut().find(|existing| existing.name == file_type.name) {
+            Some(existing) => *existing = file_type,
+            None => self.types.push(file_type),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FileType> {
+        return self.types.iter().find(|file_type| file_type.name == name);
+    }
+
+    pub fn types(&self) -> &[FileType] {
+        return &self.types;
+    }
+
+    // file name first, then extension, then the shebang on the first line of head
+    pub fn detect(&self, path: &Path, head: &[u8]) -> Option<&FileType> {
+        if let Some(file_name) = path.file_name().and_then(|file_name| file_name.to_str()) {
+            if let Some(found) = self.types.iter().find(|file_type| file_type.file_names.iter().any(|name| name == file_name)) {
+                return Some(found);
+            }
+        }
+        if let Some(found) = self.types.iter().find(|file_type| file_type.has_extension(path)) {
+            return Some(found);
+        }
+        let interpreter = shebang_interpreter(head)?;
+        return self.types.iter().find(|file_type| file_type.has_interpreter(interpreter));
+    }
+
+    // decodes bytes as the file's type asks, blanks its comments and runs the rules that apply;
+    // spans are offsets in the decoded text. Files of no known type get every rule
+    pub fn run(&self, rules: &RuleSet, path: &Path, bytes: &[u8]) -> Result<Vec<Finding>, ErrorChain> {
+        let file_type = self.detect(path, bytes);
+        if let Some(unknown) = file_type.and_then(|file_type| file_type.rules.as_ref()).and_then(|names| names.iter().find(|name| !rules.rules().iter().any(|rule| &rule.name == *name))) {
+            return Err(ErrorChain::new(format!("file type '{}' names unknown rule '{}'", file_type.map(|file_type| file_type.name.as_str()).unwrap_or_default(), unknown))
+                .with_suggestions_from(unknown, rules.rules().iter().map(|rule| &rule.name)));
+        }
+        let decoded = match file_type.and_then(|file_type| file_type.encoding) {
+            Some(encoding) => {
+                let detection = detect(bytes);
+                let bom_len = if detection.encoding == encoding { detection.bom_len } else { 0 };
+                decode_lossy_with(bytes, encoding, bom_len)
+            }
+            None => decode_lossy_to_string(bytes),
+        };
+        let Some(file_type) = file_type else {
+            return Ok(rules.run(&decoded.text));
+        };
+        let text = match &file_type.comments {
+            Some(style) => blank_comments(&decoded.text, style),
+            None => decoded.text,
+        };
+        let mut findings = rules.run(&text);
+        findings.retain(|finding| file_type.applies(&rules.rules()[finding.rule_id].name));
+        return Ok(findings);
+    }
+}
+
+fn string_list(table: &Table, key: &str) -> Result<Vec<String>, ParseError> {
+    let Some(entry) = table.entry(key) else {
+        return Ok(Vec::new());
+    };
+    let invalid = || ParseError::new(entry.value_span, format!("'{}' must be an array of strings, found {}", key, entry.value.type_name()));
+    let values = entry.value.as_array().ok_or_else(invalid)?;
+    return values.iter().map(|value| value.as_str().map(str::to_string).ok_or_else(invalid)).collect();
+}
+
+fn type_from_table(name: &str, table: &Table) -> Result<FileType, ErrorChain> {
+    if let Some(unknown) = table.entries.iter().find(|entry| !TYPE_KEYS.contains(&entry.key.as_str())) {
+        return Err(ErrorChain::from(ParseError::new(unknown.key_span, format!("unknown file type key '{}'", unknown.key)), "invalid file type definition")
+            .with_suggestions_from(&unknown.key, &TYPE_KEYS));
+    }
+    let invalid = |error: ParseError| ErrorChain::from(error, "invalid file type definition");
+    let mut file_type = FileType::new(name);
+    file_type.extensions = string_list(table, "extensions").map_err(invalid)?.into_iter().map(|extension| extension.trim_start_matches('.').to_string()).collect();
+    file_type.file_names = string_list(table, "file_names").map_err(invalid)?;
+    file_type.interpreters = string_list(table, "interpreters").map_err(invalid)?;
+    if let Some((comments, span)) = string_value(table, "comments").map_err(invalid)? {
+        file_type.comments = match comments {
+            "c" => Some(CommentStyle::C),
+            "rust" => Some(CommentStyle::RUST),
+            "hash" => Some(CommentStyle::HASH),
+            "sql" => Some(CommentStyle::SQL),
+            "none" => None,
+            _ => return Err(invalid(ParseError::new(span, format!("unknown comment style '{}'", comments))).with_help("expected 'c', 'rust', 'hash', 'sql' or 'none'")),
+        };
+    }
+    if let Some((encoding, span)) = string_value(table, "encoding").map_err(invalid)? {
+        file_type.encoding = match encoding.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "utf-16le" => Some(Encoding::Utf16Le),
+            "utf-16be" => Some(Encoding::Utf16Be),
+            "latin1" | "iso-8859-1" => Some(Encoding::Latin1),
+            "detect" => None,
+            _ => return Err(invalid(ParseError::new(span, format!("unknown encoding '{}'", encoding))).with_help("expected 'utf-8', 'utf-16le', 'utf-16be', 'latin1' or 'detect'")),
+        };
+    }
+    if table.entry("rules").is_some() {
+        file_type.rules = Some(string_list(table, "rules").map_err(invalid)?);
+    }
+    if table.get("extensions").is_none() && table.get("file_names").is_none() && table.get("interpreters").is_none() {
+        return Err(invalid(ParseError::new(table.span, "a file type needs 'extensions', 'file_names' or 'interpreters'")));
+    }
+    return Ok(file_type);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rule;
+
+    #[test]
+    fn test_detect() {
+        let types = FileTypes::defaults();
+        let name = |path: &str, head: &str| types.detect(Path::new(path), head.as_bytes()).map(|file_type| file_type.name.clone());
+        assert_eq!(name("src/main.RS", ""), Some("rust".to_string()));
+        assert_eq!(name("config/app.yml", ""), Some("yaml".to_string()));
+        assert_eq!(name("build/Makefile", ""), Some("make".to_string()));
+        assert_eq!(name("bin/tool", "#!/usr/bin/env -S python3.12 -u\nprint()"), Some("python".to_string()));
+        assert_eq!(name("bin/run", "#!/bin/bash -e\n"), Some("shell".to_string()));
+        assert_eq!(name("bin/run", "#!/usr/bin/pythonista\n"), None);
+        assert_eq!(name("README", "hello"), None);
+    }
+
+    #[test]
+    fn test_per_type_rules_from_config() {
+        let rules = RuleSet::new(vec![Rule::new("todo", "TODO"), Rule::new("key", "key:")]).unwrap();
+        let config = "[type.yaml]\nextensions = [\".yaml\", \"yml\"]\ncomments = \"hash\"\nrules = [\"key\"]\n\n[type.text]\nextensions = [\"txt\"]\nencoding = \"latin1\"\n";
+        let types = FileTypes::from_toml(config).unwrap();
+        let yaml = "key: 1 # TODO key: 2\nTODO: 3\n";
+        assert_eq!(types.run(&rules, Path::new("a.yml"), yaml.as_bytes()).unwrap(), vec![Finding { rule_id: 1, span: crate::types::span::Span::new(0, 4) }]);
+        assert_eq!(types.run(&rules, Path::new("a.c"), b"/* TODO */ TODO").unwrap().len(), 1);
+        assert_eq!(types.run(&rules, Path::new("a.txt"), b"caf\xe9 TODO").unwrap()[0].span.start, 6);
+        assert_eq!(types.run(&rules, Path::new("a"), b"# TODO").unwrap().len(), 1);
+
+        let error = FileTypes::from_toml("[type.x]\nextension = [\"x\"]\n").unwrap_err().to_string();
+        assert!(error.contains("did you mean 'extensions'?"), "{}", error);
+        assert!(FileTypes::from_toml("[type.x]\ncomments = \"hash\"\n").is_err());
+        let strict = FileTypes::from_toml("[type.x]\nextensions = [\"x\"]\nrules = [\"tdo\"]\n").unwrap();
+        assert!(strict.run(&rules, Path::new("a.x"), b"").unwrap_err().to_string().contains("did you mean 'todo'?"));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod budget;
+pub mod checkpoint;
+pub mod cluster;
+pub mod columns;
+pub mod context;
+pub mod incremental;
+pub mod preview;
+pub mod rank;
+pub mod report;
+pub mod stream;
+#[cfg(feature = "watch")]
+pub mod watch;
@@ -0,0 +1,211 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::options::Checks;
+use crate::types::options::Options;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    BufferedMatches,
+    FileLen,
+    // old lines times new lines of a diff, which bounds its work
+    DiffCells,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            Limit::BufferedMatches => "buffered matches",
+            Limit::FileLen => "file length",
+            Limit::DiffCells => "diff cells",
+        });
+    }
+}
+
+// the root cause of every budget violation, so callers can tell "too big" apart from I/O or parse
+// failures with exceeded()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub limit: Limit,
+    pub max: u64,
+    pub requested: u64,
+}
+
+impl Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "memory budget exceeded: {} {} over the limit of {}", self.limit, self.requested, self.max);
+    }
+}
+
+impl Error for BudgetExceeded {}
+
+pub fn exceeded(error: &ErrorChain) -> Option<&BudgetExceeded> {
+    return error.links().filter_map(ErrorChain::cause).find_map(|cause| cause.downcast_ref::<BudgetExceeded>());
+}
+
+// what happens to a file longer than max_file_len
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpillPolicy {
+    // read it in bounded chunks instead of whole; only streaming consumers can take it
+    #[default]
+    Stream,
+    Skip,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadPlan {
+    Whole,
+    Stream,
+    Skip,
+}
+
+// limits shared by every search holding the same Arc; matches are charged while buffered and
+// released once delivered, so the count is what is held in memory right now
+#[derive(Debug, Default)]
+pub struct MemoryBudget {
+    max_buffered_matches: Option<usize>,
+    max_file_len: Option<u64>,
+    max_diff_cells: Option<u64>,
+    spill: SpillPolicy,
+    buffered: AtomicUsize,
+}
+
+impl MemoryBudget {
+    pub fn new() -> MemoryBudget {
+        return MemoryBudget { max_buffered_matches: None, max_file_len: None, max_diff_cells: None, spill: SpillPolicy::Stream, buffered: AtomicUsize::new(0) };
+    }
+
+    pub fn max_buffered_matches(mut self, max: usize) -> MemoryBudget {
+        self.max_buffered_matches = Some(max);
+        return self;
+    }
+
+    // the longest file read into memory whole
+    pub fn max_file_len(mut self, max: u64) -> MemoryBudget {
+        self.max_file_len = Some(max);
+        return self;
+    }
+
+    // the largest old lines * new lines a diff may be computed over
+    pub fn max_diff_cells(mut self, max: u64) -> MemoryBudget {
+        self.max_diff_cells = Some(max);
+        return self;
+    }
+
+    pub fn spill(mut self, spill: SpillPolicy) -> MemoryBudget {
+        self.spill = spill;
+        return self;
+    }
+
+    pub fn buffered_matches(&self) -> usize {
+        return self.buffered.load(Ordering::Acquire);
+    }
+
+    // checks a collection that is about to hold count matches, without charging for it
+    pub fn check_matches(&self, count: usize) -> Result<(), ErrorChain> {
+        return match self.max_buffered_matches {
+            Some(max) if count > max => Err(violation(Limit::BufferedMatches, max as u64, count as u64)),
+            _ => Ok(()),
+        };
+    }
+
+    pub fn charge_matches(&self, count: usize) -> Result<(), ErrorChain> {
+        let previous = self.buffered.fetch_add(count, Ordering::AcqRel);
+        if let Err(error) = self.check_matches(previous + count) {
+            self.buffered.fetch_sub(count, Ordering::AcqRel);
+            return Err(error);
+        }
+        return Ok(());
+    }
+
+    pub fn release_matches(&self, count: usize) {
+        self.buffered.fetch_sub(count, Ordering::AcqRel);
+    }
+
+    pub fn plan_read(&self, path: &Path, len: u64) -> Result<ReadPlan, ErrorChain> {
+        let Some(max) = self.max_file_len.filter(|max| len > *max) else {
+            return Ok(ReadPlan::Whole);
+        };
+        return match self.spill {
+            SpillPolicy::Stream => Ok(ReadPlan::Stream),
+            SpillPolicy::Skip => Ok(ReadPlan::Skip),
+            SpillPolicy::Fail => Err(ErrorChain::from(violation(Limit::FileLen, max, len), format!("{} is too large", path.display()))),
+        };
+    }
+
+    // for consumers that need the whole file, whatever the spill policy says
+    pub fn check_file_len(&self, path: &Path, len: u64) -> Result<(), ErrorChain> {
+        return match self.max_file_len {
+            Some(max) if len > max => Err(ErrorChain::from(violation(Limit::FileLen, max, len), format!("{} is too large", path.display()))),
+            _ => Ok(()),
+        };
+    }
+
+    pub fn check_diff(&self, path: &Path, old_lines: usize, new_lines: usize) -> Result<(), ErrorChain> {
+        let cells = (old_lines as u64).saturating_mul(new_lines as u64);
+        return match self.max_diff_cells {
+            Some(max) if cells > max => Err(ErrorChain::from(violation(Limit::DiffCells, max, cells), format!("{} is too large to diff", path.display()))),
+            _ => Ok(()),
+        };
+    }
+}
+
+// checked by the walker that is given the budget and by set_global_budget
+impl Options for MemoryBudget {
+    const NAME: &'static str = "memory budget";
+
+    fn validate(&self, checks: &mut Checks) {
+        checks.require(self.max_buffered_matches != Some(0), "max_buffered_matches", "must be at least 1, or every match is refused");
+    }
+}
+
+fn violation(limit: Limit, max: u64, requested: u64) -> ErrorChain {
+    return ErrorChain::from(BudgetExceeded { limit, max, requested }, "search stopped to stay within its memory budget");
+}
+
+static GLOBAL: OnceLock<Arc<MemoryBudget>> = OnceLock::new();
+
+// applies to every walker that was not given a budget of its own; can only be set once per process
+pub fn set_global_budget(budget: MemoryBudget) -> Result<(), ErrorChain> {
+    budget.check_valid()?;
+    return GLOBAL.set(Arc::new(budget)).map_err(|_| ErrorChain::new("a global memory budget is already installed"));
+}
+
+pub fn global_budget() -> Option<Arc<MemoryBudget>> {
+    return GLOBAL.get().cloned();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charges_and_plans() {
+        let budget = MemoryBudget::new().max_buffered_matches(3).max_file_len(10).spill(SpillPolicy::Skip);
+        budget.charge_matches(2).unwrap();
+        let error = budget.charge_matches(2).unwrap_err();
+        assert_eq!(exceeded(&error), Some(&BudgetExceeded { limit: Limit::BufferedMatches, max: 3, requested: 4 }));
+        assert_eq!(budget.buffered_matches(), 2);
+        budget.release_matches(2);
+        budget.charge_matches(3).unwrap();
+
+        assert_eq!(budget.plan_read(Path::new("a"), 10).unwrap(), ReadPlan::Whole);
+        assert_eq!(budget.plan_read(Path::new("a"), 11).unwrap(), ReadPlan::Skip);
+        let error = MemoryBudget::new().max_file_len(10).spill(SpillPolicy::Fail).plan_read(Path::new("big.log"), 11).unwrap_err();
+        assert!(error.to_string().starts_with("big.log is too large"), "{}", error);
+        assert_eq!(exceeded(&error).map(|exceeded| exceeded.limit), Some(Limit::FileLen));
+        assert!(exceeded(&ErrorChain::new("other")).is_none());
+        assert!(MemoryBudget::new().max_buffered_matches(0).check_valid().is_err());
+        let error = MemoryBudget::new().max_diff_cells(100).check_diff(Path::new("a"), 20, 6).unwrap_err();
+        assert_eq!(exceeded(&error), Some(&BudgetExceeded { limit: Limit::DiffCells, max: 100, requested: 120 }));
+        assert!(MemoryBudget::new().max_diff_cells(100).check_diff(Path::new("a"), 10, 10).is_ok());
+    }
+}
@@ -0,0 +1,347 @@
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::events::Event;
+use crate::events::EventBus;
+use crate::formats::logfmt;
+use crate::formats::logfmt::Record;
+use crate::fs::atomic::read_to_string_capped;
+use crate::fs::atomic::write_atomic;
+use crate::metrics;
+use crate::patterns::PatternSet;
+use crate::patterns::SetMatch;
+use crate::search::stream::for_each_decided;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::match_id::MatchId;
+use crate::types::match_id::MatchIds;
+use crate::types::options::Checks;
+use crate::types::options::Options;
+
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+pub const DEFAULT_CHECKPOINT_EVERY: u64 = 16 * 1024 * 1024;
+
+const CHECKPOINT_VERSION: &str = "1";
+const MAX_CHECKPOINT_LEN: u64 = 64 * 1024;
+
+// where to resume: every match that starts before `offset` in file `file_index` has been reported,
+// and no reported match extends past it, so a resumed scan neither repeats nor misses matches
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Checkpoint {
+    pub file_index: usize,
+    pub path: Option<String>,
+    pub offset: u64,
+    pub bytes_scanned: u64,
+    pub matches: u64,
+}
+
+impl Checkpoint {
+    pub fn new() -> Checkpoint {
+        return Checkpoint::default();
+    }
+
+    pub fn encode(&self) -> String {
+        let mut record = Record::new()
+            .with("version", CHECKPOINT_VERSION)
+            .with("file_index", self.file_index.to_string())
+            .with("offset", self.offset.to_string())
+            .with("bytes_scanned", self.bytes_scanned.to_string())
+            .with("matches", self.matches.to_string());
+        if let Some(path) = &self.path {
+            record.insert("path", path.as_str());
+        }
+        return logfmt::encode(&record).expect("checkpoint keys are valid logfmt keys");
+    }
+
+    pub fn decode(src: &str) -> Result<Checkpoint, ErrorChain> {
+        let record = logfmt::decode(src.trim()).on_error("failed to decode checkpoint")?;
+        let version = record.require("version").on_error("failed to decode checkpoint")?;
+        if version != CHECKPOINT_VERSION {
+            return Err(ErrorChain::new(format!("unsupported checkpoint version '{}'", version))
+                .with_help(format!("this build reads version {} checkpoints", CHECKPOINT_VERSION)));
+        }
+        let number = |key: &str| -> Result<u64, ErrorChain> {
+            let value = record.require(key)?;
+            return value.parse::<u64>().do_on_error(|| format!("checkpoint field '{}' is not a number: '{}'", key, value));
+        };
+        return Ok(Checkpoint {
+            file_index: number("file_index").on_error("failed to decode checkpoint")? as usize,
+            path: record.get("path").map(str::to_string),
+            offset: number("offset").on_error("failed to decode checkpoint")?,
+            bytes_scanned: number("bytes_scanned").on_error("failed to decode checkpoint")?,
+            matches: number("matches").on_error("failed to decode checkpoint")?,
+        });
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), ErrorChain>
+    where P: AsRef<Path> {
+        return write_atomic(path, format!("{}\n", self.encode())).on_error("failed to save checkpoint");
+    }
+
+    pub fn load<P>(path: P) -> Result<Checkpoint, ErrorChain>
+    where P: AsRef<Path> {
+        let path = path.as_ref();
+        let src = read_to_string_capped(path, MAX_CHECKPOINT_LEN).on_error("failed to load checkpoint")?;
+        return Checkpoint::decode(&src).do_on_error(|| format!("failed to load checkpoint {}", path.display()));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanMatch<'s> {
+    pub file_index: usize,
+    pub path: &'s Path,
+    // index is absolute within the file
+    pub found: SetMatch,
+    pub text: &'s [u8],
+}
+
+impl ScanMatch<'_> {
+    // ids number occurrences from the start of each file, so a scan resumed mid-file needs the
+    // MatchIds of the interrupted run
+    pub fn match_id(&self, ids: &mut MatchIds) -> MatchId {
+        return ids.next(&self.path.to_string_lossy(), &self.found.pattern_id.to_string(), self.text);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanOptions {
+    pub chunk_size: usize,
+    pub checkpoint_every: u64,
+}
+
+impl Default for ScanOptions {
+    fn default() -> ScanOptions {
+        return ScanOptions { chunk_size: DEFAULT_CHUNK_SIZE, checkpoint_every: DEFAULT_CHECKPOINT_EVERY };
+    }
+}
+
+impl Options for ScanOptions {
+    const NAME: &'static str = "scan options";
+
+    fn validate(&self, checks: &mut Checks) {
+        checks.require(self.chunk_size > 0, "chunk_size", "must be at least 1 byte");
+        checks.require(self.checkpoint_every > 0, "checkpoint_every", "must be at least 1 byte");
+    }
+}
+
+// scans a fixed list of files in chunks, reporting checkpoints it can later be resumed from
+pub struct ResumableScan<P>
+where P: AsRef<[u8]> {
+    files: Vec<PathBuf>,
+    set: PatternSet<P>,
+    options: ScanOptions,
+    events: Option<Arc<EventBus>>,
+}
+
+impl<P> ResumableScan<P>
+where P: AsRef<[u8]> {
+    pub fn new<I, F>(files: I, set: PatternSet<P>) -> ResumableScan<P>
+    where I: IntoIterator<Item = F>, F: Into<PathBuf> {
+        return ResumableScan {
+            files: files.into_iter().map(Into::into).collect(),
+            set,
+            options: ScanOptions::default(),
+            events: None,
+        };
+    }
+
+    // like with_options, the setters are checked by run()
+    pub fn chunk_size(mut self, chunk_size: usize) -> ResumableScan<P> {
+        self.options.chunk_size = chunk_size;
+        return self;
+    }
+
+    pub fn checkpoint_every(mut self, bytes: u64) -> ResumableScan<P> {
+        self.options.checkpoint_every = bytes;
+        return self;
+    }
+
+    pub fn with_options(mut self, options: ScanOptions) -> Result<ResumableScan<P>, ErrorChain> {
+        self.options = options.validated()?;
+        return Ok(self);
+    }
+
+    pub fn options(&self) -> ScanOptions {
+        return self.options;
+    }
+
+    pub fn events(mut self, events: Arc<EventBus>) -> ResumableScan<P> {
+        self.events = Some(events);
+        return self;
+    }
+
+    fn emit<F>(&self, make_event: F)
+    where F: FnOnce() -> Event {
+        if let Some(events) = &self.events {
+            events.emit_with(make_event);
+        }
+    }
+
+    fn checkpoint_taken<C>(&self, checkpoint: &Checkpoint, on_checkpoint: &mut C) -> Result<(), ErrorChain>
+    where C: FnMut(&Checkpoint) -> Result<(), ErrorChain> {
+        self.emit(|| Event::CheckpointTaken { file_index: checkpoint.file_index, offset: checkpoint.offset });
+        return on_checkpoint(checkpoint);
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        return &self.files;
+    }
+
+    fn display_path(&self, file_index: usize) -> Option<String> {
+        return self.files.get(file_index).map(|path| path.to_string_lossy().into_owned());
+    }
+
+    // on_checkpoint runs every checkpoint_every bytes and after each file; an error from either
+    // callback stops the scan, and the last checkpoint handed out is where to resume from
+    pub fn run<M, C>(&self, from: &Checkpoint, mut on_match: M, mut on_checkpoint: C) -> Result<Checkpoint, ErrorChain>
+    where M: FnMut(ScanMatch<'_>) -> Result<(), ErrorChain>, C: FnMut(&Checkpoint) -> Result<(), ErrorChain> {
+        self.options.check_valid()?;
+        if from.file_index < self.files.len() && from.path.is_some() && from.path != self.display_path(from.file_index) {
+            return Err(ErrorChain::new(format!(
+                "checkpoint was taken at {} but file {} is now {}",
+                from.path.as_deref().unwrap_or_default(),
+                from.file_index,
+                self.display_path(from.file_index).unwrap_or_default()
+            )).with_help("resume with the same file list the checkpoint was taken with"));
+        }
+        let mut checkpoint = from.clone();
+        let max_len = self.set.patterns().iter().map(|pattern| pattern.as_ref().len()).max().unwrap_or(0);
+        while checkpoint.file_index < self.files.len() {
+            let path = &self.files[checkpoint.file_index];
+            checkpoint.path = self.display_path(checkpoint.file_index);
+            let display = checkpoint.path.clone().unwrap_or_default();
+            let (file_index, matches_before) = (checkpoint.file_index, checkpoint.matches);
+            self.emit(|| Event::FileStarted { file_index, path: display.clone() });
+            let started = Instant::now();
+            let scanned = self.scan_file(path, max_len, &mut checkpoint, &mut on_match, &mut on_checkpoint)
+                .do_on_error(|| format!("failed to scan {}", path.display()));
+            if let Err(error) = scanned {
+                self.emit(|| Event::ErrorAttached { file_index, path: display.clone(), message: error.to_string() });
+                return Err(error);
+            }
+            metrics::histogram(metrics::FILE_SCAN_SECONDS, started.elapsed().as_secs_f64());
+            self.emit(|| Event::FileFinished { file_index, path: display.clone(), matches: checkpoint.matches - matches_before });
+            checkpoint.file_index += 1;
+            checkpoint.offset = 0;
+            checkpoint.path = self.display_path(checkpoint.file_index);
+            self.checkpoint_taken(&checkpoint, &mut on_checkpoint)?;
+        }
+        return Ok(checkpoint);
+    }
+
+    fn scan_file<M, C>(&self, path: &Path, max_len: usize, checkpoint: &mut Checkpoint, on_match: &mut M, on_checkpoint: &mut C) -> Result<(), ErrorChain>
+    where M: FnMut(ScanMatch<'_>) -> Result<(), ErrorChain>, C: FnMut(&Checkpoint) -> Result<(), ErrorChain> {
+        let mut file = File::open(path).on_error("failed to open file")?;
+        file.seek(SeekFrom::Start(checkpoint.offset)).on_error("failed to seek to the checkpoint offset")?;
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut chunk = vec![0; self.options.chunk_size];
+        let mut since_checkpoint = 0;
+        loop {
+            let read = file.read(&mut chunk).on_error("failed to read file")?;
+            let at_end = read == 0;
+            buffer.extend_from_slice(&chunk[..read]);
+            checkpoint.bytes_scanned += read as u64;
+            since_checkpoint += read as u64;
+            metrics::counter(metrics::BYTES_SCANNED, read as u64);
+            let decided = for_each_decided(&self.set, &buffer, max_len, at_end, |found| {
+                let absolute = SetMatch { index: found.index + checkpoint.offset as usize, ..found };
+                self.emit(|| Event::MatchFound {
+                    file_index: checkpoint.file_index,
+                    path: checkpoint.path.clone().unwrap_or_default(),
+                    index: absolute.index,
+                    length: absolute.length,
+                    pattern_id: absolute.pattern_id,
+                });
+                on_match(ScanMatch { file_index: checkpoint.file_index, path, found: absolute, text: &buffer[found.range()] })?;
+                checkpoint.matches += 1;
+                metrics::counter(metrics::MATCHES_FOUND, 1);
+                return Ok(());
+            })?;
+            buffer.drain(..decided);
+            checkpoint.offset += decided as u64;
+            if at_end {
+                return Ok(());
+            }
+            if since_checkpoint >= self.options.checkpoint_every {
+                since_checkpoint = 0;
+                self.checkpoint_taken(checkpoint, on_checkpoint)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let checkpoint = Checkpoint { file_index: 2, path: Some(String::from("dir with space/a.log")), offset: 4096, bytes_scanned: 10_000, matches: 7 };
+        let dir = TempDir::new().unwrap();
+        checkpoint.save(dir.join("scan.checkpoint")).unwrap();
+        assert_eq!(Checkpoint::load(dir.join("scan.checkpoint")).unwrap(), checkpoint);
+        let error = Checkpoint::decode("version=9 file_index=0").unwrap_err().to_string();
+        assert!(error.contains("unsupported checkpoint version '9'"));
+        assert!(Checkpoint::decode("version=1 file_index=x offset=0 bytes_scanned=0 matches=0").unwrap_err().to_string().contains("'file_index'"));
+    }
+
+    #[test]
+    fn test_interrupted_scan_resumes_without_gaps_or_repeats() {
+        let dir = TempDir::new().unwrap();
+        let body = "needle hay ".repeat(50);
+        let files = vec![dir.create_file("a.txt", &body).unwrap(), dir.create_file("b.txt", "no match").unwrap(), dir.create_file("c.txt", &body).unwrap()];
+        let scan = ResumableScan::new(files, PatternSet::new().with("needle").with("hay")).chunk_size(7).checkpoint_every(64);
+
+        let mut expected = Vec::new();
+        scan.run(&Checkpoint::new(), |hit| { expected.push((hit.file_index, hit.found)); return Ok(()); }, |_| Ok(())).unwrap();
+        assert_eq!(expected.len(), 200);
+
+        let mut found = Vec::new();
+        let mut saved = Checkpoint::new();
+        let mut checkpoints = 0;
+        let interrupted = scan.run(&Checkpoint::new(), |hit| { found.push((hit.file_index, hit.found)); return Ok(()); }, |checkpoint| {
+            checkpoints += 1;
+            saved = checkpoint.clone();
+            if checkpoints == 5 {
+                return Err(ErrorChain::new("interrupted"));
+            }
+            return Ok(());
+        });
+        assert!(interrupted.is_err());
+        // matches reported after the last checkpoint would be reported again on resume
+        found.truncate(saved.matches as usize);
+        let resumed = Checkpoint::decode(&saved.encode()).unwrap();
+        let finished = scan.run(&resumed, |hit| { found.push((hit.file_index, hit.found)); return Ok(()); }, |_| Ok(())).unwrap();
+        assert_eq!(found, expected);
+        assert_eq!(finished.matches, 200);
+        assert_eq!(finished.file_index, 3);
+        let error = scan.chunk_size(0).run(&Checkpoint::new(), |_| Ok(()), |_| Ok(())).unwrap_err();
+        assert!(error.to_string().contains("chunk_size: must be at least 1 byte"), "{}", error);
+    }
+
+    #[test]
+    fn test_emits_events() {
+        let dir = TempDir::new().unwrap();
+        let files = vec![dir.create_file("a.txt", "x needle").unwrap(), dir.join("missing.txt")];
+        let bus = Arc::new(EventBus::new());
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        bus.subscribe(move |event: &Event| sink.lock().unwrap().push(event.clone()));
+        let scan = ResumableScan::new(files, PatternSet::new().with("needle")).events(bus);
+        assert!(scan.run(&Checkpoint::new(), |_| Ok(()), |_| Ok(())).is_err());
+        let seen = seen.lock().unwrap();
+        let path = dir.join("a.txt").to_string_lossy().into_owned();
+        assert_eq!(seen[0], Event::FileStarted { file_index: 0, path: path.clone() });
+        assert_eq!(seen[1], Event::MatchFound { file_index: 0, path: path.clone(), index: 2, length: 6, pattern_id: 0 });
+        assert_eq!(seen[2], Event::FileFinished { file_index: 0, path, matches: 1 });
+        assert!(matches!(seen[3], Event::CheckpointTaken { file_index: 1, offset: 0 }));
+        assert!(matches!(&seen[5], Event::ErrorAttached { file_index: 1, message, .. } if message.contains("failed to open file")));
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::search::report::ReportEntry;
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cluster {
+    pub label: String,
+    pub entries: Vec<ReportEntry>,
+}
+
+impl Cluster {
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    // the span covering every entry, when they all come from the same file
+    pub fn covering_span(&self) -> Option<Span> {
+        let first = self.entries.first()?;
+        if self.entries.iter().any(|entry| entry.path != first.path) {
+            return None;
+        }
+        return Some(self.entries.iter().fold(first.span, |covering, entry| covering.join(&entry.span)));
+    }
+}
+
+// entries in the same file whose gap to the previous entry is at most max_gap bytes
+pub fn cluster_by_proximity(entries: &[ReportEntry], max_gap: usize) -> Vec<Cluster> {
+    let mut sorted: Vec<&ReportEntry> = entries.iter().collect();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path).then(a.span.cmp(&b.span)));
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut reach: usize = 0;
+    for entry in sorted {
+        let joins = match clusters.last() {
+            Some(last) => last.entries[0].path == entry.path && entry.span.start <= reach.saturating_add(max_gap),
+            None => false,
+        };
+        if joins {
+            clusters.last_mut().expect("checked above").entries.push(entry.clone());
+            reach = reach.max(entry.span.end);
+        } else {
+            clusters.push(Cluster { label: format!("{}:{}", entry.path, entry.line), entries: vec![entry.clone()] });
+            reach = entry.span.end;
+        }
+    }
+    return clusters;
+}
+
+// entries grouped by a key such as the matched text, largest groups first, ties by first appearance
+pub fn cluster_by_key<K, F>(entries: &[ReportEntry], key: F) -> Vec<Cluster>
+where K: Display, F: Fn(&ReportEntry) -> K {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut positions: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in entries {
+        let label = key(entry).to_string();
+        match positions.get(&label) {
+            Some(position) => clusters[*position].entries.push(entry.clone()),
+            None => {
+                positions.insert(label.clone(), clusters.len());
+                clusters.push(Cluster { label, entries: vec![entry.clone()] });
+            }
+        }
+    }
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+    return clusters;
+}
+
+pub fn cluster_by_text(entries: &[ReportEntry]) -> Vec<Cluster> {
+    return cluster_by_key(entries, |entry| entry.text.clone());
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub total: usize,
+    pub per_path: BTreeMap<String, usize>,
+    pub per_line: BTreeMap<(String, usize), usize>,
+    pub per_pattern: BTreeMap<usize, usize>,
+}
+
+impl Summary {
+    pub fn of(entries: &[ReportEntry]) -> Summary {
+        let mut summary = Summary::default();
+        for entry in entries {
+            summary.total += 1;
+            *summary.per_path.entry(entry.path.clone()).or_default() += 1;
+            *summary.per_line.entry((entry.path.clone(), entry.line)).or_default() += 1;
+            *summary.per_pattern.entry(entry.pattern_id).or_default() += 1;
+        }
+        return summary;
+    }
+
+    // the n files with the most matches, most first
+    pub fn top_paths(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut paths: Vec<(&str, usize)> = self.per_path.iter().map(|(path, count)| (path.as_str(), *count)).collect();
+        paths.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        paths.truncate(n);
+        return paths;
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} matches in {} files on {} lines", self.total, self.per_path.len(), self.per_line.len())?;
+        for (pattern_id, count) in &self.per_pattern {
+            write!(f, "\n  pattern {}: {}", pattern_id, count)?;
+        }
+        for (path, count) in self.top_paths(self.per_path.len()) {
+            write!(f, "\n  {}: {}", path, count)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, start: usize, line: usize, pattern_id: usize, text: &str) -> ReportEntry {
+        return ReportEntry { path: path.to_string(), span: Span::new(start, start + text.len()), line, column: 1, pattern_id, text: text.to_string() };
+    }
+
+    fn entries() -> Vec<ReportEntry> {
+        return vec![
+            entry("a.rs", 0, 1, 0, "todo"),
+            entry("a.rs", 6, 1, 0, "todo"),
+            entry("a.rs", 100, 9, 1, "fixme"),
+            entry("b.rs", 2, 1, 0, "todo"),
+        ];
+    }
+
+    #[test]
+    fn test_clusters() {
+        let near = cluster_by_proximity(&entries(), 4);
+        assert_eq!(near.iter().map(Cluster::len).collect::<Vec<usize>>(), vec![2, 1, 1]);
+        assert_eq!(near[0].covering_span(), Some(Span::new(0, 10)));
+        assert_eq!(near[1].label, "a.rs:9");
+        let same = cluster_by_text(&entries());
+        assert_eq!((same[0].label.as_str(), same[0].len()), ("todo", 3));
+        assert_eq!(same[0].covering_span(), None);
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::of(&entries());
+        assert_eq!(summary.per_line[&(String::from("a.rs"), 1)], 2);
+        assert_eq!(summary.top_paths(1), vec![("a.rs", 3)]);
+        assert_eq!(summary.to_string(), "4 matches in 2 files on 3 lines\n  pattern 0: 3\n  pattern 1: 1\n  a.rs: 3\n  b.rs: 1");
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use crate::search::report::Report;
+use crate::search::report::ReportEntry;
+use crate::text::line_index::LineIndex;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::options::Checks;
+use crate::types::options::Options;
+use crate::types::span::Span;
+
+// what follows the line and column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnText {
+    // the matched text, like grep -o
+    #[default]
+    Match,
+    // the whole line the match starts on, like grep -n and rg --vimgrep
+    Line,
+}
+
+// path:line:col:text records as grep -n and rg --vimgrep print them; line and column are 1-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnFormat {
+    separator: String,
+    null_terminated: bool,
+    paths_only: bool,
+    text: ColumnText,
+}
+
+impl Default for ColumnFormat {
+    fn default() -> ColumnFormat {
+        return ColumnFormat::new();
+    }
+}
+
+impl ColumnFormat {
+    pub fn new() -> ColumnFormat {
+        return ColumnFormat { separator: ":".to_string(), null_terminated: false, paths_only: false, text: ColumnText::Match };
+    }
+
+    pub fn separator<S>(mut self, separator: S) -> ColumnFormat
+    where S: Into<String> {
+        self.separator = separator.into();
+        return self;
+    }
+
+    // end records with NUL instead of a newline, for xargs -0
+    pub fn null_terminated(mut self, null_terminated: bool) -> ColumnFormat {
+        self.null_terminated = null_terminated;
+        return self;
+    }
+
+    // each matching path once, in first-seen order, like grep -l
+    pub fn paths_only(mut self, paths_only: bool) -> ColumnFormat {
+        self.paths_only = paths_only;
+        return self;
+    }
+
+    pub fn text(mut self, text: ColumnText) -> ColumnFormat {
+        self.text = text;
+        return self;
+    }
+
+    fn terminator(&self) -> &'static str {
+        return if self.null_terminated { "\0" } else { "\n" };
+    }
+
+    fn write_record<W>(&self, writer: &mut W, path: &str, line: usize, column: usize, text: &str) -> Result<(), ErrorChain>
+    where W: Write {
+        let separator = &self.separator;
+        return write!(writer, "{}{}{}{}{}{}{}{}", path, separator, line, separator, column, separator, text, self.terminator()).on_error("failed to write match");
+    }
+
+    // ColumnText::Line needs the haystack, so report entries always print their matched text
+    pub fn write_report<W>(&self, report: &Report, writer: &mut W) -> Result<(), ErrorChain>
+    where W: Write {
+        self.check_valid()?;
+        if self.paths_only {
+            return self.write_paths(report.entries().iter().map(|entry| entry.path.as_str()), writer);
+        }
+        for entry in report.entries() {
+            self.write_entry(entry, writer)?;
+        }
+        return Ok(());
+    }
+
+    pub fn write_entry<W>(&self, entry: &ReportEntry, writer: &mut W) -> Result<(), ErrorChain>
+    where W: Write {
+        self.check_valid()?;
+        return self.write_record(writer, &entry.path, entry.line, entry.column, &entry.text);
+    }
+
+    // matches of one haystack, positioned and cut from its text
+    pub fn write_matches<W, I>(&self, path: &str, haystack: &str, spans: I, writer: &mut W) -> Result<(), ErrorChain>
+    where W: Write, I: IntoIterator<Item = Span> {
+        self.check_valid()?;
+        let mut spans = spans.into_iter().peekable();
+        if self.paths_only {
+            if spans.peek().is_some() {
+                return self.write_paths([path], writer);
+            }
+            return Ok(());
+        }
+        let index = LineIndex::new(haystack);
+        for span in spans {
+            let position = index.position(span.start);
+            let text = match self.text {
+                ColumnText::Match => haystack.get(span.start..span.end).unwrap_or(""),
+                ColumnText::Line => index.line_text(position.line - 1).unwrap_or(""),
+            };
+            self.write_record(writer, path, position.line, position.column, text)?;
+        }
+        return Ok(());
+    }
+
+    fn write_paths<'a, W, I>(&self, paths: I, writer: &mut W) -> Result<(), ErrorChain>
+    where W: Write, I: IntoIterator<Item = &'a str> {
+        let mut seen = BTreeSet::new();
+        for path in paths {
+            if seen.insert(path) {
+                write!(writer, "{}{}", path, self.terminator()).on_error("failed to write path")?;
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn report_to_string(&self, report: &Report) -> Result<String, ErrorChain> {
+        let mut out = Vec::new();
+        self.write_report(report, &mut out)?;
+        return Ok(String::from_utf8(out).expect("column output is always UTF-8"));
+    }
+}
+
+impl Options for ColumnFormat {
+    const NAME: &'static str = "column format";
+
+    // a separator that is empty or contains the terminator makes the records impossible to split
+    fn validate(&self, checks: &mut Checks) {
+        checks.require(!self.separator.is_empty(), "separator", "must not be empty");
+        checks.require(!self.separator.contains(['\n', '\0']), "separator", "must not contain a newline or NUL");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::PatternMatcher;
+
+    #[test]
+    fn test_grep_compatible_records() {
+        let haystack = "let a = 1;\n  let b = 2;\r\n";
+        let mut report = Report::new();
+        report.add_matches("src/main.rs", haystack, 0, haystack.find_every(&"let").unwrap());
+        report.add_matches("src/lib.rs", "let", 0, "let".find_every(&"let").unwrap());
+        assert_eq!(ColumnFormat::new().report_to_string(&report).unwrap(), "src/main.rs:1:1:let\nsrc/main.rs:2:3:let\nsrc/lib.rs:1:1:let\n");
+        assert_eq!(ColumnFormat::new().separator("\t").null_terminated(true).report_to_string(&report).unwrap(), "src/main.rs\t1\t1\tlet\0src/main.rs\t2\t3\tlet\0src/lib.rs\t1\t1\tlet\0");
+        assert_eq!(ColumnFormat::new().paths_only(true).null_terminated(true).report_to_string(&report).unwrap(), "src/main.rs\0src/lib.rs\0");
+
+        let mut out = Vec::new();
+        let spans = report.entries().iter().filter(|entry| entry.path == "src/main.rs").map(|entry| entry.span);
+        ColumnFormat::new().text(ColumnText::Line).write_matches("a.rs", haystack, spans, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a.rs:1:1:let a = 1;\na.rs:2:3:  let b = 2;\n");
+        let error = ColumnFormat::new().separator("").report_to_string(&report).unwrap_err().to_string();
+        assert!(error.contains("invalid column format") && error.contains("separator: must not be empty"), "{}", error);
+    }
+}
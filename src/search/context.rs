@@ -0,0 +1,122 @@
+use std::fmt::Write;
+
+use crate::text::line_index::LineIndex;
+use crate::types::span::HasSpan;
+use crate::types::span::Span;
+
+pub const SEPARATOR: &str = "--";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextLine<'a> {
+    // 1-based, like Position::line
+    pub number: usize,
+    pub text: &'a str,
+    pub span: Span,
+    pub is_match: bool,
+}
+
+// a run of consecutive lines: matching lines plus their context, merged when blocks touch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextBlock<'a> {
+    pub lines: Vec<ContextLine<'a>>,
+    pub matches: Vec<Span>,
+}
+
+impl ContextBlock<'_> {
+    pub fn first_line(&self) -> usize {
+        return self.lines.first().map(|line| line.number).unwrap_or(0);
+    }
+
+    pub fn last_line(&self) -> usize {
+        return self.lines.last().map(|line| line.number).unwrap_or(0);
+    }
+}
+
+// 0-based line ranges while blocks are still being merged
+struct PendingBlock {
+    from: usize,
+    to: usize,
+    matches: Vec<Span>,
+    matched_lines: Vec<(usize, usize)>,
+}
+
+// grep -B before -A after; a match spanning several lines marks each of them as matching
+pub fn context_blocks<'a, I, S>(index: &LineIndex<'a>, matches: I, before: usize, after: usize) -> Vec<ContextBlock<'a>>
+where I: IntoIterator<Item = S>, S: HasSpan {
+    let mut spans: Vec<Span> = matches.into_iter().map(|found| found.span()).collect();
+    spans.sort();
+    let last_line = index.line_count() - 1;
+    let mut blocks: Vec<PendingBlock> = Vec::new();
+    for span in spans {
+        let first = index.line_of(span.start);
+        let last = index.line_of(if span.is_empty() { span.start } else { span.end - 1 }).max(first);
+        let (from, to) = (first.saturating_sub(before), (last + after).min(last_line));
+        match blocks.last_mut() {
+            Some(block) if from <= block.to + 1 => {
+                block.to = block.to.max(to);
+                block.matches.push(span);
+                block.matched_lines.push((first, last));
+            }
+            _ => blocks.push(PendingBlock { from, to, matches: vec![span], matched_lines: vec![(first, last)] }),
+        }
+    }
+    return blocks.into_iter().map(|block| {
+        let lines = (block.from..=block.to).map(|line| {
+            let span = index.line_span(line).expect("line is within the index");
+            let is_match = block.matched_lines.iter().any(|(first, last)| (*first..=*last).contains(&line));
+            return ContextLine { number: line + 1, text: &index.source()[span.range()], span, is_match };
+        }).collect();
+        return ContextBlock { lines, matches: block.matches };
+    }).collect();
+}
+
+// grep's layout: "12:text" for matching lines, "13-text" for context, "--" between blocks
+pub fn render_grep(blocks: &[ContextBlock<'_>], path: Option<&str>) -> String {
+    let mut out = String::new();
+    for (index, block) in blocks.iter().enumerate() {
+        if index > 0 {
+            out.push_str(SEPARATOR);
+            out.push('\n');
+        }
+        for line in &block.lines {
+            let marker = if line.is_match { ':' } else { '-' };
+            if let Some(path) = path {
+                write!(out, "{}{}", path, marker).expect("writing to a String cannot fail");
+            }
+            writeln!(out, "{}{}{}", line.number, marker, line.text).expect("writing to a String cannot fail");
+        }
+    }
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "one\ntwo\nthree match\nfour\nfive\nsix\nseven match\neight\nnine\nten\neleven\ntwelve match\n";
+
+    fn spans(needle: &str) -> Vec<Span> {
+        return SRC.match_indices(needle).map(|(index, _)| Span::new(index, index + needle.len())).collect();
+    }
+
+    #[test]
+    fn test_blocks_merge_when_context_touches() {
+        let index = LineIndex::new(SRC);
+        let blocks = context_blocks(&index, spans("match"), 1, 2);
+        assert_eq!(blocks.iter().map(|block| (block.first_line(), block.last_line())).collect::<Vec<(usize, usize)>>(), vec![(2, 9), (11, 13)]);
+        assert_eq!(blocks[0].matches.len(), 2);
+        assert!(blocks[0].lines[1].is_match && !blocks[0].lines[0].is_match);
+        let rendered = render_grep(&context_blocks(&index, spans("match"), 0, 0), Some("f.txt"));
+        assert_eq!(rendered, "f.txt:3:three match\n--\nf.txt:7:seven match\n--\nf.txt:12:twelve match\n");
+    }
+
+    #[test]
+    fn test_multi_line_match_and_edges() {
+        let index = LineIndex::new(SRC);
+        let blocks = context_blocks(&index, vec![Span::new(0, 6)], 3, 0);
+        assert_eq!(render_grep(&blocks, None), "1:one\n2:two\n");
+        let blocks = context_blocks(&index, spans("twelve"), 0, 5);
+        assert_eq!(blocks[0].last_line(), 13);
+        assert!(context_blocks(&index, Vec::<Span>::new(), 2, 2).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::patterns::PatternSet;
+use crate::patterns::SetMatch;
+use crate::types::span::Span;
+
+// `span` is the replaced range of the old text, `inserted_len` the length of what replaced it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edit {
+    pub span: Span,
+    pub inserted_len: usize,
+}
+
+impl Edit {
+    pub fn new(span: Span, inserted_len: usize) -> Edit {
+        return Edit { span, inserted_len };
+    }
+
+    pub fn insert(offset: usize, inserted_len: usize) -> Edit {
+        return Edit::new(Span::at(offset), inserted_len);
+    }
+
+    pub fn delete(span: Span) -> Edit {
+        return Edit::new(span, 0);
+    }
+
+    // end of the inserted text in the new text
+    pub fn new_end(&self) -> usize {
+        return self.span.start + self.inserted_len;
+    }
+
+    // maps an offset at or after the replaced range into the new text
+    fn shift(&self, old_offset: usize) -> usize {
+        return old_offset - self.span.end + self.new_end();
+    }
+}
+
+fn shifted(found: &SetMatch, edit: &Edit) -> SetMatch {
+    return SetMatch { index: edit.shift(found.index), ..*found };
+}
+
+// the matches PatternSet::find_every_in would give for the edited haystack, reusing `previous`
+// (that search over the old haystack) everywhere the edit can't have changed the outcome.
+// Matches ending a pattern length before the edit are kept as is; the search restarts there and
+// runs until it lands on a position the old search also passed through after the edit, from where
+// the old matches only need shifting
+pub fn rescan<P>(set: &PatternSet<P>, previous: &[SetMatch], edit: &Edit, haystack: &[u8]) -> Vec<SetMatch>
+where P: AsRef<[u8]> {
+    let max_len = set.patterns().iter().map(|pattern| pattern.as_ref().len()).max().unwrap_or(0);
+    let safe_end = edit.span.start.saturating_sub(max_len);
+    let kept = previous.partition_point(|found| found.end() <= safe_end);
+    let mut matches: Vec<SetMatch> = previous[..kept].to_vec();
+    let mut position = matches.last().map(|found| found.end()).unwrap_or(0);
+
+    // matches starting inside the edited window fit in this slice, so nothing past it is searched
+    let edit_end = edit.new_end();
+    let window = &haystack[..(edit_end + max_len).saturating_sub(1).clamp(edit_end, haystack.len())];
+    while let Some(found) = set.find_first_in(window, position).filter(|found| found.index < edit_end) {
+        position = found.end();
+        matches.push(found);
+    }
+    position = position.max(edit_end);
+
+    loop {
+        // the old search resumes identically if it reached this point without a match across it
+        let old_position = position - edit_end + edit.span.end;
+        let next = previous.partition_point(|found| found.index < old_position);
+        if next == 0 || previous[next - 1].end() <= old_position {
+            matches.extend(previous[next..].iter().map(|found| shifted(found, edit)));
+            return matches;
+        }
+        let Some(found) = set.find_first_in(haystack, position) else {
+            return matches;
+        };
+        position = found.end();
+        matches.push(found);
+    }
+}
+
+// the match set of a document that is edited in place
+#[derive(Debug, Clone)]
+pub struct IncrementalScan<P> {
+    set: PatternSet<P>,
+    matches: Vec<SetMatch>,
+}
+
+impl<P> IncrementalScan<P>
+where P: AsRef<[u8]> {
+    pub fn new(set: PatternSet<P>, haystack: &[u8]) -> IncrementalScan<P> {
+        let matches = set.find_every_in(haystack, 0);
+        return IncrementalScan { set, matches };
+    }
+
+    pub fn set(&self) -> &PatternSet<P> {
+        return &self.set;
+    }
+
+    pub fn matches(&self) -> &[SetMatch] {
+        return &self.matches;
+    }
+
+    // haystack is the text after the edit
+    pub fn apply(&mut self, edit: &Edit, haystack: &[u8]) -> &[SetMatch] {
+        self.matches = rescan(&self.set, &self.matches, edit, haystack);
+        return &self.matches;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand_lite::Rng;
+
+    #[test]
+    fn test_rescan_matches_full_search() {
+        let set: PatternSet<&[u8]> = [&b"ab"[..], b"abab", b"ba", b"bbb"].into_iter().collect();
+        let mut rng = Rng::seed_from_u64(11);
+        let mut text: Vec<u8> = (0..200).map(|_| if rng.gen_bool(0.5) { b'a' } else { b'b' }).collect();
+        let mut scan = IncrementalScan::new(set.clone(), &text);
+        for _ in 0..300 {
+            let start = rng.below(text.len() as u64 + 1) as usize;
+            let end = (start + rng.below(4) as usize).min(text.len());
+            let inserted: Vec<u8> = (0..rng.below(4)).map(|_| if rng.gen_bool(0.5) { b'a' } else { b'b' }).collect();
+            text.splice(start..end, inserted.iter().copied());
+            scan.apply(&Edit::new(Span::new(start, end), inserted.len()), &text);
+            assert_eq!(scan.matches(), &set.find_every_in(&text[..], 0)[..]);
+        }
+    }
+
+    #[test]
+    fn test_edit_helpers() {
+        let set = PatternSet::new().with("needle");
+        let mut text = String::from("needle hay needle");
+        let mut scan = IncrementalScan::new(set, text.as_bytes());
+        text.insert_str(0, "xx");
+        assert_eq!(scan.apply(&Edit::insert(0, 2), text.as_bytes()).iter().map(|found| found.index).collect::<Vec<_>>(), vec![2, 13]);
+        text.replace_range(3..5, "");
+        assert_eq!(scan.apply(&Edit::delete(Span::new(3, 5)), text.as_bytes()).iter().map(|found| found.index).collect::<Vec<_>>(), vec![11]);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::bytes::hexdump::hexdump_highlighted;
+use crate::bytes::hexdump::BYTES_PER_ROW;
+use crate::fs::sniff::sniff_bytes;
+use crate::types::options::Options;
+use crate::types::span::Span;
+
+// rows of a match longer than this are elided in the middle
+const MAX_MATCH_ROWS: usize = 8;
+
+const RESET: &str = "\x1b[0m";
+const INVERSE: &str = "\x1b[7m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewOptions {
+    pub color: bool,
+    // hexdump rows shown before and after the rows of the match
+    pub context_rows: usize,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> PreviewOptions {
+        return PreviewOptions { color: false, context_rows: 1 };
+    }
+}
+
+// every value is usable, but going through Options gives previews the same builder as other configs
+impl Options for PreviewOptions {
+    const NAME: &'static str = "preview options";
+}
+
+// the line a match is on for text, a hexdump window around it for binary data, so output never
+// carries raw binary bytes
+pub fn preview(haystack: &[u8], span: Span, options: PreviewOptions) -> String {
+    if sniff_bytes(haystack).is_binary() {
+        return binary_preview(haystack, span, options);
+    }
+    let span_start = span.start.min(haystack.len());
+    let span = Span::new(span_start, span.end.clamp(span_start, haystack.len()));
+    let start = haystack[..span.start].iter().rposition(|byte| *byte == b'\n').map(|newline| newline + 1).unwrap_or(0);
+    let end = haystack[span.end..].iter().position(|byte| *byte == b'\n').map(|newline| span.end + newline).unwrap_or(haystack.len());
+    let line = |range: std::ops::Range<usize>| String::from_utf8_lossy(&haystack[range]).into_owned();
+    let (before, matched, after) = (line(start..span.start), line(span.start..span.end), line(span.end..end));
+    let matched = if options.color { format!("{}{}{}", INVERSE, matched, RESET) } else { matched };
+    return format!("{}{}{}\n", before, matched, after.trim_end_matches('\r'));
+}
+
+pub fn binary_preview(haystack: &[u8], span: Span, options: PreviewOptions) -> String {
+    let start = span.start.min(haystack.len());
+    let end = span.end.clamp(start, haystack.len());
+    let row_of = |offset: usize| offset / BYTES_PER_ROW;
+    let first_row = row_of(start).saturating_sub(options.context_rows);
+    let last_row = row_of(end.saturating_sub(1).max(start)).saturating_add(options.context_rows);
+    let dump = |from_row: usize, to_row: usize| {
+        let from = (from_row * BYTES_PER_ROW).min(haystack.len());
+        let to = ((to_row + 1) * BYTES_PER_ROW).min(haystack.len());
+        return hexdump_highlighted(&haystack[from..to], from, &[Span::new(start, end)], options.color);
+    };
+    let match_rows = row_of(end.saturating_sub(1).max(start)) - row_of(start) + 1;
+    if match_rows <= MAX_MATCH_ROWS {
+        return dump(first_row, last_row);
+    }
+    // `*` is hexdump's own marker for skipped rows
+    let head_end = row_of(start) + MAX_MATCH_ROWS / 2 - 1;
+    let tail_start = row_of(end - 1) + 1 - MAX_MATCH_ROWS / 2;
+    return format!("{}*\n{}", dump(first_row, head_end), dump(tail_start, last_row));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_and_text_previews() {
+        let mut haystack = vec![0u8; 64];
+        haystack[35..41].copy_from_slice(b"SECRET");
+        let options = PreviewOptions::default();
+        assert_eq!(preview(&haystack, Span::new(35, 41), options), "\
+00000010  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|
+00000020  00 00 00 53 45 43 52 45  54 00 00 00 00 00 00 00  |...SECRET.......|
+                   ^^ ^^ ^^ ^^ ^^  ^^                           ^^^^^^
+00000030  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|
+");
+        let long = binary_preview(&vec![1u8; 512], Span::new(0, 512), PreviewOptions { color: true, context_rows: 0 });
+        assert_eq!(long.lines().count(), MAX_MATCH_ROWS + 1);
+        assert!(long.lines().nth(4) == Some("*") && long.ends_with("|\n"));
+
+        let text = b"first\nkey = value\r\nlast";
+        assert_eq!(preview(text, Span::new(6, 9), options), "key = value\n");
+        assert_eq!(preview(text, Span::new(6, 9), PreviewOptions { color: true, ..options }), "\x1b[7mkey\x1b[0m = value\n");
+        assert_eq!(preview(text, Span::new(20, 40), options), "last\n");
+        assert_eq!(preview(text, Span::new(9, 3), options), "key = value\n");
+    }
+}
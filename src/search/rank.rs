@@ -0,0 +1,245 @@
+use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Factor {
+    Exactness,
+    Position,
+    WordBoundary,
+    PathDepth,
+    Recency,
+}
+
+impl Factor {
+    pub const ALL: [Factor; 5] = [Factor::Exactness, Factor::Position, Factor::WordBoundary, Factor::PathDepth, Factor::Recency];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub exactness: f64,
+    pub position: f64,
+    pub word_boundary: f64,
+    pub path_depth: f64,
+    pub recency: f64,
+}
+
+impl Weights {
+    pub fn get(&self, factor: Factor) -> f64 {
+        return match factor {
+            Factor::Exactness => self.exactness,
+            Factor::Position => self.position,
+            Factor::WordBoundary => self.word_boundary,
+            Factor::PathDepth => self.path_depth,
+            Factor::Recency => self.recency,
+        };
+    }
+
+    pub fn set(&mut self, factor: Factor, weight: f64) {
+        let slot = match factor {
+            Factor::Exactness => &mut self.exactness,
+            Factor::Position => &mut self.position,
+            Factor::WordBoundary => &mut self.word_boundary,
+            Factor::PathDepth => &mut self.path_depth,
+            Factor::Recency => &mut self.recency,
+        };
+        *slot = weight;
+    }
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        return Weights { exactness: 4.0, position: 1.0, word_boundary: 2.0, path_depth: 0.5, recency: 0.0 };
+    }
+}
+
+// one match to be scored: the text it was found in, where, and what was searched for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate<'a> {
+    pub path: &'a str,
+    pub text: &'a str,
+    pub span: Span,
+    pub pattern: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scored<T> {
+    pub item: T,
+    pub score: f64,
+}
+
+type RecencyFn = Box<dyn Fn(&str) -> f64 + Send + Sync>;
+
+// every factor scores in 0.0..=1.0; the total is their weighted sum
+pub struct Ranker {
+    weights: Weights,
+    recency: Option<RecencyFn>,
+}
+
+impl Ranker {
+    pub fn new() -> Ranker {
+        return Ranker { weights: Weights::default(), recency: None };
+    }
+
+    pub fn with_weights(mut self, weights: Weights) -> Ranker {
+        self.weights = weights;
+        return self;
+    }
+
+    pub fn weight(mut self, factor: Factor, weight: f64) -> Ranker {
+        self.weights.set(factor, weight);
+        return self;
+    }
+
+    // the callback maps a path to 0.0 (stale) ..= 1.0 (just touched); enabling it gives Recency a weight if it has none
+    pub fn recency<F>(mut self, recency: F) -> Ranker
+    where F: Fn(&str) -> f64 + Send + Sync + 'static {
+        self.recency = Some(Box::new(recency));
+        if self.weights.recency == 0.0 {
+            self.weights.recency = 1.0;
+        }
+        return self;
+    }
+
+    pub fn weights(&self) -> &Weights {
+        return &self.weights;
+    }
+
+    pub fn factor(&self, factor: Factor, candidate: &Candidate) -> f64 {
+        let matched = candidate.text.get(candidate.span.range()).unwrap_or("");
+        return match factor {
+            Factor::Exactness => {
+                if matched == candidate.pattern {
+                    1.0
+                } else if matched.eq_ignore_ascii_case(candidate.pattern) {
+                    0.5
+                } else {
+                    0.0
+                }
+            }
+            Factor::Position => {
+                1.0 - candidate.span.start.min(candidate.text.len()) as f64 / candidate.text.len().max(1) as f64
+            }
+            Factor::WordBoundary => {
+                let before = candidate.text[..candidate.span.start.min(candidate.text.len())].chars().next_back();
+                let after = candidate.text.get(candidate.span.end..).and_then(|rest| rest.chars().next());
+                let boundaries = [before, after].iter().filter(|character| !character.map(is_word_char).unwrap_or(false)).count();
+                boundaries as f64 / 2.0
+            }
+            Factor::PathDepth => {
+                let depth = candidate.path.trim_matches(['/', '\\']).matches(['/', '\\']).count();
+                1.0 / (1.0 + depth as f64)
+            }
+            Factor::Recency => self.recency.as_ref().map(|recency| recency(candidate.path).clamp(0.0, 1.0)).unwrap_or(0.0),
+        };
+    }
+
+    pub fn score(&self, candidate: &Candidate) -> f64 {
+        return Factor::ALL.iter().map(|factor| self.weights.get(*factor) * self.factor(*factor, candidate)).sum();
+    }
+
+    // best k items, highest score first; ties keep input order. Only k items are held at a time.
+    pub fn top_k_by<T, I, F>(&self, items: I, k: usize, candidate: F) -> Vec<Scored<T>>
+    where I: IntoIterator<Item = T>, F: Fn(&T) -> Candidate<'_> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut heap: BinaryHeap<Reverse<Entry<T>>> = BinaryHeap::with_capacity(k + 1);
+        for (order, item) in items.into_iter().enumerate() {
+            let score = self.score(&candidate(&item));
+            heap.push(Reverse(Entry { score, order, item }));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        let mut best: Vec<Entry<T>> = heap.into_iter().map(|Reverse(entry)| entry).collect();
+        best.sort_by(|a, b| b.cmp(a));
+        return best.into_iter().map(|entry| Scored { item: entry.item, score: entry.score }).collect();
+    }
+
+    pub fn top_k<'c, I>(&self, candidates: I, k: usize) -> Vec<Scored<Candidate<'c>>>
+    where I: IntoIterator<Item = Candidate<'c>> {
+        return self.top_k_by(candidates, k, |candidate| *candidate);
+    }
+}
+
+impl Default for Ranker {
+    fn default() -> Ranker {
+        return Ranker::new();
+    }
+}
+
+fn is_word_char(character: char) -> bool {
+    return character.is_alphanumeric() || character == '_';
+}
+
+// heap order: higher score is greater, and for equal scores the earlier item is greater
+struct Entry<T> {
+    score: f64,
+    order: usize,
+    item: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Entry<T>) -> bool {
+        return self.cmp(other) == Ordering::Equal;
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Entry<T>) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Entry<T>) -> Ordering {
+        return self.score.total_cmp(&other.score).then(other.order.cmp(&self.order));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate<'a>(path: &'a str, text: &'a str, pattern: &'a str) -> Candidate<'a> {
+        let start = text.to_lowercase().find(&pattern.to_lowercase()).unwrap();
+        return Candidate { path, text, span: Span::new(start, start + pattern.len()), pattern };
+    }
+
+    #[test]
+    fn test_factors() {
+        let ranker = Ranker::new();
+        let exact = candidate("src/lib.rs", "fn parse() {}", "parse");
+        assert_eq!(ranker.factor(Factor::Exactness, &exact), 1.0);
+        assert_eq!(ranker.factor(Factor::WordBoundary, &exact), 1.0);
+        assert_eq!(ranker.factor(Factor::PathDepth, &exact), 0.5);
+        let inner = candidate("a/b/c/d.rs", "reparsed", "PARSE");
+        assert_eq!(ranker.factor(Factor::Exactness, &inner), 0.5);
+        assert_eq!(ranker.factor(Factor::WordBoundary, &inner), 0.0);
+        assert_eq!(ranker.factor(Factor::Recency, &inner), 0.0);
+        assert!(ranker.score(&exact) > ranker.score(&inner));
+    }
+
+    #[test]
+    fn test_top_k_with_recency() {
+        let candidates = vec![
+            candidate("old.rs", "parse", "parse"),
+            candidate("deep/er/x.rs", "parser", "parse"),
+            candidate("new.rs", "parse", "parse"),
+            candidate("z.rs", "unparsed", "parse"),
+        ];
+        let ranker = Ranker::new().recency(|path| if path == "new.rs" { 1.0 } else { 0.0 });
+        let top: Vec<&str> = ranker.top_k(candidates.clone(), 2).iter().map(|scored| scored.item.path).collect();
+        assert_eq!(top, vec!["new.rs", "old.rs"]);
+        let plain = Ranker::new().weight(Factor::WordBoundary, 0.0);
+        let all = plain.top_k(candidates, 10);
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].item.path, "old.rs");
+        assert_eq!(all[3].item.path, "deep/er/x.rs");
+    }
+}
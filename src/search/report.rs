@@ -0,0 +1,212 @@
+use std::io::Write;
+
+use crate::patterns::PatternMatch;
+use crate::search::budget::MemoryBudget;
+use crate::text::line_index::LineIndex;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::match_id::MatchId;
+use crate::types::match_id::MatchIds;
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReportEntry {
+    pub path: String,
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+    pub pattern_id: usize,
+    pub text: String,
+}
+
+impl ReportEntry {
+    pub fn match_id(&self, ids: &mut MatchIds) -> MatchId {
+        return ids.next(&self.path, &self.pattern_id.to_string(), self.text.as_bytes());
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    entries: Vec<ReportEntry>,
+}
+
+impl Report {
+    pub fn new() -> Report {
+        return Report { entries: Vec::new() };
+    }
+
+    pub fn push(&mut self, entry: ReportEntry) {
+        self.entries.push(entry);
+    }
+
+    // push that refuses to grow the report past the budget's max_buffered_matches
+    pub fn try_push(&mut self, entry: ReportEntry, budget: &MemoryBudget) -> Result<(), ErrorChain> {
+        budget.check_matches(self.entries.len() + 1).on_error("report is full")?;
+        self.entries.push(entry);
+        return Ok(());
+    }
+
+    pub fn add_matches<'a, I>(&mut self, path: &str, haystack: &'a str, pattern_id: usize, matches: I)
+    where I: IntoIterator<Item = PatternMatch<&'a str>> {
+        let index = LineIndex::new(haystack);
+        for found in matches {
+            let position = index.position(found.start());
+            self.entries.push(ReportEntry {
+                path: path.to_string(),
+                span: Span::from(&found),
+                line: position.line,
+                column: position.column,
+                pattern_id,
+                text: found.slice.to_string(),
+            });
+        }
+    }
+
+    pub fn entries(&self) -> &[ReportEntry] {
+        return &self.entries;
+    }
+
+    // one id per entry; occurrences are numbered in entry order, so sort first when entries were
+    // added out of order
+    pub fn match_ids(&self) -> Vec<MatchId> {
+        let mut ids = MatchIds::new();
+        return self.entries.iter().map(|entry| entry.match_id(&mut ids)).collect();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    pub fn sort(&mut self) {
+        self.entries.sort_by(|a, b| {
+            a.path.cmp(&b.path)
+                .then(a.span.start.cmp(&b.span.start))
+                .then(a.span.end.cmp(&b.span.end))
+                .then(a.pattern_id.cmp(&b.pattern_id))
+        });
+    }
+
+    pub fn write_json_lines<W>(&self, writer: &mut W) -> Result<(), ErrorChain>
+    where W: Write {
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{{\"path\":{},\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"pattern_id\":{},\"text\":{}}}",
+                json_string(&entry.path),
+                entry.span.start,
+                entry.span.end,
+                entry.line,
+                entry.column,
+                entry.pattern_id,
+                json_string(&entry.text)
+            ).on_error("failed to write JSON lines report")?;
+        }
+        return Ok(());
+    }
+
+    pub fn to_json_lines(&self) -> String {
+        let mut out = Vec::new();
+        self.write_json_lines(&mut out).expect("writing to a Vec cannot fail");
+        return String::from_utf8(out).expect("report output is always UTF-8");
+    }
+
+    pub fn write_csv<W>(&self, writer: &mut W) -> Result<(), ErrorChain>
+    where W: Write {
+        writeln!(writer, "path,start,end,line,column,pattern_id,text").on_error("failed to write CSV report")?;
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                csv_field(&entry.path),
+                entry.span.start,
+                entry.span.end,
+                entry.line,
+                entry.column,
+                entry.pattern_id,
+                csv_field(&entry.text)
+            ).on_error("failed to write CSV report")?;
+        }
+        return Ok(());
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = Vec::new();
+        self.write_csv(&mut out).expect("writing to a Vec cannot fail");
+        return String::from_utf8(out).expect("report output is always UTF-8");
+    }
+}
+
+impl Extend<ReportEntry> for Report {
+    fn extend<I: IntoIterator<Item = ReportEntry>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+impl IntoIterator for Report {
+    type Item = ReportEntry;
+    type IntoIter = std::vec::IntoIter<ReportEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.entries.into_iter();
+    }
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            control if (control as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", control as u32)),
+            other => out.push(other),
+        }
+    }
+    out.push('"');
+    return out;
+}
+
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    return value.to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::PatternMatcher;
+
+    fn report() -> Report {
+        let haystack = "let a = 1;\nlet \"b\" = 2;";
+        let mut report = Report::new();
+        report.add_matches("src/main.rs", haystack, 0, haystack.find_every(&"let").unwrap());
+        report.add_matches("src/main.rs", haystack, 1, haystack.find_every(&"\"b\"").unwrap());
+        return report;
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let lines = report().to_json_lines();
+        let lines: Vec<&str> = lines.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "{\"path\":\"src/main.rs\",\"start\":11,\"end\":14,\"line\":2,\"column\":1,\"pattern_id\":0,\"text\":\"let\"}");
+        assert!(lines[2].ends_with("\"text\":\"\\\"b\\\"\"}"));
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "path,start,end,line,column,pattern_id,text");
+        assert_eq!(lines[3], "src/main.rs,15,18,2,5,1,\"\"\"b\"\"\"");
+    }
+}
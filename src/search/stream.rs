@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::fs::walk::Walker;
+use crate::patterns::PatternSet;
+use crate::patterns::SetMatch;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+// where the decided part of a streamed buffer ends: a match starting within the last max_len - 1
+// bytes could still grow with more input, so those bytes wait unless the stream is at its end
+pub fn decided_until(buffer_len: usize, max_len: usize, at_end: bool) -> usize {
+    if at_end {
+        return buffer_len;
+    }
+    return buffer_len.saturating_sub(max_len.saturating_sub(1));
+}
+
+// the matches of a streamed buffer that more input cannot change, in order; returns how many
+// leading bytes are decided and can be drained, which covers every reported match
+pub fn for_each_decided<P, E, F>(set: &PatternSet<P>, buffer: &[u8], max_len: usize, at_end: bool, mut on_match: F) -> Result<usize, E>
+where P: AsRef<[u8]>, F: FnMut(SetMatch) -> Result<(), E> {
+    let mut cursor = set.cursor(buffer);
+    let mut pos = 0;
+    while let Some(found) = cursor.find_first_from(pos) {
+        if !at_end && found.index + max_len > buffer.len() {
+            break;
+        }
+        on_match(found)?;
+        pos = found.end();
+    }
+    return Ok(decided_until(buffer.len(), max_len, at_end).max(pos));
+}
+
+// matches a reader chunk by chunk without holding more than a chunk plus the longest pattern in
+// memory; indexes are absolute offsets in the stream
+pub fn scan_reader<P, R, F>(set: &PatternSet<P>, mut reader: R, chunk_size: usize, mut on_match: F) -> Result<u64, ErrorChain>
+where P: AsRef<[u8]>, R: Read, F: FnMut(SetMatch) -> Result<(), ErrorChain> {
+    let max_len = set.patterns().iter().map(|pattern| pattern.as_ref().len()).max().unwrap_or(0);
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = vec![0; chunk_size.max(1)];
+    let mut offset: u64 = 0;
+    loop {
+        let read = reader.read(&mut chunk).on_error("failed to read input")?;
+        let at_end = read == 0;
+        buffer.extend_from_slice(&chunk[..read]);
+        let decided = for_each_decided(set, &buffer, max_len, at_end, |found| on_match(SetMatch { index: found.index + offset as usize, ..found }))?;
+        buffer.drain(..decided);
+        offset += decided as u64;
+        if at_end {
+            return Ok(offset);
+        }
+    }
+}
+
+// every file the walker yields, archive members included when it descends into archives
+pub fn scan_walker<P, F>(walker: &Walker, set: &PatternSet<P>, mut on_match: F) -> Result<(), ErrorChain>
+where P: AsRef<[u8]>, F: FnMut(&Path, SetMatch) -> Result<(), ErrorChain> {
+    return walker.for_each(|path, reader| {
+        scan_reader(set, reader, DEFAULT_CHUNK_SIZE, |found| on_match(path, found))?;
+        return Ok(());
+    });
+}
+
+// files a parallel scan may finish ahead of the one being delivered, per worker
+const REORDER_WINDOW_PER_THREAD: usize = 4;
+
+type FileMatches = Result<Vec<(PathBuf, SetMatch)>, ErrorChain>;
+
+struct Progress {
+    claimed: usize,
+    delivered: usize,
+    stopped: bool,
+}
+
+// scan_walker with files scanned on threads worker threads (0 for one per core). Matches reach
+// on_match on the calling thread in exactly the order scan_walker delivers them; files finished
+// early wait in a reordering buffer, and workers pause instead of running more than a bounded
+// number of files ahead. The first error in file order stops the scan
+pub fn scan_walker_parallel<P, F>(walker: &Walker, set: &PatternSet<P>, threads: usize, mut on_match: F) -> Result<(), ErrorChain>
+where P: AsRef<[u8]> + Sync, F: FnMut(&Path, SetMatch) -> Result<(), ErrorChain> {
+    let files = walker.files()?;
+    let threads = match threads {
+        0 => thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1),
+        threads => threads,
+    }.min(files.len().max(1));
+    let window = threads * REORDER_WINDOW_PER_THREAD;
+    let progress = Mutex::new(Progress { claimed: 0, delivered: 0, stopped: false });
+    let changed = Condvar::new();
+    let claim = || -> Option<usize> {
+        let mut state = progress.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        while !state.stopped && state.claimed < files.len() && state.claimed >= state.delivered + window {
+            state = changed.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        if state.stopped || state.claimed >= files.len() {
+            return None;
+        }
+        state.claimed += 1;
+        return Some(state.claimed - 1);
+    };
+    // matches waiting in the reordering buffer are charged to the walker's memory budget
+    let budget = walker.memory_budget();
+    let release = |count: usize| {
+        if let Some(budget) = &budget {
+            budget.release_matches(count);
+        }
+    };
+    let scan_file = |path: &Path| -> FileMatches {
+        let mut found = Vec::new();
+        let result = walker.read_file(path, |name, reader| {
+            scan_reader(set, reader, DEFAULT_CHUNK_SIZE, |found_match| {
+                if let Some(budget) = &budget {
+                    budget.charge_matches(1).do_on_error(|| format!("too many matches buffered while scanning {}", name.display()))?;
+                }
+                found.push((name.to_path_buf(), found_match));
+                return Ok(());
+            })?;
+            return Ok(());
+        });
+        if let Err(error) = result {
+            release(found.len());
+            return Err(error);
+        }
+        return Ok(found);
+    };
+    return thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel::<(usize, FileMatches)>();
+        for _ in 0..threads {
+            let sender = sender.clone();
+            scope.spawn(|| {
+                let sender = sender;
+                while let Some(index) = claim() {
+                    let result = scan_file(&files[index]);
+                    let charged = result.as_ref().map(Vec::len).unwrap_or(0);
+                    if sender.send((index, result)).is_err() {
+                        release(charged);
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        let mut pending: BTreeMap<usize, FileMatches> = BTreeMap::new();
+        let mut deliver = || -> Result<(), ErrorChain> {
+            let mut next = 0;
+            for (index, result) in &receiver {
+                pending.insert(index, result);
+                while let Some(result) = pending.remove(&next) {
+                    let result = result.map(|found| {
+                        release(found.len());
+                        return found;
+                    });
+                    for (path, found) in result? {
+                        on_match(&path, found)?;
+                    }
+                    next += 1;
+                    progress.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).delivered = next;
+                    changed.notify_all();
+                }
+            }
+            return Ok(());
+        };
+        let result = deliver();
+        progress.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).stopped = true;
+        changed.notify_all();
+        // after an early stop, give back what buffered and in-flight files hold; workers finish
+        // their current file and then see the stop
+        for (_, undelivered) in pending.into_iter().chain(&receiver) {
+            release(undelivered.map(|found| found.len()).unwrap_or(0));
+        }
+        return result;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_across_chunks() {
+        let set = PatternSet::new().with("needle").with("need");
+        let haystack = "hay needle haystack need needle".repeat(3);
+        for chunk_size in [1, 3, 7, 1024] {
+            let mut found = Vec::new();
+            let total = scan_reader(&set, haystack.as_bytes(), chunk_size, |found_match| {
+                found.push(found_match);
+                return Ok(());
+            }).unwrap();
+            assert_eq!(total, haystack.len() as u64);
+            assert_eq!(found, set.find_every_in(haystack.as_bytes(), 0));
+        }
+    }
+
+    #[test]
+    fn test_parallel_scan_keeps_walk_order() {
+        let contents: Vec<(String, String)> = (0..40).map(|n| (format!("d{}/f{:02}.txt", n % 3, n), "x needle ".repeat(n % 7 + 1))).collect();
+        let dir = crate::fs::temp::TempDir::new().unwrap().populate(contents.iter().map(|(path, text)| (path.as_str(), text.as_str()))).unwrap();
+        let walker = Walker::new(dir.path());
+        let set = PatternSet::new().with("needle");
+        let mut expected = Vec::new();
+        scan_walker(&walker, &set, |path, found| {
+            expected.push((path.to_path_buf(), found));
+            return Ok(());
+        }).unwrap();
+        assert_eq!(expected.len(), (0..40).map(|n| n % 7 + 1).sum::<usize>());
+        for threads in [0, 1, 3, 8] {
+            let mut found = Vec::new();
+            scan_walker_parallel(&walker, &set, threads, |path, found_match| {
+                found.push((path.to_path_buf(), found_match));
+                return Ok(());
+            }).unwrap();
+            assert_eq!(found, expected);
+        }
+        let mut delivered = 0;
+        let error = scan_walker_parallel(&walker, &set, 4, |_, _| {
+            delivered += 1;
+            return if delivered == 5 { Err(ErrorChain::new("enough")) } else { Ok(()) };
+        }).unwrap_err();
+        assert_eq!(error.to_string(), "enough");
+        assert_eq!(delivered, 5);
+
+        let budget = std::sync::Arc::new(crate::search::budget::MemoryBudget::new().max_buffered_matches(6));
+        let walker = Walker::new(dir.path()).budget(budget.clone());
+        let error = scan_walker_parallel(&walker, &set, 4, |_, _| Ok(())).unwrap_err();
+        assert_eq!(crate::search::budget::exceeded(&error).map(|exceeded| exceeded.max), Some(6));
+        assert_eq!(budget.buffered_matches(), 0);
+    }
+}
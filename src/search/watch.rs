@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crate::fs::walk::Walker;
+use crate::rules::Finding;
+use crate::rules::RuleSet;
+use crate::time::debounce::Debouncer;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::match_id::fnv1a;
+
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+
+// findings that appeared in or disappeared from one file since it was last scanned; removed
+// findings carry their spans in the previous contents of the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    pub path: PathBuf,
+    pub added: Vec<Finding>,
+    pub removed: Vec<Finding>,
+}
+
+// a file is rescanned when its length or modification time changes
+type Stamp = (u64, Option<SystemTime>);
+
+// findings are compared by rule and matched text, so a finding that only moved is not reported
+type Keyed = (Finding, (usize, u64));
+
+// the watched files are whatever the walker lists on each poll, so its ignore rules, overrides and
+// budget apply; paths that don't exist yet, or stop existing, are not an error
+#[derive(Debug)]
+pub struct Watcher<'r> {
+    walker: Walker,
+    rules: &'r RuleSet,
+    stamps: HashMap<PathBuf, Stamp>,
+    findings: HashMap<PathBuf, Vec<Keyed>>,
+    debouncer: Debouncer<PathBuf>,
+    poll_interval: Duration,
+}
+
+impl<'r> Watcher<'r> {
+    pub fn new(walker: Walker, rules: &'r RuleSet) -> Watcher<'r> {
+        return Watcher {
+            walker: walker.allow_missing(true),
+            rules,
+            stamps: HashMap::new(),
+            findings: HashMap::new(),
+            debouncer: Debouncer::new(DEFAULT_DEBOUNCE),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        };
+    }
+
+    pub fn poll_interval(mut self, interval: Duration) -> Watcher<'r> {
+        self.poll_interval = interval;
+        return self;
+    }
+
+    pub fn debounce(mut self, delay: Duration) -> Watcher<'r> {
+        self.debouncer = Debouncer::new(delay);
+        return self;
+    }
+
+    // the first poll reports every existing finding as added
+    pub fn poll(&mut self, now: Instant) -> Result<Vec<WatchEvent>, ErrorChain> {
+        let current = list_files(&self.walker).on_error("failed to list watched files")?;
+        for (path, stamp) in &current {
+            if self.stamps.get(path) != Some(stamp) {
+                self.debouncer.touch(path.clone(), now);
+            }
+        }
+        for path in self.stamps.keys().filter(|path| !current.contains_key(*path)) {
+            self.debouncer.touch(path.clone(), now);
+        }
+        self.stamps = current;
+
+        let mut ready = self.debouncer.ready(now);
+        ready.sort();
+        let mut events = Vec::new();
+        for path in ready {
+            let found = match self.stamps.contains_key(&path) {
+                true => self.scan(&path)?,
+                false => Vec::new(),
+            };
+            let previous = self.findings.remove(&path).unwrap_or_default();
+            let (added, removed) = diff(&previous, &found);
+            if !found.is_empty() {
+                self.findings.insert(path.clone(), found);
+            }
+            if !added.is_empty() || !removed.is_empty() {
+                events.push(WatchEvent { path, added, removed });
+            }
+        }
+        return Ok(events);
+    }
+
+    fn scan(&self, path: &Path) -> Result<Vec<Keyed>, ErrorChain> {
+        let haystack = match fs::read(path) {
+            Ok(haystack) => haystack,
+            // deleted between listing and reading; the next poll notices it's gone
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(ErrorChain::from(err, format!("failed to read {}", path.display()))),
+        };
+        let found = self.rules.run(&haystack).into_iter().map(|finding| {
+            let key = (finding.rule_id, fnv1a(haystack[finding.span.range()].iter().copied()));
+            return (finding, key);
+        }).collect();
+        return Ok(found);
+    }
+
+    // polls until on_event breaks, sleeping poll_interval between polls
+    pub fn run<F>(&mut self, mut on_event: F) -> Result<(), ErrorChain>
+    where F: FnMut(&WatchEvent) -> ControlFlow<()> {
+        loop {
+            for event in self.poll(Instant::now())? {
+                if on_event(&event).is_break() {
+                    return Ok(());
+                }
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+fn list_files(walker: &Walker) -> Result<HashMap<PathBuf, Stamp>, ErrorChain> {
+    let mut stamps = HashMap::new();
+    for path in walker.files()? {
+        match fs::metadata(&path) {
+            Ok(metadata) => stamps.insert(path, (metadata.len(), metadata.modified().ok())),
+            // deleted since the walk listed it; the next poll won't list it either
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(ErrorChain::from(err, format!("failed to stat {}", path.display()))),
+        };
+    }
+    return Ok(stamps);
+}
+
+fn diff(previous: &[Keyed], current: &[Keyed]) -> (Vec<Finding>, Vec<Finding>) {
+    let mut unmatched: HashMap<(usize, u64), usize> = HashMap::new();
+    for (_, key) in previous {
+        *unmatched.entry(*key).or_insert(0) += 1;
+    }
+    let mut added = Vec::new();
+    for (finding, key) in current {
+        match unmatched.get_mut(key) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added.push(finding.clone()),
+        }
+    }
+    let mut removed = Vec::new();
+    for (finding, key) in previous.iter().rev() {
+        if let Some(count) = unmatched.get_mut(key).filter(|count| **count > 0) {
+            *count -= 1;
+            removed.push(finding.clone());
+        }
+    }
+    removed.reverse();
+    return (added, removed);
+}
+
+// watches the files the walker lists and calls on_event with the findings added and removed by
+// each change, until on_event breaks
+pub fn watch<F>(walker: Walker, rules: &RuleSet, on_event: F) -> Result<(), ErrorChain>
+where F: FnMut(&WatchEvent) -> ControlFlow<()> {
+    return Watcher::new(walker, rules).run(on_event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+    use crate::rules::Rule;
+    use crate::types::span::Span;
+
+    #[test]
+    fn test_poll_reports_changes() {
+        let dir = TempDir::new().unwrap().with_file("logs/a.log", "ok\nERROR one\n").unwrap();
+        let rules = RuleSet::new(vec![Rule::new("error", "ERROR")]).unwrap();
+        let mut watcher = Watcher::new(Walker::new(dir.path()).exclude("*.tmp"), &rules).debounce(Duration::from_millis(50));
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        assert!(watcher.poll(at(0)).unwrap().is_empty());
+        let events = watcher.poll(at(60)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].added.len(), events[0].removed.len()), (1, 0));
+
+        fs::write(dir.join("logs/a.log"), "new line\nok\nERROR one\nERROR two\n").unwrap();
+        dir.create_file("b.log", "nothing").unwrap();
+        dir.create_file("c.tmp", "ERROR ignored").unwrap();
+        assert!(watcher.poll(at(100)).unwrap().is_empty());
+        let events = watcher.poll(at(200)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].added, vec![Finding { rule_id: 0, span: Span::new(22, 27) }]);
+        assert!(events[0].removed.is_empty());
+
+        fs::remove_file(dir.join("logs/a.log")).unwrap();
+        watcher.poll(at(300)).unwrap();
+        let events = watcher.poll(at(400)).unwrap();
+        assert_eq!((events[0].added.len(), events[0].removed.len()), (0, 2));
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::collections::ring_buffer::RingBuffer;
+
+pub fn percentile_of_sorted(sorted: &[f64], percentile: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let weight = rank - lower as f64;
+    return Some(sorted[lower] + (sorted[upper] - sorted[lower]) * weight);
+}
+
+fn sorted_copy<'a, I>(values: I) -> Vec<f64>
+where I: IntoIterator<Item = &'a f64> {
+    let mut sorted: Vec<f64> = values.into_iter().copied().filter(|value| !value.is_nan()).collect();
+    sorted.sort_by(f64::total_cmp);
+    return sorted;
+}
+
+#[derive(Debug, Clone)]
+pub struct Window {
+    samples: RingBuffer<f64>,
+    sum: f64,
+}
+
+impl Window {
+    pub fn new(size: usize) -> Window {
+        return Window { samples: RingBuffer::with_capacity(size), sum: 0.0 };
+    }
+
+    pub fn push(&mut self, sample: f64) {
+        if self.samples.is_full() {
+            self.sum -= self.samples.get(0).copied().unwrap_or(0.0);
+        }
+        self.samples.push(sample);
+        self.sum += sample;
+    }
+
+    pub fn push_duration(&mut self, sample: Duration) {
+        self.push(sample.as_secs_f64());
+    }
+
+    pub fn len(&self) -> usize {
+        return self.samples.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.samples.is_empty();
+    }
+
+    pub fn is_full(&self) -> bool {
+        return self.samples.is_full();
+    }
+
+    pub fn size(&self) -> usize {
+        return self.samples.capacity();
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        return Some(self.sum / self.samples.len() as f64);
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        return self.samples.iter().copied().reduce(f64::min);
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        return self.samples.iter().copied().reduce(f64::max);
+    }
+
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        return percentile_of_sorted(&sorted_copy(self.samples.iter()), percentile);
+    }
+
+    pub fn summary(&self) -> Summary {
+        return Summary::from_samples(self.samples.iter());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Summary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Summary {
+    pub fn from_samples<'a, I>(samples: I) -> Summary
+    where I: IntoIterator<Item = &'a f64> {
+        let sorted = sorted_copy(samples);
+        if sorted.is_empty() {
+            return Summary::default();
+        }
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = if count > 1 {
+            sorted.iter().map(|sample| (sample - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+        } else {
+            0.0
+        };
+        return Summary {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            stddev: variance.sqrt(),
+            p50: percentile_of_sorted(&sorted, 50.0).unwrap_or_default(),
+            p95: percentile_of_sorted(&sorted, 95.0).unwrap_or_default(),
+            p99: percentile_of_sorted(&sorted, 99.0).unwrap_or_default(),
+        };
+    }
+
+    pub fn from_durations<'a, I>(samples: I) -> Summary
+    where I: IntoIterator<Item = &'a Duration> {
+        let seconds: Vec<f64> = samples.into_iter().map(Duration::as_secs_f64).collect();
+        return Summary::from_samples(&seconds);
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "n={} mean={:.6} stddev={:.6} min={:.6} p50={:.6} p95={:.6} p99={:.6} max={:.6}",
+            self.count, self.mean, self.stddev, self.min, self.p50, self.p95, self.p99, self.max
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_rolls() {
+        let mut window = Window::new(3);
+        assert_eq!(window.mean(), None);
+        for sample in [1.0, 2.0, 3.0, 10.0] {
+            window.push(sample);
+        }
+        assert_eq!(window.len(), 3);
+        assert_eq!(window.mean(), Some(5.0));
+        assert_eq!(window.min(), Some(2.0));
+        assert_eq!(window.max(), Some(10.0));
+        assert_eq!(window.percentile(50.0), Some(3.0));
+    }
+
+    #[test]
+    fn test_summary() {
+        let samples: Vec<f64> = (1..=100).map(|value| value as f64).collect();
+        let summary = Summary::from_samples(&samples);
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.mean, 50.5);
+        assert_eq!(summary.p50, 50.5);
+        assert!((summary.p95 - 95.05).abs() < 1e-9);
+        assert!((summary.stddev - 29.011491975882016).abs() < 1e-9);
+        let durations = [Duration::from_millis(10), Duration::from_millis(30)];
+        assert!((Summary::from_durations(&durations).mean - 0.02).abs() < 1e-12);
+        assert_eq!(Summary::from_samples(&[]).count, 0);
+    }
+}
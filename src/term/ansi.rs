@@ -0,0 +1,181 @@
+use std::borrow::Cow;
+
+use crate::types::span::Span;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+// length of the escape sequence starting at bytes[start], which must be ESC
+fn escape_len(bytes: &[u8], start: usize) -> usize {
+    let mut pos = start + 1;
+    match bytes.get(pos) {
+        Some(b'[') => {
+            pos += 1;
+            while pos < bytes.len() && (0x20..=0x3f).contains(&bytes[pos]) {
+                pos += 1;
+            }
+            if pos < bytes.len() && (0x40..=0x7e).contains(&bytes[pos]) {
+                pos += 1;
+            }
+        }
+        Some(b']') | Some(b'P') | Some(b'_') => {
+            // string sequences (OSC, DCS, APC) run until BEL or ST (ESC \)
+            pos += 1;
+            while pos < bytes.len() {
+                if bytes[pos] == BEL {
+                    pos += 1;
+                    break;
+                }
+                if bytes[pos] == ESC && bytes.get(pos + 1) == Some(&b'\\') {
+                    pos += 2;
+                    break;
+                }
+                pos += 1;
+            }
+        }
+        Some(byte) if (0x20..=0x7e).contains(byte) => pos += 1,
+        _ => {}
+    }
+    return pos - start;
+}
+
+pub fn ansi_spans(src: &str) -> Vec<Span> {
+    let bytes = src.as_bytes();
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        if bytes[pos] == ESC {
+            let len = escape_len(bytes, pos);
+            spans.push(Span::new(pos, pos + len));
+            pos += len;
+        } else {
+            pos += 1;
+        }
+    }
+    return spans;
+}
+
+pub fn strip_ansi(src: &str) -> Cow<'_, str> {
+    let spans = ansi_spans(src);
+    if spans.is_empty() {
+        return Cow::Borrowed(src);
+    }
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    for span in spans {
+        out.push_str(&src[last..span.start]);
+        last = span.end;
+    }
+    out.push_str(&src[last..]);
+    return Cow::Owned(out);
+}
+
+pub fn char_width(character: char) -> usize {
+    let code = character as u32;
+    if character.is_control() {
+        return 0;
+    }
+    let zero_width = matches!(code, 0x0300..=0x036f | 0x1ab0..=0x1aff | 0x1dc0..=0x1dff | 0x200b..=0x200f | 0x20d0..=0x20ff | 0xfe00..=0xfe0f | 0xfe20..=0xfe2f);
+    if zero_width {
+        return 0;
+    }
+    let wide = matches!(code,
+        0x1100..=0x115f | 0x2e80..=0x303e | 0x3041..=0x33ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3 | 0xf900..=0xfaff | 0xfe30..=0xfe4f | 0xff00..=0xff60 | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f | 0x1f900..=0x1f9ff | 0x20000..=0x3fffd);
+    if wide {
+        return 2;
+    }
+    return 1;
+}
+
+pub fn visible_width(src: &str) -> usize {
+    return strip_ansi(src).chars().map(char_width).sum();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stripped {
+    pub text: String,
+    // offsets[i] is the byte offset in the original string of stripped byte i; the final entry maps the end
+    offsets: Vec<usize>,
+}
+
+impl Stripped {
+    pub fn original_offset(&self, stripped_offset: usize) -> Option<usize> {
+        return self.offsets.get(stripped_offset).copied();
+    }
+
+    pub fn original_span(&self, span: Span) -> Option<Span> {
+        let start = self.original_offset(span.start)?;
+        if span.is_empty() {
+            return Some(Span::at(start));
+        }
+        let end = self.original_offset(span.end - 1)? + 1;
+        return Some(Span::new(start, end));
+    }
+
+    // byte offset in the original string of the character drawn at the given 0-based visible column
+    pub fn column_to_offset(&self, column: usize) -> Option<usize> {
+        let mut current = 0;
+        for (index, character) in self.text.char_indices() {
+            let width = char_width(character);
+            if width > 0 && column < current + width {
+                return self.original_offset(index);
+            }
+            current += width;
+        }
+        if column == current {
+            return self.offsets.last().copied();
+        }
+        return None;
+    }
+
+    pub fn width(&self) -> usize {
+        return self.text.chars().map(char_width).sum();
+    }
+}
+
+pub fn strip_ansi_mapped(src: &str) -> Stripped {
+    let mut text = String::with_capacity(src.len());
+    let mut offsets = Vec::with_capacity(src.len() + 1);
+    let mut last = 0;
+    let keep = |from: usize, to: usize, text: &mut String, offsets: &mut Vec<usize>| {
+        text.push_str(&src[from..to]);
+        offsets.extend(from..to);
+    };
+    for span in ansi_spans(src) {
+        keep(last, span.start, &mut text, &mut offsets);
+        last = span.end;
+    }
+    keep(last, src.len(), &mut text, &mut offsets);
+    offsets.push(src.len());
+    return Stripped { text, offsets };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_and_width() {
+        let colored = "\x1b[1;31merror\x1b[0m: \x1b]8;;http://x\x07link\x1b]8;;\x1b\\ done";
+        assert_eq!(strip_ansi(colored), "error: link done");
+        assert!(matches!(strip_ansi("plain"), Cow::Borrowed(_)));
+        assert_eq!(visible_width(colored), 16);
+        assert_eq!(visible_width("\x1b[32m日本\x1b[0m"), 4);
+        assert_eq!(visible_width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn test_mapping_back_to_original_offsets() {
+        let colored = "\x1b[33mwarn\x1b[0m disk full";
+        let stripped = strip_ansi_mapped(colored);
+        let hit = stripped.text.find("disk").unwrap();
+        let span = stripped.original_span(Span::new(hit, hit + 4)).unwrap();
+        assert_eq!(&colored[span.range()], "disk");
+        assert_eq!(stripped.column_to_offset(0), Some(5));
+        assert_eq!(stripped.column_to_offset(4), Some(13));
+        assert_eq!(stripped.column_to_offset(stripped.width()), Some(colored.len()));
+        assert_eq!(stripped.column_to_offset(100), None);
+    }
+}
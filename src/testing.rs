@@ -0,0 +1,4 @@
+pub mod bench_support;
+pub mod error_chain;
+pub mod haystack;
+pub mod snapshot;
@@ -0,0 +1,168 @@
+use std::hint::black_box;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::formats::logfmt;
+use crate::formats::logfmt::Record;
+use crate::rand_lite::Rng;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub const BASELINE_ENV_VAR: &str = "GMEC_BENCH_BASELINE";
+pub const DEFAULT_TOLERANCE: f64 = 0.10;
+
+const WORDS: &[&str] = &[
+    "the", "search", "pattern", "match", "buffer", "offset", "error", "chain", "context", "value",
+    "request", "response", "timeout", "connection", "record", "index", "window", "stream", "token", "cache",
+];
+const LEVELS: &[&str] = &["DEBUG", "INFO", "INFO", "INFO", "WARN", "ERROR"];
+const SERVICES: &[&str] = &["api", "auth", "billing", "scheduler", "worker"];
+
+// representative corpora: same seed, same bytes, so runs on different commits are comparable
+
+pub fn prose_corpus(len: usize, seed: u64) -> String {
+    let mut rng = Rng::seed_from_u64(seed);
+    let mut out = String::with_capacity(len + 16);
+    while out.len() < len {
+        out.push_str(rng.choose(WORDS).unwrap_or(&"the"));
+        out.push(if rng.gen_bool(0.1) { '\n' } else { ' ' });
+    }
+    out.truncate(len);
+    return out;
+}
+
+pub fn log_corpus(lines: usize, seed: u64) -> String {
+    let mut rng = Rng::seed_from_u64(seed);
+    let mut out = String::new();
+    for line in 0..lines {
+        let level = rng.choose(LEVELS).unwrap_or(&"INFO");
+        let service = rng.choose(SERVICES).unwrap_or(&"api");
+        let took = rng.below(5000);
+        let word = rng.choose(WORDS).unwrap_or(&"request");
+        out.push_str(&format!("2024-05-01T12:{:02}:{:02}Z {} [{}] {} {} took={}ms id={:08x}\n", (line / 60) % 60, line % 60, level, service, word, line, took, rng.next_u32()));
+    }
+    return out;
+}
+
+pub fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+    let mut out = vec![0; len];
+    Rng::seed_from_u64(seed).fill_bytes(&mut out);
+    return out;
+}
+
+// distinct needles drawn from the corpus vocabulary, so most of them actually occur
+pub fn needle_set(count: usize, seed: u64) -> Vec<String> {
+    let mut rng = Rng::seed_from_u64(seed);
+    let mut needles: Vec<String> = Vec::new();
+    while needles.len() < count {
+        let needle = if needles.len() < WORDS.len() { WORDS[needles.len()].to_string() } else { format!("{}_{}", rng.choose(WORDS).unwrap_or(&"x"), needles.len()) };
+        needles.push(needle);
+    }
+    rng.shuffle(&mut needles);
+    return needles;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub name: String,
+    pub iterations: usize,
+    pub median: Duration,
+    pub min: Duration,
+    pub bytes: Option<u64>,
+}
+
+impl Measurement {
+    pub fn throughput_mib_per_sec(&self) -> Option<f64> {
+        let seconds = self.median.as_secs_f64();
+        return self.bytes.filter(|_| seconds > 0.0).map(|bytes| bytes as f64 / (1024.0 * 1024.0) / seconds);
+    }
+
+    pub fn record(&self) -> Record {
+        return Record::new()
+            .with("name", self.name.as_str())
+            .with("median_ns", self.median.as_nanos().to_string())
+            .with("min_ns", self.min.as_nanos().to_string())
+            .with("iterations", self.iterations.to_string());
+    }
+}
+
+// runs f once to warm up, then `iterations` timed runs; the median is what gets compared
+pub fn measure<R, F>(name: &str, iterations: usize, bytes: Option<u64>, mut f: F) -> Measurement
+where F: FnMut() -> R {
+    black_box(f());
+    let mut samples: Vec<Duration> = (0..iterations.max(1)).map(|_| {
+        let started = Instant::now();
+        black_box(f());
+        return started.elapsed();
+    }).collect();
+    samples.sort_unstable();
+    return Measurement { name: name.to_string(), iterations: samples.len(), median: samples[samples.len() / 2], min: samples[0], bytes };
+}
+
+// one logfmt record per measurement, the format compare_to_baseline reads back
+pub fn encode_results(measurements: &[Measurement]) -> String {
+    let lines: Vec<String> = measurements.iter().map(|measurement| logfmt::encode(&measurement.record()).expect("bench record keys are valid logfmt keys")).collect();
+    return lines.join("\n") + "\n";
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub name: String,
+    pub baseline: Duration,
+    pub current: Duration,
+}
+
+impl Regression {
+    pub fn slowdown(&self) -> f64 {
+        return self.current.as_secs_f64() / self.baseline.as_secs_f64().max(f64::MIN_POSITIVE) - 1.0;
+    }
+}
+
+// measurements whose median is more than `tolerance` (0.10 = 10%) slower than the baseline;
+// benchmarks missing from the baseline are new and never count as regressions
+pub fn compare_to_baseline(baseline: &str, measurements: &[Measurement], tolerance: f64) -> Result<Vec<Regression>, ErrorChain> {
+    let records = logfmt::decode_lines(baseline).on_error("failed to read benchmark baseline")?;
+    let mut regressions = Vec::new();
+    for measurement in measurements {
+        let Some(record) = records.iter().find(|record| record.get("name") == Some(measurement.name.as_str())) else {
+            continue;
+        };
+        let median = record.require("median_ns")?;
+        let nanos: u64 = median.parse().do_on_error(|| format!("baseline median_ns for '{}' is not a number: '{}'", measurement.name, median))?;
+        let baseline = Duration::from_nanos(nanos);
+        if measurement.median.as_secs_f64() > baseline.as_secs_f64() * (1.0 + tolerance) {
+            regressions.push(Regression { name: measurement.name.clone(), baseline, current: measurement.median });
+        }
+    }
+    return Ok(regressions);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpora_are_deterministic() {
+        assert_eq!(prose_corpus(1000, 7), prose_corpus(1000, 7));
+        assert_eq!(prose_corpus(1000, 7).len(), 1000);
+        assert_ne!(log_corpus(10, 1), log_corpus(10, 2));
+        assert_eq!(log_corpus(10, 1).lines().count(), 10);
+        assert_eq!(random_bytes(64, 3), random_bytes(64, 3));
+        let needles = needle_set(30, 5);
+        assert_eq!(needles.len(), 30);
+        assert!(needles.contains(&"search".to_string()));
+    }
+
+    #[test]
+    fn test_baseline_comparison() {
+        let at = |name: &str, micros: u64| Measurement { name: name.to_string(), iterations: 5, median: Duration::from_micros(micros), min: Duration::from_micros(micros), bytes: Some(1 << 20) };
+        let baseline = encode_results(&[at("naive", 100), at("set", 200)]);
+        let current = [at("naive", 105), at("set", 260), at("new", 1)];
+        let regressions = compare_to_baseline(&baseline, &current, DEFAULT_TOLERANCE).unwrap();
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].name, "set");
+        assert!((regressions[0].slowdown() - 0.3).abs() < 1e-9);
+        assert!(current[0].throughput_mib_per_sec().unwrap() > 9000.0);
+        assert_eq!(measure("noop", 3, None, || 1).iterations, 3);
+    }
+}
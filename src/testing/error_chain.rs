@@ -0,0 +1,150 @@
+use std::fmt;
+use std::fmt::Display;
+
+use crate::types::error_chain::ErrorChain;
+
+// one property asserted about an error chain; built by assert_error_chain! from `name = value` pairs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expectation {
+    Context(String),
+    RootCause(String),
+    Help(String),
+    Depth(usize),
+    UserMessage(String),
+}
+
+impl Expectation {
+    // some link's context contains the text
+    pub fn context<S>(text: S) -> Expectation
+    where S: Into<String> {
+        return Expectation::Context(text.into());
+    }
+
+    // the root cause type, either its full type_name or a trailing path like "io::error::Error"
+    pub fn root_cause<S>(type_name: S) -> Expectation
+    where S: Into<String> {
+        return Expectation::RootCause(type_name.into());
+    }
+
+    pub fn help<S>(text: S) -> Expectation
+    where S: Into<String> {
+        return Expectation::Help(text.into());
+    }
+
+    pub fn depth(depth: usize) -> Expectation {
+        return Expectation::Depth(depth);
+    }
+
+    pub fn user_message<S>(text: S) -> Expectation
+    where S: Into<String> {
+        return Expectation::UserMessage(text.into());
+    }
+
+    fn holds(&self, error: &ErrorChain) -> bool {
+        let links = links(error);
+        return match self {
+            Expectation::Context(text) => links.iter().any(|link| link.context_string().contains(text.as_str())),
+            Expectation::RootCause(expected) => error.root_cause_type().map(|actual| actual == expected || actual.ends_with(&format!("::{}", expected))).unwrap_or(false),
+            Expectation::Help(text) => links.iter().any(|link| link.help().iter().any(|help| help.contains(text.as_str()))),
+            Expectation::Depth(depth) => error.depth() == *depth,
+            Expectation::UserMessage(text) => error.user_message() == *text,
+        };
+    }
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Expectation::Context(text) => write!(f, "a context containing {:?}", text),
+            Expectation::RootCause(type_name) => write!(f, "a root cause of type {}", type_name),
+            Expectation::Help(text) => write!(f, "help containing {:?}", text),
+            Expectation::Depth(depth) => write!(f, "depth {}", depth),
+            Expectation::UserMessage(text) => write!(f, "user message {:?}", text),
+        };
+    }
+}
+
+fn links(error: &ErrorChain) -> Vec<&ErrorChain> {
+    let mut links = vec![error];
+    while let Some(next) = links.last().and_then(|link| link.cause()).and_then(|cause| cause.downcast_ref::<ErrorChain>()) {
+        links.push(next);
+    }
+    return links;
+}
+
+// the chain one link per line, so a failure shows structure rather than the joined Display output
+pub fn describe_chain(error: &ErrorChain) -> String {
+    let links = links(error);
+    let mut out = String::new();
+    for (index, link) in links.iter().enumerate() {
+        out.push_str(&format!("  {}: {}\n", index, link.context_string()));
+        for help in link.help() {
+            out.push_str(&format!("     help: {}\n", help));
+        }
+    }
+    if let Some(root) = links.last().and_then(|link| link.cause()) {
+        out.push_str(&format!("  root cause ({}): {}\n", error.root_cause_type().unwrap_or("?"), root));
+    }
+    out.push_str(&format!("  depth: {}, user message: {:?}\n", error.depth(), error.user_message()));
+    return out;
+}
+
+pub fn check_chain(error: &ErrorChain, expectations: &[Expectation]) -> Result<(), String> {
+    let failed: Vec<&Expectation> = expectations.iter().filter(|expectation| !expectation.holds(error)).collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let mut message = String::from("error chain did not match:\n");
+    for expectation in failed {
+        message.push_str(&format!("  expected {}\n", expectation));
+    }
+    message.push_str("chain:\n");
+    message.push_str(&describe_chain(error));
+    return Err(message);
+}
+
+pub fn check_result<T>(result: &Result<T, ErrorChain>, expectations: &[Expectation]) -> Result<(), String> {
+    return match result {
+        Ok(_) => Err(String::from("expected an error chain, got Ok")),
+        Err(error) => check_chain(error, expectations),
+    };
+}
+
+// assert_error_chain!(result, context = "reading config", root_cause = "io::error::Error", depth = 3)
+#[macro_export]
+macro_rules! assert_error_chain {
+    ($result:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        let expectations = [$($crate::testing::error_chain::Expectation::$name($value)),+];
+        if let Err(message) = $crate::testing::error_chain::check_result(&$result, &expectations) {
+            panic!("{}", message);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load() -> Result<(), ErrorChain> {
+        let io = ErrorChain::from(std::io::Error::other("permission denied"), "reading config").with_help("check the file mode");
+        return Err(ErrorChain::from(io, "starting server").user_facing());
+    }
+
+    #[test]
+    fn test_assert_error_chain() {
+        crate::assert_error_chain!(load(), context = "reading", root_cause = "io::error::Error", help = "file mode", depth = 3, user_message = "starting server");
+        let message = check_result(&load(), &[Expectation::context("network"), Expectation::depth(3), Expectation::root_cause("ParseIntError")]).unwrap_err();
+        assert_eq!(message, concat!(
+            "error chain did not match:\n",
+            "  expected a context containing \"network\"\n",
+            "  expected a root cause of type ParseIntError\n",
+            "chain:\n",
+            "  0: starting server\n",
+            "  1: reading config\n",
+            "     help: check the file mode\n",
+            "  root cause (std::io::error::Error): permission denied\n",
+            "  depth: 3, user message: \"starting server\"\n",
+        ));
+        assert_eq!(check_result(&Ok::<(), ErrorChain>(()), &[]), Err(String::from("expected an error chain, got Ok")));
+    }
+}
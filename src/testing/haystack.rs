@@ -0,0 +1,185 @@
+use std::ops::Range;
+
+use crate::patterns::PatternMatcher;
+use crate::rand_lite::Rng;
+
+#[derive(Debug, Clone)]
+pub struct HaystackSpec {
+    pub len: usize,
+    pub alphabet: Vec<u8>,
+    pub needles: Vec<Vec<u8>>,
+    pub plants: usize,
+}
+
+impl HaystackSpec {
+    pub fn new(len: usize) -> HaystackSpec {
+        return HaystackSpec { len, alphabet: b"abcdefghij".to_vec(), needles: Vec::new(), plants: 0 };
+    }
+
+    pub fn alphabet(mut self, alphabet: &[u8]) -> HaystackSpec {
+        self.alphabet = alphabet.to_vec();
+        return self;
+    }
+
+    pub fn needle<N>(mut self, needle: N) -> HaystackSpec
+    where N: AsRef<[u8]> {
+        self.needles.push(needle.as_ref().to_vec());
+        return self;
+    }
+
+    pub fn plants(mut self, plants: usize) -> HaystackSpec {
+        self.plants = plants;
+        return self;
+    }
+
+    pub fn generate(&self, seed: u64) -> GeneratedHaystack {
+        let mut rng = Rng::seed_from_u64(seed);
+        let mut bytes: Vec<u8> = (0..self.len)
+            .map(|_| *rng.choose(&self.alphabet).unwrap_or(&b'a'))
+            .collect();
+        let mut planted = Vec::new();
+        if !self.needles.is_empty() {
+            for _ in 0..self.plants {
+                let needle_id = rng.gen_index(self.needles.len());
+                let needle = &self.needles[needle_id];
+                if needle.len() > bytes.len() {
+                    continue;
+                }
+                let offset = rng.gen_index(bytes.len() - needle.len() + 1);
+                bytes[offset..offset + needle.len()].copy_from_slice(needle);
+                planted.push(Planted { needle_id, offset, len: needle.len() });
+            }
+        }
+        // later plants may overwrite earlier ones, so only keep plants that are still intact
+        planted.retain(|plant| bytes[plant.range()] == self.needles[plant.needle_id][..]);
+        planted.sort_by_key(|plant| (plant.offset, plant.needle_id));
+        return GeneratedHaystack { seed, bytes, planted };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Planted {
+    pub needle_id: usize,
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Planted {
+    pub fn range(&self) -> Range<usize> {
+        return self.offset..self.offset + self.len;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedHaystack {
+    pub seed: u64,
+    pub bytes: Vec<u8>,
+    pub planted: Vec<Planted>,
+}
+
+impl GeneratedHaystack {
+    pub fn as_str(&self) -> Option<&str> {
+        return std::str::from_utf8(&self.bytes).ok();
+    }
+}
+
+pub fn oracle_find_every<T>(haystack: &[T], needle: &[T], offset: usize) -> Vec<Range<usize>>
+where T: PartialEq {
+    let mut found = Vec::new();
+    if needle.is_empty() {
+        return found;
+    }
+    let mut pos = offset;
+    while pos + needle.len() <= haystack.len() {
+        if haystack[pos..pos + needle.len()] == *needle {
+            found.push(pos..pos + needle.len());
+            pos += needle.len();
+        } else {
+            pos += 1;
+        }
+    }
+    return found;
+}
+
+pub fn compare_with_oracle<P>(haystack: &[u8], pattern: &P, needle: &[u8], offset: usize) -> Result<(), String>
+where for<'a> [u8]: PatternMatcher<'a, P> {
+    let actual: Vec<Range<usize>> = haystack.find_every_from(pattern, offset)
+        .unwrap_or_default()
+        .iter()
+        .map(|found| found.range())
+        .collect();
+    let expected = oracle_find_every(haystack, needle, offset);
+    if actual == expected {
+        return Ok(());
+    }
+    let first_difference = actual.iter()
+        .zip(expected.iter())
+        .position(|(a, e)| a != e)
+        .unwrap_or(actual.len().min(expected.len()));
+    return Err(format!(
+        "matcher disagrees with oracle for needle {:?} from offset {}: first difference at result #{} (matcher {:?}, oracle {:?}); {} vs {} results",
+        String::from_utf8_lossy(needle),
+        offset,
+        first_difference,
+        actual.get(first_difference),
+        expected.get(first_difference),
+        actual.len(),
+        expected.len()
+    ));
+}
+
+pub fn assert_matches_oracle<P, F>(spec: &HaystackSpec, seeds: Range<u64>, make_pattern: F)
+where for<'a> [u8]: PatternMatcher<'a, P>, F: Fn(&[u8]) -> P {
+    for seed in seeds {
+        let generated = spec.generate(seed);
+        for needle in &spec.needles {
+            let pattern = make_pattern(needle);
+            for plant in generated.planted.iter().filter(|plant| spec.needles[plant.needle_id] == *needle) {
+                let hit = generated.bytes.find_first_from(&pattern, plant.offset).map(|hit| hit.range());
+                assert!(
+                    hit.as_ref().map(|hit| hit.start == plant.offset).unwrap_or(false),
+                    "seed {}: planted needle {:?} at {} was not found there (got {:?})",
+                    seed,
+                    String::from_utf8_lossy(needle),
+                    plant.offset,
+                    hit
+                );
+            }
+            if let Err(message) = compare_with_oracle(&generated.bytes, &pattern, needle, 0) {
+                panic!("seed {}: {}", seed, message);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        let spec = HaystackSpec::new(256).needle("needle").needle("pin").plants(5);
+        let a = spec.generate(3);
+        let b = spec.generate(3);
+        assert_eq!(a.bytes, b.bytes);
+        assert_eq!(a.planted, b.planted);
+        assert!(!a.planted.is_empty());
+        for plant in &a.planted {
+            assert_eq!(a.bytes[plant.range()], spec.needles[plant.needle_id][..]);
+        }
+        assert!(a.as_str().is_some());
+    }
+
+    #[test]
+    fn test_oracle() {
+        assert_eq!(oracle_find_every(b"aaaa", b"aa", 0), vec![0..2, 2..4]);
+        assert_eq!(oracle_find_every(b"abcabc", b"bc", 2), vec![4..6]);
+        assert!(compare_with_oracle(b"abcabc", &b"bc", b"bc", 0).is_ok());
+    }
+
+    #[test]
+    fn test_builtin_matcher_against_oracle() {
+        let spec = HaystackSpec::new(512).alphabet(b"ab").needle("abba").needle("bab").plants(8);
+        assert_matches_oracle(&spec, 0..20, |needle| needle.to_vec());
+    }
+}
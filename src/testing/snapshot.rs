@@ -0,0 +1,127 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::text::diff::unified_diff;
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+
+pub const UPDATE_ENV_VAR: &str = "GMEC_UPDATE_SNAPSHOTS";
+pub const DIR_ENV_VAR: &str = "GMEC_SNAPSHOT_DIR";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    Matched,
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl Snapshots {
+    pub fn new<P>(dir: P) -> Snapshots
+    where P: Into<PathBuf> {
+        return Snapshots { dir: dir.into(), update: update_requested() };
+    }
+
+    pub fn from_env() -> Snapshots {
+        if let Some(dir) = env::var_os(DIR_ENV_VAR) {
+            return Snapshots::new(dir);
+        }
+        let root = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+        return Snapshots::new(root.join("tests").join("snapshots"));
+    }
+
+    pub fn update(mut self, update: bool) -> Snapshots {
+        self.update = update;
+        return self;
+    }
+
+    pub fn dir(&self) -> &Path {
+        return &self.dir;
+    }
+
+    pub fn path_of(&self, name: &str) -> PathBuf {
+        return self.dir.join(format!("{}.snap", name));
+    }
+
+    pub fn check(&self, name: &str, value: &str) -> Result<SnapshotOutcome, ErrorChain> {
+        let path = self.path_of(name);
+        let stored = match fs::read_to_string(&path) {
+            Ok(stored) => Some(stored),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => return Err(ErrorChain::from(error, format!("failed to read snapshot {}", path.display()))),
+        };
+        match stored {
+            Some(stored) if stored == value => return Ok(SnapshotOutcome::Matched),
+            Some(stored) if !self.update => {
+                let mut diff = unified_diff(&stored, value, &format!("{} (stored)", name), &format!("{} (actual)", name), 3);
+                if diff.is_empty() {
+                    diff = String::from("contents differ only in line endings or the trailing newline");
+                }
+                return Err(ErrorChain::new(format!("snapshot '{}' does not match {}\n{}", name, path.display(), diff))
+                    .with_help(format!("rerun with {}=1 to accept the new output", UPDATE_ENV_VAR)));
+            }
+            None if !self.update => {
+                return Err(ErrorChain::new(format!("snapshot '{}' does not exist at {}", name, path.display()))
+                    .with_help(format!("rerun with {}=1 to record it", UPDATE_ENV_VAR)));
+            }
+            Some(_) => {
+                self.write(&path, value)?;
+                return Ok(SnapshotOutcome::Updated);
+            }
+            None => {
+                self.write(&path, value)?;
+                return Ok(SnapshotOutcome::Created);
+            }
+        }
+    }
+
+    fn write(&self, path: &Path, value: &str) -> Result<(), ErrorChain> {
+        fs::create_dir_all(&self.dir).do_on_error(|| format!("failed to create snapshot directory {}", self.dir.display()))?;
+        fs::write(path, value).do_on_error(|| format!("failed to write snapshot {}", path.display()))?;
+        return Ok(());
+    }
+}
+
+fn update_requested() -> bool {
+    return env::var(UPDATE_ENV_VAR).map(|value| !value.is_empty() && value != "0").unwrap_or(false);
+}
+
+pub fn assert_snapshot<V>(name: &str, value: V)
+where V: AsRef<str> {
+    if let Err(error) = Snapshots::from_env().check(name, value.as_ref()) {
+        panic!("{}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("gmec-snapshot-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        return dir;
+    }
+
+    #[test]
+    fn test_create_match_and_mismatch() {
+        let dir = scratch_dir("basic");
+        let recording = Snapshots::new(&dir).update(true);
+        assert_eq!(recording.check("greeting", "hello\nworld\n").unwrap(), SnapshotOutcome::Created);
+        let checking = Snapshots::new(&dir).update(false);
+        assert_eq!(checking.check("greeting", "hello\nworld\n").unwrap(), SnapshotOutcome::Matched);
+        let error = checking.check("greeting", "hello\nthere\n").unwrap_err().to_string();
+        assert!(error.contains("-world\n+there"));
+        assert!(error.contains(UPDATE_ENV_VAR));
+        assert!(checking.check("missing", "x").is_err());
+        assert_eq!(recording.check("greeting", "hello\nthere\n").unwrap(), SnapshotOutcome::Updated);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,10 @@
+pub mod comments;
+pub mod diff;
+pub mod encoding;
+pub mod folding;
+pub mod glob;
+pub mod line_endings;
+pub mod line_index;
+pub mod markdown;
+pub mod natural;
+pub mod similarity;
@@ -0,0 +1,214 @@
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    pub delimiter: char,
+    pub multiline: bool,
+    pub escapes: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentStyle {
+    pub line: &'static [&'static str],
+    pub block: &'static [(&'static str, &'static str)],
+    pub quotes: &'static [Quote],
+    pub nested_blocks: bool,
+}
+
+const DOUBLE_QUOTE: Quote = Quote { delimiter: '"', multiline: true, escapes: true };
+const CHAR_QUOTE: Quote = Quote { delimiter: '\'', multiline: false, escapes: true };
+
+impl CommentStyle {
+    pub const C: CommentStyle = CommentStyle {
+        line: &["//"],
+        block: &[("/*", "*/")],
+        quotes: &[DOUBLE_QUOTE, CHAR_QUOTE],
+        nested_blocks: false,
+    };
+
+    pub const RUST: CommentStyle = CommentStyle {
+        line: &["//"],
+        block: &[("/*", "*/")],
+        quotes: &[DOUBLE_QUOTE, CHAR_QUOTE],
+        nested_blocks: true,
+    };
+
+    pub const HASH: CommentStyle = CommentStyle {
+        line: &["#"],
+        block: &[],
+        quotes: &[
+            Quote { delimiter: '"', multiline: true, escapes: true },
+            Quote { delimiter: '\'', multiline: true, escapes: false },
+        ],
+        nested_blocks: false,
+    };
+
+    pub const SQL: CommentStyle = CommentStyle {
+        line: &["--"],
+        block: &[("/*", "*/")],
+        quotes: &[
+            Quote { delimiter: '\'', multiline: true, escapes: false },
+            Quote { delimiter: '"', multiline: true, escapes: false },
+        ],
+        nested_blocks: false,
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    Line,
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comment {
+    pub kind: CommentKind,
+    pub span: Span,
+}
+
+pub fn comments(src: &str, style: &CommentStyle) -> Vec<Comment> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while pos < src.len() {
+        let rest = &src[pos..];
+        if let Some(quote) = style.quotes.iter().find(|quote| rest.starts_with(quote.delimiter)) {
+            pos = skip_string(src, pos, quote);
+            continue;
+        }
+        if style.line.iter().any(|marker| rest.starts_with(marker)) {
+            let end = rest.find('\n').map(|index| pos + index).unwrap_or(src.len());
+            let end = if end > pos && src.as_bytes()[end - 1] == b'\r' { end - 1 } else { end };
+            found.push(Comment { kind: CommentKind::Line, span: Span::new(pos, end) });
+            pos = end;
+            continue;
+        }
+        if let Some((open, close)) = style.block.iter().find(|(open, _)| rest.starts_with(open)) {
+            let end = skip_block(src, pos, open, close, style.nested_blocks);
+            found.push(Comment { kind: CommentKind::Block, span: Span::new(pos, end) });
+            pos = end;
+            continue;
+        }
+        pos += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+    }
+    return found;
+}
+
+pub fn comment_spans(src: &str, style: &CommentStyle) -> Vec<Span> {
+    return comments(src, style).into_iter().map(|comment| comment.span).collect();
+}
+
+pub fn strip_comments(src: &str, style: &CommentStyle) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    for comment in comments(src, style) {
+        out.push_str(&src[last..comment.span.start]);
+        if comment.kind == CommentKind::Block {
+            let newlines = src[comment.span.range()].matches('\n').count();
+            if newlines == 0 {
+                out.push(' ');
+            }
+            for _ in 0..newlines {
+                out.push('\n');
+            }
+        }
+        last = comment.span.end;
+    }
+    out.push_str(&src[last..]);
+    return out;
+}
+
+pub fn blank_comments(src: &str, style: &CommentStyle) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    for comment in comments(src, style) {
+        out.push_str(&src[last..comment.span.start]);
+        for character in src[comment.span.range()].chars() {
+            match character {
+                '\n' | '\r' => out.push(character),
+                _ => out.extend(std::iter::repeat_n(' ', character.len_utf8())),
+            }
+        }
+        last = comment.span.end;
+    }
+    out.push_str(&src[last..]);
+    return out;
+}
+
+fn skip_string(src: &str, start: usize, quote: &Quote) -> usize {
+    let mut chars = src[start..].char_indices().skip(1);
+    while let Some((offset, character)) = chars.next() {
+        if character == quote.delimiter {
+            return start + offset + character.len_utf8();
+        }
+        if character == '\\' && quote.escapes {
+            chars.next();
+        } else if character == '\n' && !quote.multiline {
+            // not a string after all (e.g. a Rust lifetime), so resume right after the quote
+            return start + quote.delimiter.len_utf8();
+        }
+    }
+    if !quote.multiline {
+        return start + quote.delimiter.len_utf8();
+    }
+    return src.len();
+}
+
+fn skip_block(src: &str, start: usize, open: &str, close: &str, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut pos = start;
+    while pos < src.len() {
+        let rest = &src[pos..];
+        if rest.starts_with(open) && (nested || depth == 0) {
+            depth += 1;
+            pos += open.len();
+        } else if rest.starts_with(close) {
+            depth -= 1;
+            pos += close.len();
+            if depth == 0 {
+                return pos;
+            }
+        } else {
+            pos += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+        }
+    }
+    return src.len();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_c_comments() {
+        let src = "let url = \"http://x\"; // trailing\nint a; /* block */ int b;\n";
+        assert_eq!(strip_comments(src, &CommentStyle::C), "let url = \"http://x\"; \nint a;   int b;\n");
+        let spans = comment_spans(src, &CommentStyle::C);
+        assert_eq!(&src[spans[0].range()], "// trailing");
+        assert_eq!(&src[spans[1].range()], "/* block */");
+    }
+
+    #[test]
+    fn test_string_awareness() {
+        let src = "x = 'it''s # not a comment' # real\n";
+        assert_eq!(strip_comments(src, &CommentStyle::SQL), src);
+        assert_eq!(strip_comments(src, &CommentStyle::HASH), "x = 'it''s # not a comment' \n");
+        let sql = "SELECT '--' AS dashes -- comment\nFROM t";
+        assert_eq!(strip_comments(sql, &CommentStyle::SQL), "SELECT '--' AS dashes \nFROM t");
+    }
+
+    #[test]
+    fn test_rust_lifetimes_and_nesting() {
+        let src = "fn f<'a>(x: &'a str) {} /* outer /* inner */ still */ // end\n";
+        let spans = comment_spans(src, &CommentStyle::RUST);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(&src[spans[0].range()], "/* outer /* inner */ still */");
+    }
+
+    #[test]
+    fn test_blank_comments_preserves_offsets() {
+        let src = "a /* x\ny */ b // z\n";
+        let blanked = blank_comments(src, &CommentStyle::C);
+        assert_eq!(blanked.len(), src.len());
+        assert_eq!(blanked, "a     \n     b     \n");
+    }
+}
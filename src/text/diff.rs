@@ -0,0 +1,256 @@
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineOp<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<LineOp<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut ops = Vec::with_capacity(old_lines.len().max(new_lines.len()));
+    diff_range(&old_lines, &new_lines, &mut ops);
+    return normalize_order(ops);
+}
+
+// Myers' diff, split at a middle snake and recursed on both halves: O((n + m) * d) time for d
+// changed lines and O(n + m) memory, so a large file with a few edits stays cheap
+fn diff_range<'a>(old: &[&'a str], new: &[&'a str], ops: &mut Vec<LineOp<'a>>) {
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    ops.extend(old[..prefix].iter().map(|line| LineOp::Equal(line)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = old.iter().rev().zip(new.iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old_mid, new_mid) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+    if old_mid.is_empty() || new_mid.is_empty() {
+        ops.extend(old_mid.iter().map(|line| LineOp::Delete(line)));
+        ops.extend(new_mid.iter().map(|line| LineOp::Insert(line)));
+    } else {
+        match middle_snake(old_mid, new_mid) {
+            Some((x, y)) => {
+                diff_range(&old_mid[..x], &new_mid[..y], ops);
+                diff_range(&old_mid[x..], &new_mid[y..], ops);
+            }
+            None => {
+                ops.extend(old_mid.iter().map(|line| LineOp::Delete(line)));
+                ops.extend(new_mid.iter().map(|line| LineOp::Insert(line)));
+            }
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|line| LineOp::Equal(line)));
+}
+
+// where the furthest reaching paths from both ends first overlap, walked a diagonal k = x - y at
+// a time; None when the sides have nothing in common
+fn middle_snake(old: &[&str], new: &[&str]) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let len = 2 * max_d + 2;
+    // furthest x on each diagonal, from the start and (mirrored) from the end; -1 is unreached
+    let mut forward = vec![-1isize; len as usize];
+    let mut backward = vec![-1isize; len as usize];
+    forward[(offset + 1) as usize] = 0;
+    backward[(offset + 1) as usize] = 0;
+    let delta = n - m;
+    // with an odd delta the forward paths meet the backward ones, otherwise the other way round
+    let front = delta % 2 != 0;
+    let (mut forward_start, mut forward_end, mut backward_start, mut backward_end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        let mut k = -d + forward_start;
+        while k <= d - forward_end {
+            let at = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && forward[at - 1] < forward[at + 1]) { forward[at + 1] } else { forward[at - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at] = x;
+            if x > n {
+                forward_end += 2;
+            } else if y > m {
+                forward_start += 2;
+            } else if front {
+                let mirrored = offset + delta - k;
+                if mirrored >= 0 && mirrored < len && backward[mirrored as usize] != -1 && x >= n - backward[mirrored as usize] {
+                    return Some((x as usize, y as usize));
+                }
+            }
+            k += 2;
+        }
+        let mut k = -d + backward_start;
+        while k <= d - backward_end {
+            let at = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && backward[at - 1] < backward[at + 1]) { backward[at + 1] } else { backward[at - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at] = x;
+            if x > n {
+                backward_end += 2;
+            } else if y > m {
+                backward_start += 2;
+            } else if !front {
+                let mirrored = offset + delta - k;
+                if mirrored >= 0 && mirrored < len && forward[mirrored as usize] != -1 {
+                    let forward_x = forward[mirrored as usize];
+                    let forward_y = forward_x - (mirrored - offset);
+                    if forward_x >= n - x {
+                        return Some((forward_x as usize, forward_y as usize));
+                    }
+                }
+            }
+            k += 2;
+        }
+    }
+    return None;
+}
+
+fn normalize_order(ops: Vec<LineOp<'_>>) -> Vec<LineOp<'_>> {
+    // emit deletions before insertions inside each changed run, the way unified diffs read best
+    let mut out = Vec::with_capacity(ops.len());
+    let mut inserts = Vec::new();
+    for op in ops {
+        match op {
+            LineOp::Insert(_) => inserts.push(op),
+            LineOp::Delete(_) => out.push(op),
+            LineOp::Equal(_) => {
+                out.append(&mut inserts);
+                out.push(op);
+            }
+        }
+    }
+    out.append(&mut inserts);
+    return out;
+}
+
+// output patch and git apply accept, "\\ No newline at end of file" markers included
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str, context: usize) -> String {
+    let mut ops = diff_lines(old, new);
+    // lines() drops the final newline, so a last line that only gained or lost one still differs
+    let old_open = !old.is_empty() && !old.ends_with('\n');
+    let new_open = !new.is_empty() && !new.ends_with('\n');
+    if old_open != new_open {
+        if let Some(LineOp::Equal(line)) = ops.last().copied() {
+            ops.pop();
+            ops.push(LineOp::Delete(line));
+            ops.push(LineOp::Insert(line));
+        }
+    }
+    let old_total = ops.iter().filter(|op| !matches!(op, LineOp::Insert(_))).count();
+    let new_total = ops.iter().filter(|op| !matches!(op, LineOp::Delete(_))).count();
+    let mut out = String::new();
+    if ops.iter().all(|op| matches!(op, LineOp::Equal(_))) {
+        return out;
+    }
+    let _ = writeln!(out, "--- {}", old_name);
+    let _ = writeln!(out, "+++ {}", new_name);
+
+    let changed: Vec<usize> = ops.iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, LineOp::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+    let mut hunk_start = 0;
+    while hunk_start < changed.len() {
+        let mut hunk_end = hunk_start;
+        while hunk_end + 1 < changed.len() && changed[hunk_end + 1] - changed[hunk_end] <= context * 2 + 1 {
+            hunk_end += 1;
+        }
+        let first = changed[hunk_start].saturating_sub(context);
+        let last = (changed[hunk_end] + context + 1).min(ops.len());
+
+        let (mut old_line, mut new_line) = (1, 1);
+        for op in &ops[..first] {
+            match op {
+                LineOp::Equal(_) => {
+                    old_line += 1;
+                    new_line += 1;
+                }
+                LineOp::Delete(_) => old_line += 1,
+                LineOp::Insert(_) => new_line += 1,
+            }
+        }
+        let old_count = ops[first..last].iter().filter(|op| !matches!(op, LineOp::Insert(_))).count();
+        let new_count = ops[first..last].iter().filter(|op| !matches!(op, LineOp::Delete(_))).count();
+        let old_start = if old_count == 0 { old_line - 1 } else { old_line };
+        let new_start = if new_count == 0 { new_line - 1 } else { new_line };
+        let _ = writeln!(out, "@@ -{},{} +{},{} @@", old_start, old_count, new_start, new_count);
+        for op in &ops[first..last] {
+            let _ = match op {
+                LineOp::Equal(line) => writeln!(out, " {}", line),
+                LineOp::Delete(line) => writeln!(out, "-{}", line),
+                LineOp::Insert(line) => writeln!(out, "+{}", line),
+            };
+            let ends_old = !matches!(op, LineOp::Insert(_)) && old_line == old_total;
+            let ends_new = !matches!(op, LineOp::Delete(_)) && new_line == new_total;
+            if (ends_old && old_open) || (ends_new && new_open) {
+                out.push_str("\\ No newline at end of file\n");
+            }
+            match op {
+                LineOp::Equal(_) => {
+                    old_line += 1;
+                    new_line += 1;
+                }
+                LineOp::Delete(_) => old_line += 1,
+                LineOp::Insert(_) => new_line += 1,
+            }
+        }
+        hunk_start = hunk_end + 1;
+    }
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let ops = diff_lines("a\nb\nc\n", "a\nx\nc\nd\n");
+        assert_eq!(ops, vec![
+            LineOp::Equal("a"),
+            LineOp::Delete("b"),
+            LineOp::Insert("x"),
+            LineOp::Equal("c"),
+            LineOp::Insert("d"),
+        ]);
+        assert!(diff_lines("same", "same").iter().all(|op| matches!(op, LineOp::Equal(_))));
+        assert_eq!(diff_lines("a\nb\nc\nd\n", "x\nb\nd\ny\n"), vec![
+            LineOp::Delete("a"),
+            LineOp::Insert("x"),
+            LineOp::Equal("b"),
+            LineOp::Delete("c"),
+            LineOp::Equal("d"),
+            LineOp::Insert("y"),
+        ]);
+    }
+
+    #[test]
+    fn test_large_diff_with_few_changes() {
+        // a quadratic table would need 10^10 cells here
+        let old: String = (0..100_000).map(|n| format!("line {}\n", n)).collect();
+        let new = format!("first\n{}last\n", &old[old.find('\n').unwrap() + 1..old.len() - "line 99999\n".len()]);
+        let ops = diff_lines(&old, &new);
+        let changed: Vec<&LineOp> = ops.iter().filter(|op| !matches!(op, LineOp::Equal(_))).collect();
+        assert_eq!(changed, vec![&LineOp::Delete("line 0"), &LineOp::Insert("first"), &LineOp::Delete("line 99999"), &LineOp::Insert("last")]);
+        assert_eq!(ops.len(), 100_002);
+    }
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new = old.replace("\n3\n", "\nthree\n").replace("\n18\n", "\n");
+        let diff = unified_diff(&old, &new, "a/file", "b/file", 2);
+        assert_eq!(diff, "--- a/file\n+++ b/file\n\
+            @@ -1,5 +1,5 @@\n 1\n 2\n-3\n+three\n 4\n 5\n\
+            @@ -16,5 +16,4 @@\n 16\n 17\n-18\n 19\n 20\n");
+        assert_eq!(unified_diff("x\n", "x\n", "a", "b", 3), "");
+        assert_eq!(unified_diff("a\nb", "a\nc", "a", "b", 1), "--- a\n+++ b\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n");
+        assert_eq!(unified_diff("a\nb", "a\nb\n", "a", "b", 0), "--- a\n+++ b\n@@ -2,1 +2,1 @@\n-b\n\\ No newline at end of file\n+b\n");
+    }
+}
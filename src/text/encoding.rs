@@ -0,0 +1,190 @@
+use std::fmt;
+use std::fmt::Display;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::parse_error::ParseError;
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "ISO-8859-1",
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detection {
+    pub encoding: Encoding,
+    pub bom_len: usize,
+}
+
+impl Detection {
+    pub fn has_bom(&self) -> bool {
+        return self.bom_len > 0;
+    }
+}
+
+pub fn detect(bytes: &[u8]) -> Detection {
+    if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+        return Detection { encoding: Encoding::Utf8, bom_len: 3 };
+    }
+    if bytes.starts_with(&[0xff, 0xfe]) {
+        return Detection { encoding: Encoding::Utf16Le, bom_len: 2 };
+    }
+    if bytes.starts_with(&[0xfe, 0xff]) {
+        return Detection { encoding: Encoding::Utf16Be, bom_len: 2 };
+    }
+    let encoding = if std::str::from_utf8(bytes).is_ok() {
+        Encoding::Utf8
+    } else if looks_like_latin1(bytes) {
+        Encoding::Latin1
+    } else {
+        Encoding::Utf8
+    };
+    return Detection { encoding, bom_len: 0 };
+}
+
+// Latin-1 text uses high bytes for accented letters, which are almost never valid UTF-8 continuations;
+// bytes 0x80..0x9f are C1 controls that practically never appear in real Latin-1 text
+fn looks_like_latin1(bytes: &[u8]) -> bool {
+    let mut high = 0;
+    for byte in bytes {
+        match byte {
+            0x00 => return false,
+            0x80..=0x9f => return false,
+            0xa0..=0xff => high += 1,
+            _ => {}
+        }
+    }
+    return high > 0 && high * 4 <= bytes.len();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Replacement {
+    pub source: Span,
+    pub text_offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub text: String,
+    pub encoding: Encoding,
+    pub bom_len: usize,
+    pub replacements: Vec<Replacement>,
+}
+
+impl Decoded {
+    pub fn is_lossless(&self) -> bool {
+        return self.replacements.is_empty();
+    }
+}
+
+pub fn decode_lossy_to_string(bytes: &[u8]) -> Decoded {
+    let detection = detect(bytes);
+    return decode_lossy_with(bytes, detection.encoding, detection.bom_len);
+}
+
+pub fn decode_lossy_with(bytes: &[u8], encoding: Encoding, bom_len: usize) -> Decoded {
+    let body = &bytes[bom_len.min(bytes.len())..];
+    let mut decoded = Decoded { text: String::with_capacity(body.len()), encoding, bom_len, replacements: Vec::new() };
+    match encoding {
+        Encoding::Utf8 => decode_utf8(body, bom_len, &mut decoded),
+        Encoding::Utf16Le => decode_utf16(body, bom_len, u16::from_le_bytes, &mut decoded),
+        Encoding::Utf16Be => decode_utf16(body, bom_len, u16::from_be_bytes, &mut decoded),
+        Encoding::Latin1 => decoded.text.extend(body.iter().map(|byte| *byte as char)),
+    }
+    return decoded;
+}
+
+fn decode_utf8(body: &[u8], base: usize, decoded: &mut Decoded) {
+    let mut offset = base;
+    for chunk in body.utf8_chunks() {
+        decoded.text.push_str(chunk.valid());
+        offset += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            decoded.replacements.push(Replacement {
+                source: Span::new(offset, offset + chunk.invalid().len()),
+                text_offset: decoded.text.len(),
+            });
+            decoded.text.push(char::REPLACEMENT_CHARACTER);
+            offset += chunk.invalid().len();
+        }
+    }
+}
+
+fn decode_utf16(body: &[u8], base: usize, unit: fn([u8; 2]) -> u16, decoded: &mut Decoded) {
+    let units = body.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    let mut offset = base;
+    for result in char::decode_utf16(units) {
+        match result {
+            Ok(character) => {
+                decoded.text.push(character);
+                offset += character.len_utf16() * 2;
+            }
+            Err(_) => {
+                decoded.replacements.push(Replacement { source: Span::new(offset, offset + 2), text_offset: decoded.text.len() });
+                decoded.text.push(char::REPLACEMENT_CHARACTER);
+                offset += 2;
+            }
+        }
+    }
+    if body.len() % 2 == 1 {
+        decoded.replacements.push(Replacement { source: Span::new(offset, offset + 1), text_offset: decoded.text.len() });
+        decoded.text.push(char::REPLACEMENT_CHARACTER);
+    }
+}
+
+pub fn decode_to_string(bytes: &[u8]) -> Result<Decoded, ErrorChain> {
+    let decoded = decode_lossy_to_string(bytes);
+    if let Some(first) = decoded.replacements.first() {
+        return Err(ErrorChain::from(
+            ParseError::new(first.source, format!("invalid {} sequence", decoded.encoding)),
+            format!("failed to decode input as {} ({} invalid sequences)", decoded.encoding, decoded.replacements.len()),
+        ));
+    }
+    return Ok(decoded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_boms_and_latin1() {
+        assert_eq!(detect(b"\xef\xbb\xbfhi"), Detection { encoding: Encoding::Utf8, bom_len: 3 });
+        assert_eq!(detect(b"\xff\xfeh\x00").encoding, Encoding::Utf16Le);
+        assert_eq!(detect(b"\xfe\xff\x00h").encoding, Encoding::Utf16Be);
+        assert_eq!(detect("caf\u{e9}".as_bytes()).encoding, Encoding::Utf8);
+        assert_eq!(detect(b"caf\xe9 cr\xe8me").encoding, Encoding::Latin1);
+        assert_eq!(decode_lossy_to_string(b"caf\xe9").text, "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_lossy_decode_reports_replacements() {
+        let decoded = decode_lossy_to_string(b"ok\xff then \x80!");
+        assert_eq!(decoded.text, "ok\u{fffd} then \u{fffd}!");
+        assert_eq!(decoded.replacements.len(), 2);
+        assert_eq!(decoded.replacements[0].text_offset, 2);
+        assert_eq!(decoded.replacements[1].source, Span::new(9, 10));
+
+        let utf16 = decode_lossy_to_string(b"\xff\xfeh\x00i\x00\x00\xd8x");
+        assert_eq!(utf16.text, "hi\u{fffd}\u{fffd}");
+        assert_eq!(utf16.replacements[0].source, Span::new(6, 8));
+
+        let error = decode_to_string(b"a\xc3").unwrap_err().to_string();
+        assert!(error.contains("invalid UTF-8 sequence at bytes 1..2"));
+        assert!(decode_to_string("fine".as_bytes()).is_ok());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::patterns::PatternMatcher;
+use crate::types::span::Span;
+
+// a folded or normalized copy of some text, plus where every byte of the copy came from; spans
+// found in the copy map back to whole characters of the original, so a case-insensitive match on
+// 'İ' (two bytes, lowercasing to three) still reports the original two bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folded {
+    text: String,
+    origins: Vec<Span>,
+    source_len: usize,
+}
+
+impl Folded {
+    pub fn as_str(&self) -> &str {
+        return &self.text;
+    }
+
+    pub fn source_len(&self) -> usize {
+        return self.source_len;
+    }
+
+    // the original character a folded byte was produced from; offsets at or past the end of the
+    // copy map to the end of the original
+    pub fn original_offset(&self, folded_offset: usize) -> usize {
+        return self.origins.get(folded_offset).map(|origin| origin.start).unwrap_or(self.source_len);
+    }
+
+    // the inverse direction: where the fold of the character at or after an original offset starts
+    pub fn folded_offset(&self, original_offset: usize) -> usize {
+        return self.origins.partition_point(|origin| origin.start < original_offset);
+    }
+
+    pub fn original_span(&self, folded: Span) -> Span {
+        if folded.is_empty() {
+            return Span::at(self.original_offset(folded.start));
+        }
+        let start = self.original_offset(folded.start);
+        let end = self.origins.get(folded.end - 1).map(|origin| origin.end).unwrap_or(self.source_len);
+        return Span::new(start, end.max(start));
+    }
+
+    // non-overlapping matches of an already folded pattern, as spans of the original text
+    pub fn find_every(&self, folded_pattern: &str) -> Vec<Span> {
+        let found = self.text.find_every(&folded_pattern).unwrap_or_default();
+        return found.iter().map(|found| self.original_span(Span::from(found))).collect();
+    }
+}
+
+// builds a Folded copy by letting `fold` write the replacement for each character; it sees the
+// output so far, so it can also drop characters (e.g. collapsing runs of whitespace)
+pub fn fold_with<F>(src: &str, mut fold: F) -> Folded
+where F: FnMut(char, &mut String) {
+    let mut text = String::with_capacity(src.len());
+    let mut origins = Vec::with_capacity(src.len());
+    for (index, c) in src.char_indices() {
+        fold(c, &mut text);
+        origins.resize(text.len(), Span::new(index, index + c.len_utf8()));
+    }
+    return Folded { text, origins, source_len: src.len() };
+}
+
+pub fn fold_case(src: &str) -> Folded {
+    return fold_with(src, |c, out| out.extend(c.to_lowercase()));
+}
+
+// every run of whitespace becomes a single space
+pub fn fold_whitespace(src: &str) -> Folded {
+    return fold_with(src, |c, out| {
+        if !c.is_whitespace() {
+            out.push(c);
+        } else if !out.ends_with(' ') {
+            out.push(' ');
+        }
+    });
+}
+
+pub fn fold_case_and_whitespace(src: &str) -> Folded {
+    return fold_with(src, |c, out| {
+        if !c.is_whitespace() {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with(' ') {
+            out.push(' ');
+        }
+    });
+}
+
+// case-insensitive search with spans in the original text
+pub fn find_ignore_case(haystack: &str, pattern: &str) -> Vec<Span> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    return fold_case(haystack).find_every(fold_case(pattern).as_str());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_folding_keeps_original_spans() {
+        let text = "İstanbul vs ISTANBUL, straße";
+        let folded = fold_case(text);
+        assert_eq!(folded.as_str().len(), text.len() + 1);
+        let found = find_ignore_case(text, "istanbul");
+        assert_eq!(found, vec![Span::new(13, 21)]);
+        let dotted = find_ignore_case(text, "İstanbul");
+        assert_eq!(dotted, vec![Span::new(0, 9)]);
+        assert_eq!(&text[dotted[0].range()], "İstanbul");
+        assert_eq!(find_ignore_case(text, "STRASSE"), Vec::<Span>::new());
+        assert_eq!(find_ignore_case(text, "STRAßE").iter().map(|span| &text[span.range()]).collect::<Vec<_>>(), vec!["straße"]);
+        assert_eq!(folded.original_span(Span::new(1, 1)), Span::at(0));
+        assert_eq!(folded.original_span(Span::at(folded.as_str().len())), Span::at(text.len()));
+        assert_eq!((folded.folded_offset(2), folded.folded_offset(text.len())), (3, folded.as_str().len()));
+    }
+
+    #[test]
+    fn test_whitespace_normalization() {
+        let text = "let  x =\n\t1;  let x = 2;";
+        let folded = fold_whitespace(text);
+        assert_eq!(folded.as_str(), "let x = 1; let x = 2;");
+        let spans = folded.find_every("x = 1");
+        assert_eq!(spans.iter().map(|span| &text[span.range()]).collect::<Vec<_>>(), vec!["x =\n\t1"]);
+        assert_eq!(fold_case_and_whitespace("A\n\nB").find_every("a b"), vec![Span::new(0, 4)]);
+    }
+}
@@ -0,0 +1,162 @@
+// shell-style globbing over '/'-separated paths: '*' and '?' stay within a segment, '**' crosses
+// segments, and [abc], [a-z] and [!abc] match one character from a class. The pattern is matched
+// one token at a time against every suffix of the text, so it takes O(pattern * text) whatever the
+// pattern, with no backtracking
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let tokens = tokenize(pattern);
+    let text: Vec<char> = text.chars().collect();
+    let len = text.len();
+    // matched[t]: the tokens after the current one match text[t..]
+    let mut matched: Vec<bool> = (0..=len).map(|start| start == len).collect();
+    for token in tokens.iter().rev() {
+        let mut row = vec![false; len + 1];
+        // for "**/": some text[k..] with k >= t starts with '/' followed by a match
+        let mut slash_later = false;
+        for start in (0..=len).rev() {
+            let character = text.get(start).copied();
+            let one = |accepts: bool| accepts && matched[start + 1];
+            row[start] = match token {
+                Token::Char(expected) => one(character == Some(*expected)),
+                Token::One => one(matches!(character, Some(character) if character != '/')),
+                Token::Class(class) => one(matches!(character, Some(character) if character != '/' && class.contains(character))),
+                Token::Star => matched[start] || (matches!(character, Some(character) if character != '/') && row[start + 1]),
+                Token::GlobStar => matched[start] || (character.is_some() && row[start + 1]),
+                Token::GlobStarSlash => {
+                    slash_later |= character == Some('/') && matched[start + 1];
+                    matched[start] || slash_later
+                }
+            };
+        }
+        matched = row;
+    }
+    return matched[0];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    // '?'
+    One,
+    Class(CharClass),
+    // '*'
+    Star,
+    // '**' anywhere but right before a '/'
+    GlobStar,
+    // "**/", which may also match zero directories
+    GlobStarSlash,
+}
+
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < pattern.len() {
+        match pattern[index] {
+            '*' => {
+                let stars = pattern[index..].iter().take_while(|character| **character == '*').count();
+                index += stars;
+                if stars == 1 {
+                    tokens.push(Token::Star);
+                } else if stars == 2 && pattern.get(index) == Some(&'/') {
+                    tokens.push(Token::GlobStarSlash);
+                    index += 1;
+                } else {
+                    tokens.push(Token::GlobStar);
+                }
+            }
+            '?' => {
+                tokens.push(Token::One);
+                index += 1;
+            }
+            '[' => match parse_class(&pattern[index + 1..]) {
+                Some((class, consumed)) => {
+                    tokens.push(Token::Class(class));
+                    index += 1 + consumed;
+                }
+                // an unclosed '[' is a literal
+                None => {
+                    tokens.push(Token::Char('['));
+                    index += 1;
+                }
+            },
+            '\\' if index + 1 < pattern.len() => {
+                tokens.push(Token::Char(pattern[index + 1]));
+                index += 2;
+            }
+            literal => {
+                tokens.push(Token::Char(literal));
+                index += 1;
+            }
+        }
+    }
+    return tokens;
+}
+
+// a [...] class, shared with the wildcard text patterns; callers decide which characters (a path
+// separator, a newline) no class may match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharClass {
+    pub negated: bool,
+    pub ranges: Vec<(char, char)>,
+}
+
+impl CharClass {
+    pub fn contains(&self, character: char) -> bool {
+        return self.ranges.iter().any(|(low, high)| (*low..=*high).contains(&character)) != self.negated;
+    }
+}
+
+// the class after '[' and the chars it used including the closing ']', or None when it never
+// closes; '!' or '^' first negates it, and a ']' right after that is a member
+pub fn parse_class(class: &[char]) -> Option<(CharClass, usize)> {
+    let negated = matches!(class.first(), Some('!') | Some('^'));
+    let mut index = if negated { 1 } else { 0 };
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while index < class.len() {
+        let current = class[index];
+        if current == ']' && !first {
+            return Some((CharClass { negated, ranges }, index + 1));
+        }
+        first = false;
+        if class.get(index + 1) == Some(&'-') && class.get(index + 2).map(|end| *end != ']').unwrap_or(false) {
+            ranges.push((current, class[index + 2]));
+            index += 3;
+        } else {
+            ranges.push((current, current));
+            index += 1;
+        }
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("src/**/*.rs", "src/main.rs"));
+        assert!(glob_match("src/**/*.rs", "src/a/b/lib.rs"));
+        assert!(glob_match("**", "any/thing"));
+        assert!(glob_match("file?.[ch]", "file1.h"));
+        assert!(!glob_match("file?.[!ch]", "file1.h"));
+        assert!(glob_match("[a-c]x", "bx"));
+        assert!(glob_match("a[", "a["));
+        assert!(glob_match("\\*", "*"));
+        assert!(!glob_match("\\*", "x"));
+        assert!(glob_match("a/**/b", "a/b") && glob_match("a/**/b", "a/x/y/b") && !glob_match("a/**/b", "a/xb"));
+        assert!(glob_match("[]x]", "]") && !glob_match("[a-z]", "/"));
+    }
+
+    #[test]
+    fn test_pathological_patterns_stay_linear() {
+        let text = "a".repeat(40);
+        assert!(!glob_match(&format!("{}b", "*a".repeat(12)), &text));
+        assert!(glob_match(&"*a".repeat(12), &text));
+        let long = "a".repeat(20_000);
+        assert!(!glob_match(&format!("{}b", "**a".repeat(200)), &long));
+    }
+}
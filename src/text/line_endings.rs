@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        };
+    }
+
+    pub fn byte_len(&self) -> usize {
+        return self.as_str().len();
+    }
+
+    pub fn native() -> LineEnding {
+        if cfg!(windows) {
+            return LineEnding::CrLf;
+        }
+        return LineEnding::Lf;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Detected {
+    None,
+    Uniform(LineEnding),
+    Mixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineEndingCounts {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+}
+
+impl LineEndingCounts {
+    pub fn of(src: &str) -> LineEndingCounts {
+        let mut counts = LineEndingCounts::default();
+        for line in split_lines(src) {
+            match line.terminator {
+                Some(LineEnding::Lf) => counts.lf += 1,
+                Some(LineEnding::CrLf) => counts.crlf += 1,
+                Some(LineEnding::Cr) => counts.cr += 1,
+                None => {}
+            }
+        }
+        return counts;
+    }
+
+    pub fn most_common(&self) -> Option<LineEnding> {
+        let ranked = [(self.lf, LineEnding::Lf), (self.crlf, LineEnding::CrLf), (self.cr, LineEnding::Cr)];
+        return ranked.iter()
+            .filter(|(count, _)| *count > 0)
+            .max_by_key(|(count, _)| *count)
+            .map(|(_, ending)| *ending);
+    }
+}
+
+pub fn detect(src: &str) -> Detected {
+    let counts = LineEndingCounts::of(src);
+    let kinds = [counts.lf, counts.crlf, counts.cr].iter().filter(|count| **count > 0).count();
+    return match kinds {
+        0 => Detected::None,
+        1 => Detected::Uniform(counts.most_common().expect("one kind was counted")),
+        _ => Detected::Mixed,
+    };
+}
+
+pub fn normalize_to(src: &str, ending: LineEnding) -> Cow<'_, str> {
+    let detected = detect(src);
+    if detected == Detected::Uniform(ending) || detected == Detected::None {
+        return Cow::Borrowed(src);
+    }
+    let mut out = String::with_capacity(src.len());
+    for line in split_lines(src) {
+        out.push_str(line.text);
+        if line.terminator.is_some() {
+            out.push_str(ending.as_str());
+        }
+    }
+    return Cow::Owned(out);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'a> {
+    pub text: &'a str,
+    pub span: Span,
+    pub terminator: Option<LineEnding>,
+}
+
+impl Line<'_> {
+    pub fn full_span(&self) -> Span {
+        return Span::new(self.span.start, self.span.end + self.terminator.map(|ending| ending.byte_len()).unwrap_or(0));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitLines<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+pub fn split_lines(src: &str) -> SplitLines<'_> {
+    return SplitLines { src, pos: 0 };
+}
+
+impl<'a> Iterator for SplitLines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Line<'a>> {
+        if self.pos >= self.src.len() {
+            return None;
+        }
+        let start = self.pos;
+        let bytes = self.src.as_bytes();
+        let found = bytes[start..].iter().position(|byte| *byte == b'\n' || *byte == b'\r');
+        let (end, terminator) = match found {
+            None => (self.src.len(), None),
+            Some(index) if bytes[start + index] == b'\n' => (start + index, Some(LineEnding::Lf)),
+            Some(index) if bytes.get(start + index + 1) == Some(&b'\n') => (start + index, Some(LineEnding::CrLf)),
+            Some(index) => (start + index, Some(LineEnding::Cr)),
+        };
+        self.pos = end + terminator.map(|ending| ending.byte_len()).unwrap_or(0);
+        return Some(Line { text: &self.src[start..end], span: Span::new(start, end), terminator });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("no newline"), Detected::None);
+        assert_eq!(detect("a\nb\n"), Detected::Uniform(LineEnding::Lf));
+        assert_eq!(detect("a\r\nb\r\n"), Detected::Uniform(LineEnding::CrLf));
+        assert_eq!(detect("a\rb"), Detected::Uniform(LineEnding::Cr));
+        assert_eq!(detect("a\r\nb\nc"), Detected::Mixed);
+        assert_eq!(LineEndingCounts::of("a\r\nb\nc\r\n").most_common(), Some(LineEnding::CrLf));
+    }
+
+    #[test]
+    fn test_split_and_normalize() {
+        let lines: Vec<Line> = split_lines("one\r\ntwo\rthree\nfour").collect();
+        assert_eq!(lines.iter().map(|line| line.text).collect::<Vec<&str>>(), vec!["one", "two", "three", "four"]);
+        assert_eq!(lines[0].span, Span::new(0, 3));
+        assert_eq!(lines[0].full_span(), Span::new(0, 5));
+        assert_eq!(lines[1].terminator, Some(LineEnding::Cr));
+        assert_eq!(lines[3].terminator, None);
+        assert_eq!(normalize_to("one\r\ntwo\rthree\nfour", LineEnding::Lf), "one\ntwo\nthree\nfour");
+        assert!(matches!(normalize_to("a\nb\n", LineEnding::Lf), Cow::Borrowed(_)));
+        assert_eq!(normalize_to("a\nb\n", LineEnding::CrLf), "a\r\nb\r\n");
+    }
+}
@@ -0,0 +1,110 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::Display;
+
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}:{}", self.line, self.column);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    src: &'a str,
+    starts: Cow<'a, [usize]>,
+}
+
+pub(crate) fn line_starts(src: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (index, byte) in src.bytes().enumerate() {
+        if byte == b'\n' {
+            starts.push(index + 1);
+        }
+    }
+    return starts;
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(src: &'a str) -> LineIndex<'a> {
+        return LineIndex { src, starts: Cow::Owned(line_starts(src)) };
+    }
+
+    // starts must be line_starts(src), e.g. cached from an earlier call
+    pub(crate) fn from_starts(src: &'a str, starts: &'a [usize]) -> LineIndex<'a> {
+        return LineIndex { src, starts: Cow::Borrowed(starts) };
+    }
+
+    pub fn source(&self) -> &'a str {
+        return self.src;
+    }
+
+    pub fn line_count(&self) -> usize {
+        return self.starts.len();
+    }
+
+    pub fn line_of(&self, offset: usize) -> usize {
+        match self.starts.binary_search(&offset) {
+            Ok(line) => return line,
+            Err(next) => return next - 1,
+        }
+    }
+
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let start = *self.starts.get(line)?;
+        let mut end = self.starts.get(line + 1).map(|next| next - 1).unwrap_or(self.src.len());
+        if end > start && self.src.as_bytes()[end - 1] == b'\r' {
+            end -= 1;
+        }
+        return Some(Span::new(start, end));
+    }
+
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        return self.line_span(line).map(|span| &self.src[span.range()]);
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.src.len());
+        let line = self.line_of(offset);
+        let start = self.starts[line];
+        let column = match self.src.get(start..offset) {
+            Some(prefix) => prefix.chars().count(),
+            None => offset - start,
+        };
+        return Position { line: line + 1, column: column + 1 };
+    }
+
+    pub fn offset(&self, position: Position) -> Option<usize> {
+        let span = self.line_span(position.line.checked_sub(1)?)?;
+        let column = position.column.checked_sub(1)?;
+        let line = &self.src[span.start..span.end];
+        if column == line.chars().count() {
+            return Some(span.end);
+        }
+        return line.char_indices().nth(column).map(|(index, _)| span.start + index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("first\r\nsécond\nthird");
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line_text(0), Some("first"));
+        assert_eq!(index.line_text(1), Some("sécond"));
+        assert_eq!(index.line_of(7), 1);
+        assert_eq!(index.position(10), Position { line: 2, column: 3 });
+        assert_eq!(index.offset(Position { line: 2, column: 3 }), Some(10));
+        assert_eq!(index.position(100), Position { line: 3, column: 6 });
+    }
+}
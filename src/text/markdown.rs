@@ -0,0 +1,200 @@
+use crate::patterns::PatternMatcher;
+use crate::types::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterKind {
+    Yaml,
+    Toml,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontMatter<'a> {
+    pub kind: FrontMatterKind,
+    pub span: Span,
+    pub content_span: Span,
+    pub content: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeFence<'a> {
+    pub info: &'a str,
+    pub language: Option<&'a str>,
+    pub span: Span,
+    pub content_span: Span,
+    pub content: &'a str,
+    pub closed: bool,
+}
+
+struct Line {
+    start: usize,
+    end: usize,
+    next: usize,
+}
+
+fn lines(src: &str) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < src.len() {
+        match src.find_first_from(&"\n", start) {
+            Some(newline) => {
+                let end = if newline.index > start && src.as_bytes()[newline.index - 1] == b'\r' { newline.index - 1 } else { newline.index };
+                lines.push(Line { start, end, next: newline.end() });
+                start = newline.end();
+            }
+            None => {
+                lines.push(Line { start, end: src.len(), next: src.len() });
+                start = src.len();
+            }
+        }
+    }
+    return lines;
+}
+
+pub fn front_matter(src: &str) -> Option<FrontMatter<'_>> {
+    let lines = lines(src);
+    let first = lines.first()?;
+    let (kind, delimiter) = match src[first.start..first.end].trim_end() {
+        "---" => (FrontMatterKind::Yaml, "---"),
+        "+++" => (FrontMatterKind::Toml, "+++"),
+        _ => return None,
+    };
+    for line in &lines[1..] {
+        let text = src[line.start..line.end].trim_end();
+        if text == delimiter || (kind == FrontMatterKind::Yaml && text == "...") {
+            let content_span = Span::new(first.next, line.start);
+            return Some(FrontMatter {
+                kind,
+                span: Span::new(0, line.next),
+                content_span,
+                content: &src[content_span.range()],
+            });
+        }
+    }
+    return None;
+}
+
+pub fn body(src: &str) -> &str {
+    match front_matter(src) {
+        Some(found) => return &src[found.span.end..],
+        None => return src,
+    }
+}
+
+fn fence_open(text: &str) -> Option<(u8, usize, &str)> {
+    let indent = text.len() - text.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &text[indent..];
+    let marker = *rest.as_bytes().first()?;
+    if marker != b'`' && marker != b'~' {
+        return None;
+    }
+    let length = rest.bytes().take_while(|byte| *byte == marker).count();
+    if length < 3 {
+        return None;
+    }
+    let info = rest[length..].trim();
+    if marker == b'`' && info.contains('`') {
+        return None;
+    }
+    return Some((marker, length, info));
+}
+
+fn is_fence_close(text: &str, marker: u8, length: usize) -> bool {
+    let indent = text.len() - text.trim_start_matches(' ').len();
+    if indent > 3 {
+        return false;
+    }
+    let rest = text[indent..].trim_end();
+    return rest.len() >= length && rest.bytes().all(|byte| byte == marker);
+}
+
+pub fn code_fences(src: &str) -> Vec<CodeFence<'_>> {
+    let lines = lines(src);
+    let mut fences = Vec::new();
+    let mut index = match front_matter(src) {
+        Some(found) => lines.iter().take_while(|line| line.start < found.span.end).count(),
+        None => 0,
+    };
+    while index < lines.len() {
+        let open = &lines[index];
+        index += 1;
+        let (marker, length, info) = match fence_open(&src[open.start..open.end]) {
+            Some(fence) => fence,
+            None => continue,
+        };
+        let content_start = open.next;
+        let mut closing = None;
+        while index < lines.len() {
+            let line = &lines[index];
+            index += 1;
+            if is_fence_close(&src[line.start..line.end], marker, length) {
+                closing = Some(line);
+                break;
+            }
+        }
+        let (content_end, end, closed) = match closing {
+            Some(line) => (line.start, line.next, true),
+            None => (src.len(), src.len(), false),
+        };
+        let language = info.split_whitespace()
+            .next()
+            .map(|word| word.trim_start_matches('{').trim_end_matches('}').trim_start_matches('.'))
+            .filter(|word| !word.is_empty());
+        let content_span = Span::new(content_start, content_end);
+        fences.push(CodeFence {
+            info,
+            language,
+            span: Span::new(open.start, end),
+            content_span,
+            content: &src[content_span.range()],
+            closed,
+        });
+    }
+    return fences;
+}
+
+pub fn code_fences_with_language<'a>(src: &'a str, language: &str) -> Vec<CodeFence<'a>> {
+    return code_fences(src).into_iter().filter(|fence| fence.language == Some(language)).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "---\ntitle: Post\n---\n# Heading\n\n```rust ignore\nfn main() {}\n```\n\n~~~~\nplain\n```\nstill plain\n~~~~\n";
+
+    #[test]
+    fn test_front_matter() {
+        let found = front_matter(DOC).unwrap();
+        assert_eq!(found.kind, FrontMatterKind::Yaml);
+        assert_eq!(found.content, "title: Post\n");
+        assert_eq!(found.span, Span::new(0, 20));
+        assert!(body(DOC).starts_with("# Heading"));
+        let toml = front_matter("+++\na = 1\n+++\n").unwrap();
+        assert_eq!(toml.kind, FrontMatterKind::Toml);
+        assert!(front_matter("---\nnever closed\n").is_none());
+    }
+
+    #[test]
+    fn test_code_fences() {
+        let fences = code_fences(DOC);
+        assert_eq!(fences.len(), 2);
+        assert_eq!(fences[0].language, Some("rust"));
+        assert_eq!(fences[0].info, "rust ignore");
+        assert_eq!(fences[0].content, "fn main() {}\n");
+        assert_eq!(&DOC[fences[0].span.range()], "```rust ignore\nfn main() {}\n```\n");
+        assert_eq!(fences[1].language, None);
+        assert_eq!(fences[1].content, "plain\n```\nstill plain\n");
+        assert_eq!(code_fences_with_language(DOC, "rust").len(), 1);
+    }
+
+    #[test]
+    fn test_unclosed_fence() {
+        let fences = code_fences("text\n```python\nprint(1)\n");
+        assert_eq!(fences.len(), 1);
+        assert!(!fences[0].closed);
+        assert_eq!(fences[0].content, "print(1)\n");
+    }
+}
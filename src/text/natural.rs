@@ -0,0 +1,155 @@
+use std::cmp::Ordering;
+
+enum Chunk<'a> {
+    Digits(&'a str),
+    Text(&'a str),
+}
+
+fn chunks(src: &str) -> impl Iterator<Item = Chunk<'_>> {
+    let mut rest = src;
+    return std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let digits = first.is_ascii_digit();
+        let end = rest.find(|character: char| character.is_ascii_digit() != digits).unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        return Some(if digits { Chunk::Digits(chunk) } else { Chunk::Text(chunk) });
+    });
+}
+
+fn compare_digits(a: &str, b: &str) -> Ordering {
+    let a_trimmed = a.trim_start_matches('0');
+    let b_trimmed = b.trim_start_matches('0');
+    return a_trimmed.len().cmp(&b_trimmed.len())
+        .then_with(|| a_trimmed.cmp(b_trimmed));
+}
+
+fn compare_text(a: &str, b: &str) -> Ordering {
+    return a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase));
+}
+
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chunks = chunks(a);
+    let mut b_chunks = chunks(b);
+    loop {
+        let ordering = match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => break,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(Chunk::Digits(x)), Some(Chunk::Digits(y))) => compare_digits(x, y),
+            (Some(Chunk::Text(x)), Some(Chunk::Text(y))) => compare_text(x, y),
+            (Some(Chunk::Digits(_)), Some(Chunk::Text(_))) => Ordering::Less,
+            (Some(Chunk::Text(_)), Some(Chunk::Digits(_))) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    // equal under natural rules ("a01" vs "a1", "A" vs "a"): fall back to plain order so sorting stays total
+    return a.cmp(b);
+}
+
+pub fn natural_sort<S>(items: &mut [S])
+where S: AsRef<str> {
+    items.sort_by(|a, b| natural_cmp(a.as_ref(), b.as_ref()));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VersionSort;
+
+struct Version<'a> {
+    release: Vec<&'a str>,
+    pre_release: Option<&'a str>,
+}
+
+fn parse_version(src: &str) -> Version<'_> {
+    let src = src.strip_prefix(['v', 'V']).filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit())).unwrap_or(src);
+    let without_build = src.split('+').next().unwrap_or(src);
+    let (release, pre_release) = match without_build.split_once('-') {
+        Some((release, pre_release)) => (release, Some(pre_release)),
+        None => (without_build, None),
+    };
+    return Version { release: release.split('.').collect(), pre_release };
+}
+
+fn compare_identifier(a: &str, b: &str) -> Ordering {
+    let a_numeric = !a.is_empty() && a.bytes().all(|byte| byte.is_ascii_digit());
+    let b_numeric = !b.is_empty() && b.bytes().all(|byte| byte.is_ascii_digit());
+    return match (a_numeric, b_numeric) {
+        (true, true) => compare_digits(a, b),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => natural_cmp(a, b),
+    };
+}
+
+impl VersionSort {
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let a_version = parse_version(a);
+        let b_version = parse_version(b);
+        let components = a_version.release.len().max(b_version.release.len());
+        for index in 0..components {
+            let x = a_version.release.get(index).copied().unwrap_or("0");
+            let y = b_version.release.get(index).copied().unwrap_or("0");
+            let ordering = compare_identifier(x, y);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        let pre_release = match (a_version.pre_release, b_version.pre_release) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(x), Some(y)) => {
+                let mut x_parts = x.split('.');
+                let mut y_parts = y.split('.');
+                loop {
+                    let ordering = match (x_parts.next(), y_parts.next()) {
+                        (None, None) => break Ordering::Equal,
+                        (None, Some(_)) => break Ordering::Less,
+                        (Some(_), None) => break Ordering::Greater,
+                        (Some(x_part), Some(y_part)) => compare_identifier(x_part, y_part),
+                    };
+                    if ordering != Ordering::Equal {
+                        break ordering;
+                    }
+                }
+            }
+        };
+        return pre_release.then_with(|| natural_cmp(a, b));
+    }
+
+    pub fn sort<S>(&self, items: &mut [S])
+    where S: AsRef<str> {
+        items.sort_by(|a, b| self.compare(a.as_ref(), b.as_ref()));
+    }
+}
+
+pub fn version_sort<S>(items: &mut [S])
+where S: AsRef<str> {
+    VersionSort.sort(items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_order() {
+        let mut files = vec!["file10.txt", "file2.txt", "File1.txt", "file02.txt", "file1b.txt", "file"];
+        natural_sort(&mut files);
+        assert_eq!(files, vec!["file", "File1.txt", "file1b.txt", "file02.txt", "file2.txt", "file10.txt"]);
+        assert_eq!(natural_cmp("x99999999999999999999999", "x100000000000000000000000"), Ordering::Less);
+        let mut owned: Vec<String> = vec![String::from("b3"), String::from("b21")];
+        natural_sort(&mut owned);
+        assert_eq!(owned, vec!["b3", "b21"]);
+    }
+
+    #[test]
+    fn test_version_order() {
+        let mut tags = vec!["v1.10.0", "1.2.0", "v1.2.0-rc.2", "1.2.0-rc.10", "1.2.0-alpha", "1.2", "0.9.9+build.5"];
+        version_sort(&mut tags);
+        assert_eq!(tags, vec!["0.9.9+build.5", "1.2.0-alpha", "v1.2.0-rc.2", "1.2.0-rc.10", "1.2", "1.2.0", "v1.10.0"]);
+        assert_eq!(VersionSort.compare("2.0.0", "10.0.0"), Ordering::Less);
+    }
+}
@@ -0,0 +1,63 @@
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current = vec![0; b_chars.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    return previous[b_chars.len()];
+}
+
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    return 1.0 - levenshtein(a, b) as f64 / longest as f64;
+}
+
+pub fn closest<'c, I, S>(input: &str, candidates: I, max_results: usize) -> Vec<&'c str>
+where I: IntoIterator<Item = &'c S>, S: AsRef<str> + ?Sized + 'c {
+    let input_lower = input.to_lowercase();
+    let threshold = (input.chars().count() / 3).max(1);
+    let mut scored: Vec<(usize, &'c str)> = Vec::new();
+    for candidate in candidates {
+        let candidate = candidate.as_ref();
+        if candidate == input {
+            continue;
+        }
+        let distance = levenshtein(&input_lower, &candidate.to_lowercase());
+        if distance <= threshold {
+            scored.push((distance, candidate));
+        }
+    }
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+    scored.dedup_by(|a, b| a.1 == b.1);
+    return scored.into_iter().take(max_results).map(|(_, candidate)| candidate).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("héllo", "hello"), 1);
+        assert!((similarity("abcd", "abce") - 0.75).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_closest() {
+        let candidates = ["verbose", "version", "verify", "quiet"];
+        assert_eq!(closest("verison", &candidates, 3), vec!["version"]);
+        assert_eq!(closest("verfy", &candidates, 3), vec!["verify"]);
+        assert!(closest("xyz", &candidates, 3).is_empty());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod debounce;
+pub mod duration;
+pub mod timestamp;
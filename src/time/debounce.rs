@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+use std::time::Instant;
+
+// collapses bursts of events per key: a key becomes ready once `delay` has passed since it was
+// last touched, so a file saved five times in a row is handled once
+#[derive(Debug, Clone)]
+pub struct Debouncer<K> {
+    delay: Duration,
+    pending: HashMap<K, Instant>,
+}
+
+impl<K> Debouncer<K>
+where K: Eq + Hash + Clone {
+    pub fn new(delay: Duration) -> Debouncer<K> {
+        return Debouncer { delay, pending: HashMap::new() };
+    }
+
+    pub fn delay(&self) -> Duration {
+        return self.delay;
+    }
+
+    pub fn touch(&mut self, key: K, now: Instant) {
+        self.pending.insert(key, now);
+    }
+
+    pub fn cancel(&mut self, key: &K) -> bool {
+        return self.pending.remove(key).is_some();
+    }
+
+    pub fn is_pending(&self, key: &K) -> bool {
+        return self.pending.contains_key(key);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.pending.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.pending.is_empty();
+    }
+
+    // removes and returns the keys that have been quiet for the whole delay
+    pub fn ready(&mut self, now: Instant) -> Vec<K> {
+        let ready: Vec<K> = self.pending.iter()
+            .filter(|(_, touched)| now.saturating_duration_since(**touched) >= self.delay)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &ready {
+            self.pending.remove(key);
+        }
+        return ready;
+    }
+
+    // when the next key would become ready, if any are pending
+    pub fn next_deadline(&self) -> Option<Instant> {
+        return self.pending.values().min().map(|touched| *touched + self.delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bursts_collapse() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut debouncer = Debouncer::new(Duration::from_millis(100));
+        debouncer.touch("a.log", at(0));
+        debouncer.touch("b.log", at(10));
+        debouncer.touch("a.log", at(50));
+        assert_eq!(debouncer.next_deadline(), Some(at(110)));
+        assert!(debouncer.ready(at(120)).contains(&"b.log"));
+        assert!(debouncer.ready(at(140)).is_empty());
+        assert_eq!(debouncer.ready(at(150)), vec!["a.log"]);
+        assert!(debouncer.is_empty());
+        debouncer.touch("c.log", at(200));
+        assert!(debouncer.cancel(&"c.log"));
+        assert_eq!(debouncer.next_deadline(), None);
+    }
+}
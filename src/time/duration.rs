@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::parse_error::ParseError;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+#[derive(Debug, Clone)]
+pub struct UnitTable {
+    units: Vec<(String, u128)>,
+}
+
+impl UnitTable {
+    pub fn new() -> UnitTable {
+        return UnitTable { units: Vec::new() };
+    }
+
+    pub fn standard() -> UnitTable {
+        return UnitTable::new()
+            .with_unit_names(&["ns", "nsec", "nanosecond", "nanoseconds"], Duration::from_nanos(1))
+            .with_unit_names(&["us", "µs", "usec", "microsecond", "microseconds"], Duration::from_micros(1))
+            .with_unit_names(&["ms", "msec", "millisecond", "milliseconds"], Duration::from_millis(1))
+            .with_unit_names(&["s", "sec", "secs", "second", "seconds"], Duration::from_secs(1))
+            .with_unit_names(&["m", "min", "mins", "minute", "minutes"], Duration::from_secs(60))
+            .with_unit_names(&["h", "hr", "hrs", "hour", "hours"], Duration::from_secs(3600))
+            .with_unit_names(&["d", "day", "days"], Duration::from_secs(86_400))
+            .with_unit_names(&["w", "week", "weeks"], Duration::from_secs(604_800));
+    }
+
+    pub fn with_unit<N>(mut self, name: N, length: Duration) -> UnitTable
+    where N: Into<String> {
+        let name = name.into();
+        self.units.retain(|(existing, _)| *existing != name);
+        self.units.push((name, length.as_nanos()));
+        return self;
+    }
+
+    pub fn with_unit_names(mut self, names: &[&str], length: Duration) -> UnitTable {
+        for name in names {
+            self = self.with_unit(*name, length);
+        }
+        return self;
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Duration> {
+        return self.lookup_nanos(name).map(nanos_to_duration);
+    }
+
+    fn lookup_nanos(&self, name: &str) -> Option<u128> {
+        return self.units.iter().find(|(unit, _)| unit == name).map(|(_, nanos)| *nanos);
+    }
+
+    pub fn parse(&self, src: &str) -> Result<Duration, ErrorChain> {
+        return self.parse_nanos(src)
+            .do_on_error(|| format!("failed to parse duration '{}'", src))
+            .map(nanos_to_duration);
+    }
+
+    fn parse_nanos(&self, src: &str) -> Result<u128, ParseError> {
+        let bytes = src.as_bytes();
+        let mut pos = skip_spaces(bytes, 0);
+        if pos == bytes.len() {
+            return Err(ParseError::at(pos, "expected a duration"));
+        }
+        let mut total: u128 = 0;
+        while pos < bytes.len() {
+            let number_start = pos;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            let whole_end = pos;
+            let mut fraction_end = pos;
+            if pos < bytes.len() && bytes[pos] == b'.' {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                fraction_end = pos;
+            }
+            if whole_end == number_start && fraction_end <= whole_end + 1 {
+                return Err(ParseError::at(number_start, "expected a number"));
+            }
+            let unit_start = skip_spaces(bytes, pos);
+            pos = unit_start;
+            while pos < bytes.len() && !bytes[pos].is_ascii_digit() && bytes[pos] != b'.' && bytes[pos] != b' ' {
+                pos += 1;
+            }
+            if pos == unit_start {
+                return Err(ParseError::at(unit_start, "expected a unit after number"));
+            }
+            let unit_name = &src[unit_start..pos];
+            let unit = match self.lookup_nanos(unit_name) {
+                Some(unit) => unit,
+                None => return Err(ParseError::new(unit_start..pos, format!("unknown duration unit '{}'", unit_name))),
+            };
+            let overflow = || ParseError::new(number_start..pos, "duration is too large");
+            let whole: u128 = if whole_end > number_start {
+                src[number_start..whole_end].parse().map_err(|_| overflow())?
+            } else {
+                0
+            };
+            let mut component = whole.checked_mul(unit).ok_or_else(overflow)?;
+            if fraction_end > whole_end + 1 {
+                let digits = &src[whole_end + 1..fraction_end];
+                let mut scale: u128 = 1;
+                let mut fraction: u128 = 0;
+                for digit in digits.bytes().take(18) {
+                    fraction = fraction * 10 + (digit - b'0') as u128;
+                    scale *= 10;
+                }
+                let scaled = fraction.checked_mul(unit).ok_or_else(overflow)? / scale;
+                component = component.checked_add(scaled).ok_or_else(overflow)?;
+            }
+            total = total.checked_add(component).ok_or_else(overflow)?;
+            pos = skip_spaces(bytes, pos);
+        }
+        if total / NANOS_PER_SECOND > u64::MAX as u128 {
+            return Err(ParseError::new(0..src.len(), "duration is too large"));
+        }
+        return Ok(total);
+    }
+}
+
+impl Default for UnitTable {
+    fn default() -> UnitTable {
+        return UnitTable::standard();
+    }
+}
+
+pub fn parse_duration(src: &str) -> Result<Duration, ErrorChain> {
+    return UnitTable::standard().parse(src);
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    const PARTS: [(&str, u128); 7] = [
+        ("d", 86_400 * NANOS_PER_SECOND),
+        ("h", 3600 * NANOS_PER_SECOND),
+        ("m", 60 * NANOS_PER_SECOND),
+        ("s", NANOS_PER_SECOND),
+        ("ms", 1_000_000),
+        ("us", 1_000),
+        ("ns", 1),
+    ];
+    let mut remaining = duration.as_nanos();
+    if remaining == 0 {
+        return String::from("0s");
+    }
+    let mut out = String::new();
+    for (name, size) in PARTS {
+        if remaining >= size {
+            out.push_str(&format!("{}{}", remaining / size, name));
+            remaining %= size;
+        }
+    }
+    return out;
+}
+
+fn nanos_to_duration(nanos: u128) -> Duration {
+    return Duration::new((nanos / NANOS_PER_SECOND) as u64, (nanos % NANOS_PER_SECOND) as u32);
+}
+
+fn skip_spaces(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && bytes[pos] == b' ' {
+        pos += 1;
+    }
+    return pos;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::from_secs(172_800));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration(" 1 hour 5 minutes ").unwrap(), Duration::from_secs(3900));
+    }
+
+    #[test]
+    fn test_parse_duration_errors() {
+        let table = UnitTable::standard();
+        assert_eq!(table.parse_nanos("10 fortnights").unwrap_err().span.range(), 3..13);
+        assert_eq!(table.parse_nanos("10").unwrap_err().span.start, 2);
+        assert_eq!(table.parse_nanos("h").unwrap_err().span.start, 0);
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_custom_units() {
+        let table = UnitTable::new().with_unit("tick", Duration::from_millis(50));
+        assert_eq!(table.parse("3tick").unwrap(), Duration::from_millis(150));
+        assert!(table.parse("3s").is_err());
+        let huge = UnitTable::new().with_unit("eon", Duration::MAX);
+        let error = huge.parse("1.999999999999999999eon").unwrap_err();
+        assert!(error.to_string().contains("duration is too large"), "{}", error);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_duration(Duration::from_millis(1250)), "1s250ms");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        let duration = Duration::new(93_784, 5_006_007);
+        assert_eq!(parse_duration(&format_duration(duration)).unwrap(), duration);
+    }
+}
@@ -0,0 +1,489 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::types::error_chain::ErrorChain;
+use crate::types::error_chain::ErrorPropogation;
+use crate::types::parse_error::ParseError;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const MONTH_NAMES: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timestamp {
+    seconds: i64,
+    nanos: u32,
+    offset: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    pub fn from_unix(seconds: i64, nanos: u32) -> Timestamp {
+        let seconds = seconds + (nanos / 1_000_000_000) as i64;
+        return Timestamp { seconds, nanos: nanos % 1_000_000_000, offset: 0 };
+    }
+
+    pub fn now() -> Timestamp {
+        return Timestamp::from_system_time(SystemTime::now());
+    }
+
+    pub fn from_system_time(time: SystemTime) -> Timestamp {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => return Timestamp::from_unix(since.as_secs() as i64, since.subsec_nanos()),
+            Err(error) => {
+                let before = error.duration();
+                let mut seconds = -(before.as_secs() as i64);
+                let mut nanos = before.subsec_nanos();
+                if nanos > 0 {
+                    seconds -= 1;
+                    nanos = 1_000_000_000 - nanos;
+                }
+                return Timestamp { seconds, nanos, offset: 0 };
+            }
+        }
+    }
+
+    pub fn to_system_time(&self) -> SystemTime {
+        if self.seconds >= 0 {
+            return UNIX_EPOCH + Duration::new(self.seconds as u64, self.nanos);
+        }
+        return UNIX_EPOCH - Duration::from_secs(self.seconds.unsigned_abs()) + Duration::from_nanos(self.nanos as u64);
+    }
+
+    pub fn from_date_time(date_time: DateTime, offset_seconds: i32) -> Timestamp {
+        let days = days_from_civil(date_time.year, date_time.month, date_time.day);
+        let local = days * SECONDS_PER_DAY
+            + date_time.hour as i64 * 3600
+            + date_time.minute as i64 * 60
+            + date_time.second as i64;
+        return Timestamp { seconds: local - offset_seconds as i64, nanos: date_time.nanos, offset: offset_seconds };
+    }
+
+    pub fn unix_seconds(&self) -> i64 {
+        return self.seconds;
+    }
+
+    pub fn subsec_nanos(&self) -> u32 {
+        return self.nanos;
+    }
+
+    pub fn offset_seconds(&self) -> i32 {
+        return self.offset;
+    }
+
+    pub fn with_offset(&self, offset_seconds: i32) -> Timestamp {
+        return Timestamp { seconds: self.seconds, nanos: self.nanos, offset: offset_seconds };
+    }
+
+    pub fn to_utc(&self) -> Timestamp {
+        return self.with_offset(0);
+    }
+
+    pub fn date_time(&self) -> DateTime {
+        let local = self.seconds + self.offset as i64;
+        let days = local.div_euclid(SECONDS_PER_DAY);
+        let of_day = local.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        return DateTime {
+            year,
+            month,
+            day,
+            hour: (of_day / 3600) as u32,
+            minute: (of_day % 3600 / 60) as u32,
+            second: (of_day % 60) as u32,
+            nanos: self.nanos,
+        };
+    }
+
+    pub fn parse(src: &str) -> Result<Timestamp, ErrorChain> {
+        if let Ok(timestamp) = parse_rfc3339(src) {
+            return Ok(timestamp);
+        }
+        if let Ok(timestamp) = parse_clf(src) {
+            return Ok(timestamp);
+        }
+        let year = Timestamp::now().date_time().year;
+        return parse_syslog(src, year)
+            .do_on_error(|| format!("'{}' is not an RFC 3339, CLF, or syslog timestamp", src));
+    }
+
+    pub fn parse_rfc3339(src: &str) -> Result<Timestamp, ErrorChain> {
+        return parse_rfc3339(src).on_error("failed to parse RFC 3339 timestamp");
+    }
+
+    pub fn parse_syslog(src: &str, year: i64) -> Result<Timestamp, ErrorChain> {
+        return parse_syslog(src, year).on_error("failed to parse syslog timestamp");
+    }
+
+    pub fn parse_clf(src: &str) -> Result<Timestamp, ErrorChain> {
+        return parse_clf(src).on_error("failed to parse common log format timestamp");
+    }
+
+    pub fn format_rfc3339(&self) -> String {
+        let dt = self.date_time();
+        let mut out = format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second);
+        if dt.nanos != 0 {
+            if dt.nanos.is_multiple_of(1_000_000) {
+                out.push_str(&format!(".{:03}", dt.nanos / 1_000_000));
+            } else if dt.nanos.is_multiple_of(1_000) {
+                out.push_str(&format!(".{:06}", dt.nanos / 1_000));
+            } else {
+                out.push_str(&format!(".{:09}", dt.nanos));
+            }
+        }
+        if self.offset == 0 {
+            out.push('Z');
+        } else {
+            let (sign, hours, minutes) = split_offset(self.offset);
+            out.push_str(&format!("{}{:02}:{:02}", sign, hours, minutes));
+        }
+        return out;
+    }
+
+    pub fn format_syslog(&self) -> String {
+        let dt = self.date_time();
+        return format!("{} {:>2} {:02}:{:02}:{:02}", MONTH_NAMES[dt.month as usize - 1], dt.day, dt.hour, dt.minute, dt.second);
+    }
+
+    pub fn format_clf(&self) -> String {
+        let dt = self.date_time();
+        let (sign, hours, minutes) = split_offset(self.offset);
+        return format!(
+            "{:02}/{}/{:04}:{:02}:{:02}:{:02} {}{:02}{:02}",
+            dt.day, MONTH_NAMES[dt.month as usize - 1], dt.year, dt.hour, dt.minute, dt.second, sign, hours, minutes
+        );
+    }
+}
+
+impl PartialEq for Timestamp {
+    fn eq(&self, other: &Self) -> bool {
+        return self.seconds == other.seconds && self.nanos == other.nanos;
+    }
+}
+
+impl Eq for Timestamp {}
+
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        return (self.seconds, self.nanos).cmp(&(other.seconds, other.nanos));
+    }
+}
+
+impl Hash for Timestamp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.seconds.hash(state);
+        self.nanos.hash(state);
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Timestamp {
+        return Timestamp::from_system_time(time);
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> SystemTime {
+        return timestamp.to_system_time();
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.format_rfc3339());
+    }
+}
+
+fn split_offset(offset: i32) -> (char, i32, i32) {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let abs = offset.abs() / 60;
+    return (sign, abs / 60, abs % 60);
+}
+
+fn is_leap_year(year: i64) -> bool {
+    return (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => return 29,
+        2 => return 28,
+        4 | 6 | 9 | 11 => return 30,
+        _ => return 31,
+    }
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    return era * 146_097 + day_of_era - 719_468;
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, day);
+}
+
+struct Scanner<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(src: &'a str) -> Scanner<'a> {
+        return Scanner { src: src.as_bytes(), pos: 0 };
+    }
+
+    fn peek(&self) -> Option<u8> {
+        return self.src.get(self.pos).copied();
+    }
+
+    fn literal(&mut self, expected: u8) -> Result<(), ParseError> {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            return Ok(());
+        }
+        return Err(ParseError::at(self.pos, format!("expected '{}'", expected as char)));
+    }
+
+    fn number(&mut self, digits: usize, what: &str) -> Result<u32, ParseError> {
+        let start = self.pos;
+        let mut value: u32 = 0;
+        for _ in 0..digits {
+            match self.peek() {
+                Some(byte) if byte.is_ascii_digit() => {
+                    value = value * 10 + (byte - b'0') as u32;
+                    self.pos += 1;
+                }
+                _ => return Err(ParseError::new(start..self.pos, format!("expected {}-digit {}", digits, what))),
+            }
+        }
+        return Ok(value);
+    }
+
+    fn ranged(&mut self, digits: usize, what: &str, min: u32, max: u32) -> Result<u32, ParseError> {
+        let start = self.pos;
+        let value = self.number(digits, what)?;
+        if value < min || value > max {
+            return Err(ParseError::new(start..self.pos, format!("{} {} out of range {}-{}", what, value, min, max)));
+        }
+        return Ok(value);
+    }
+
+    fn day(&mut self, year: i64, month: u32, padded: bool) -> Result<u32, ParseError> {
+        let start = self.pos;
+        if padded && self.peek() == Some(b' ') {
+            self.pos += 1;
+            return self.ranged(1, "day", 1, 9);
+        }
+        let day = self.number(2, "day")?;
+        if day < 1 || day > days_in_month(year, month) {
+            return Err(ParseError::new(start..self.pos, format!("day {} out of range for month {}", day, month)));
+        }
+        return Ok(day);
+    }
+
+    fn month_name(&mut self) -> Result<u32, ParseError> {
+        let end = (self.pos + 3).min(self.src.len());
+        let name = &self.src[self.pos..end];
+        for (index, candidate) in MONTH_NAMES.iter().enumerate() {
+            if name.eq_ignore_ascii_case(candidate.as_bytes()) {
+                self.pos = end;
+                return Ok(index as u32 + 1);
+            }
+        }
+        return Err(ParseError::new(self.pos..end, "expected three-letter month name"));
+    }
+
+    fn fraction(&mut self) -> Result<u32, ParseError> {
+        let start = self.pos;
+        let mut nanos: u32 = 0;
+        let mut scale: u32 = 100_000_000;
+        while let Some(byte) = self.peek().filter(u8::is_ascii_digit) {
+            nanos += (byte - b'0') as u32 * scale;
+            scale /= 10;
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(ParseError::at(start, "expected fractional seconds"));
+        }
+        return Ok(nanos);
+    }
+
+    fn offset(&mut self, colon: bool) -> Result<i32, ParseError> {
+        let sign = match self.peek() {
+            Some(b'Z') | Some(b'z') if colon => {
+                self.pos += 1;
+                return Ok(0);
+            }
+            Some(b'+') => 1,
+            Some(b'-') => -1,
+            _ => return Err(ParseError::at(self.pos, "expected UTC offset")),
+        };
+        self.pos += 1;
+        let hours = self.ranged(2, "offset hour", 0, 23)? as i32;
+        if colon {
+            self.literal(b':')?;
+        }
+        let minutes = self.ranged(2, "offset minute", 0, 59)? as i32;
+        return Ok(sign * (hours * 3600 + minutes * 60));
+    }
+
+    fn time(&mut self) -> Result<(u32, u32, u32), ParseError> {
+        let hour = self.ranged(2, "hour", 0, 23)?;
+        self.literal(b':')?;
+        let minute = self.ranged(2, "minute", 0, 59)?;
+        self.literal(b':')?;
+        let second = self.ranged(2, "second", 0, 60)?;
+        return Ok((hour, minute, second));
+    }
+
+    fn finish(&self) -> Result<(), ParseError> {
+        if self.pos < self.src.len() {
+            return Err(ParseError::new(self.pos..self.src.len(), "unexpected trailing input"));
+        }
+        return Ok(());
+    }
+}
+
+fn parse_rfc3339(src: &str) -> Result<Timestamp, ParseError> {
+    let mut scanner = Scanner::new(src);
+    let year = scanner.number(4, "year")? as i64;
+    scanner.literal(b'-')?;
+    let month = scanner.ranged(2, "month", 1, 12)?;
+    scanner.literal(b'-')?;
+    let day = scanner.day(year, month, false)?;
+    match scanner.peek() {
+        Some(b'T') | Some(b't') | Some(b' ') => scanner.pos += 1,
+        _ => return Err(ParseError::at(scanner.pos, "expected 'T' between date and time")),
+    }
+    let (hour, minute, second) = scanner.time()?;
+    let mut nanos = 0;
+    if scanner.peek() == Some(b'.') {
+        scanner.pos += 1;
+        nanos = scanner.fraction()?;
+    }
+    let offset = scanner.offset(true)?;
+    scanner.finish()?;
+    let date_time = DateTime { year, month, day, hour, minute, second, nanos };
+    return Ok(Timestamp::from_date_time(date_time, offset));
+}
+
+fn parse_syslog(src: &str, year: i64) -> Result<Timestamp, ParseError> {
+    let mut scanner = Scanner::new(src);
+    let month = scanner.month_name()?;
+    scanner.literal(b' ')?;
+    let day = scanner.day(year, month, true)?;
+    scanner.literal(b' ')?;
+    let (hour, minute, second) = scanner.time()?;
+    scanner.finish()?;
+    let date_time = DateTime { year, month, day, hour, minute, second, nanos: 0 };
+    return Ok(Timestamp::from_date_time(date_time, 0));
+}
+
+fn parse_clf(src: &str) -> Result<Timestamp, ParseError> {
+    let mut scanner = Scanner::new(src);
+    let bracketed = scanner.peek() == Some(b'[');
+    if bracketed {
+        scanner.pos += 1;
+    }
+    let day_start = scanner.pos;
+    let day = scanner.ranged(2, "day", 1, 31)?;
+    scanner.literal(b'/')?;
+    let month = scanner.month_name()?;
+    scanner.literal(b'/')?;
+    let year = scanner.number(4, "year")? as i64;
+    if day > days_in_month(year, month) {
+        return Err(ParseError::new(day_start..day_start + 2, format!("day {} out of range for month {}", day, month)));
+    }
+    scanner.literal(b':')?;
+    let (hour, minute, second) = scanner.time()?;
+    scanner.literal(b' ')?;
+    let offset = scanner.offset(false)?;
+    if bracketed {
+        scanner.literal(b']')?;
+    }
+    scanner.finish()?;
+    let date_time = DateTime { year, month, day, hour, minute, second, nanos: 0 };
+    return Ok(Timestamp::from_date_time(date_time, offset));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rfc3339() {
+        let ts = Timestamp::parse_rfc3339("2023-03-27T14:05:09.250+02:00").unwrap();
+        assert_eq!(ts.unix_seconds(), 1679918709);
+        assert_eq!(ts.subsec_nanos(), 250_000_000);
+        assert_eq!(ts.offset_seconds(), 7200);
+        assert_eq!(ts.format_rfc3339(), "2023-03-27T14:05:09.250+02:00");
+        assert_eq!(ts.to_utc().to_string(), "2023-03-27T12:05:09.250Z");
+    }
+
+    #[test]
+    fn test_parse_rfc3339_error_offset() {
+        let error = parse_rfc3339("2023-02-30T00:00:00Z").unwrap_err();
+        assert_eq!(error.span.start, 8);
+        let error = parse_rfc3339("2023-03-27T25:00:00Z").unwrap_err();
+        assert_eq!(error.span.start, 11);
+        assert!(Timestamp::parse_rfc3339("2023-03-27T12:00:00Z junk").is_err());
+    }
+
+    #[test]
+    fn test_parse_clf() {
+        let ts = Timestamp::parse_clf("[10/Oct/2000:13:55:36 -0700]").unwrap();
+        assert_eq!(ts.unix_seconds(), 971211336);
+        assert_eq!(ts.format_clf(), "10/Oct/2000:13:55:36 -0700");
+    }
+
+    #[test]
+    fn test_parse_syslog() {
+        let ts = Timestamp::parse_syslog("Feb  5 07:08:09", 2024).unwrap();
+        assert_eq!(ts.format_rfc3339(), "2024-02-05T07:08:09Z");
+        assert_eq!(ts.format_syslog(), "Feb  5 07:08:09");
+    }
+
+    #[test]
+    fn test_system_time_round_trip() {
+        let ts = Timestamp::from_unix(-1, 500);
+        assert_eq!(Timestamp::from_system_time(ts.to_system_time()), ts);
+        assert_eq!(ts.format_rfc3339(), "1969-12-31T23:59:59.000000500Z");
+        let now = SystemTime::now();
+        assert_eq!(SystemTime::from(Timestamp::from(now)), now);
+    }
+}
@@ -1 +1,13 @@
-pub mod error_chain;
\ No newline at end of file
+pub mod error_accumulator;
+pub mod error_chain;
+pub mod error_scope;
+pub mod error_throttle;
+pub mod inline_string;
+pub mod inline_vec;
+pub mod match_id;
+pub mod message_key;
+pub mod options;
+pub mod parse_error;
+pub mod scope_guard;
+pub mod span;
+pub mod state_machine;
@@ -0,0 +1,109 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+use super::error_chain::ErrorChain;
+
+#[derive(Debug, Default)]
+pub struct ErrorList {
+    pub errors: Vec<ErrorChain>,
+}
+
+impl Display for ErrorList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, error) in self.errors.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "[{}] {}", index + 1, error)?;
+        }
+        return Ok(());
+    }
+}
+
+impl Error for ErrorList {}
+
+#[derive(Debug, Default)]
+pub struct ErrorAccumulator {
+    errors: Vec<ErrorChain>,
+}
+
+impl ErrorAccumulator {
+    pub fn new() -> ErrorAccumulator {
+        return ErrorAccumulator { errors: Vec::new() };
+    }
+
+    pub fn push(&mut self, error: ErrorChain) {
+        self.errors.push(error);
+    }
+
+    pub fn capture<T>(&mut self, result: Result<T, ErrorChain>) -> Option<T> {
+        match result {
+            Ok(value) => return Some(value),
+            Err(error) => {
+                self.errors.push(error);
+                return None;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.errors.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.errors.is_empty();
+    }
+
+    pub fn errors(&self) -> &[ErrorChain] {
+        return &self.errors;
+    }
+
+    pub fn into_errors(self) -> Vec<ErrorChain> {
+        return self.errors;
+    }
+
+    pub fn into_result<C>(self, context: C) -> Result<(), ErrorChain>
+    where C: Display + Send + Sync + 'static {
+        return match aggregate(self.errors, context) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        };
+    }
+}
+
+impl Extend<ErrorChain> for ErrorAccumulator {
+    fn extend<I: IntoIterator<Item = ErrorChain>>(&mut self, iter: I) {
+        self.errors.extend(iter);
+    }
+}
+
+pub fn aggregate<C>(mut errors: Vec<ErrorChain>, context: C) -> Option<ErrorChain>
+where C: Display + Send + Sync + 'static {
+    match errors.len() {
+        0 => return None,
+        1 => return Some(ErrorChain::from(errors.remove(0), context)),
+        count => return Some(ErrorChain::from(ErrorList { errors }, format!("{} ({} errors)", context, count))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulate_and_aggregate() {
+        let mut errors = ErrorAccumulator::new();
+        assert_eq!(errors.capture(Ok::<i32, ErrorChain>(1)), Some(1));
+        assert!(errors.into_result("batch failed").is_ok());
+
+        let mut errors = ErrorAccumulator::new();
+        errors.capture::<()>(Err(ErrorChain::new("first")));
+        errors.push(ErrorChain::new("second"));
+        assert_eq!(errors.len(), 2);
+        let message = errors.into_result("batch failed").unwrap_err().to_string();
+        assert_eq!(message, "batch failed (2 errors)\n\\ \\ \\\n[1] first\n[2] second");
+        let single = aggregate(vec![ErrorChain::new("only")], "batch failed").unwrap();
+        assert_eq!(single.to_string(), "batch failed\n\\ \\ \\\nonly");
+    }
+}
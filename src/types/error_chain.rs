@@ -1,9 +1,18 @@
+use std::cell::Cell;
 use std::error::Error;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Debug;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use core::convert::Infallible;
 
+use super::error_scope::ErrorScope;
+use crate::metrics;
+use crate::text::similarity;
+
 pub trait ErrorPropogation<T, E> {
     fn on_error<C>(self, context: C) -> Result<T, ErrorChain>
     where C: Display + Send + Sync + 'static;
@@ -49,47 +58,377 @@ impl<T> ErrorPropogation<T, Infallible> for Option<T> {
     }
 }
 
+pub type Context = Box<dyn Display + Sync + Send + 'static>;
+pub type Cause = Box<dyn Error + Send + Sync + 'static>;
+
+pub trait ChainAdapters<T> {
+    fn tap_err<F>(self, f: F) -> Result<T, ErrorChain>
+    where F: FnOnce(&ErrorChain);
+
+    fn map_context<C, F>(self, f: F) -> Result<T, ErrorChain>
+    where C: Display + Send + Sync + 'static, F: FnOnce(String) -> C;
+
+    fn split_context(self) -> Result<T, (Context, Option<Cause>)>;
+}
+
+impl<T> ChainAdapters<T> for Result<T, ErrorChain> {
+    fn tap_err<F>(self, f: F) -> Result<T, ErrorChain>
+    where F: FnOnce(&ErrorChain) {
+        if let Err(error) = &self {
+            f(error);
+        }
+        return self;
+    }
+
+    fn map_context<C, F>(self, f: F) -> Result<T, ErrorChain>
+    where C: Display + Send + Sync + 'static, F: FnOnce(String) -> C {
+        return self.map_err(|error| error.map_context(f));
+    }
+
+    fn split_context(self) -> Result<T, (Context, Option<Cause>)> {
+        return self.map_err(ErrorChain::split_context);
+    }
+}
+
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+pub const DEFAULT_MAX_CONTEXT_LEN: usize = 4096;
+
+// limits applied when rendering, so runaway wrapping can't produce megabyte error strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    pub max_depth: usize,
+    pub max_context_len: usize,
+}
+
+impl Default for Truncation {
+    fn default() -> Truncation {
+        return Truncation { max_depth: DEFAULT_MAX_DEPTH, max_context_len: DEFAULT_MAX_CONTEXT_LEN };
+    }
+}
+
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DEPTH);
+static MAX_CONTEXT_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CONTEXT_LEN);
+
+thread_local! {
+    static LOCAL_TRUNCATION: Cell<Option<Truncation>> = const { Cell::new(None) };
+}
+
+pub fn set_truncation(truncation: Truncation) {
+    MAX_DEPTH.store(truncation.max_depth.max(1), Ordering::Relaxed);
+    MAX_CONTEXT_LEN.store(truncation.max_context_len, Ordering::Relaxed);
+}
+
+// the thread-local override if one is active, otherwise the process-wide setting
+pub fn truncation() -> Truncation {
+    return LOCAL_TRUNCATION.with(Cell::get).unwrap_or_else(|| Truncation {
+        max_depth: MAX_DEPTH.load(Ordering::Relaxed),
+        max_context_len: MAX_CONTEXT_LEN.load(Ordering::Relaxed),
+    });
+}
+
+pub fn with_truncation<T, F>(truncation: Truncation, f: F) -> T
+where F: FnOnce() -> T {
+    let previous = LOCAL_TRUNCATION.with(|local| local.replace(Some(Truncation { max_depth: truncation.max_depth.max(1), ..truncation })));
+    let result = f();
+    LOCAL_TRUNCATION.with(|local| local.set(previous));
+    return result;
+}
+
+fn write_truncated(f: &mut fmt::Formatter<'_>, text: &str, max_len: usize) -> fmt::Result {
+    if text.len() <= max_len {
+        return write!(f, "{}", text);
+    }
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    return write!(f, "{}... [{} more bytes truncated]", &text[..end], text.len() - end);
+}
+
 pub struct ErrorChain {
-    context: Box<dyn Display + Sync + Send + 'static>,
-    cause: Option<Box<dyn Error + Send + Sync + 'static>>,
+    context: Context,
+    cause: Option<Cause>,
+    cause_type: Option<&'static str>,
+    scopes: Vec<String>,
+    help: Vec<String>,
+    user_facing: bool,
+    exit_code: Option<i32>,
 }
 
 impl ErrorChain {
     pub fn new<C>(context: C) -> ErrorChain 
     where C: Display + Sync + Send + 'static  {
-        return ErrorChain { context: Box::new(context), cause: None }
+        metrics::counter(metrics::ERRORS_CREATED, 1);
+        return ErrorChain { context: Box::new(context), cause: None, cause_type: None, scopes: ErrorScope::current(), help: Vec::new(), user_facing: false, exit_code: None }
     }
 
     pub fn from<E, C>(error: E, context: C) -> ErrorChain 
     where E: Error + Send + Sync + 'static,
         C: Display + Sync + Send + 'static {
-            return ErrorChain { context: Box::new(context), cause: Some(Box::new(error)) }
+            metrics::counter(metrics::ERRORS_CREATED, 1);
+            return ErrorChain { context: Box::new(context), cause: Some(Box::new(error)), cause_type: Some(std::any::type_name::<E>()), scopes: ErrorScope::current(), help: Vec::new(), user_facing: false, exit_code: None }
+        }
+
+    pub fn with_help<H>(mut self, help: H) -> ErrorChain
+    where H: Into<String> {
+        self.help.push(help.into());
+        return self;
+    }
+
+    pub fn context(&self) -> &(dyn Display + Sync + Send + 'static) {
+        return &*self.context;
+    }
+
+    pub fn context_string(&self) -> String {
+        return self.context.to_string();
+    }
+
+    pub fn cause(&self) -> Option<&(dyn Error + Send + Sync + 'static)> {
+        return self.cause.as_deref();
+    }
+
+    pub fn help(&self) -> &[String] {
+        return &self.help;
+    }
+
+    // marks this link's context as safe to show to end users
+    pub fn user_facing(mut self) -> ErrorChain {
+        self.user_facing = true;
+        return self;
+    }
+
+    pub fn is_user_facing(&self) -> bool {
+        return self.user_facing;
+    }
+
+    pub fn with_exit_code(mut self, code: i32) -> ErrorChain {
+        self.exit_code = Some(code);
+        return self;
+    }
+
+    // the outermost exit code attached anywhere in the chain
+    pub fn exit_code(&self) -> Option<i32> {
+        return self.chain().iter().find_map(|link| link.exit_code);
+    }
+
+    // every link in order, outermost first
+    pub fn links(&self) -> impl Iterator<Item = &ErrorChain> {
+        return self.chain().into_iter();
+    }
+
+    // the user-facing contexts joined outermost first, or the outermost context if none are marked
+    pub fn user_message(&self) -> String {
+        let marked: Vec<String> = self.chain().iter().filter(|link| link.user_facing).map(|link| link.context.to_string()).collect();
+        if marked.is_empty() {
+            return self.context.to_string();
         }
+        return marked.join(": ");
+    }
+
+    // rewrites only the outermost context; cause, help and scope links are kept
+    pub fn map_context<C, F>(self, f: F) -> ErrorChain
+    where C: Display + Send + Sync + 'static, F: FnOnce(String) -> C {
+        let context = f(self.context.to_string());
+        return ErrorChain { context: Box::new(context), ..self };
+    }
+
+    pub fn split_context(self) -> (Context, Option<Cause>) {
+        return (self.context, self.cause);
+    }
+
+    pub fn with_suggestions_from<'c, I, S>(self, input: &str, candidates: I) -> ErrorChain
+    where I: IntoIterator<Item = &'c S>, S: AsRef<str> + ?Sized + 'c {
+        let suggestions = similarity::closest(input, candidates, 3);
+        match suggestions.len() {
+            0 => return self,
+            1 => return self.with_help(format!("did you mean '{}'?", suggestions[0])),
+            _ => {
+                let quoted: Vec<String> = suggestions.iter().map(|suggestion| format!("'{}'", suggestion)).collect();
+                return self.with_help(format!("did you mean one of {}?", quoted.join(", ")));
+            }
+        }
+    }
+}
+
+impl ErrorChain {
+    fn chain(&self) -> Vec<&ErrorChain> {
+        let mut links = vec![self];
+        while let Some(next) = links.last().and_then(|link| link.cause.as_ref()).and_then(|cause| cause.downcast_ref::<ErrorChain>()) {
+            links.push(next);
+        }
+        return links;
+    }
+
+    // number of links including a non-ErrorChain root cause
+    pub fn depth(&self) -> usize {
+        let links = self.chain();
+        let root_cause = links.last().map(|link| link.cause.is_some()).unwrap_or(false);
+        return links.len() + root_cause as usize;
+    }
+
+    // the type of the innermost cause that is not itself an ErrorChain
+    pub fn root_cause_type(&self) -> Option<&'static str> {
+        return self.chain().last().and_then(|link| link.cause_type);
+    }
+
+    // identity ignores help text and the cause's message, which tend to carry volatile data
+    fn identity(&self) -> (Vec<String>, Option<&'static str>) {
+        let contexts = self.chain().iter().map(|link| link.context.to_string()).collect();
+        return (contexts, self.root_cause_type());
+    }
+
+    // FNV-1a over the identity, so the value is the same across runs and processes
+    pub fn fingerprint(&self) -> u64 {
+        let (contexts, root_cause_type) = self.identity();
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let parts = contexts.iter().map(String::as_str).chain(std::iter::once(root_cause_type.unwrap_or("")));
+        for part in parts {
+            for byte in part.bytes().chain(std::iter::once(0x1f)) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        return hash;
+    }
+}
+
+impl PartialEq for ErrorChain {
+    fn eq(&self, other: &ErrorChain) -> bool {
+        return self.identity() == other.identity();
+    }
+}
+
+impl Eq for ErrorChain {}
+
+impl Hash for ErrorChain {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.identity().hash(state);
+    }
+}
+
+impl ErrorChain {
+    // scope links the enclosing error already rendered are skipped, so a chain built inside one
+    // scope shows that scope once, above its outermost link
+    fn fmt_chain(&self, f: &mut fmt::Formatter<'_>, inherited: &[String], limits: Truncation, depth: usize, debug: bool) -> fmt::Result {
+        let shared = self.scopes.iter().zip(inherited).take_while(|(own, outer)| own == outer).count();
+        for scope in &self.scopes[shared..] {
+            write_truncated(f, scope, limits.max_context_len)?;
+            write!(f, "\n\\ \\ \\\n")?;
+        }
+        write_truncated(f, &self.context.to_string(), limits.max_context_len)?;
+        for help in &self.help {
+            write!(f, "\nhelp: {}", help)?;
+        }
+        let Some(cause) = &self.cause else {
+            return Ok(());
+        };
+        write!(f, "\n\\ \\ \\\n")?;
+        if depth + 1 >= limits.max_depth {
+            let remaining = cause.downcast_ref::<ErrorChain>().map(ErrorChain::depth).unwrap_or(1);
+            return write!(f, "... [{} more links truncated]", remaining);
+        }
+        if let Some(inner) = cause.downcast_ref::<ErrorChain>() {
+            return inner.fmt_chain(f, &self.scopes, limits, depth + 1, debug);
+        }
+        let rendered = if debug { format!("{:?}", cause) } else { cause.to_string() };
+        return write_truncated(f, &rendered, limits.max_context_len);
+    }
 }
 
 impl Display for ErrorChain {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.context)?;
-        if let Some(cause) = &self.cause {
-            write!(f, "\n\\ \\ \\\n{}", cause)?;
-        }
-        Ok(())
+        return self.fmt_chain(f, &[], truncation(), 0, false);
     }
 }
 
 impl Debug for ErrorChain {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.context)?;
-        if let Some(cause) = &self.cause {
-            write!(f, "\n\\ \\ \\\n{:?}", cause)?;
-        }
-        Ok(())
+        return self.fmt_chain(f, &[], truncation(), 0, true);
     }
 }
 
 impl Error for ErrorChain {
-    // fn source(&self) -> Option<&(dyn Error + 'static)> {
-    //     return self.cause;
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        return self.cause.as_deref().map(|cause| cause as &(dyn Error + 'static));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // }
-}
\ No newline at end of file
+    #[test]
+    fn test_with_suggestions_from() {
+        let error = ErrorChain::new("unknown flag '--verison'").with_suggestions_from("verison", &["verbose", "version", "quiet"]);
+        assert_eq!(error.help(), ["did you mean 'version'?"]);
+        assert_eq!(error.to_string(), "unknown flag '--verison'\nhelp: did you mean 'version'?");
+        let error = ErrorChain::new("unknown color").with_suggestions_from("gren", &["green", "grey", "red"]);
+        assert_eq!(error.help(), ["did you mean one of 'green', 'grey'?"]);
+        let error = ErrorChain::new("unknown").with_suggestions_from("zzz", &["green"]);
+        assert!(error.help().is_empty());
+    }
+
+    #[test]
+    fn test_identity_and_fingerprint() {
+        let io = |message: &str| std::io::Error::other(message.to_string());
+        let first = ErrorChain::from(ErrorChain::from(io("disk full at 12:00"), "writing cache"), "saving state").with_help("retry later");
+        let second = ErrorChain::from(ErrorChain::from(io("disk full at 12:05"), "writing cache"), "saving state");
+        assert_eq!(first, second);
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        assert_eq!(first.root_cause_type(), Some("std::io::error::Error"));
+        let parse = ErrorChain::from(ErrorChain::from("x".parse::<u32>().unwrap_err(), "writing cache"), "saving state");
+        assert_ne!(first, parse);
+        assert_ne!(first.fingerprint(), parse.fingerprint());
+        assert_ne!(ErrorChain::new("a"), ErrorChain::new("b"));
+        let set: std::collections::HashSet<ErrorChain> = [first, second, parse].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let error = ErrorChain::from(ErrorChain::from(std::io::Error::other("refused"), "connecting"), "loading profile");
+        assert_eq!(error.context().to_string(), "loading profile");
+        assert_eq!(error.context_string(), "loading profile");
+        let inner = error.cause().and_then(|cause| cause.downcast_ref::<ErrorChain>()).unwrap();
+        assert_eq!(inner.context_string(), "connecting");
+        assert_eq!(inner.cause().unwrap().to_string(), "refused");
+        assert_eq!(error.source().and_then(Error::source).map(|root| root.to_string()), Some("refused".to_string()));
+        assert!(ErrorChain::new("alone").cause().is_none());
+    }
+
+    #[test]
+    fn test_user_message() {
+        let io = ErrorChain::from(std::io::Error::other("ENOSPC at /var/lib/app/tmp.3f2a"), "write_all failed in flush_pages");
+        let error = ErrorChain::from(ErrorChain::from(io, "disk is full").user_facing(), "could not save document").user_facing();
+        assert_eq!(error.user_message(), "could not save document: disk is full");
+        assert!(error.to_string().contains("ENOSPC"));
+        assert_eq!(ErrorChain::from(ErrorChain::new("internal"), "request failed").user_message(), "request failed");
+    }
+
+    #[test]
+    fn test_result_adapters() {
+        let mut seen = Vec::new();
+        let failed: Result<(), ErrorChain> = Err(ErrorChain::from(std::io::Error::other("refused"), "connecting"));
+        let failed = failed.tap_err(|error| seen.push(error.to_string())).map_context(|context| format!("{} to db", context));
+        assert_eq!(seen, ["connecting\n\\ \\ \\\nrefused"]);
+        let (context, cause) = failed.split_context().unwrap_err();
+        assert_eq!(context.to_string(), "connecting to db");
+        assert_eq!(cause.unwrap().to_string(), "refused");
+        assert_eq!(Ok::<i32, ErrorChain>(1).tap_err(|_| panic!("not an error")).map_context(|context| context).split_context().ok(), Some(1));
+    }
+
+    #[test]
+    fn test_truncation_limits() {
+        let mut error = ErrorChain::from(std::io::Error::other("root"), "level 0");
+        for level in 1..10 {
+            error = ErrorChain::from(error, format!("level {}", level));
+        }
+        assert_eq!(error.depth(), 11);
+        assert_eq!(ErrorChain::new("alone").depth(), 1);
+        let limits = Truncation { max_depth: 3, max_context_len: 5 };
+        let rendered = with_truncation(limits, || error.to_string());
+        assert_eq!(rendered, "level... [2 more bytes truncated]\n\\ \\ \\\nlevel... [2 more bytes truncated]\n\\ \\ \\\nlevel... [2 more bytes truncated]\n\\ \\ \\\n... [8 more links truncated]");
+        assert_eq!(with_truncation(Truncation { max_depth: 2, max_context_len: 2 }, || ErrorChain::new("héllo").to_string()), "h... [5 more bytes truncated]");
+        assert!(error.to_string().ends_with("level 0\n\\ \\ \\\nroot"));
+    }
+}
@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+
+thread_local! {
+    static SCOPES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+// ambient context for the current thread: every ErrorChain created while the guard is alive
+// is rendered under this scope's context, outermost scope first
+#[must_use = "the scope ends as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ErrorScope {
+    depth: usize,
+}
+
+impl ErrorScope {
+    pub fn enter<C>(context: C) -> ErrorScope
+    where C: Display {
+        let depth = SCOPES.with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            scopes.push(context.to_string());
+            return scopes.len() - 1;
+        });
+        return ErrorScope { depth };
+    }
+
+    pub fn with<C, T, F>(context: C, f: F) -> T
+    where C: Display, F: FnOnce() -> T {
+        let _scope = ErrorScope::enter(context);
+        return f();
+    }
+
+    // the contexts in effect on this thread, outermost first
+    pub fn current() -> Vec<String> {
+        return SCOPES.with(|scopes| scopes.borrow().clone());
+    }
+}
+
+impl Drop for ErrorScope {
+    fn drop(&mut self) {
+        SCOPES.with(|scopes| scopes.borrow_mut().truncate(self.depth));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::error_chain::ErrorChain;
+
+    fn read_config() -> Result<(), ErrorChain> {
+        return Err(ErrorChain::from(ErrorChain::new("missing key 'port'"), "reading config"));
+    }
+
+    #[test]
+    fn test_errors_gain_scope_links() {
+        let error = ErrorScope::with("processing file a.toml", || {
+            let _inner = ErrorScope::enter("validating");
+            assert_eq!(ErrorScope::current(), vec!["processing file a.toml", "validating"]);
+            return read_config().unwrap_err();
+        });
+        assert!(ErrorScope::current().is_empty());
+        assert_eq!(error.to_string(), "processing file a.toml\n\\ \\ \\\nvalidating\n\\ \\ \\\nreading config\n\\ \\ \\\nmissing key 'port'");
+        let outside = read_config().unwrap_err();
+        assert_eq!(outside.to_string(), "reading config\n\\ \\ \\\nmissing key 'port'");
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::error_chain::ErrorChain;
+
+// what the sink receives: an error that got through, or a summary of repeats that were held back
+#[derive(Debug)]
+pub enum Throttled<'a> {
+    Error(&'a ErrorChain),
+    Suppressed { fingerprint: u64, summary: &'a str, count: usize },
+}
+
+impl Display for Throttled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Throttled::Error(error) => write!(f, "{}", error),
+            Throttled::Suppressed { summary, count: 1, .. } => write!(f, "suppressed 1 similar error: {}", summary),
+            Throttled::Suppressed { summary, count, .. } => write!(f, "suppressed {} similar errors: {}", count, summary),
+        };
+    }
+}
+
+struct Window {
+    opened: Instant,
+    summary: String,
+    suppressed: usize,
+}
+
+// passes the first error of each fingerprint through, then holds back repeats until the window
+// closes; the held-back count is reported when the window closes or on flush
+pub struct ThrottledErrors<S>
+where S: FnMut(Throttled<'_>) {
+    sink: S,
+    window: Duration,
+    windows: HashMap<u64, Window>,
+}
+
+impl<S> ThrottledErrors<S>
+where S: FnMut(Throttled<'_>) {
+    pub fn new(window: Duration, sink: S) -> ThrottledErrors<S> {
+        return ThrottledErrors { sink, window, windows: HashMap::new() };
+    }
+
+    pub fn window(&self) -> Duration {
+        return self.window;
+    }
+
+    pub fn suppressed(&self) -> usize {
+        return self.windows.values().map(|window| window.suppressed).sum();
+    }
+
+    // true when the error reached the sink
+    pub fn report(&mut self, error: &ErrorChain) -> bool {
+        return self.report_at(error, Instant::now());
+    }
+
+    pub fn report_at(&mut self, error: &ErrorChain, now: Instant) -> bool {
+        self.flush_expired(now);
+        let fingerprint = error.fingerprint();
+        if let Some(window) = self.windows.get_mut(&fingerprint) {
+            window.suppressed += 1;
+            return false;
+        }
+        let summary = error.to_string().lines().next().unwrap_or("").to_string();
+        self.windows.insert(fingerprint, Window { opened: now, summary, suppressed: 0 });
+        (self.sink)(Throttled::Error(error));
+        return true;
+    }
+
+    // closes every window older than the throttle window, reporting what it held back
+    pub fn flush_expired(&mut self, now: Instant) {
+        let window = self.window;
+        let mut expired: Vec<(u64, Window)> = Vec::new();
+        self.windows.retain(|fingerprint, open| {
+            if now.saturating_duration_since(open.opened) < window {
+                return true;
+            }
+            expired.push((*fingerprint, Window { opened: open.opened, summary: std::mem::take(&mut open.summary), suppressed: open.suppressed }));
+            return false;
+        });
+        expired.sort_by_key(|(_, closed)| closed.opened);
+        for (fingerprint, closed) in expired {
+            self.emit_suppressed(fingerprint, &closed);
+        }
+    }
+
+    // reports every pending suppression regardless of age, e.g. at shutdown
+    pub fn flush(&mut self) {
+        let mut pending: Vec<(u64, Window)> = self.windows.drain().collect();
+        pending.sort_by_key(|(_, closed)| closed.opened);
+        for (fingerprint, closed) in pending {
+            self.emit_suppressed(fingerprint, &closed);
+        }
+    }
+
+    fn emit_suppressed(&mut self, fingerprint: u64, closed: &Window) {
+        if closed.suppressed > 0 {
+            (self.sink)(Throttled::Suppressed { fingerprint, summary: &closed.summary, count: closed.suppressed });
+        }
+    }
+
+    pub fn into_sink(mut self) -> S {
+        self.flush();
+        return self.sink;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_are_suppressed_per_window() {
+        let mut lines: Vec<String> = Vec::new();
+        let start = Instant::now();
+        let mut throttle = ThrottledErrors::new(Duration::from_secs(10), |out: Throttled<'_>| lines.push(out.to_string()));
+        let failure = |attempt: usize| ErrorChain::from(std::io::Error::other(format!("attempt {}", attempt)), "connecting to db");
+        assert!(throttle.report_at(&failure(1), start));
+        for attempt in 2..6 {
+            assert!(!throttle.report_at(&failure(attempt), start + Duration::from_secs(attempt as u64)));
+        }
+        assert!(throttle.report_at(&ErrorChain::new("disk full"), start + Duration::from_secs(6)));
+        assert_eq!(throttle.suppressed(), 4);
+        assert!(throttle.report_at(&failure(7), start + Duration::from_secs(12)));
+        assert!(!throttle.report_at(&failure(8), start + Duration::from_secs(13)));
+        throttle.flush();
+        assert_eq!(lines, vec![
+            "connecting to db\n\\ \\ \\\nattempt 1",
+            "disk full",
+            "suppressed 4 similar errors: connecting to db",
+            "connecting to db\n\\ \\ \\\nattempt 7",
+            "suppressed 1 similar error: connecting to db",
+        ]);
+    }
+}
@@ -0,0 +1,189 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::ops::Deref;
+
+use super::error_chain::ErrorChain;
+
+#[derive(Clone, Copy)]
+pub struct InlineString<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> InlineString<N> {
+    pub fn new() -> InlineString<N> {
+        return InlineString { bytes: [0; N], len: 0 };
+    }
+
+    pub fn try_from_str(value: &str) -> Result<InlineString<N>, ErrorChain> {
+        let mut string = InlineString::new();
+        string.push_str(value)?;
+        return Ok(string);
+    }
+
+    pub fn capacity(&self) -> usize {
+        return N;
+    }
+
+    pub fn remaining(&self) -> usize {
+        return N - self.len;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    pub fn push_str(&mut self, value: &str) -> Result<(), ErrorChain> {
+        if value.len() > self.remaining() {
+            return Err(ErrorChain::new(format!(
+                "InlineString capacity of {} bytes exceeded: {} bytes stored, {} more requested",
+                N,
+                self.len,
+                value.len()
+            )));
+        }
+        self.bytes[self.len..self.len + value.len()].copy_from_slice(value.as_bytes());
+        self.len += value.len();
+        return Ok(());
+    }
+
+    pub fn push(&mut self, character: char) -> Result<(), ErrorChain> {
+        let mut buffer = [0u8; 4];
+        return self.push_str(character.encode_utf8(&mut buffer));
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let character = self.as_str().chars().next_back()?;
+        self.len -= character.len_utf8();
+        return Some(character);
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            assert!(self.as_str().is_char_boundary(len), "truncate position is not a char boundary");
+            self.len = len;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn as_str(&self) -> &str {
+        return std::str::from_utf8(&self.bytes[..self.len]).expect("InlineString only ever stores whole UTF-8 strings");
+    }
+}
+
+impl<const N: usize> Default for InlineString<N> {
+    fn default() -> InlineString<N> {
+        return InlineString::new();
+    }
+}
+
+impl<const N: usize> Deref for InlineString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        return self.as_str();
+    }
+}
+
+impl<const N: usize> AsRef<str> for InlineString<N> {
+    fn as_ref(&self) -> &str {
+        return self.as_str();
+    }
+}
+
+impl<const N: usize> Borrow<str> for InlineString<N> {
+    fn borrow(&self) -> &str {
+        return self.as_str();
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for InlineString<N> {
+    type Error = ErrorChain;
+
+    fn try_from(value: &str) -> Result<InlineString<N>, ErrorChain> {
+        return InlineString::try_from_str(value);
+    }
+}
+
+impl<const N: usize> fmt::Write for InlineString<N> {
+    fn write_str(&mut self, value: &str) -> fmt::Result {
+        return self.push_str(value).map_err(|_| fmt::Error);
+    }
+}
+
+impl<const N: usize> Display for InlineString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(self.as_str());
+    }
+}
+
+impl<const N: usize> Debug for InlineString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return Debug::fmt(self.as_str(), f);
+    }
+}
+
+impl<const N: usize> PartialEq for InlineString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        return self.as_str() == other.as_str();
+    }
+}
+
+impl<const N: usize> Eq for InlineString<N> {}
+
+impl<const N: usize> PartialEq<str> for InlineString<N> {
+    fn eq(&self, other: &str) -> bool {
+        return self.as_str() == other;
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for InlineString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        return self.as_str() == *other;
+    }
+}
+
+impl<const N: usize> Hash for InlineString<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_push_and_overflow() {
+        let mut string: InlineString<8> = InlineString::new();
+        string.push_str("héllo").unwrap();
+        assert_eq!(string.len(), 6);
+        assert!(string.push_str("!!!").is_err());
+        string.push('!').unwrap();
+        assert_eq!(string, "héllo!");
+        assert_eq!(string.pop(), Some('!'));
+        assert_eq!(string.pop(), Some('o'));
+        assert_eq!(string.to_uppercase(), "HÉLL");
+    }
+
+    #[test]
+    fn test_fmt_write() {
+        let mut string: InlineString<4> = InlineString::new();
+        assert!(write!(string, "{}", 12).is_ok());
+        assert!(write!(string, "{}", 345).is_err());
+        assert_eq!(string.as_str(), "12");
+        assert!(InlineString::<2>::try_from("abc").is_err());
+    }
+}
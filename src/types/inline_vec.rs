@@ -0,0 +1,185 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use super::error_chain::ErrorChain;
+
+pub struct InlineVec<T, const N: usize> {
+    items: [T; N],
+    len: usize,
+}
+
+impl<T, const N: usize> InlineVec<T, N>
+where T: Default {
+    pub fn new() -> InlineVec<T, N> {
+        return InlineVec { items: std::array::from_fn(|_| T::default()), len: 0 };
+    }
+
+    pub fn capacity(&self) -> usize {
+        return N;
+    }
+
+    pub fn remaining(&self) -> usize {
+        return N - self.len;
+    }
+
+    pub fn is_full(&self) -> bool {
+        return self.len == N;
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), ErrorChain> {
+        if self.len == N {
+            return Err(ErrorChain::new(format!("InlineVec capacity of {} exceeded", N)));
+        }
+        self.items[self.len] = item;
+        self.len += 1;
+        return Ok(());
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        return Some(std::mem::take(&mut self.items[self.len]));
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        return &self.items[..self.len];
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        return &mut self.items[..self.len];
+    }
+}
+
+impl<T, const N: usize> InlineVec<T, N>
+where T: Default + Clone {
+    pub fn extend_from_slice(&mut self, items: &[T]) -> Result<(), ErrorChain> {
+        if items.len() > self.remaining() {
+            return Err(ErrorChain::new(format!(
+                "InlineVec capacity of {} exceeded: {} items stored, {} more requested",
+                N,
+                self.len,
+                items.len()
+            )));
+        }
+        for item in items {
+            self.items[self.len] = item.clone();
+            self.len += 1;
+        }
+        return Ok(());
+    }
+
+    pub fn try_from_slice(items: &[T]) -> Result<InlineVec<T, N>, ErrorChain> {
+        let mut vec = InlineVec::new();
+        vec.extend_from_slice(items)?;
+        return Ok(vec);
+    }
+}
+
+impl<T, const N: usize> Default for InlineVec<T, N>
+where T: Default {
+    fn default() -> InlineVec<T, N> {
+        return InlineVec::new();
+    }
+}
+
+impl<T, const N: usize> Deref for InlineVec<T, N>
+where T: Default {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        return self.as_slice();
+    }
+}
+
+impl<T, const N: usize> DerefMut for InlineVec<T, N>
+where T: Default {
+    fn deref_mut(&mut self) -> &mut [T] {
+        return self.as_mut_slice();
+    }
+}
+
+impl<T, const N: usize> Clone for InlineVec<T, N>
+where T: Default + Clone {
+    fn clone(&self) -> InlineVec<T, N> {
+        return InlineVec { items: self.items.clone(), len: self.len };
+    }
+}
+
+impl<T, const N: usize> Debug for InlineVec<T, N>
+where T: Default + Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_list().entries(self.as_slice()).finish();
+    }
+}
+
+impl<T, const N: usize> PartialEq for InlineVec<T, N>
+where T: Default + PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        return self.as_slice() == other.as_slice();
+    }
+}
+
+impl<T, const N: usize> Eq for InlineVec<T, N> where T: Default + Eq {}
+
+impl<T, const N: usize> Hash for InlineVec<T, N>
+where T: Default + Hash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a InlineVec<T, N>
+where T: Default {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.as_slice().iter();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_overflow() {
+        let mut vec: InlineVec<u32, 3> = InlineVec::new();
+        vec.push(1).unwrap();
+        vec.extend_from_slice(&[2, 3]).unwrap();
+        assert!(vec.is_full());
+        assert!(vec.push(4).is_err());
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.iter().sum::<u32>(), 3);
+        assert!(InlineVec::<u8, 2>::try_from_slice(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_non_copy_items() {
+        let mut vec: InlineVec<String, 2> = InlineVec::new();
+        vec.push(String::from("a")).unwrap();
+        vec.push(String::from("b")).unwrap();
+        vec[0].push('!');
+        let cloned = vec.clone();
+        vec.clear();
+        assert!(vec.is_empty());
+        assert_eq!(format!("{:?}", cloned), "[\"a!\", \"b\"]");
+    }
+}
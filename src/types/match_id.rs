@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+
+use super::error_chain::ErrorChain;
+
+// identifies one match across runs by what matched rather than where: the source, the pattern,
+// a hash of the matched text and which occurrence of that text in the source it is. Edits
+// elsewhere in the source keep the id, and the text itself is never stored since it may be a
+// secret; rules::baseline::BaselineKey is the same identity with the occurrence left out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MatchId(pub u64);
+
+impl MatchId {
+    // the 16 lowercase hex digits Display writes, and nothing else
+    pub fn parse(src: &str) -> Result<MatchId, ErrorChain> {
+        if src.len() != 16 || !src.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')) {
+            return Err(ErrorChain::new(format!("match id '{}' is not 16 hex digits", src)));
+        }
+        return u64::from_str_radix(src, 16).map(MatchId).map_err(|err| ErrorChain::from(err, format!("match id '{}' is not 16 hex digits", src)));
+    }
+}
+
+impl Display for MatchId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{:016x}", self.0);
+    }
+}
+
+pub(crate) fn fnv1a<I>(bytes: I) -> u64
+where I: IntoIterator<Item = u8> {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+pub fn text_fingerprint(text: &[u8]) -> u64 {
+    return fnv1a(text.iter().copied());
+}
+
+// pattern names what matched in a way that survives between runs: a rule's name, or a pattern id
+// when the pattern set is fixed
+pub fn match_id(source: &str, pattern: &str, text: &[u8], occurrence: usize) -> MatchId {
+    return fingerprint_match_id(source, pattern, text_fingerprint(text), occurrence);
+}
+
+// FNV-1a over the source name, the pattern and the numbers; integers are hashed as little-endian
+// u64 so the id doesn't depend on the platform's pointer width
+pub fn fingerprint_match_id(source: &str, pattern: &str, fingerprint: u64, occurrence: usize) -> MatchId {
+    let numbers = [fingerprint, occurrence as u64];
+    let names = source.bytes().chain(std::iter::once(0x1f)).chain(pattern.bytes()).chain(std::iter::once(0x1f));
+    return MatchId(fnv1a(names.chain(numbers.iter().flat_map(|number| number.to_le_bytes()))));
+}
+
+// numbers the occurrences of each (source, pattern, text) as matches are handed over in source
+// order, so one MatchIds has to see every match of a source from its start
+#[derive(Debug, Clone, Default)]
+pub struct MatchIds {
+    seen: HashMap<MatchId, usize>,
+}
+
+impl MatchIds {
+    pub fn new() -> MatchIds {
+        return MatchIds { seen: HashMap::new() };
+    }
+
+    pub fn next(&mut self, source: &str, pattern: &str, text: &[u8]) -> MatchId {
+        let fingerprint = text_fingerprint(text);
+        let occurrence = self.seen.entry(fingerprint_match_id(source, pattern, fingerprint, 0)).or_insert(0);
+        let id = fingerprint_match_id(source, pattern, fingerprint, *occurrence);
+        *occurrence += 1;
+        return id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_ids() {
+        let id = match_id("src/main.rs", "secret", b"sk_live_1", 0);
+        assert_eq!(id, match_id("src/main.rs", "secret", b"sk_live_1", 0));
+        assert_eq!(id.to_string(), "0a5b015d0200c0d0");
+        assert_ne!(id, match_id("src/main.rs", "secret", b"sk_live_2", 0));
+        assert_ne!(id, match_id("src/main.rs", "secret", b"sk_live_1", 1));
+        assert_ne!(id, match_id("src/main.rs", "token", b"sk_live_1", 0));
+        assert_ne!(id, match_id("src/lib.rs", "secret", b"sk_live_1", 0));
+
+        let mut ids = MatchIds::new();
+        let first = ids.next("src/main.rs", "secret", b"sk_live_1");
+        assert_eq!((first, ids.next("src/main.rs", "secret", b"sk_live_1")), (id, match_id("src/main.rs", "secret", b"sk_live_1", 1)));
+
+        assert_eq!(MatchId::parse(&id.to_string()).unwrap(), id);
+        for bad in ["xyz", "zzzzzzzzzzzzzzzz", "+123456789abcdef", "0A5B015D0200C0D0"] {
+            assert!(MatchId::parse(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+// turns a key and its arguments into text in the current language; None falls back to the key's
+// own template
+pub type Translator = Arc<dyn Fn(&MessageKey) -> Option<String> + Send + Sync>;
+
+static GLOBAL: RwLock<Option<Translator>> = RwLock::new(None);
+
+thread_local! {
+    static LOCAL: RefCell<Option<Translator>> = const { RefCell::new(None) };
+}
+
+// unlike metrics recorders this can be replaced, e.g. when the user switches locale
+pub fn set_translator(translator: Option<Translator>) {
+    *GLOBAL.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = translator;
+}
+
+pub fn with_local_translator<T, F>(translator: Translator, f: F) -> T
+where F: FnOnce() -> T {
+    let previous = LOCAL.with(|local| local.borrow_mut().replace(translator));
+    let result = f();
+    LOCAL.with(|local| *local.borrow_mut() = previous);
+    return result;
+}
+
+fn translate(message: &MessageKey) -> Option<String> {
+    let local = LOCAL.try_with(|local| local.borrow().clone()).ok().flatten();
+    let translator = local.or_else(|| GLOBAL.read().ok().and_then(|global| global.clone()))?;
+    return translator(message);
+}
+
+// an error context stored as a key plus arguments and rendered when displayed, so the same
+// ErrorChain can be shown in whichever language the translator provides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageKey {
+    pub key: String,
+    pub args: Vec<(String, String)>,
+    pub fallback: Option<String>,
+}
+
+impl MessageKey {
+    pub fn new<K>(key: K) -> MessageKey
+    where K: Into<String> {
+        return MessageKey { key: key.into(), args: Vec::new(), fallback: None };
+    }
+
+    pub fn arg<N, V>(mut self, name: N, value: V) -> MessageKey
+    where N: Into<String>, V: Display {
+        self.args.push((name.into(), value.to_string()));
+        return self;
+    }
+
+    // template used when no translator is installed or it doesn't know the key
+    pub fn fallback<S>(mut self, template: S) -> MessageKey
+    where S: Into<String> {
+        self.fallback = Some(template.into());
+        return self;
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        return self.args.iter().find(|(arg, _)| arg == name).map(|(_, value)| value.as_str());
+    }
+
+    // replaces each {name} with its argument; unknown names are left as written
+    pub fn render(&self, template: &str) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            out.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            match after.find('}').and_then(|close| self.get(&after[..close]).map(|value| (close, value))) {
+                Some((close, value)) => {
+                    out.push_str(value);
+                    rest = &after[close + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        return out;
+    }
+}
+
+impl Display for MessageKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(translated) = translate(self) {
+            return write!(f, "{}", translated);
+        }
+        if let Some(template) = &self.fallback {
+            return write!(f, "{}", self.render(template));
+        }
+        write!(f, "{}", self.key)?;
+        for (name, value) in &self.args {
+            write!(f, " {}={}", name, value)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::error_chain::ErrorChain;
+
+    fn german(message: &MessageKey) -> Option<String> {
+        return match message.key.as_str() {
+            "file.not_found" => Some(message.render("Datei {path} nicht gefunden")),
+            _ => None,
+        };
+    }
+
+    #[test]
+    fn test_rendered_at_display_time() {
+        let error = ErrorChain::new(MessageKey::new("file.not_found").arg("path", "a.txt").fallback("file {path} not found"));
+        assert_eq!(error.to_string(), "file a.txt not found");
+        assert_eq!(with_local_translator(Arc::new(german), || error.to_string()), "Datei a.txt nicht gefunden");
+        let unknown = MessageKey::new("net.timeout").arg("seconds", 30);
+        assert_eq!(with_local_translator(Arc::new(german), || unknown.to_string()), "net.timeout seconds=30");
+        assert_eq!(unknown.render("{seconds}s {missing} {"), "30s {missing} {");
+    }
+}
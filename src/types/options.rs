@@ -0,0 +1,144 @@
+use super::error_accumulator::aggregate;
+use super::error_chain::ErrorChain;
+
+// collects every problem with an options value so build() can report them all at once
+#[derive(Debug)]
+pub struct Checks {
+    problems: Vec<ErrorChain>,
+}
+
+impl Checks {
+    pub fn require<M>(&mut self, condition: bool, field: &str, message: M)
+    where M: Into<String> {
+        if !condition {
+            self.problems.push(ErrorChain::new(format!("{}: {}", field, message.into())));
+        }
+    }
+
+    pub fn problem(&mut self, error: ErrorChain) {
+        self.problems.push(error);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.problems.is_empty();
+    }
+}
+
+// a config struct plus the rules that make it valid; plain structs with public fields get a builder,
+// ones with setters of their own are checked with check_valid() by whatever consumes them
+pub trait Options: Sized {
+    const NAME: &'static str;
+
+    fn validate(&self, _checks: &mut Checks) {}
+
+    fn builder() -> OptionsBuilder<Self>
+    where Self: Default {
+        return OptionsBuilder::new();
+    }
+
+    fn check_valid(&self) -> Result<(), ErrorChain> {
+        return run_checks(self, &[]);
+    }
+
+    // checks an existing value, e.g. one assembled with struct update syntax
+    fn validated(self) -> Result<Self, ErrorChain> {
+        self.check_valid()?;
+        return Ok(self);
+    }
+}
+
+fn run_checks<T>(value: &T, extra: &[Check<T>]) -> Result<(), ErrorChain>
+where T: Options {
+    let mut checks = Checks { problems: Vec::new() };
+    value.validate(&mut checks);
+    for check in extra {
+        check(value, &mut checks);
+    }
+    return match aggregate(checks.problems, format!("invalid {}", T::NAME)) {
+        Some(error) => Err(error),
+        None => Ok(()),
+    };
+}
+
+type Check<T> = Box<dyn Fn(&T, &mut Checks)>;
+
+pub struct OptionsBuilder<T>
+where T: Options {
+    value: T,
+    checks: Vec<Check<T>>,
+}
+
+impl<T> OptionsBuilder<T>
+where T: Options + Default {
+    pub fn new() -> OptionsBuilder<T> {
+        return OptionsBuilder::from(T::default());
+    }
+}
+
+impl<T> OptionsBuilder<T>
+where T: Options {
+    pub fn from(value: T) -> OptionsBuilder<T> {
+        return OptionsBuilder { value, checks: Vec::new() };
+    }
+
+    pub fn set<F>(mut self, f: F) -> OptionsBuilder<T>
+    where F: FnOnce(&mut T) {
+        f(&mut self.value);
+        return self;
+    }
+
+    // an extra rule on top of T::validate, for constraints only this caller cares about
+    pub fn check<F>(mut self, f: F) -> OptionsBuilder<T>
+    where F: Fn(&T, &mut Checks) + 'static {
+        self.checks.push(Box::new(f));
+        return self;
+    }
+
+    pub fn build(self) -> Result<T, ErrorChain> {
+        run_checks(&self.value, &self.checks)?;
+        return Ok(self.value);
+    }
+}
+
+impl<T> Default for OptionsBuilder<T>
+where T: Options + Default {
+    fn default() -> OptionsBuilder<T> {
+        return OptionsBuilder::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct WrapOptions {
+        width: usize,
+        indent: usize,
+    }
+
+    impl Default for WrapOptions {
+        fn default() -> WrapOptions {
+            return WrapOptions { width: 80, indent: 0 };
+        }
+    }
+
+    impl Options for WrapOptions {
+        const NAME: &'static str = "wrap options";
+
+        fn validate(&self, checks: &mut Checks) {
+            checks.require(self.width > 0, "width", "must be at least 1");
+            checks.require(self.indent < self.width, "indent", format!("must be less than the width ({})", self.width));
+        }
+    }
+
+    #[test]
+    fn test_build_and_validate() {
+        assert_eq!(WrapOptions::builder().set(|o| o.indent = 4).build().unwrap(), WrapOptions { width: 80, indent: 4 });
+        let error = WrapOptions::builder().set(|o| o.width = 0).build().unwrap_err();
+        assert_eq!(error.to_string(), "invalid wrap options (2 errors)\n\\ \\ \\\n[1] width: must be at least 1\n[2] indent: must be less than the width (0)");
+        let strict = WrapOptions::builder().check(|o, checks| checks.require(o.width <= 72, "width", "must be at most 72"));
+        assert_eq!(strict.build().unwrap_err().to_string(), "invalid wrap options\n\\ \\ \\\nwidth: must be at most 72");
+        assert!(WrapOptions { width: 10, indent: 10 }.validated().is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+use super::span::Span;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new<S, M>(span: S, message: M) -> ParseError
+    where S: Into<Span>, M: Into<String> {
+        return ParseError { span: span.into(), message: message.into() };
+    }
+
+    pub fn at<M>(offset: usize, message: M) -> ParseError
+    where M: Into<String> {
+        return ParseError { span: Span::at(offset), message: message.into() };
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.span.is_empty() {
+            return write!(f, "{} at byte {}", self.message, self.span.start);
+        }
+        return write!(f, "{} at bytes {}", self.message, self.span);
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,94 @@
+use super::error_accumulator::ErrorAccumulator;
+use super::error_chain::ErrorChain;
+
+// runs the cleanup when dropped, including during unwinding, unless dismissed first
+#[must_use = "the cleanup runs as soon as the guard is dropped"]
+pub struct ScopeGuard<F>
+where F: FnOnce() {
+    cleanup: Option<F>,
+}
+
+impl<F> ScopeGuard<F>
+where F: FnOnce() {
+    pub fn new(cleanup: F) -> ScopeGuard<F> {
+        return ScopeGuard { cleanup: Some(cleanup) };
+    }
+
+    pub fn dismiss(mut self) {
+        self.cleanup = None;
+    }
+}
+
+impl<F> Drop for ScopeGuard<F>
+where F: FnOnce() {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup();
+        }
+    }
+}
+
+// like ScopeGuard, but a failed cleanup is pushed onto the accumulator instead of being lost
+#[must_use = "the cleanup runs as soon as the guard is dropped"]
+pub struct FallibleGuard<'a, F>
+where F: FnOnce() -> Result<(), ErrorChain> {
+    cleanup: Option<F>,
+    errors: &'a mut ErrorAccumulator,
+}
+
+impl<'a, F> FallibleGuard<'a, F>
+where F: FnOnce() -> Result<(), ErrorChain> {
+    pub fn new(errors: &'a mut ErrorAccumulator, cleanup: F) -> FallibleGuard<'a, F> {
+        return FallibleGuard { cleanup: Some(cleanup), errors };
+    }
+
+    pub fn dismiss(mut self) {
+        self.cleanup = None;
+    }
+}
+
+impl<F> Drop for FallibleGuard<'_, F>
+where F: FnOnce() -> Result<(), ErrorChain> {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            self.errors.capture(cleanup());
+        }
+    }
+}
+
+// defer! { cleanup statements } runs them when the enclosing block ends
+#[macro_export]
+macro_rules! defer {
+    ($($body:tt)*) => {
+        let _guard = $crate::types::scope_guard::ScopeGuard::new(|| { $($body)* });
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_guards_run_on_drop() {
+        let log = RefCell::new(Vec::new());
+        {
+            crate::defer! { log.borrow_mut().push("deferred"); }
+            let kept = ScopeGuard::new(|| log.borrow_mut().push("guard"));
+            ScopeGuard::new(|| log.borrow_mut().push("dismissed")).dismiss();
+            log.borrow_mut().push("body");
+            drop(kept);
+        }
+        assert_eq!(*log.borrow(), vec!["body", "guard", "deferred"]);
+
+        let mut errors = ErrorAccumulator::new();
+        {
+            let _ok = FallibleGuard::new(&mut errors, || Ok(()));
+        }
+        {
+            let _failed = FallibleGuard::new(&mut errors, || Err(ErrorChain::new("failed to remove temp dir")));
+        }
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors.errors()[0].to_string(), "failed to remove temp dir");
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::fmt::Display;
+use std::ops::Range;
+
+use crate::patterns::PatternMatch;
+use crate::patterns::SetMatch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub const fn new(start: usize, end: usize) -> Span {
+        return Span { start, end };
+    }
+
+    pub const fn at(offset: usize) -> Span {
+        return Span { start: offset, end: offset };
+    }
+
+    pub const fn len(&self) -> usize {
+        return self.end.saturating_sub(self.start);
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        return self.end <= self.start;
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        return self.start..self.end;
+    }
+
+    pub fn contains(&self, offset: usize) -> bool {
+        return offset >= self.start && offset < self.end;
+    }
+
+    pub fn overlaps(&self, other: &Span) -> bool {
+        return self.start < other.end && other.start < self.end;
+    }
+
+    pub fn join(&self, other: &Span) -> Span {
+        return Span { start: self.start.min(other.start), end: self.end.max(other.end) };
+    }
+
+    pub fn shifted(&self, offset: usize) -> Span {
+        return Span { start: self.start + offset, end: self.end + offset };
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Span {
+        return Span { start: range.start, end: range.end };
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Range<usize> {
+        return span.start..span.end;
+    }
+}
+
+impl<T> From<&PatternMatch<T>> for Span {
+    fn from(found_match: &PatternMatch<T>) -> Span {
+        return Span { start: found_match.start(), end: found_match.end() };
+    }
+}
+
+impl From<&SetMatch> for Span {
+    fn from(found_match: &SetMatch) -> Span {
+        return Span { start: found_match.start(), end: found_match.end() };
+    }
+}
+
+pub trait HasSpan {
+    fn span(&self) -> Span;
+}
+
+impl HasSpan for Span {
+    fn span(&self) -> Span {
+        return *self;
+    }
+}
+
+impl<T> HasSpan for PatternMatch<T> {
+    fn span(&self) -> Span {
+        return Span::from(self);
+    }
+}
+
+impl HasSpan for SetMatch {
+    fn span(&self) -> Span {
+        return Span::from(self);
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}..{}", self.start, self.end);
+    }
+}
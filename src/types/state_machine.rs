@@ -0,0 +1,104 @@
+use std::fmt::Debug;
+
+use super::error_chain::ErrorChain;
+
+// a transition table over small Copy state and event types; tables are expected to be small, so
+// lookups are a linear scan that keeps the declaration order for error messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateMachine<S, E>
+where S: Copy + PartialEq + Debug, E: Copy + PartialEq + Debug {
+    initial: S,
+    state: S,
+    transitions: Vec<(S, E, S)>,
+}
+
+impl<S, E> StateMachine<S, E>
+where S: Copy + PartialEq + Debug, E: Copy + PartialEq + Debug {
+    pub fn new(initial: S) -> StateMachine<S, E> {
+        return StateMachine { initial, state: initial, transitions: Vec::new() };
+    }
+
+    // declaring the same from/event pair again replaces its target
+    pub fn transition(mut self, from: S, event: E, to: S) -> StateMachine<S, E> {
+        match self.transitions.iter_mut().find(|(state, on, _)| *state == from && *on == event) {
+            Some(existing) => existing.2 = to,
+            None => self.transitions.push((from, event, to)),
+        }
+        return self;
+    }
+
+    pub fn state(&self) -> S {
+        return self.state;
+    }
+
+    pub fn is_in(&self, state: S) -> bool {
+        return self.state == state;
+    }
+
+    pub fn next(&self, event: E) -> Option<S> {
+        return self.transitions.iter().find(|(from, on, _)| *from == self.state && *on == event).map(|(_, _, to)| *to);
+    }
+
+    pub fn can_fire(&self, event: E) -> bool {
+        return self.next(event).is_some();
+    }
+
+    pub fn events_from(&self, state: S) -> Vec<(E, S)> {
+        return self.transitions.iter().filter(|(from, _, _)| *from == state).map(|(_, event, to)| (*event, *to)).collect();
+    }
+
+    // moves to the next state, or leaves the state unchanged and explains what was allowed
+    pub fn fire(&mut self, event: E) -> Result<S, ErrorChain> {
+        let Some(to) = self.next(event) else {
+            let error = ErrorChain::new(format!("invalid transition: event {:?} in state {:?}", event, self.state));
+            let allowed: Vec<String> = self.events_from(self.state).iter().map(|(event, to)| format!("{:?} -> {:?}", event, to)).collect();
+            if allowed.is_empty() {
+                return Err(error.with_help(format!("{:?} is a terminal state", self.state)));
+            }
+            return Err(error.with_help(format!("from {:?} the allowed events are {}", self.state, allowed.join(", "))));
+        };
+        self.state = to;
+        return Ok(to);
+    }
+
+    pub fn reset(&mut self) {
+        self.state = self.initial;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Conn {
+        Idle,
+        Open,
+        Closed,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Signal {
+        Connect,
+        Send,
+        Close,
+    }
+
+    #[test]
+    fn test_transitions_and_errors() {
+        let mut machine = StateMachine::new(Conn::Idle)
+            .transition(Conn::Idle, Signal::Connect, Conn::Open)
+            .transition(Conn::Open, Signal::Send, Conn::Open)
+            .transition(Conn::Open, Signal::Close, Conn::Closed);
+        assert!(machine.can_fire(Signal::Connect) && !machine.can_fire(Signal::Send));
+        assert_eq!(machine.fire(Signal::Connect).unwrap(), Conn::Open);
+        assert_eq!(machine.fire(Signal::Send).unwrap(), Conn::Open);
+        let error = machine.fire(Signal::Connect).unwrap_err();
+        assert_eq!(error.to_string(), "invalid transition: event Connect in state Open\nhelp: from Open the allowed events are Send -> Open, Close -> Closed");
+        assert!(machine.is_in(Conn::Open));
+        machine.fire(Signal::Close).unwrap();
+        assert_eq!(machine.fire(Signal::Send).unwrap_err().help(), ["Closed is a terminal state"]);
+        machine.reset();
+        assert_eq!(machine.state(), Conn::Idle);
+    }
+}